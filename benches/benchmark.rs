@@ -4,7 +4,7 @@ extern crate polylabel;
 
 use criterion::Criterion;
 use geo::Polygon;
-use polylabel::polylabel;
+use polylabel::{polylabel, polylabel_with_options, PolylabelOptions};
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("Threaded", |bencher| {
@@ -24,6 +24,23 @@ fn criterion_benchmark(c: &mut Criterion) {
         });
     });
 
+    c.bench_function("Threaded (f32)", |bencher| {
+        // an L shape
+        let coords: Vec<(f32, f32)> = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        bencher.iter(|| {
+            polylabel(&poly, &10.0).unwrap();
+        });
+    });
+
     c.bench_function("Large Polygon", |bencher| {
         let points = include!("../data/norway_main.rs");
         let poly = Polygon::new(points.into(), vec![]);
@@ -31,6 +48,35 @@ fn criterion_benchmark(c: &mut Criterion) {
             polylabel(&poly, &1.0).unwrap();
         });
     });
+
+    c.bench_function("Large Polygon (simplified)", |bencher| {
+        let points = include!("../data/norway_main.rs");
+        let poly = Polygon::new(points.into(), vec![]);
+        let options = PolylabelOptions::new(1.0).simplify(true);
+        bencher.iter(|| {
+            polylabel_with_options(&poly, &options).unwrap();
+        });
+    });
+
+    c.bench_function("Large Polygon (f32)", |bencher| {
+        let points: Vec<(f32, f32)> = include!("../data/norway_main.rs")
+            .into_iter()
+            .map(|[x, y]: [f64; 2]| (x as f32, y as f32))
+            .collect();
+        let poly = Polygon::new(points.into(), vec![]);
+        bencher.iter(|| {
+            polylabel(&poly, &1.0).unwrap();
+        });
+    });
+
+    #[cfg(feature = "rayon")]
+    c.bench_function("Large Polygon (parallel)", |bencher| {
+        let points = include!("../data/norway_main.rs");
+        let poly = Polygon::new(points.into(), vec![]);
+        bencher.iter(|| {
+            polylabel::polylabel_parallel(&poly, &1.0).unwrap();
+        });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -1,4 +1,6 @@
-/* Generated with cbindgen:0.24.5 */
+#pragma once
+
+/* Generated with cbindgen:0.26.0 */
 
 /* Warning, this file is autogenerated by cbindgen. Don't modify this manually. */
 
@@ -7,6 +9,102 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * The version of the FFI's ABI, which is incremented whenever a struct, enum, or function signature changes
+ * incompatibly. Compare it to [`polylabel_abi_version`](fn.polylabel_abi_version.html) when loading the library.
+ */
+#define POLYLABEL_ABI_VERSION 1
+
+/**
+ * Severity of a message passed to a [`PolylabelLogCallback`](type.PolylabelLogCallback.html)
+ */
+typedef enum PolylabelLogLevel {
+    PolylabelLogLevel_Error = 0,
+    PolylabelLogLevel_Warn = 1,
+    PolylabelLogLevel_Info = 2,
+    PolylabelLogLevel_Debug = 3,
+} PolylabelLogLevel;
+
+/**
+ * Which parts of a MultiPolygon to calculate label positions for. Mirrors [`Parts`](enum.Parts.html).
+ */
+typedef enum PolylabelParts {
+    /**
+     * Only the part with the largest area
+     */
+    PolylabelParts_Largest = 0,
+    /**
+     * Every part
+     */
+    PolylabelParts_All = 1,
+} PolylabelParts;
+
+/**
+ * Outcome of an FFI label position calculation
+ *
+ * Other than `Ok`, `NullPointer`, `InvalidLength`, `InvalidInput`, `Panic`, and `Cancelled`, these mirror the
+ * variants of [`PolylabelError`](errors/enum.PolylabelError.html).
+ */
+typedef enum PolylabelStatus {
+    /**
+     * The label position was calculated successfully
+     */
+    PolylabelStatus_Ok = 0,
+    /**
+     * Couldn't calculate a centroid for the input Polygon
+     */
+    PolylabelStatus_CentroidCalculation = 1,
+    /**
+     * Couldn't calculate a bounding box for the input Polygon
+     */
+    PolylabelStatus_RectCalculation = 2,
+    /**
+     * A required pointer argument was null
+     */
+    PolylabelStatus_NullPointer = 3,
+    /**
+     * A length or offset argument was inconsistent
+     */
+    PolylabelStatus_InvalidLength = 4,
+    /**
+     * The input geometry couldn't be parsed, or wasn't a Polygon or MultiPolygon
+     */
+    PolylabelStatus_InvalidInput = 5,
+    /**
+     * An internal error (a Rust panic) occurred
+     */
+    PolylabelStatus_Panic = 6,
+    /**
+     * The calculation was cancelled before it finished
+     */
+    PolylabelStatus_Cancelled = 7,
+} PolylabelStatus;
+
+typedef struct Option_PolylabelLogCallback Option_PolylabelLogCallback;
+
+typedef struct Option_PolylabelProgress Option_PolylabelProgress;
+
+/**
+ * An opaque handle to a [`PreparedPolygon`](struct.PreparedPolygon.html), for repeated label position
+ * calculations across the FFI boundary
+ *
+ * Created by [`polylabel_prepare`](fn.polylabel_prepare.html) or [`polylabel_create`](fn.polylabel_create.html),
+ * used by [`polylabel_compute`](fn.polylabel_compute.html), and destroyed by [`polylabel_free`](fn.polylabel_free.html)
+ * or [`polylabel_destroy`](fn.polylabel_destroy.html).
+ */
+typedef struct PolylabelHandle PolylabelHandle;
+
+/**
+ * An opaque handle to a sequence of [`PositionResult`](struct.PositionResult.html)s, for callers which can't
+ * safely receive library-allocated pointers inside structs (e.g. C# using P/Invoke, which can wrap it in a
+ * `SafeHandle`)
+ *
+ * Created by [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html), read using
+ * [`polylabel_results_len`](fn.polylabel_results_len.html) and [`polylabel_results_get`](fn.polylabel_results_get.html),
+ * and destroyed by [`polylabel_results_free`](fn.polylabel_results_free.html).
+ */
+typedef struct PolylabelResults PolylabelResults;
+
 /**
  * FFI struct for returned optimum Polygon label position
  */
@@ -18,7 +116,7 @@ typedef struct Position {
 /**
  * Wrapper for a void pointer to a sequence of 2-element arrays representing points, and the sequence length. Used for FFI.
  *
- * Used for the outer Polygon shell. `data` is a `Vec<[c_double; 2]>`.
+ * Used for the outer Polygon shell. `data` is a `Vec<[c_double; 2]>`, and may only be null if `len` is 0.
  */
 typedef struct Array {
     const void *data;
@@ -28,13 +126,167 @@ typedef struct Array {
 /**
  * Wrapper for a void pointer to a sequence of [`Array`](struct.Array.html)s, and the sequence length. Used for FFI.
  *
- * Each sequence entry represents an inner Polygon ring.
+ * Each sequence entry represents an inner Polygon ring. `data` may be null if `len` is 0.
  */
 typedef struct WrapperArray {
     const struct Array *data;
     size_t len;
 } WrapperArray;
 
+/**
+ * FFI struct for a returned single-precision optimum Polygon label position
+ */
+typedef struct PositionF32 {
+    float x_pos;
+    float y_pos;
+} PositionF32;
+
+/**
+ * Single-precision equivalent of [`Array`](struct.Array.html). Used for FFI.
+ *
+ * `data` is a `Vec<[c_float; 2]>`, and may only be null if `len` is 0.
+ */
+typedef struct ArrayF32 {
+    const void *data;
+    size_t len;
+} ArrayF32;
+
+/**
+ * Single-precision equivalent of [`WrapperArray`](struct.WrapperArray.html). Used for FFI.
+ *
+ * Each sequence entry represents an inner Polygon ring. `data` may be null if `len` is 0.
+ */
+typedef struct WrapperArrayF32 {
+    const struct ArrayF32 *data;
+    size_t len;
+} WrapperArrayF32;
+
+/**
+ * FFI struct for a returned optimum Polygon label position, its distance from the Polygon's edges,
+ * and the outcome of the calculation
+ *
+ * If `status` is `Cancelled`, the other fields hold the best label position found before the calculation
+ * was cancelled. Otherwise, if it isn't `Ok`, they're `NaN`.
+ *
+ * Its layout is fixed: three `double`s, followed by the status as a 32-bit `int` and four bytes of padding, 32
+ * bytes in all. It can be mirrored directly (e.g. by a Dart `Struct` with three `@Double()` fields and an
+ * `@Int32()` field), and written to caller-allocated memory by functions such as
+ * [`polylabel_compute`](fn.polylabel_compute.html), so repeated calculations needn't allocate.
+ */
+typedef struct PositionResult {
+    double x;
+    double y;
+    double distance;
+    enum PolylabelStatus status;
+} PositionResult;
+
+#if defined(POLYLABEL_ARROW)
+/**
+ * An Arrow C Data Interface array, as defined by the
+ * [specification](https://arrow.apache.org/docs/format/CDataInterface.html#the-arrowarray-structure)
+ */
+typedef struct ArrowArray {
+    int64_t length;
+    int64_t null_count;
+    int64_t offset;
+    int64_t n_buffers;
+    int64_t n_children;
+    const void **buffers;
+    struct ArrowArray **children;
+    struct ArrowArray *dictionary;
+    void (*release)(struct ArrowArray *array);
+    void *private_data;
+} ArrowArray;
+#endif
+
+#if defined(POLYLABEL_ARROW)
+/**
+ * An Arrow C Data Interface schema, as defined by the
+ * [specification](https://arrow.apache.org/docs/format/CDataInterface.html#the-arrowschema-structure)
+ */
+typedef struct ArrowSchema {
+    const char *format;
+    const char *name;
+    const char *metadata;
+    int64_t flags;
+    int64_t n_children;
+    struct ArrowSchema **children;
+    struct ArrowSchema *dictionary;
+    void (*release)(struct ArrowSchema *schema);
+    void *private_data;
+} ArrowSchema;
+#endif
+
+/**
+ * FFI struct representing a Polygon: an exterior ring, and zero or more interior rings. Used for batch FFI.
+ */
+typedef struct PolygonFFI {
+    struct Array outer;
+    struct WrapperArray inners;
+} PolygonFFI;
+
+/**
+ * A sequence of [`PositionResult`](struct.PositionResult.html)s allocated by the library, and the sequence
+ * length. Used for FFI functions which return a variable number of label positions.
+ *
+ * Must be freed using [`polylabel_position_array_free`](fn.polylabel_position_array_free.html). `data` is
+ * null if `len` is 0.
+ */
+typedef struct PositionArray {
+    struct PositionResult *data;
+    size_t len;
+} PositionArray;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The crate's version, as a static, NUL-terminated string (e.g. `"4.0.0"`), which mustn't be freed
+ */
+const char *polylabel_version(void);
+
+/**
+ * The version of the library's FFI ABI
+ *
+ * If this differs from the [`POLYLABEL_ABI_VERSION`](constant.POLYLABEL_ABI_VERSION.html) in the header
+ * you compiled against, the library's structs or functions are incompatible with your code.
+ */
+uint32_t polylabel_abi_version(void);
+
+/**
+ * Set a callback which receives the library's log messages, or pass null to remove it
+ *
+ * Warnings are logged when the library silently adjusts its input: e.g. when a ring which isn't closed is
+ * closed, or when a tolerance which is finer than the coordinates' precision is raised. `user_data` is passed to
+ * the callback unchanged. The callback is shared by every thread, and may be called from any thread (including
+ * the batch functions' worker threads), so it (and `user_data`) must be thread-safe. It mustn't unwind.
+ *
+ * # Nullability
+ *
+ * `callback` and `user_data` are nullable.
+ */
+void polylabel_set_log_callback(struct Option_PolylabelLogCallback callback,
+                                void *user_data);
+
+/**
+ * A description of the most recent failure on the calling thread, as a NUL-terminated string
+ *
+ * Functions which return a [`PolylabelStatus`](enum.PolylabelStatus.html) other than `Ok` (or `Cancelled`),
+ * or a `NaN` label position, record a description of the failure, e.g. `"interior ring 2: null pointer with a
+ * length of 5"`. Successful calls don't clear it. Returns null if there hasn't been a failure on this thread since
+ * the last call to [`polylabel_clear_last_error`](fn.polylabel_clear_last_error.html).
+ *
+ * The string is owned by the library, and remains valid until the next failure on the same thread, or until
+ * the description is cleared.
+ */
+const char *polylabel_last_error_message(void);
+
+/**
+ * Clear the calling thread's most recent failure description
+ */
+void polylabel_clear_last_error(void);
+
 /**
  * FFI access to the [`polylabel`](fn.polylabel.html) function
  *
@@ -43,9 +295,776 @@ typedef struct WrapperArray {
  * - an exterior ring representing a Polygon shell or closed LineString
  * - zero or more interior rings representing Polygon holes
  * - a tolerance `c_double`.
+ *
  * If an error occurs while attempting to calculate the label position, the resulting point coordinates
- * will be `NaN, NaN`.
+ * will be `NaN, NaN`. Use [`polylabel_ext_ffi`](fn.polylabel_ext_ffi.html) to find out which error occurred.
  */
 struct Position polylabel_ffi(struct Array outer,
                               struct WrapperArray inners,
                               double tolerance);
+
+/**
+ * Single-precision FFI access to the [`polylabel`](fn.polylabel.html) function
+ *
+ * Accepts the same arguments as [`polylabel_ffi`](fn.polylabel_ffi.html), but its rings hold `c_float`
+ * coordinates, its tolerance is a `c_float`, and it returns a [`PositionF32`](struct.PositionF32.html).
+ * The label position is calculated in single precision, and the tolerance is raised if necessary to
+ * one which the coordinates are precise enough to achieve.
+ *
+ * If an error occurs while attempting to calculate the label position, the resulting point coordinates
+ * will be `NaN, NaN`.
+ */
+struct PositionF32 polylabel_f32_ffi(struct ArrayF32 outer,
+                                     struct WrapperArrayF32 inners,
+                                     float tolerance);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function
+ *
+ * Accepts the same arguments as [`polylabel_ffi`](fn.polylabel_ffi.html), but returns a
+ * [`PositionResult`](struct.PositionResult.html), which also holds the label position's distance from
+ * the Polygon's edges, and a status indicating whether the calculation succeeded.
+ *
+ * The status is `InvalidLength` if the exterior ring is empty, or a ring's length is too large to be
+ * addressed, and `NullPointer` if a ring with a nonzero length has a null `data` pointer.
+ */
+struct PositionResult polylabel_ext_ffi(struct Array outer,
+                                        struct WrapperArray inners,
+                                        double tolerance);
+
+/**
+ * FFI access to the [`PreparedPolygon::label_with_progress`](struct.PreparedPolygon.html#method.label_with_progress)
+ * method
+ *
+ * Accepts the same arguments as [`polylabel_ext_ffi`](fn.polylabel_ext_ffi.html), followed by:
+ *
+ * - a [`PolylabelProgress`](type.PolylabelProgress.html) callback, which is called periodically during the
+ *   search, and once when it finishes. May be null
+ * - a `user_data` pointer, which is passed to the callback unchanged. May be null.
+ *
+ * The search finishes once the callback's two distances are within the tolerance of each other, so they can be
+ * used to display its progress. Returns a [`PositionResult`](struct.PositionResult.html). The callback is called
+ * on the calling thread, and mustn't unwind.
+ *
+ * # Nullability
+ *
+ * `progress` and `user_data` are nullable.
+ */
+struct PositionResult polylabel_progress_ffi(struct Array outer,
+                                             struct WrapperArray inners,
+                                             double tolerance,
+                                             struct Option_PolylabelProgress progress,
+                                             void *user_data);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, with cancellation
+ *
+ * Accepts the same arguments as [`polylabel_ext_ffi`](fn.polylabel_ext_ffi.html), followed by a pointer to
+ * an `int` cancellation flag, which may be null. If another thread sets the flag to a nonzero value while the
+ * label position is being calculated, the calculation stops, and the returned
+ * [`PositionResult`](struct.PositionResult.html) holds the best label position found so far, with a
+ * `Cancelled` status. Its distance may be further than the tolerance from the ideal label position's.
+ *
+ * The flag should be written atomically (e.g. using C11 `atomic_store`) or through a `volatile` pointer.
+ *
+ * # Nullability
+ *
+ * `cancel` is nullable.
+ *
+ * # Safety
+ *
+ * `cancel` must be null, or point to an `int` which remains valid until the function returns.
+ */
+struct PositionResult polylabel_cancellable_ffi(struct Array outer,
+                                                struct WrapperArray inners,
+                                                double tolerance,
+                                                const int *cancel);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a Polygon
+ * held in a single interleaved coordinate buffer
+ *
+ * Accepts four arguments:
+ *
+ * - a pointer to a buffer of interleaved `x, y` `c_double` coordinates
+ * - a pointer to a sequence of `n_rings + 1` `size_t` ring offsets. Ring `i` consists of points
+ *   `ring_offsets[i]` up to (but not including) `ring_offsets[i + 1]`. The first ring is the exterior,
+ *   and the remainder are interior rings. The buffer must hold `ring_offsets[n_rings]` points
+ * - the number of rings, a `size_t`
+ * - a tolerance `c_double`.
+ *
+ * Returns a [`PositionResult`](struct.PositionResult.html). Its status is `InvalidLength` if there are no rings,
+ * or the offsets decrease, and `NullPointer` if either pointer is null.
+ *
+ * # Nullability
+ *
+ * `coords` and `ring_offsets` are non-null.
+ *
+ * # Safety
+ *
+ * `ring_offsets` must point to `n_rings + 1` `size_t`s, and `coords` must point to `2 * ring_offsets[n_rings]`
+ * `c_double`s.
+ */
+struct PositionResult polylabel_flat_ffi(const double *coords,
+                                         const size_t *ring_offsets,
+                                         size_t n_rings,
+                                         double tolerance);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a Polygon
+ * whose coordinates are held in separate `x` and `y` arrays
+ *
+ * Accepts six arguments:
+ *
+ * - a pointer to a sequence of `n` `x` `c_double` coordinates
+ * - a pointer to a sequence of `n` `y` `c_double` coordinates
+ * - the number of points, a `size_t`
+ * - a pointer to a sequence of `n_holes` `size_t` hole offsets: the index of each interior ring's first point.
+ *   The exterior ring consists of the points preceding the first hole. May be null if there are no holes
+ * - the number of holes, a `size_t`
+ * - a tolerance `c_double`.
+ *
+ * Returns a [`PositionResult`](struct.PositionResult.html). Its status is `InvalidLength` if there are no points,
+ * or the hole offsets decrease or exceed `n`, and `NullPointer` if a required pointer is null.
+ *
+ * # Nullability
+ *
+ * `xs` and `ys` are non-null. `hole_offsets` is nullable if `n_holes` is 0.
+ *
+ * # Safety
+ *
+ * `xs` and `ys` must point to `n` `c_double`s, and `hole_offsets` must point to `n_holes` `size_t`s.
+ */
+struct PositionResult polylabel_xy_ffi(const double *xs,
+                                       const double *ys,
+                                       size_t n,
+                                       const size_t *hole_offsets,
+                                       size_t n_holes,
+                                       double tolerance);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, using only
+ * primitive arrays
+ *
+ * Intended for callers which can't easily build structs, such as JNI code using arrays from
+ * `GetDoubleArrayElements` and `GetIntArrayElements`. Accepts six arguments:
+ *
+ * - a pointer to a buffer of interleaved `x, y` `c_double` coordinates
+ * - the number of `c_double`s in the buffer, a `c_int`
+ * - a pointer to a sequence of `c_int` ring offsets. As for [`polylabel_flat_ffi`](fn.polylabel_flat_ffi.html),
+ *   ring `i` consists of points `ring_offsets[i]` up to (but not including) `ring_offsets[i + 1]`, and the first
+ *   ring is the exterior
+ * - the number of ring offsets (one more than the number of rings), a `c_int`
+ * - a tolerance `c_double`
+ * - a pointer to three `c_double`s, which receive the label position's `x` and `y` coordinates, and its distance
+ *   from the Polygon's edges. They're `NaN` if the label position couldn't be calculated.
+ *
+ * Returns a [`PolylabelStatus`](enum.PolylabelStatus.html), which is a C `int`. It's `InvalidLength` if a length
+ * or offset is negative, the offsets decrease, or the buffer is too short, and `NullPointer` if a pointer is null.
+ *
+ * # Nullability
+ *
+ * `coords`, `ring_offsets`, and `out` are non-null.
+ *
+ * # Safety
+ *
+ * `coords` must point to `coords_len` `c_double`s, `ring_offsets` must point to `n_offsets` `c_int`s, and `out`
+ * must point to three writable `c_double`s.
+ */
+enum PolylabelStatus polylabel_primitive_ffi(const double *coords,
+                                             int coords_len,
+                                             const int *ring_offsets,
+                                             int n_offsets,
+                                             double tolerance,
+                                             double *out);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function for many Polygons in a
+ * single call, using only primitive arrays
+ *
+ * Accepts the same arguments as [`polylabel_primitive_ffi`](fn.polylabel_primitive_ffi.html), with the ring
+ * offsets of every Polygon in a single sequence, followed by:
+ *
+ * - a pointer to a sequence of `c_int` Polygon offsets: Polygon `i` consists of rings `polygon_offsets[i]` up to
+ *   (but not including) `polygon_offsets[i + 1]`
+ * - the number of Polygon offsets (one more than the number of Polygons), a `c_int`
+ * - a tolerance `c_double`
+ * - a pointer to three `c_double`s for each Polygon, which receive its label position's `x` and `y` coordinates,
+ *   and its distance, in order.
+ *
+ * Returns `Ok` if every label position was calculated, or the status of the first Polygon whose label position
+ * couldn't be. Polygons are labelled on the calling thread.
+ *
+ * # Nullability
+ *
+ * `coords`, `ring_offsets`, and `polygon_offsets` are non-null. `out` is nullable if
+ * there are no Polygons.
+ *
+ * # Safety
+ *
+ * `coords` must point to `coords_len` `c_double`s, `ring_offsets` must point to `n_ring_offsets` `c_int`s,
+ * `polygon_offsets` must point to `n_polygon_offsets` `c_int`s, and `out` must point to three writable
+ * `c_double`s for each Polygon.
+ */
+enum PolylabelStatus polylabel_primitive_batch_ffi(const double *coords,
+                                                   int coords_len,
+                                                   const int *ring_offsets,
+                                                   int n_ring_offsets,
+                                                   const int *polygon_offsets,
+                                                   int n_polygon_offsets,
+                                                   double tolerance,
+                                                   double *out);
+
+#if defined(POLYLABEL_GEOJSON)
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a
+ * GeoJSON Polygon or MultiPolygon geometry
+ *
+ * Accepts three arguments:
+ *
+ * - a pointer to a NUL-terminated, UTF-8 GeoJSON geometry object string
+ * - a tolerance `c_double`
+ * - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+ *
+ * The label position of a MultiPolygon is that of its part with the largest area.
+ *
+ * Returns the result's status, which is `InvalidInput` if the string can't be parsed as a Polygon or
+ * MultiPolygon geometry, and `NullPointer` if either pointer is null. Requires the `geojson` feature.
+ *
+ * # Nullability
+ *
+ * `geojson` and `out` are non-null.
+ *
+ * # Safety
+ *
+ * `geojson` must point to a NUL-terminated string, and `out` must point to a writable `PositionResult`.
+ */
+enum PolylabelStatus polylabel_geojson_ffi(const char *geojson,
+                                           double tolerance,
+                                           struct PositionResult *out);
+#endif
+
+#if defined(POLYLABEL_WKT)
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a
+ * WKT `POLYGON` or `MULTIPOLYGON`
+ *
+ * Accepts three arguments:
+ *
+ * - a pointer to a NUL-terminated, UTF-8 WKT string. `Z`, `M`, and `ZM` coordinates, and an EWKT `SRID=…;`
+ *   prefix, are accepted
+ * - a tolerance `c_double`
+ * - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+ *
+ * The label position of a MultiPolygon is that of its part with the largest area.
+ *
+ * Returns the result's status, which is `InvalidInput` if the string can't be parsed as a `POLYGON` or
+ * `MULTIPOLYGON`, or is `EMPTY`, and `NullPointer` if either pointer is null. Requires the `wkt` feature.
+ *
+ * # Nullability
+ *
+ * `wkt` and `out` are non-null.
+ *
+ * # Safety
+ *
+ * `wkt` must point to a NUL-terminated string, and `out` must point to a writable `PositionResult`.
+ */
+enum PolylabelStatus polylabel_wkt_ffi(const char *wkt,
+                                       double tolerance,
+                                       struct PositionResult *out);
+#endif
+
+#if defined(POLYLABEL_WKB)
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a
+ * WKB or EWKB Polygon or MultiPolygon
+ *
+ * Accepts four arguments:
+ *
+ * - a pointer to a WKB buffer. Either byte order, ISO and EWKB `Z` and `M` coordinates, and an EWKB SRID
+ *   are accepted
+ * - the length of the buffer in bytes, a `size_t`
+ * - a tolerance `c_double`
+ * - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+ *
+ * The label position of a MultiPolygon is that of its part with the largest area.
+ *
+ * Returns the result's status, which is `InvalidInput` if the buffer can't be parsed as a Polygon or
+ * MultiPolygon, or is empty, and `NullPointer` if either pointer is null. Requires the `wkb` feature.
+ *
+ * # Nullability
+ *
+ * `wkb` and `out` are non-null.
+ *
+ * # Safety
+ *
+ * `wkb` must point to `len` bytes, and `out` must point to a writable `PositionResult`.
+ */
+enum PolylabelStatus polylabel_wkb_ffi(const uint8_t *wkb,
+                                       size_t len,
+                                       double tolerance,
+                                       struct PositionResult *out);
+#endif
+
+#if defined(POLYLABEL_ARROW)
+/**
+ * Calculate label positions for a whole array of Polygons or MultiPolygons, using the
+ * [Arrow C Data Interface](https://arrow.apache.org/docs/format/CDataInterface.html)
+ *
+ * Accepts five arguments:
+ *
+ * - a pointer to an `ArrowArray`, and a pointer to its `ArrowSchema`. The array may be a WKB array (`binary` or
+ *   `large_binary`), or a GeoArrow `polygon` or `multipolygon` array with interleaved or separated coordinates.
+ *   They're borrowed, and aren't released
+ * - a tolerance `c_double`
+ * - a pointer to an `ArrowArray`, and a pointer to an `ArrowSchema`, which receive a GeoArrow `point` array with
+ *   interleaved coordinates, which the caller must release.
+ *
+ * The output holds each geometry's label position, in order. The label position of a MultiPolygon is that of its
+ * part with the largest area. Null geometries, and geometries whose label position couldn't be calculated, are
+ * null. Geometries are labelled on rayon's global thread pool.
+ *
+ * Returns `Ok` if every label position was calculated, or the status of the first geometry whose label position
+ * couldn't be. `InvalidInput` is returned if the array isn't supported, and `NullPointer` if any pointer is null,
+ * in which case nothing is written to the output. Requires the `arrow` feature.
+ *
+ * # Nullability
+ *
+ * `array`, `schema`, `out_array`, and `out_schema` are non-null.
+ *
+ * # Safety
+ *
+ * `array` and `schema` must follow the Arrow C Data Interface specification, and `out_array` and `out_schema`
+ * must point to writable structures, which the caller hasn't initialised or has already released.
+ */
+enum PolylabelStatus polylabel_arrow_ffi(const struct ArrowArray *array,
+                                         const struct ArrowSchema *schema,
+                                         double tolerance,
+                                         struct ArrowArray *out_array,
+                                         struct ArrowSchema *out_schema);
+#endif
+
+/**
+ * FFI access to the [`polylabel_multi`](fn.polylabel_multi.html) function
+ *
+ * Accepts five arguments:
+ *
+ * - a pointer to a sequence of [`PolygonFFI`](struct.PolygonFFI.html)s: the MultiPolygon's parts
+ * - the number of parts, a `size_t`
+ * - a tolerance `c_double`
+ * - a [`PolylabelParts`](enum.PolylabelParts.html) specifying which parts to calculate label positions for
+ * - a pointer to a caller-allocated sequence of [`PositionResult`](struct.PositionResult.html)s: one
+ *   if `parts` is `Largest`, or `len` if it's `All`.
+ *
+ * With `Largest`, the label position of the part with the largest area is written to `out`, and its status is
+ * `InvalidInput` if there are no parts. With `All`, each part's label position is written to the `out` entry with
+ * the same index.
+ *
+ * Returns `Ok` if every label position was calculated, or the status of the first that couldn't be.
+ * `NullPointer` is returned if `polygons` (when `len` isn't 0) or `out` is null.
+ *
+ * # Nullability
+ *
+ * `polygons` is nullable if `len` is 0. `out` is non-null.
+ *
+ * # Safety
+ *
+ * `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to enough writable `PositionResult`s.
+ */
+enum PolylabelStatus polylabel_multi_ffi(const struct PolygonFFI *polygons,
+                                         size_t len,
+                                         double tolerance,
+                                         enum PolylabelParts parts,
+                                         struct PositionResult *out);
+
+/**
+ * FFI access to the [`polylabel_multi`](fn.polylabel_multi.html) function, returning a library-allocated
+ * sequence of label positions
+ *
+ * Accepts the same arguments as [`polylabel_multi_ffi`](fn.polylabel_multi_ffi.html), but `out` points to a
+ * single [`PositionArray`](struct.PositionArray.html), which receives one label position if `parts` is
+ * `Largest`, or one for each part if it's `All`. It receives an empty `PositionArray` if the parts are invalid.
+ *
+ * Returns `Ok` if every label position was calculated, or the status of the first that couldn't be.
+ * `NullPointer` is returned if `polygons` (when `len` isn't 0) or `out` is null.
+ *
+ * # Nullability
+ *
+ * `polygons` is nullable if `len` is 0. `out` is non-null.
+ *
+ * # Safety
+ *
+ * `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to a writable `PositionArray`, whose
+ * contents must be freed using [`polylabel_position_array_free`](fn.polylabel_position_array_free.html).
+ */
+enum PolylabelStatus polylabel_multi_array_ffi(const struct PolygonFFI *polygons,
+                                               size_t len,
+                                               double tolerance,
+                                               enum PolylabelParts parts,
+                                               struct PositionArray *out);
+
+/**
+ * Free a [`PositionArray`](struct.PositionArray.html) allocated by the library
+ *
+ * # Safety
+ *
+ * `array` must have been returned by the library, and not already freed. An empty `PositionArray` (whose
+ * `data` is null) may be freed, and nothing happens.
+ */
+void polylabel_position_array_free(struct PositionArray array);
+
+/**
+ * FFI access to the [`polylabel_multi`](fn.polylabel_multi.html) function, returning an opaque handle to the
+ * label positions
+ *
+ * Accepts the same first four arguments as [`polylabel_multi_ffi`](fn.polylabel_multi_ffi.html), followed by a
+ * pointer to a [`PolylabelStatus`](enum.PolylabelStatus.html), which receives `Ok` if every label position was
+ * calculated, or the status of the first part whose label position couldn't be. May be null.
+ *
+ * Returns a pointer to a [`PolylabelResults`](struct.PolylabelResults.html), which must be freed using
+ * [`polylabel_results_free`](fn.polylabel_results_free.html), or null if the input is invalid.
+ *
+ * # Nullability
+ *
+ * `polygons` is nullable if `len` is 0. `status` is nullable.
+ *
+ * # Safety
+ *
+ * `polygons` must point to `len` valid [`PolygonFFI`](struct.PolygonFFI.html)s, and `status` must be null,
+ * or point to a writable `PolylabelStatus`.
+ */
+struct PolylabelResults *polylabel_multi_results_ffi(const struct PolygonFFI *polygons,
+                                                     size_t len,
+                                                     double tolerance,
+                                                     enum PolylabelParts parts,
+                                                     enum PolylabelStatus *status);
+
+/**
+ * The number of label positions held by a [`PolylabelResults`](struct.PolylabelResults.html), which is 0 if
+ * `results` is null
+ *
+ * # Nullability
+ *
+ * `results` is nullable.
+ *
+ * # Safety
+ *
+ * `results` must be null, or a live handle returned by
+ * [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html).
+ */
+size_t polylabel_results_len(const struct PolylabelResults *results);
+
+/**
+ * Copy a label position held by a [`PolylabelResults`](struct.PolylabelResults.html)
+ *
+ * Accepts three arguments:
+ *
+ * - a pointer to a `PolylabelResults`
+ * - the label position's index, a `size_t`
+ * - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the label position.
+ *
+ * Returns the label position's status, `InvalidLength` if `index` is out of bounds, or `NullPointer` if either
+ * pointer is null.
+ *
+ * # Nullability
+ *
+ * `results` and `out` are non-null.
+ *
+ * # Safety
+ *
+ * `results` must be a live handle returned by
+ * [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html), and `out` must point to a writable
+ * `PositionResult`.
+ */
+enum PolylabelStatus polylabel_results_get(const struct PolylabelResults *results,
+                                           size_t index,
+                                           struct PositionResult *out);
+
+/**
+ * Free a [`PolylabelResults`](struct.PolylabelResults.html). Passing null is a no-op.
+ *
+ * # Nullability
+ *
+ * `results` is nullable.
+ *
+ * # Safety
+ *
+ * `results` must be null, or a handle returned by
+ * [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html) which hasn't already been freed.
+ */
+void polylabel_results_free(struct PolylabelResults *results);
+
+/**
+ * Prepare a Polygon for repeated label position calculation
+ *
+ * Accepts three arguments:
+ *
+ * - an exterior ring representing a Polygon shell or closed LineString
+ * - zero or more interior rings representing Polygon holes
+ * - a pointer to a [`PolylabelStatus`](enum.PolylabelStatus.html), which receives the outcome. May be null.
+ *
+ * Returns a pointer to a [`PolylabelHandle`](struct.PolylabelHandle.html), which must be freed using
+ * [`polylabel_free`](fn.polylabel_free.html), or null if the Polygon couldn't be prepared.
+ *
+ * # Nullability
+ *
+ * `status` is nullable.
+ *
+ * # Safety
+ *
+ * `status` must be null, or point to a writable `PolylabelStatus`.
+ */
+struct PolylabelHandle *polylabel_prepare(struct Array outer,
+                                          struct WrapperArray inners,
+                                          enum PolylabelStatus *status);
+
+/**
+ * Prepare a Polygon held in a single interleaved coordinate buffer for repeated label position calculation
+ *
+ * Accepts four arguments:
+ *
+ * - a pointer to a buffer of interleaved `x, y` `c_double` coordinates
+ * - a pointer to a sequence of `n_rings + 1` `size_t` ring offsets, as for
+ *   [`polylabel_flat_ffi`](fn.polylabel_flat_ffi.html)
+ * - the number of rings, a `size_t`
+ * - a pointer to a [`PolylabelStatus`](enum.PolylabelStatus.html), which receives the outcome. May be null.
+ *
+ * Returns a pointer to a [`PolylabelHandle`](struct.PolylabelHandle.html), which must be freed using
+ * [`polylabel_destroy`](fn.polylabel_destroy.html) (or [`polylabel_free`](fn.polylabel_free.html)), or null if
+ * the Polygon couldn't be prepared. The buffers are copied, so they may be freed as soon as this returns.
+ *
+ * Together with `polylabel_destroy`, this suits garbage-collected callers such as Dart: fill a single native
+ * buffer, create a handle, and attach `polylabel_destroy` to the wrapping object using a `NativeFinalizer`.
+ *
+ * # Nullability
+ *
+ * `coords` and `ring_offsets` are non-null. `status` is nullable.
+ *
+ * # Safety
+ *
+ * `ring_offsets` must point to `n_rings + 1` `size_t`s, `coords` must point to `2 * ring_offsets[n_rings]`
+ * `c_double`s, and `status` must be null, or point to a writable `PolylabelStatus`.
+ */
+struct PolylabelHandle *polylabel_create(const double *coords,
+                                         const size_t *ring_offsets,
+                                         size_t n_rings,
+                                         enum PolylabelStatus *status);
+
+/**
+ * Calculate a prepared Polygon's label position
+ *
+ * Accepts three arguments:
+ *
+ * - a pointer to a [`PolylabelHandle`](struct.PolylabelHandle.html) returned by
+ *   [`polylabel_prepare`](fn.polylabel_prepare.html)
+ * - a tolerance `c_double`
+ * - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+ *
+ * Returns the result's status, which is `NullPointer` if either pointer is null.
+ *
+ * A handle may be used from several threads at once.
+ *
+ * # Nullability
+ *
+ * `handle` and `out` are non-null.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle returned by `polylabel_prepare`, and `out` must point to a writable
+ * `PositionResult`.
+ */
+enum PolylabelStatus polylabel_compute(const struct PolylabelHandle *handle,
+                                       double tolerance,
+                                       struct PositionResult *out);
+
+/**
+ * Free a [`PolylabelHandle`](struct.PolylabelHandle.html). Passing null is a no-op.
+ *
+ * # Nullability
+ *
+ * `handle` is nullable.
+ *
+ * # Safety
+ *
+ * `handle` must be null, or a handle returned by [`polylabel_prepare`](fn.polylabel_prepare.html)
+ * which hasn't already been freed.
+ */
+void polylabel_free(struct PolylabelHandle *handle);
+
+/**
+ * Free a [`PolylabelHandle`](struct.PolylabelHandle.html) created by
+ * [`polylabel_create`](fn.polylabel_create.html) or [`polylabel_prepare`](fn.polylabel_prepare.html).
+ * Passing null is a no-op.
+ *
+ * Equivalent to [`polylabel_free`](fn.polylabel_free.html), but takes a `void` pointer, so it matches the
+ * `void (*)(void *)` signature required of finalizers, such as Dart's `NativeFinalizer`.
+ *
+ * # Nullability
+ *
+ * `handle` is nullable.
+ *
+ * # Safety
+ *
+ * `handle` must be null, or a handle which hasn't already been freed.
+ */
+void polylabel_destroy(void *handle);
+
+/**
+ * FFI access to the [`polylabel`](fn.polylabel.html) function for many Polygons in a single call
+ *
+ * Accepts five arguments:
+ *
+ * - a pointer to a sequence of [`PolygonFFI`](struct.PolygonFFI.html)s
+ * - the length of the sequence, a `size_t`
+ * - a tolerance `c_double`
+ * - the number of threads to calculate label positions on, a `size_t`. 0 uses one thread per available core
+ * - a pointer to a caller-allocated sequence of `len` [`Position`](struct.Position.html)s.
+ *
+ * Each Polygon's label position is written to the `out` entry with the same index. Polygons are labelled
+ * by an internal thread pool, which is kept between calls, so repeated calls don't have to spawn new threads.
+ * If an error occurs while attempting to calculate a label position, that entry's point coordinates
+ * will be `NaN, NaN`.
+ *
+ * Returns `Ok` if every label position was calculated, or the [`PolylabelStatus`](enum.PolylabelStatus.html)
+ * of the first Polygon whose label position couldn't be. `NullPointer` is returned if `polygons` or `out` is null.
+ *
+ * # Nullability
+ *
+ * `polygons` is nullable if `len` is 0. `out` is non-null.
+ *
+ * # Safety
+ *
+ * `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to `len` writable `Position`s.
+ */
+enum PolylabelStatus polylabel_batch_ffi(const struct PolygonFFI *polygons,
+                                         size_t len,
+                                         double tolerance,
+                                         size_t threads,
+                                         struct Position *out);
+
+/**
+ * FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function for many Polygons in a
+ * single call, writing each result to a caller-allocated [`PositionResult`](struct.PositionResult.html)
+ *
+ * Accepts the same arguments as [`polylabel_batch_ffi`](fn.polylabel_batch_ffi.html), but `out` must point to a
+ * caller-allocated sequence of `len` `PositionResult`s, so each Polygon's status and distance are available.
+ * The library doesn't allocate any output storage, so `out` can be reused between calls.
+ *
+ * Returns `Ok` if every label position was calculated, or the [`PolylabelStatus`](enum.PolylabelStatus.html)
+ * of the first Polygon whose label position couldn't be. `NullPointer` is returned if `polygons` or `out` is null.
+ *
+ * # Nullability
+ *
+ * `polygons` is nullable if `len` is 0. `out` is non-null.
+ *
+ * # Safety
+ *
+ * `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to `len` writable `PositionResult`s.
+ */
+enum PolylabelStatus polylabel_batch_ext_ffi(const struct PolygonFFI *polygons,
+                                             size_t len,
+                                             double tolerance,
+                                             size_t threads,
+                                             struct PositionResult *out);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#ifdef __cplusplus
+#include <stdexcept>
+#include <utility>
+
+namespace polylabel {
+
+/**
+ * A scoped mirror of `PolylabelStatus`
+ */
+enum class Status : int {
+    Ok = PolylabelStatus_Ok,
+    CentroidCalculation = PolylabelStatus_CentroidCalculation,
+    RectCalculation = PolylabelStatus_RectCalculation,
+    NullPointer = PolylabelStatus_NullPointer,
+    InvalidLength = PolylabelStatus_InvalidLength,
+    InvalidInput = PolylabelStatus_InvalidInput,
+    Panic = PolylabelStatus_Panic,
+    Cancelled = PolylabelStatus_Cancelled,
+};
+
+static_assert(sizeof(PolylabelStatus) == sizeof(int), "PolylabelStatus must be an int");
+
+/**
+ * Thrown when a label position can't be calculated, holding its status and the library's error message
+ */
+class Error : public std::runtime_error {
+public:
+    explicit Error(PolylabelStatus status)
+        : std::runtime_error(polylabel_last_error_message() ? polylabel_last_error_message()
+                                                            : "polylabel error"),
+          status_(static_cast<Status>(status)) {}
+
+    Status status() const noexcept { return status_; }
+
+private:
+    Status status_;
+};
+
+/**
+ * Owns a `PolylabelHandle`, which is freed when it goes out of scope
+ */
+class Prepared {
+public:
+    /**
+     * Prepare a Polygon held in an interleaved coordinate buffer. See `polylabel_create`.
+     */
+    Prepared(const double *coords, const size_t *ring_offsets, size_t n_rings) {
+        PolylabelStatus status = PolylabelStatus_Ok;
+        handle_ = polylabel_create(coords, ring_offsets, n_rings, &status);
+        if (!handle_) {
+            throw Error(status);
+        }
+    }
+
+    /**
+     * Prepare a Polygon given as an `Array` and a `WrapperArray`. See `polylabel_prepare`.
+     */
+    Prepared(struct Array outer, struct WrapperArray inners) {
+        PolylabelStatus status = PolylabelStatus_Ok;
+        handle_ = polylabel_prepare(outer, inners, &status);
+        if (!handle_) {
+            throw Error(status);
+        }
+    }
+
+    Prepared(const Prepared &) = delete;
+    Prepared &operator=(const Prepared &) = delete;
+    Prepared(Prepared &&other) noexcept : handle_(other.handle_) { other.handle_ = nullptr; }
+    Prepared &operator=(Prepared &&other) noexcept {
+        std::swap(handle_, other.handle_);
+        return *this;
+    }
+    ~Prepared() { polylabel_free(handle_); }
+
+    /**
+     * Calculate the label position. See `polylabel_compute`.
+     */
+    PositionResult compute(double tolerance) const {
+        PositionResult out;
+        PolylabelStatus status = polylabel_compute(handle_, tolerance, &out);
+        if (status != PolylabelStatus_Ok) {
+            throw Error(status);
+        }
+        return out;
+    }
+
+    /**
+     * The owned handle, which remains owned by this object
+     */
+    const PolylabelHandle *get() const noexcept { return handle_; }
+
+private:
+    PolylabelHandle *handle_;
+};
+
+}  // namespace polylabel
+#endif  // __cplusplus
@@ -0,0 +1,88 @@
+//! Longitude and latitude Polygons which cross the antimeridian
+//!
+//! A Polygon crossing ±180° has edges spanning nearly 360° of longitude, so its label position is searched for
+//! on the wrong side of the world. Shifting its western longitudes east by 360° makes it contiguous, and the label
+//! position is shifted back afterwards.
+
+use geo::{BoundingRect, Coord, GeoFloat, MapCoords, MultiPolygon, Point, Polygon};
+
+fn half_turn<T: GeoFloat>() -> T {
+    T::from(180.0).unwrap()
+}
+
+/// Whether any of a Polygon's edges spans more than 180° of longitude, which it would only do if it crossed the
+/// antimeridian
+pub(crate) fn crosses<T: GeoFloat>(polygon: &Polygon<T>) -> bool {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .flat_map(|ring| ring.lines())
+        .any(|line| line.dx().abs() > half_turn())
+}
+
+/// Whether a MultiPolygon has parts meeting the antimeridian from both sides, as features split there do
+pub(crate) fn straddles<T: GeoFloat>(multi: &MultiPolygon<T>) -> bool {
+    let bboxes: Vec<_> = multi
+        .iter()
+        .filter_map(|poly| poly.bounding_rect())
+        .collect();
+    bboxes.iter().any(|bbox| bbox.max().x >= half_turn())
+        && bboxes.iter().any(|bbox| bbox.min().x <= -half_turn::<T>())
+}
+
+/// Shift a geometry's western longitudes east by 360°
+pub(crate) fn shift<T, G>(geometry: &G) -> G
+where
+    T: GeoFloat,
+    G: MapCoords<T, T, Output = G>,
+{
+    geometry.map_coords(|c| Coord {
+        x: if c.x < T::zero() {
+            c.x + half_turn() + half_turn()
+        } else {
+            c.x
+        },
+        y: c.y,
+    })
+}
+
+/// Shift a shifted label position back into the range ±180°
+pub(crate) fn unshift<T: GeoFloat>(point: Point<T>) -> Point<T> {
+    if point.x() > half_turn() {
+        Point::new(point.x() - half_turn() - half_turn(), point.y())
+    } else {
+        point
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crosses, shift, straddles, unshift};
+    use geo::{MultiPolygon, Point, Polygon, Rect};
+
+    #[test]
+    fn test_antimeridian() {
+        let fiji = Polygon::new(
+            vec![
+                (177.0, -18.0),
+                (-179.0, -18.0),
+                (-179.0, -16.0),
+                (177.0, -16.0),
+            ]
+            .into(),
+            vec![],
+        );
+        assert!(crosses(&fiji));
+        let shifted = shift(&fiji);
+        assert!(!crosses(&shifted));
+        assert_eq!(shifted.exterior()[1].x, 181.0);
+        assert_eq!(unshift(Point::new(181.0, -17.0)), Point::new(-179.0, -17.0));
+        assert_eq!(unshift(Point::new(179.0, -17.0)), Point::new(179.0, -17.0));
+        assert!(!crosses(&Rect::new((-10.0, 0.0), (10.0, 1.0)).to_polygon()));
+        let split = MultiPolygon::new(vec![
+            Rect::new((177.0, -18.0), (180.0, -16.0)).to_polygon(),
+            Rect::new((-180.0, -18.0), (-179.0, -16.0)).to_polygon(),
+        ]);
+        assert!(straddles(&split));
+        assert!(!straddles(&MultiPolygon::new(vec![split.0[0].clone()])));
+    }
+}
@@ -0,0 +1,620 @@
+//! Reading Polygon and MultiPolygon arrays, and writing Point arrays, using the
+//! [Arrow C Data Interface](https://arrow.apache.org/docs/format/CDataInterface.html)
+
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+use libc::{c_char, c_void};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::ops::Range;
+use std::ptr;
+use std::slice;
+
+// Set on an exported field whose values may be null
+const ARROW_FLAG_NULLABLE: i64 = 2;
+
+/// An Arrow C Data Interface array, as defined by the
+/// [specification](https://arrow.apache.org/docs/format/CDataInterface.html#the-arrowarray-structure)
+#[repr(C)]
+pub struct ArrowArray {
+    pub length: i64,
+    pub null_count: i64,
+    pub offset: i64,
+    pub n_buffers: i64,
+    pub n_children: i64,
+    pub buffers: *mut *const c_void,
+    pub children: *mut *mut ArrowArray,
+    pub dictionary: *mut ArrowArray,
+    pub release: Option<unsafe extern "C" fn(array: *mut ArrowArray)>,
+    pub private_data: *mut c_void,
+}
+
+/// An Arrow C Data Interface schema, as defined by the
+/// [specification](https://arrow.apache.org/docs/format/CDataInterface.html#the-arrowschema-structure)
+#[repr(C)]
+pub struct ArrowSchema {
+    pub format: *const c_char,
+    pub name: *const c_char,
+    pub metadata: *const c_char,
+    pub flags: i64,
+    pub n_children: i64,
+    pub children: *mut *mut ArrowSchema,
+    pub dictionary: *mut ArrowSchema,
+    pub release: Option<unsafe extern "C" fn(schema: *mut ArrowSchema)>,
+    pub private_data: *mut c_void,
+}
+
+/// A borrowed array of Polygon or MultiPolygon geometries
+pub(crate) struct Geometries<'a> {
+    len: usize,
+    validity: Option<Bitmap<'a>>,
+    encoding: Encoding<'a>,
+}
+
+/// How an array's geometries are stored
+enum Encoding<'a> {
+    /// A binary array of WKB values
+    Wkb {
+        offsets: Offsets<'a>,
+        data: &'a [u8],
+    },
+    /// A GeoArrow array: nested lists of rings (and parts, for MultiPolygons) ending in coordinates
+    Native {
+        lists: Vec<Offsets<'a>>,
+        coords: Coords<'a>,
+    },
+}
+
+/// A validity bitmap, and the index of an array's first value within it
+struct Bitmap<'a> {
+    bits: &'a [u8],
+    offset: usize,
+}
+
+/// A list or binary array's offsets, starting at its first value
+enum Offsets<'a> {
+    Small(&'a [i32]),
+    Large(&'a [i64]),
+}
+
+/// A GeoArrow coordinate array's values, and the index of its first coordinate
+enum Coords<'a> {
+    /// A fixed-size list of `dimensions` ordinates per coordinate, of which `x` and `y` are the first two
+    Interleaved {
+        values: &'a [f64],
+        dimensions: usize,
+        offset: usize,
+    },
+    /// A struct of ordinate arrays, of which `x` and `y` are the first two
+    Separated {
+        x: &'a [f64],
+        y: &'a [f64],
+        offset: usize,
+    },
+}
+
+impl Bitmap<'_> {
+    fn is_valid(&self, i: usize) -> bool {
+        let i = self.offset + i;
+        self.bits[i / 8] & (1 << (i % 8)) != 0
+    }
+}
+
+impl Offsets<'_> {
+    // the range of child values of value `i`, if its offsets are valid
+    fn range(&self, i: usize) -> Option<Range<usize>> {
+        let (start, end) = match self {
+            Offsets::Small(offsets) => (
+                usize::try_from(*offsets.get(i)?).ok()?,
+                usize::try_from(*offsets.get(i + 1)?).ok()?,
+            ),
+            Offsets::Large(offsets) => (
+                usize::try_from(*offsets.get(i)?).ok()?,
+                usize::try_from(*offsets.get(i + 1)?).ok()?,
+            ),
+        };
+        (start <= end).then_some(start..end)
+    }
+}
+
+impl Coords<'_> {
+    fn get(&self, i: usize) -> Option<Coord<f64>> {
+        match *self {
+            Coords::Interleaved {
+                values,
+                dimensions,
+                offset,
+            } => {
+                let start = (offset + i).checked_mul(dimensions)?;
+                Some(Coord {
+                    x: *values.get(start)?,
+                    y: *values.get(start + 1)?,
+                })
+            }
+            Coords::Separated { x, y, offset } => Some(Coord {
+                x: *x.get(offset + i)?,
+                y: *y.get(offset + i)?,
+            }),
+        }
+    }
+}
+
+impl Geometries<'_> {
+    /// The number of geometries in the array
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Geometry `i`, as a MultiPolygon. A Polygon becomes a single-part MultiPolygon.
+    ///
+    /// Returns `None` if the geometry is null, or an error if it isn't a valid Polygon or MultiPolygon.
+    pub(crate) fn get(&self, i: usize) -> Option<Result<MultiPolygon<f64>, &'static str>> {
+        if !self
+            .validity
+            .as_ref()
+            .map_or(true, |bitmap| bitmap.is_valid(i))
+        {
+            return None;
+        }
+        Some(match &self.encoding {
+            Encoding::Wkb { offsets, data } => offsets
+                .range(i)
+                .and_then(|range| data.get(range))
+                .and_then(crate::wkb::parse)
+                .ok_or("the WKB value isn't a valid Polygon or MultiPolygon"),
+            Encoding::Native { lists, coords } => match lists.as_slice() {
+                [rings, points] => polygon(rings, points, coords, i).map(|poly| vec![poly]),
+                [parts, rings, points] => parts.range(i).and_then(|parts| {
+                    parts
+                        .map(|part| polygon(rings, points, coords, part))
+                        .collect()
+                }),
+                _ => None,
+            }
+            .map(MultiPolygon::new)
+            .ok_or("the geometry's offsets are out of bounds"),
+        })
+    }
+}
+
+// build Polygon `i` of a GeoArrow array
+fn polygon(rings: &Offsets, points: &Offsets, coords: &Coords, i: usize) -> Option<Polygon<f64>> {
+    let mut rings = rings.range(i)?.map(|ring| {
+        points
+            .range(ring)?
+            .map(|point| coords.get(point))
+            .collect::<Option<LineString<_>>>()
+    });
+    let exterior = rings
+        .next()
+        .unwrap_or_else(|| Some(LineString::new(vec![])))?;
+    Some(Polygon::new(exterior, rings.collect::<Option<_>>()?))
+}
+
+/// Borrow a WKB or GeoArrow Polygon or MultiPolygon array
+///
+/// The array is validated against its schema, and its buffers are borrowed, but its values aren't checked
+/// until they're read.
+///
+/// # Safety
+///
+/// The array and schema must follow the Arrow C Data Interface specification, and mustn't be released while
+/// the returned geometries are in use.
+pub(crate) unsafe fn import<'a>(
+    array: &'a ArrowArray,
+    schema: &'a ArrowSchema,
+) -> Result<Geometries<'a>, String> {
+    if array.release.is_none() {
+        return Err("the array has been released".to_owned());
+    }
+    let (offset, len) = bounds(array)?;
+    // the validity bitmap may be omitted if there are no nulls
+    let validity = if array.null_count == 0 || array.n_buffers < 1 || (*array.buffers).is_null() {
+        None
+    } else {
+        Some(Bitmap {
+            bits: buffer(array, 0, (offset + len + 7) / 8)?,
+            offset,
+        })
+    };
+    let encoding = match format(schema)? {
+        "z" | "Z" => {
+            let offsets = offsets(array, schema)?;
+            let end = offsets
+                .range(len.saturating_sub(1))
+                .map_or(0, |range| range.end);
+            Encoding::Wkb {
+                offsets,
+                data: buffer(array, 2, end)?,
+            }
+        }
+        "+l" | "+L" => {
+            let mut lists = vec![];
+            let (mut array, mut schema) = (array, schema);
+            while let "+l" | "+L" = format(schema)? {
+                lists.push(offsets(array, schema)?);
+                (array, schema) = child(array, schema, 0)?;
+            }
+            if !(2..=3).contains(&lists.len()) {
+                return Err("the array isn't a Polygon or MultiPolygon array".to_owned());
+            }
+            Encoding::Native {
+                lists,
+                coords: coords(array, schema)?,
+            }
+        }
+        format => return Err(format!("the array's format ({:?}) isn't supported", format)),
+    };
+    Ok(Geometries {
+        len,
+        validity,
+        encoding,
+    })
+}
+
+// an array's offset and length
+fn bounds(array: &ArrowArray) -> Result<(usize, usize), String> {
+    match (usize::try_from(array.offset), usize::try_from(array.length)) {
+        (Ok(offset), Ok(len)) => Ok((offset, len)),
+        _ => Err("an array has a negative offset or length".to_owned()),
+    }
+}
+
+unsafe fn format(schema: &ArrowSchema) -> Result<&str, String> {
+    if schema.format.is_null() {
+        return Err("a schema has a null format".to_owned());
+    }
+    if !schema.dictionary.is_null() {
+        return Err("dictionary-encoded arrays aren't supported".to_owned());
+    }
+    CStr::from_ptr(schema.format)
+        .to_str()
+        .map_err(|_| "a schema's format isn't valid UTF-8".to_owned())
+}
+
+// borrow buffer `i` of an array as `len` values
+unsafe fn buffer<T>(array: &ArrowArray, i: usize, len: usize) -> Result<&[T], String> {
+    if usize::try_from(array.n_buffers).map_or(true, |n_buffers| n_buffers <= i) {
+        return Err(format!("an array is missing buffer {}", i));
+    }
+    let data = *array.buffers.add(i) as *const T;
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() || data.align_offset(mem::align_of::<T>()) != 0 {
+        return Err(format!("buffer {} of an array is null or misaligned", i));
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+// borrow a list or binary array's offsets, starting at its first value
+unsafe fn offsets<'a>(array: &'a ArrowArray, schema: &ArrowSchema) -> Result<Offsets<'a>, String> {
+    let (offset, len) = bounds(array)?;
+    // an empty array's offsets may be omitted
+    let count = if len == 0 { 0 } else { offset + len + 1 };
+    Ok(match format(schema)? {
+        "z" | "+l" => Offsets::Small(buffer(array, 1, count)?.get(offset..).unwrap_or(&[])),
+        _ => Offsets::Large(buffer(array, 1, count)?.get(offset..).unwrap_or(&[])),
+    })
+}
+
+// borrow child `i` of an array, and its schema
+unsafe fn child<'a>(
+    array: &'a ArrowArray,
+    schema: &'a ArrowSchema,
+    i: usize,
+) -> Result<(&'a ArrowArray, &'a ArrowSchema), String> {
+    let missing =
+        |n_children| usize::try_from(n_children).map_or(true, |n_children| n_children <= i);
+    if missing(array.n_children) || missing(schema.n_children) {
+        return Err(format!("an array is missing child {}", i));
+    }
+    match (
+        (*array.children.add(i)).as_ref(),
+        (*schema.children.add(i)).as_ref(),
+    ) {
+        (Some(array), Some(schema)) => Ok((array, schema)),
+        _ => Err(format!("child {} of an array is null", i)),
+    }
+}
+
+// borrow a float64 array's values, starting at its first value
+unsafe fn values<'a>(array: &'a ArrowArray, schema: &ArrowSchema) -> Result<&'a [f64], String> {
+    if format(schema)? != "g" {
+        return Err("the array's coordinates aren't float64 values".to_owned());
+    }
+    let (offset, len) = bounds(array)?;
+    Ok(&buffer(array, 1, offset + len)?[offset..])
+}
+
+// borrow a GeoArrow coordinate array
+unsafe fn coords<'a>(array: &'a ArrowArray, schema: &'a ArrowSchema) -> Result<Coords<'a>, String> {
+    let (offset, _) = bounds(array)?;
+    let format = format(schema)?;
+    if let Some(dimensions) = format.strip_prefix("+w:") {
+        let dimensions = dimensions
+            .parse()
+            .ok()
+            .filter(|&dimensions| dimensions >= 2)
+            .ok_or("the array's coordinates have fewer than two dimensions")?;
+        let (values_array, values_schema) = child(array, schema, 0)?;
+        Ok(Coords::Interleaved {
+            values: values(values_array, values_schema)?,
+            dimensions,
+            offset,
+        })
+    } else if format == "+s" {
+        let (x_array, x_schema) = child(array, schema, 0)?;
+        let (y_array, y_schema) = child(array, schema, 1)?;
+        Ok(Coords::Separated {
+            x: values(x_array, x_schema)?,
+            y: values(y_array, y_schema)?,
+            offset,
+        })
+    } else {
+        Err(format!(
+            "the array's coordinate format ({:?}) isn't supported",
+            format
+        ))
+    }
+}
+
+// The storage behind an exported array: its buffers, and its children, which own their own storage
+struct ExportedArray {
+    validity: Option<Vec<u8>>,
+    values: Option<Vec<f64>>,
+    buffers: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+}
+
+impl ExportedArray {
+    fn into_array(self, length: usize, null_count: usize) -> ArrowArray {
+        let mut private = Box::new(self);
+        let validity = private
+            .validity
+            .as_ref()
+            .filter(|_| null_count > 0)
+            .map_or(ptr::null(), |bits| bits.as_ptr() as *const c_void);
+        let values = private
+            .values
+            .as_ref()
+            .map(|values| values.as_ptr() as *const c_void);
+        private.buffers = Some(validity).into_iter().chain(values).collect();
+        ArrowArray {
+            length: length as i64,
+            null_count: null_count as i64,
+            offset: 0,
+            n_buffers: private.buffers.len() as i64,
+            n_children: private.children.len() as i64,
+            buffers: private.buffers.as_mut_ptr(),
+            children: private.children.as_mut_ptr(),
+            dictionary: ptr::null_mut(),
+            release: Some(release_array),
+            private_data: Box::into_raw(private) as *mut c_void,
+        }
+    }
+}
+
+unsafe extern "C" fn release_array(array: *mut ArrowArray) {
+    let private = Box::from_raw((*array).private_data as *mut ExportedArray);
+    for &child in &private.children {
+        let mut child = Box::from_raw(child);
+        // a child which has been moved out by the consumer has already been marked as released
+        if let Some(release) = child.release {
+            release(&mut *child);
+        }
+    }
+    (*array).release = None;
+}
+
+// The storage behind an exported schema's strings, and its children, which own their own storage
+struct ExportedSchema {
+    format: CString,
+    name: CString,
+    metadata: Option<Vec<u8>>,
+    children: Vec<*mut ArrowSchema>,
+}
+
+impl ExportedSchema {
+    fn into_schema(self) -> ArrowSchema {
+        let mut private = Box::new(self);
+        ArrowSchema {
+            format: private.format.as_ptr(),
+            name: private.name.as_ptr(),
+            metadata: private
+                .metadata
+                .as_ref()
+                .map_or(ptr::null(), |metadata| metadata.as_ptr() as *const c_char),
+            flags: ARROW_FLAG_NULLABLE,
+            n_children: private.children.len() as i64,
+            children: private.children.as_mut_ptr(),
+            dictionary: ptr::null_mut(),
+            release: Some(release_schema),
+            private_data: Box::into_raw(private) as *mut c_void,
+        }
+    }
+}
+
+unsafe extern "C" fn release_schema(schema: *mut ArrowSchema) {
+    let private = Box::from_raw((*schema).private_data as *mut ExportedSchema);
+    for &child in &private.children {
+        let mut child = Box::from_raw(child);
+        if let Some(release) = child.release {
+            release(&mut *child);
+        }
+    }
+    (*schema).release = None;
+}
+
+// Encode key-value metadata in the C Data Interface's binary format
+fn metadata(pairs: &[(&str, &str)]) -> Vec<u8> {
+    let mut encoded = (pairs.len() as i32).to_ne_bytes().to_vec();
+    for (key, value) in pairs {
+        for field in [key, value] {
+            encoded.extend((field.len() as i32).to_ne_bytes());
+            encoded.extend(field.as_bytes());
+        }
+    }
+    encoded
+}
+
+/// Export points as a GeoArrow `point` array with interleaved coordinates, in which `None` is null
+pub(crate) fn export(points: &[Option<Coord<f64>>]) -> (ArrowArray, ArrowSchema) {
+    let mut validity = vec![0; (points.len() + 7) / 8];
+    let mut values = Vec::with_capacity(points.len() * 2);
+    let mut null_count = 0;
+    for (i, point) in points.iter().enumerate() {
+        match point {
+            Some(point) => {
+                validity[i / 8] |= 1 << (i % 8);
+                values.extend([point.x, point.y]);
+            }
+            None => {
+                null_count += 1;
+                values.extend([f64::NAN, f64::NAN]);
+            }
+        }
+    }
+    let xy = ExportedArray {
+        validity: None,
+        values: Some(values),
+        buffers: vec![],
+        children: vec![],
+    }
+    .into_array(points.len() * 2, 0);
+    let array = ExportedArray {
+        validity: Some(validity),
+        values: None,
+        buffers: vec![],
+        children: vec![Box::into_raw(Box::new(xy))],
+    }
+    .into_array(points.len(), null_count);
+
+    let xy = ExportedSchema {
+        format: CString::new("g").unwrap(),
+        name: CString::new("xy").unwrap(),
+        metadata: None,
+        children: vec![],
+    }
+    .into_schema();
+    let schema = ExportedSchema {
+        format: CString::new("+w:2").unwrap(),
+        name: CString::new("").unwrap(),
+        metadata: Some(metadata(&[("ARROW:extension:name", "geoarrow.point")])),
+        children: vec![Box::into_raw(Box::new(xy))],
+    }
+    .into_schema();
+    (array, schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, import, ArrowArray, ArrowSchema};
+    use geo::Coord;
+    use libc::c_void;
+    use std::ffi::CStr;
+    use std::ptr;
+
+    // a borrowed array with the given buffers and children, which is never released
+    fn array(
+        length: usize,
+        buffers: &mut [*const c_void],
+        children: &mut [*mut ArrowArray],
+    ) -> ArrowArray {
+        unsafe extern "C" fn release(array: *mut ArrowArray) {
+            (*array).release = None;
+        }
+        ArrowArray {
+            length: length as i64,
+            null_count: 0,
+            offset: 0,
+            n_buffers: buffers.len() as i64,
+            n_children: children.len() as i64,
+            buffers: buffers.as_mut_ptr(),
+            children: children.as_mut_ptr(),
+            dictionary: ptr::null_mut(),
+            release: Some(release),
+            private_data: ptr::null_mut(),
+        }
+    }
+
+    fn schema(format: &CStr, children: &mut [*mut ArrowSchema]) -> ArrowSchema {
+        ArrowSchema {
+            format: format.as_ptr(),
+            name: ptr::null(),
+            metadata: ptr::null(),
+            flags: 0,
+            n_children: children.len() as i64,
+            children: children.as_mut_ptr(),
+            dictionary: ptr::null_mut(),
+            release: None,
+            private_data: ptr::null_mut(),
+        }
+    }
+
+    #[test]
+    fn test_import_native() {
+        // a MultiPolygon of two squares with separated coordinates, in an array sliced to skip a leading value
+        let x = [9.0, 0.0, 1.0, 1.0, 0.0, 0.0, 5.0, 6.0, 6.0, 5.0, 5.0];
+        let y = [9.0, 0.0, 0.0, 1.0, 1.0, 0.0, 5.0, 5.0, 6.0, 6.0, 5.0];
+        let points = [0, 5, 10];
+        let rings = [0, 1, 2];
+        let parts = [0i64, 0, 2];
+        let mut x_buffers = [ptr::null(), x.as_ptr().cast()];
+        let mut y_buffers = [ptr::null(), y.as_ptr().cast()];
+        let mut x = array(x.len(), &mut x_buffers, &mut []);
+        let mut y = array(y.len(), &mut y_buffers, &mut []);
+        let mut coords_children = [&mut x as *mut _, &mut y];
+        let mut coords_buffers = [ptr::null()];
+        let mut coords = array(10, &mut coords_buffers, &mut coords_children);
+        coords.offset = 1;
+        let mut points_buffers = [ptr::null(), points.as_ptr().cast()];
+        let mut points_children = [&mut coords as *mut _];
+        let mut points = array(2, &mut points_buffers, &mut points_children);
+        let mut rings_buffers = [ptr::null(), rings.as_ptr().cast()];
+        let mut rings_children = [&mut points as *mut _];
+        let mut rings = array(2, &mut rings_buffers, &mut rings_children);
+        let mut parts_buffers = [ptr::null(), parts.as_ptr().cast()];
+        let mut parts_children = [&mut rings as *mut _];
+        let mut multi = array(1, &mut parts_buffers, &mut parts_children);
+        multi.offset = 1;
+
+        let format = |format: &'static [u8]| CStr::from_bytes_with_nul(format).unwrap();
+        let mut x_schema = schema(format(b"g\0"), &mut []);
+        let mut y_schema = schema(format(b"g\0"), &mut []);
+        let mut coords_children = [&mut x_schema as *mut _, &mut y_schema];
+        let mut coords_schema = schema(format(b"+s\0"), &mut coords_children);
+        let mut points_children = [&mut coords_schema as *mut _];
+        let mut points_schema = schema(format(b"+l\0"), &mut points_children);
+        let mut rings_children = [&mut points_schema as *mut _];
+        let mut rings_schema = schema(format(b"+l\0"), &mut rings_children);
+        let mut multi_children = [&mut rings_schema as *mut _];
+        let multi_schema = schema(format(b"+L\0"), &mut multi_children);
+
+        let geometries = unsafe { import(&multi, &multi_schema) }.unwrap();
+        assert_eq!(geometries.len(), 1);
+        let geometry = geometries.get(0).unwrap().unwrap();
+        assert_eq!(geometry.0.len(), 2);
+        assert_eq!(geometry.0[1].exterior().0[2], Coord { x: 6.0, y: 6.0 });
+
+        // a list of coordinates isn't a Polygon array
+        assert!(unsafe { import(&points, &points_schema) }.is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let (mut array, mut schema) = export(&[Some(Coord { x: 1.0, y: 2.0 }), None]);
+        assert_eq!((array.length, array.null_count), (2, 1));
+        unsafe {
+            assert_eq!(CStr::from_ptr(schema.format).to_bytes(), b"+w:2");
+            let xy = &**array.children;
+            let values = *xy.buffers.add(1) as *const f64;
+            assert_eq!((*values, *values.add(1)), (1.0, 2.0));
+            let validity = *array.buffers as *const u8;
+            assert_eq!(*validity, 1);
+            array.release.unwrap()(&mut array);
+            schema.release.unwrap()(&mut schema);
+        }
+        assert!(array.release.is_none());
+        assert!(schema.release.is_none());
+    }
+}
@@ -0,0 +1,127 @@
+//! A lane-chunked point-to-segment distance kernel
+//!
+//! Segments are stored column-wise and padded to a multiple of [`LANES`], so the inner loop
+//! operates on fixed-size chunks, measuring four segments at a time. The kernel doesn't use explicit
+//! SIMD types: whether it's vectorised depends on the compiler's autovectorisation, and the target.
+//! Enable the `autovec` feature to use this kernel for rings with few segments.
+
+use geo::{GeoFloat, LineString, Point};
+
+/// The number of segments measured per iteration
+pub(crate) const LANES: usize = 4;
+
+/// Rings with at most this many segments are scanned using the kernel, rather than the R-tree
+pub(crate) const MAX_SEGMENTS: usize = 128;
+
+/// A ring's segments, as start coordinates, deltas, and reciprocal squared lengths
+pub(crate) struct Segments<T>
+where
+    T: GeoFloat,
+{
+    x: Vec<T>,
+    y: Vec<T>,
+    dx: Vec<T>,
+    dy: Vec<T>,
+    // zero for zero-length segments, so they're measured as points
+    inv_length_2: Vec<T>,
+}
+
+impl<T> Segments<T>
+where
+    T: GeoFloat,
+{
+    /// Store a ring's segments, or `None` if the ring is empty or too long to benefit
+    pub(crate) fn new(ring: &LineString<T>) -> Option<Self> {
+        let len = ring.lines().len();
+        if len == 0 || len > MAX_SEGMENTS {
+            return None;
+        }
+        // Pad with repeats of the last segment, which can't change the minimum
+        let padded = (len + LANES - 1) / LANES * LANES;
+        let last = ring.lines().last()?;
+        let lines = ring.lines().chain(std::iter::repeat(last)).take(padded);
+        let mut segments = Self {
+            x: Vec::with_capacity(padded),
+            y: Vec::with_capacity(padded),
+            dx: Vec::with_capacity(padded),
+            dy: Vec::with_capacity(padded),
+            inv_length_2: Vec::with_capacity(padded),
+        };
+        for line in lines {
+            let (dx, dy) = (line.dx(), line.dy());
+            let length_2 = dx * dx + dy * dy;
+            segments.x.push(line.start.x);
+            segments.y.push(line.start.y);
+            segments.dx.push(dx);
+            segments.dy.push(dy);
+            segments.inv_length_2.push(if length_2 == T::zero() {
+                T::zero()
+            } else {
+                length_2.recip()
+            });
+        }
+        Some(segments)
+    }
+
+    /// Squared distance from a point to the nearest segment
+    pub(crate) fn distance_2(&self, point: Point<T>) -> T {
+        let (px, py) = point.x_y();
+        let (zero, one) = (T::zero(), T::one());
+        let mut nearest = [T::infinity(); LANES];
+        for chunk in (0..self.x.len()).step_by(LANES) {
+            let x: &[T; LANES] = self.x[chunk..chunk + LANES].try_into().unwrap();
+            let y: &[T; LANES] = self.y[chunk..chunk + LANES].try_into().unwrap();
+            let dx: &[T; LANES] = self.dx[chunk..chunk + LANES].try_into().unwrap();
+            let dy: &[T; LANES] = self.dy[chunk..chunk + LANES].try_into().unwrap();
+            let inv_length_2: &[T; LANES] =
+                self.inv_length_2[chunk..chunk + LANES].try_into().unwrap();
+            for lane in 0..LANES {
+                let (vx, vy) = (px - x[lane], py - y[lane]);
+                // position of the point's projection along the segment, clamped to its ends
+                let r = (vx * dx[lane] + vy * dy[lane]) * inv_length_2[lane];
+                let r = if r < zero {
+                    zero
+                } else if r > one {
+                    one
+                } else {
+                    r
+                };
+                let (ex, ey) = (vx - r * dx[lane], vy - r * dy[lane]);
+                let distance_2 = ex * ex + ey * ey;
+                if distance_2 < nearest[lane] {
+                    nearest[lane] = distance_2;
+                }
+            }
+        }
+        nearest.into_iter().fold(T::infinity(), T::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Segments, MAX_SEGMENTS};
+    use geo::{Distance, Euclidean, LineString, Point};
+
+    #[test]
+    // the kernel must agree with geo's point-to-LineString distance
+    fn test_kernel() {
+        let ring: LineString<f64> = include!("../tests/fixtures/poly2.rs").into();
+        let ring = LineString::from(ring.0[..42].to_vec());
+        let segments = Segments::new(&ring).unwrap();
+        for (x, y) in [(0.0, 0.0), (-50.0, 20.0), (300.0, -7.5), (-9.8, -99.5)] {
+            let point = Point::new(x, y);
+            let expected = Euclidean::distance(&point, &ring);
+            assert!((segments.distance_2(point).sqrt() - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_too_long() {
+        let ring: LineString<f64> = (0..=MAX_SEGMENTS + 1)
+            .map(|i| (i as f64).to_radians())
+            .map(|a| (a.cos(), a.sin()))
+            .collect::<Vec<_>>()
+            .into();
+        assert!(Segments::new(&ring).is_none());
+    }
+}
@@ -0,0 +1,114 @@
+//! Parallel label position calculation for many Polygons, using [rayon](https://docs.rs/rayon)
+//!
+//! Enable the `rayon` feature to use this functionality.
+
+use crate::errors::PolylabelError;
+use crate::polylabel;
+use geo::{GeoFloat, Point, Polygon};
+use num_traits::FromPrimitive;
+use rayon::iter::MapWith;
+use rayon::prelude::*;
+use std::borrow::Borrow;
+use std::iter::Sum;
+
+/// The function applied to each item by [`ParallelPolylabel::polylabel`]
+pub type LabelFn<P, T> = fn(&mut T, P) -> Result<Point<T>, PolylabelError>;
+
+/// Extension trait adding label position calculation to rayon's parallel iterators
+///
+/// Works with any parallel iterator over Polygons or Polygon references. Ordering is preserved when
+/// collecting from an indexed iterator such as `par_iter()`, and each item yields its own `Result`.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::ParallelPolylabel;
+/// use geo::{Point, Polygon};
+/// use rayon::prelude::*;
+///
+/// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+/// let polygons = vec![Polygon::new(coords.into(), vec![]); 3];
+///
+/// let labels: Result<Vec<Point<f64>>, _> = polygons.par_iter().polylabel(&0.1).collect();
+/// assert_eq!(labels.unwrap()[0], Point::new(0.5625, 0.5625));
+/// ```
+pub trait ParallelPolylabel<T>: ParallelIterator
+where
+    T: GeoFloat + FromPrimitive + Sum + Send + Sync,
+    Self::Item: Borrow<Polygon<T>>,
+{
+    /// Calculate each Polygon's label position using the given tolerance
+    fn polylabel(self, tolerance: &T) -> MapWith<Self, T, LabelFn<Self::Item, T>>;
+}
+
+impl<I, T> ParallelPolylabel<T> for I
+where
+    I: ParallelIterator,
+    I::Item: Borrow<Polygon<T>>,
+    T: GeoFloat + FromPrimitive + Sum + Send + Sync,
+{
+    fn polylabel(self, tolerance: &T) -> MapWith<Self, T, LabelFn<Self::Item, T>> {
+        let label: LabelFn<Self::Item, T> =
+            |tolerance, polygon| polylabel(polygon.borrow(), tolerance);
+        self.map_with(*tolerance, label)
+    }
+}
+
+/// Calculate label positions for a slice of Polygons in parallel
+///
+/// Polygons are distributed across rayon's global thread pool. Results are returned in input order,
+/// and a failure for one Polygon doesn't affect the others.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_batch;
+/// use geo::{Point, Polygon};
+///
+/// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+/// let polygons = vec![Polygon::new(coords.into(), vec![]); 3];
+///
+/// let labels = polylabel_batch(&polygons, &0.1);
+/// assert_eq!(labels.len(), 3);
+/// assert_eq!(labels[2], Ok(Point::new(0.5625, 0.5625)));
+/// ```
+pub fn polylabel_batch<T>(
+    polygons: &[Polygon<T>],
+    tolerance: &T,
+) -> Vec<Result<Point<T>, PolylabelError>>
+where
+    T: GeoFloat + FromPrimitive + Sum + Send + Sync,
+{
+    polygons.par_iter().polylabel(tolerance).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{polylabel_batch, ParallelPolylabel};
+    use crate::polylabel;
+    use geo::Polygon;
+    use rayon::prelude::*;
+
+    #[test]
+    // batch results must match serial results, in the same order
+    fn test_batch() {
+        let polygons = vec![
+            Polygon::new(include!("../tests/fixtures/poly1.rs").into(), vec![]),
+            Polygon::new(include!("../tests/fixtures/poly2.rs").into(), vec![]),
+            Polygon::new(include!("../tests/fixtures/poly3.rs").into(), vec![]),
+        ];
+        let serial: Vec<_> = polygons.iter().map(|p| polylabel(p, &1.0)).collect();
+        assert_eq!(polylabel_batch(&polygons, &1.0), serial);
+    }
+    #[test]
+    // owned Polygons work too, and errors don't stop the iterator
+    fn test_par_iter_owned() {
+        let polygons = vec![
+            Polygon::new(include!("../tests/fixtures/poly2.rs").into(), vec![]),
+            Polygon::new(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 0.0)].into(), vec![]),
+        ];
+        let serial: Vec<_> = polygons.iter().map(|p| polylabel(p, &1.0)).collect();
+        let parallel: Vec<_> = polygons.into_par_iter().polylabel(&1.0).collect();
+        assert_eq!(parallel, serial);
+    }
+}
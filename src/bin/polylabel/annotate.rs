@@ -0,0 +1,166 @@
+//! Annotating the input's features with their label positions
+
+use crate::{map_lines, parse_line, progress, read_geojson, Labeller};
+use polylabel::Parts;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io::Write;
+
+/// Write the input's features, adding a `label_point` property to each Polygon and MultiPolygon feature
+///
+/// The input is a GeoJSON document, or a GeoJSON text sequence if `seq` is set, and is written in the same form.
+/// With [`Parts::All`], `label_point` is a MultiPoint holding a label position for each part. Other features,
+/// features which don't meet the filter's conditions, and bare geometries, are written unchanged. If
+/// `label_properties` is set, `label_radius` and `label_error_bound` properties are added too, as arrays with
+/// [`Parts::All`].
+pub(crate) fn run_annotate(
+    input: &str,
+    labeller: &Labeller,
+    writer: &mut dyn Write,
+    seq: bool,
+    label_properties: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let annotate = |number: usize, feature: &mut Value| {
+        progress::record(annotate(feature, number, labeller, label_properties).then_some(()));
+    };
+    if seq {
+        map_lines(
+            input,
+            |number, line| {
+                Ok(parse_line(line)?.map(|mut feature| {
+                    annotate(number, &mut feature);
+                    feature
+                }))
+            },
+            |feature| writeln!(writer, "{}", feature),
+        )?;
+    } else {
+        let mut geojson = read_geojson(input)?;
+        if geojson.get("type").and_then(Value::as_str) == Some("FeatureCollection") {
+            if let Some(features) = geojson.get_mut("features").and_then(Value::as_array_mut) {
+                progress::set_total(features.len());
+                features
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(i, feature)| annotate(i + 1, feature));
+            }
+        } else {
+            annotate(1, &mut geojson);
+        }
+        writeln!(writer, "{}", geojson)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// add a feature's label position to its properties, given its number in the input, returning whether it was
+// labelled
+fn annotate(
+    feature: &mut Value,
+    number: usize,
+    labeller: &Labeller,
+    label_properties: bool,
+) -> bool {
+    if feature.get("type").and_then(Value::as_str) != Some("Feature") {
+        return false;
+    }
+    let labels = match labeller.label(feature, number) {
+        Some(labels) => labels,
+        None => return false,
+    };
+    let properties = &mut feature["properties"];
+    if !properties.is_object() {
+        *properties = json!({});
+    }
+    let points: Vec<_> = labels
+        .iter()
+        .map(|label| json!([label.point.x(), label.point.y()]))
+        .collect();
+    let radii: Vec<_> = labels.iter().map(|label| json!(label.distance)).collect();
+    let bounds: Vec<_> = labels.iter().map(|label| json!(label.bound)).collect();
+    if labeller.parts == Parts::All {
+        properties["label_point"] = json!({"type": "MultiPoint", "coordinates": points});
+        if label_properties {
+            properties["label_radius"] = json!(radii);
+            properties["label_error_bound"] = json!(bounds);
+        }
+    } else {
+        properties["label_point"] = json!({"type": "Point", "coordinates": points[0]});
+        if label_properties {
+            properties["label_radius"] = radii[0].clone();
+            properties["label_error_bound"] = bounds[0].clone();
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::annotate;
+    use crate::filter::{parse_condition, Filter};
+    use crate::tests::labeller;
+    use polylabel::Parts;
+    use serde_json::json;
+
+    #[test]
+    fn test_annotate() {
+        let largest = labeller(Filter::default(), Parts::Largest);
+        let square = json!({
+            "type": "Polygon",
+            "coordinates": [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]]
+        });
+        let mut feature = json!({"type": "Feature", "properties": null, "geometry": square});
+        assert!(annotate(&mut feature, 1, &largest, true));
+        assert_eq!(
+            feature["properties"]["label_point"],
+            json!({"type": "Point", "coordinates": [2.0, 2.0]})
+        );
+        assert_eq!(feature["properties"]["label_radius"], json!(2.0));
+        assert_eq!(feature["geometry"], square);
+
+        // bare geometries and other features are unchanged
+        let mut geometry = square.clone();
+        assert!(!annotate(&mut geometry, 1, &largest, false));
+        assert_eq!(geometry, square);
+        let point = json!({
+            "type": "Feature",
+            "properties": {"a": 1},
+            "geometry": {"type": "Point", "coordinates": [0.0, 0.0]}
+        });
+        let mut annotated = point.clone();
+        assert!(!annotate(&mut annotated, 1, &largest, false));
+        assert_eq!(annotated, point);
+
+        // as are features which don't meet the filter's conditions
+        let filter = labeller(
+            Filter {
+                conditions: vec![parse_condition("a=2").unwrap()],
+                geometry_types: vec![],
+            },
+            Parts::Largest,
+        );
+        let feature = json!({"type": "Feature", "properties": {"a": 1}, "geometry": square});
+        let mut annotated = feature.clone();
+        assert!(!annotate(&mut annotated, 1, &filter, false));
+        assert_eq!(annotated, feature);
+
+        // every part's label position is a MultiPoint
+        let mut feature = json!({
+            "type": "Feature",
+            "properties": {},
+            "geometry": {"type": "MultiPolygon", "coordinates": [square["coordinates"]]}
+        });
+        assert!(annotate(
+            &mut feature,
+            1,
+            &labeller(Filter::default(), Parts::All),
+            true
+        ));
+        assert_eq!(
+            feature["properties"]["label_point"],
+            json!({"type": "MultiPoint", "coordinates": [[2.0, 2.0]]})
+        );
+        assert_eq!(feature["properties"]["label_radius"], json!([2.0]));
+    }
+}
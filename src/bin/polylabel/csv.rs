@@ -0,0 +1,352 @@
+//! CSV input and output, with geometries in a WKT column
+
+use crate::filter::wkt_type;
+use crate::output::{Format, Labelled, Output};
+use crate::{parse_tolerance, progress, Labeller, CHUNK_SIZE};
+use polylabel::Label;
+use rayon::prelude::*;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+/// Label each record of a CSV file's WKT geometry
+///
+/// If the output format is CSV, the file is copied, with `label_x`, `label_y`, and `label_radius` columns
+/// holding each record's label position appended, and a `label_error_bound` column if `label_properties` is
+/// set. Records are copied unchanged, and the columns are empty for records whose geometry can't be labelled,
+/// or which don't meet the filter's conditions. Otherwise, the label positions are written with each record's
+/// other columns as properties, and those records are skipped. With [`Parts::All`](polylabel::Parts::All), a
+/// label position is written for each part of a MultiPolygon, so CSV records are copied once for each part. If
+/// the tolerance has a property, each record's tolerance is read from the column with its name, and the filter's
+/// conditions compare columns.
+pub(crate) fn run_csv(
+    reader: &mut dyn BufRead,
+    writer: &mut dyn Write,
+    column: &str,
+    labeller: &Labeller,
+    format: Format,
+    label_properties: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut header = String::new();
+    if !read_record(reader, &mut header)? {
+        return Ok(());
+    }
+    let names = fields(&header);
+    let index = |column: &str| {
+        names
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| format!("there's no {} column", column))
+    };
+    let columns = Columns {
+        wkt: index(column)?,
+        tolerance: labeller.tolerance.property.map(index).transpose()?,
+    };
+    for property in labeller.filter.properties() {
+        index(property)?;
+    }
+    let mut sink = match format {
+        Format::Csv => {
+            let bound = if label_properties {
+                ",label_error_bound"
+            } else {
+                ""
+            };
+            writeln!(writer, "{},label_x,label_y,label_radius{}", header, bound)?;
+            Sink::Csv(writer)
+        }
+        _ => Sink::Output(Output::new(writer, format, label_properties)?),
+    };
+    // the number of records read before the current chunk
+    let mut read = 0;
+    loop {
+        let mut chunk = vec![];
+        let mut record = String::new();
+        while chunk.len() < CHUNK_SIZE && read_record(reader, &mut record)? {
+            chunk.push(std::mem::take(&mut record));
+        }
+        if chunk.is_empty() {
+            break;
+        }
+        let labels: Vec<_> = chunk
+            .par_iter()
+            .enumerate()
+            .map(|(i, record)| label_record(record, read + i + 1, &names, &columns, labeller))
+            .collect();
+        read += chunk.len();
+        for (record, (fields, labels)) in chunk.iter().zip(labels) {
+            match &mut sink {
+                Sink::Csv(writer) if labels.is_empty() && label_properties => {
+                    writeln!(writer, "{},,,,", record)?
+                }
+                Sink::Csv(writer) if labels.is_empty() => writeln!(writer, "{},,,", record)?,
+                Sink::Csv(writer) => {
+                    for label in labels {
+                        write!(
+                            writer,
+                            "{},{},{},{}",
+                            record,
+                            label.point.x(),
+                            label.point.y(),
+                            label.distance
+                        )?;
+                        if label_properties {
+                            write!(writer, ",{}", label.bound)?;
+                        }
+                        writeln!(writer)?
+                    }
+                }
+                Sink::Output(output) => {
+                    let properties = names
+                        .iter()
+                        .zip(fields)
+                        .enumerate()
+                        .filter(|&(i, _)| i != columns.wkt)
+                        .map(|(_, (name, field))| (name.clone(), Value::String(field)))
+                        .collect::<Map<_, _>>();
+                    for label in labels {
+                        output.write(&Labelled {
+                            id: None,
+                            properties: Value::Object(properties.clone()),
+                            label,
+                        })?
+                    }
+                }
+            }
+        }
+    }
+    match sink {
+        Sink::Csv(writer) => writer.flush()?,
+        Sink::Output(output) => output.finish()?,
+    }
+    Ok(())
+}
+
+// where labelled records are written: appended to the records, or as label positions in another format
+enum Sink<'a> {
+    Csv(&'a mut dyn Write),
+    Output(Output<'a>),
+}
+
+// the indices of the WKT and tolerance columns
+struct Columns {
+    wkt: usize,
+    tolerance: Option<usize>,
+}
+
+// a record's fields, and its label positions if it meets the filter's conditions, given its number in the input
+fn label_record(
+    record: &str,
+    number: usize,
+    names: &[String],
+    columns: &Columns,
+    labeller: &Labeller,
+) -> (Vec<String>, Vec<Label<f64>>) {
+    let fields = fields(record);
+    let wkt = fields.get(columns.wkt).map_or("", String::as_str);
+    let property = |name: &str| {
+        let index = names.iter().position(|column| column == name)?;
+        fields.get(index).map(|field| Cow::Borrowed(field.as_str()))
+    };
+    if !labeller.filter.matches(property, Some(wkt_type(wkt))) {
+        progress::record(None::<()>);
+        return (fields, vec![]);
+    }
+    let tolerance = columns
+        .tolerance
+        .and_then(|index| parse_tolerance(fields.get(index)?).ok())
+        .unwrap_or(labeller.tolerance.default);
+    let labels = labeller.label_wkt(wkt, tolerance, number);
+    (fields, progress::record(labels).unwrap_or_default())
+}
+
+// read a record without its line ending, which spans several lines if a quoted field contains line breaks,
+// returning false at the end of the input
+fn read_record(reader: &mut dyn BufRead, record: &mut String) -> io::Result<bool> {
+    record.clear();
+    loop {
+        if reader.read_line(record)? == 0 {
+            break;
+        }
+        // an odd number of quotes means a quoted field continues on the next line
+        if record.bytes().filter(|&byte| byte == b'"').count() % 2 == 0 {
+            break;
+        }
+    }
+    let len = record.trim_end_matches(['\r', '\n']).len();
+    let read = !record.is_empty();
+    record.truncate(len);
+    Ok(read)
+}
+
+// split a record into unquoted fields
+fn fields(record: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => field.push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fields, run_csv};
+    use crate::filter::{parse_condition, Filter};
+    use crate::output::Format;
+    use crate::tests::labeller;
+    use crate::{Labeller, Tolerance};
+    use polylabel::Parts;
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(fields("a,\"b,\"\"c\"\"\",,"), ["a", "b,\"c\"", "", ""]);
+    }
+
+    #[test]
+    fn test_run_csv() {
+        let csv = "name,geom\r\nsquare,\"POLYGON((0 0,4 0,4 4,0 4,0 0))\"\n\"a\nb\",POINT(1 1)\n";
+        let largest = labeller(Filter::default(), Parts::Largest);
+        let mut output = vec![];
+        run_csv(
+            &mut csv.as_bytes(),
+            &mut output,
+            "geom",
+            &largest,
+            Format::Csv,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "name,geom,label_x,label_y,label_radius\n\
+             square,\"POLYGON((0 0,4 0,4 4,0 4,0 0))\",2,2,2\n\
+             \"a\nb\",POINT(1 1),,,\n"
+        );
+        assert!(run_csv(
+            &mut csv.as_bytes(),
+            &mut vec![],
+            "wkt",
+            &largest,
+            Format::Csv,
+            false
+        )
+        .is_err());
+
+        // a coarser tolerance than the default, and an invalid one
+        let csv = "tolerance,geom\n\
+                   1,\"POLYGON((0 0,4 0,4 1,1 1,1 4,0 4,0 0))\"\n\
+                   x,\"POLYGON((0 0,4 0,4 1,1 1,1 4,0 4,0 0))\"\n";
+        let tolerance = Labeller {
+            tolerance: Tolerance {
+                default: 0.1,
+                property: Some("tolerance"),
+            },
+            filter: Filter::default(),
+            parts: Parts::Largest,
+            metres: false,
+        };
+        let mut output = vec![];
+        run_csv(
+            &mut csv.as_bytes(),
+            &mut output,
+            "geom",
+            &tolerance,
+            Format::Csv,
+            false,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let labels: Vec<_> = output
+            .lines()
+            .skip(1)
+            .map(|line| line.rsplit(')').next())
+            .collect();
+        assert_eq!(
+            labels,
+            [Some("\",0.5,0.5,0.5"), Some("\",0.5625,0.5625,0.5625")]
+        );
+    }
+
+    #[test]
+    // other formats write the other columns as properties
+    fn test_run_csv_formats() {
+        let largest = labeller(Filter::default(), Parts::Largest);
+        let csv = "name,geom\nsquare,\"POLYGON((0 0,4 0,4 4,0 4,0 0))\"\nnone,POINT(1 1)\n";
+        let mut output = vec![];
+        run_csv(
+            &mut csv.as_bytes(),
+            &mut output,
+            "geom",
+            &largest,
+            Format::GeoJsonSeq,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"geometry\":{\"coordinates\":[2.0,2.0],\"type\":\"Point\"},\
+             \"properties\":{\"name\":\"square\"},\"type\":\"Feature\"}\n"
+        );
+
+        // records which don't meet the filter's conditions are skipped, and the columns must exist
+        let filter = |condition| {
+            let filter = Filter {
+                conditions: vec![parse_condition(condition).unwrap()],
+                geometry_types: vec!["Polygon".to_string()],
+            };
+            labeller(filter, Parts::Largest)
+        };
+        let mut output = vec![];
+        run_csv(
+            &mut csv.as_bytes(),
+            &mut output,
+            "geom",
+            &filter("name!=square"),
+            Format::Wkt,
+            false,
+        )
+        .unwrap();
+        assert!(output.is_empty());
+        assert!(run_csv(
+            &mut csv.as_bytes(),
+            &mut vec![],
+            "geom",
+            &filter("missing=1"),
+            Format::Wkt,
+            false
+        )
+        .is_err());
+
+        // records are copied for each part
+        let csv = "geom\n\"MULTIPOLYGON(((0 0,4 0,4 4,0 4,0 0)),((10 0,12 0,12 2,10 2,10 0)))\"\n";
+        let mut output = vec![];
+        run_csv(
+            &mut csv.as_bytes(),
+            &mut output,
+            "geom",
+            &labeller(Filter::default(), Parts::All),
+            Format::Csv,
+            false,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let labels: Vec<_> = output
+            .lines()
+            .filter_map(|line| line.split_once(")\","))
+            .collect();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[1].1, "11,1,1");
+    }
+}
@@ -0,0 +1,195 @@
+//! Selecting the features to label by their properties and geometry types
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// The conditions a feature must meet to be labelled
+#[derive(Debug, Default)]
+pub(crate) struct Filter {
+    /// Property conditions, all of which must be met
+    pub(crate) conditions: Vec<Condition>,
+    /// Geometry types, one of which the feature's geometry must have, if there are any
+    pub(crate) geometry_types: Vec<String>,
+}
+
+impl Filter {
+    /// Whether a feature with the given property lookup and geometry type meets the conditions
+    pub(crate) fn matches<'a, P>(&self, property: P, geometry_type: Option<&str>) -> bool
+    where
+        P: Fn(&str) -> Option<Cow<'a, str>>,
+    {
+        let geometry_type = geometry_type.unwrap_or_default();
+        (self.geometry_types.is_empty()
+            || self
+                .geometry_types
+                .iter()
+                .any(|kind| kind.eq_ignore_ascii_case(geometry_type)))
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(property(&condition.property).as_deref()))
+    }
+
+    /// The properties the conditions compare
+    pub(crate) fn properties(&self) -> impl Iterator<Item = &str> {
+        self.conditions
+            .iter()
+            .map(|condition| condition.property.as_str())
+    }
+
+    /// Whether a GeoJSON feature (or bare geometry) meets the conditions
+    pub(crate) fn matches_feature(&self, feature: &Value) -> bool {
+        let properties = feature.get("properties");
+        let geometry = match feature.get("type").and_then(Value::as_str) {
+            Some("Feature") => feature.get("geometry"),
+            _ => Some(feature),
+        };
+        self.matches(
+            |name| match properties?.get(name)? {
+                Value::String(property) => Some(Cow::Borrowed(property.as_str())),
+                Value::Null => None,
+                property => Some(Cow::Owned(property.to_string())),
+            },
+            geometry.and_then(|geometry| geometry.get("type")?.as_str()),
+        )
+    }
+}
+
+/// The geometry type of a WKT (or EWKT) geometry, e.g. `POLYGON`
+pub(crate) fn wkt_type(wkt: &str) -> &str {
+    let wkt = wkt.trim_start();
+    // EWKT geometries are prefixed with their SRID
+    let wkt = match wkt.split_once(';') {
+        Some((srid, wkt)) if srid.to_ascii_uppercase().starts_with("SRID=") => wkt.trim_start(),
+        _ => wkt,
+    };
+    let end = wkt
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(wkt.len());
+    &wkt[..end]
+}
+
+/// A comparison of a property with a value, e.g. `admin_level=4`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Condition {
+    property: String,
+    operator: Operator,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Condition {
+    // whether a property's value meets the condition. Missing properties are unequal to every value, and
+    // can't be compared
+    fn matches(&self, property: Option<&str>) -> bool {
+        let property = match property {
+            Some(property) => property,
+            None => return self.operator == Operator::NotEqual,
+        };
+        // values are compared as numbers if they're both numbers, and as strings otherwise
+        let ordering = match (property.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(property), Ok(value)) => property.partial_cmp(&value),
+            _ => Some(property.cmp(self.value.as_str())),
+        };
+        match (self.operator, ordering) {
+            (Operator::Equal, ordering) => ordering == Some(Ordering::Equal),
+            (Operator::NotEqual, ordering) => ordering != Some(Ordering::Equal),
+            (Operator::Less, ordering) => ordering == Some(Ordering::Less),
+            (Operator::LessOrEqual, ordering) => {
+                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+            }
+            (Operator::Greater, ordering) => ordering == Some(Ordering::Greater),
+            (Operator::GreaterOrEqual, ordering) => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+            }
+        }
+    }
+}
+
+/// Parse a condition, e.g. `admin_level=4` or `population>=100000`
+pub(crate) fn parse_condition(condition: &str) -> Result<Condition, String> {
+    // two-character operators are checked first, as they start with one-character operators
+    let operators = [
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("=", Operator::Equal),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ];
+    let (index, symbol, operator) = operators
+        .iter()
+        .filter_map(|&(symbol, operator)| Some((condition.find(symbol)?, symbol, operator)))
+        .min_by_key(|&(index, symbol, _)| (index, usize::MAX - symbol.len()))
+        .ok_or("must be a comparison of a property with a value, e.g. admin_level=4")?;
+    let property = condition[..index].trim();
+    if property.is_empty() {
+        return Err("must start with a property name".to_string());
+    }
+    Ok(Condition {
+        property: property.to_string(),
+        operator,
+        value: condition[index + symbol.len()..].trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_condition, wkt_type, Filter};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_condition() {
+        let condition = parse_condition("admin_level >= 4").unwrap();
+        assert_eq!(
+            (condition.property.as_str(), condition.value.as_str()),
+            ("admin_level", "4")
+        );
+        assert_eq!(
+            parse_condition("name=a=b").unwrap().value,
+            "a=b",
+            "values can contain operators"
+        );
+        assert!(parse_condition("admin_level").is_err());
+        assert!(parse_condition("=4").is_err());
+    }
+
+    #[test]
+    fn test_filter() {
+        let feature = json!({
+            "type": "Feature",
+            "properties": {"admin_level": "4", "population": 120000, "name": "b"},
+            "geometry": {"type": "MultiPolygon", "coordinates": []}
+        });
+        let filter = |conditions: &[&str], geometry_types: &[&str]| Filter {
+            conditions: conditions
+                .iter()
+                .map(|condition| parse_condition(condition).unwrap())
+                .collect(),
+            geometry_types: geometry_types.iter().map(|kind| kind.to_string()).collect(),
+        };
+        let matches = |conditions, geometry_types| {
+            filter(conditions, geometry_types).matches_feature(&feature)
+        };
+        assert!(matches(&[], &[]));
+        assert!(matches(&["admin_level=4", "population>100000"], &[]));
+        // numbers are compared numerically, even as strings
+        assert!(matches(&["admin_level=4.0", "admin_level<10"], &[]));
+        assert!(!matches(&["admin_level=4", "population<=100000"], &[]));
+        assert!(matches(&["name>a", "name!=a", "missing!=a"], &[]));
+        assert!(!matches(&["missing=a"], &[]));
+        assert!(matches(&[], &["polygon", "multipolygon"]));
+        assert!(!matches(&[], &["Polygon"]));
+        assert_eq!(wkt_type("SRID=4326; POLYGON Z((0 0 0))"), "POLYGON");
+    }
+}
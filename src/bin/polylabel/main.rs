@@ -0,0 +1,678 @@
+//! Calculate label positions for the Polygon and MultiPolygon features in a GeoJSON file, a stream of
+//! newline-delimited GeoJSON features, a polygon shapefile, or a CSV file with a WKT geometry column, writing
+//! them as GeoJSON, CSV, or WKT, or label the polygon features of a directory of vector tiles
+//!
+//! Build with the `cli` feature enabled.
+
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgAction, Command};
+use filter::Filter;
+use geo::MultiPolygon;
+use output::{Format, Labelled, Output};
+use polylabel::{
+    geojson_multi_polygon, polylabel_multi_with_stats, wkt_multi_polygon, Label, Parts,
+    PolylabelOptions,
+};
+use rayon::prelude::*;
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process;
+use std::time::Instant;
+
+mod annotate;
+mod csv;
+mod filter;
+mod output;
+mod progress;
+mod project;
+mod shapefile;
+mod stats;
+mod tiles;
+
+// the number of lines (or CSV records) read from a stream before they're labelled
+const CHUNK_SIZE: usize = 4096;
+
+fn main() {
+    let matches = Command::new("polylabel")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Calculate optimum label positions for the Polygon and MultiPolygon features in a GeoJSON file")
+        .arg(
+            Arg::new("input")
+                .value_name("INPUT")
+                .default_value("-")
+                .help(
+                    "A GeoJSON FeatureCollection, Feature, or geometry, a polygon shapefile (.shp), whose \
+                     records are read as features with their .dbf attributes as properties, or - for stdin",
+                ),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(["geojson", "csv"])
+                .help(
+                    "The input's format. CSV is written with label_x, label_y, and label_radius columns \
+                     appended",
+                ),
+        )
+        .arg(
+            Arg::new("wkt_column")
+                .long("wkt-column")
+                .value_name("COLUMN")
+                .default_value("WKT")
+                .help("The name of a CSV input's WKT geometry column"),
+        )
+        .arg(
+            Arg::new("seq")
+                .long("seq")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Read newline-delimited GeoJSON (GeoJSONSeq) features, writing a label feature per line",
+                ),
+        )
+        .arg(
+            Arg::new("tolerance")
+                .short('t')
+                .long("tolerance")
+                .value_name("TOLERANCE")
+                .value_parser(parse_tolerance)
+                .default_value("0.001")
+                .help("The label positions' tolerance, in the input's units"),
+        )
+        .arg(
+            Arg::new("tolerance_meters")
+                .long("tolerance-meters")
+                .value_name("METRES")
+                .value_parser(parse_tolerance)
+                .conflicts_with("tolerance")
+                .help(
+                    "The label positions' tolerance in metres, for longitude and latitude (EPSG:4326) input. \
+                     Each feature is projected to a local equal-area projection to be labelled, and tolerance \
+                     properties, label_radius, and label_error_bound are in metres too",
+                ),
+        )
+        .arg(
+            Arg::new("tolerance_property")
+                .long("tolerance-property")
+                .value_name("PROPERTY")
+                .help(
+                    "A property (or CSV column) holding each feature's tolerance. Features without a valid \
+                     tolerance use TOLERANCE",
+                ),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("JOBS")
+                .value_parser(value_parser!(usize))
+                .default_value("1")
+                .help("The number of features to label in parallel, or 0 to use every CPU"),
+        )
+        .arg(
+            Arg::new("where")
+                .long("where")
+                .value_name("CONDITION")
+                .value_parser(filter::parse_condition)
+                .action(ArgAction::Append)
+                .help(
+                    "Only label features whose property (or CSV column) meets a condition, e.g. \
+                     admin_level=4, using =, !=, <, <=, >, or >=. Values are compared as numbers if they're \
+                     both numbers. Can be repeated, and features must meet every condition",
+                ),
+        )
+        .arg(
+            Arg::new("geometry_type")
+                .long("geometry-type")
+                .value_name("TYPE")
+                .value_parser(PossibleValuesParser::new(["Polygon", "MultiPolygon"]))
+                .ignore_case(true)
+                .action(ArgAction::Append)
+                .help("Only label features with a geometry type. Can be repeated"),
+        )
+        .arg(
+            Arg::new("parts")
+                .long("parts")
+                .value_name("PARTS")
+                .value_parser(["largest", "all", "union"])
+                .default_value("largest")
+                .help(
+                    "Label a MultiPolygon's largest part, every part (writing a label position per part), \
+                     or the largest part of its union, once overlapping and adjacent parts are dissolved",
+                ),
+        )
+        .arg(
+            Arg::new("output_format")
+                .long("output-format")
+                .value_name("FORMAT")
+                .value_parser(["geojson", "csv", "wkt"])
+                .help(
+                    "The output's format: GeoJSON Point features (one per line with --seq), a CSV file with \
+                     id, label_x, label_y, and properties columns, or a WKT POINT per line. Defaults to the \
+                     input's format",
+                ),
+        )
+        .arg(
+            Arg::new("label_properties")
+                .long("label-properties")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Write each label position's distance from its feature's edges and its error bound as \
+                     label_radius and label_error_bound properties (or CSV columns)",
+                ),
+        )
+        .arg(
+            Arg::new("annotate")
+                .long("annotate")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["format", "output_format"])
+                .help(
+                    "Write the input's features, adding a label_point property holding each Polygon and \
+                     MultiPolygon feature's label position, instead of label features",
+                ),
+        )
+        .arg(
+            Arg::new("progress")
+                .long("progress")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Report the number of features processed, the rate, and (for input files) the time \
+                     remaining on stderr, and a summary of labelled and skipped features",
+                ),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .value_name("FILE")
+                .help(
+                    "Write each feature's labelling time, number of cells examined, and maximum cell queue \
+                     size to a CSV file (or - for stderr), slowest first, and the total time on stderr",
+                ),
+        )
+        .arg(
+            Arg::new("tiles")
+                .long("tiles")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "format",
+                    "seq",
+                    "tolerance_meters",
+                    "tolerance_property",
+                    "where",
+                    "geometry_type",
+                    "parts",
+                    "output_format",
+                    "label_properties",
+                    "annotate",
+                    "progress",
+                    "stats",
+                ])
+                .help(
+                    "Read INPUT as a directory of uncompressed vector tiles laid out as {z}/{x}/{y}.pbf (or \
+                     .mvt), and write a tile of label positions for each tile holding polygons to the same path \
+                     in the OUTPUT directory, with a point layer for each polygon layer, named after it with a \
+                     _label suffix. TOLERANCE is in tile units",
+                ),
+        )
+        .arg(
+            Arg::new("tile_buffer")
+                .long("tile-buffer")
+                .value_name("UNITS")
+                .value_parser(value_parser!(u32))
+                .default_value("0")
+                .requires("tiles")
+                .help(
+                    "Discard label positions more than UNITS tile units outside their tile, so features which \
+                     are in several tiles' buffers aren't labelled in every tile",
+                ),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT")
+                .default_value("-")
+                .help("Write the label positions to a file, or - for stdout"),
+        )
+        .get_matches();
+    let input = matches.get_one::<String>("input").unwrap();
+    let metres = matches.get_one::<f64>("tolerance_meters");
+    let labeller = Labeller {
+        tolerance: Tolerance {
+            default: *metres.or(matches.get_one::<f64>("tolerance")).unwrap(),
+            property: matches
+                .get_one::<String>("tolerance_property")
+                .map(String::as_str),
+        },
+        filter: Filter {
+            conditions: matches
+                .get_many::<filter::Condition>("where")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+            geometry_types: matches
+                .get_many::<String>("geometry_type")
+                .unwrap_or_default()
+                .cloned()
+                .collect(),
+        },
+        parts: match matches.get_one::<String>("parts").map(String::as_str) {
+            Some("all") => Parts::All,
+            Some("union") => Parts::Union,
+            _ => Parts::Largest,
+        },
+        metres: metres.is_some(),
+    };
+    let output = matches.get_one::<String>("output").unwrap();
+    let csv_input = matches.get_one::<String>("format").map(String::as_str) == Some("csv");
+    let seq = matches.get_flag("seq");
+    let format = match matches
+        .get_one::<String>("output_format")
+        .map(String::as_str)
+    {
+        Some("csv") => Format::Csv,
+        Some("wkt") => Format::Wkt,
+        Some(_) if seq => Format::GeoJsonSeq,
+        Some(_) => Format::GeoJson,
+        None if csv_input => Format::Csv,
+        None if seq => Format::GeoJsonSeq,
+        None => Format::GeoJson,
+    };
+    let label_properties = matches.get_flag("label_properties");
+    let jobs = *matches.get_one::<usize>("jobs").unwrap();
+    if matches.get_flag("progress") {
+        progress::start(input);
+    }
+    if let Some(stats) = matches.get_one::<String>("stats") {
+        stats::start(stats);
+    }
+    if shapefile::is_shapefile(input) && (csv_input || seq) {
+        eprintln!("polylabel: shapefiles can't be read as CSV or GeoJSONSeq");
+        process::exit(1);
+    }
+    let result = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(Box::from)
+        .and_then(|pool| {
+            pool.install(|| {
+                if matches.get_flag("tiles") {
+                    let buffer = *matches.get_one::<u32>("tile_buffer").unwrap();
+                    return tiles::run_tiles(input, output, labeller.tolerance.default, buffer);
+                }
+                let mut writer = writer(output)?;
+                if matches.get_flag("annotate") {
+                    annotate::run_annotate(input, &labeller, &mut *writer, seq, label_properties)
+                } else if csv_input {
+                    let column = matches.get_one::<String>("wkt_column").unwrap();
+                    csv::run_csv(
+                        &mut *reader(input)?,
+                        &mut *writer,
+                        column,
+                        &labeller,
+                        format,
+                        label_properties,
+                    )
+                } else if seq {
+                    run_seq(
+                        input,
+                        &labeller,
+                        Output::new(&mut *writer, format, label_properties)?,
+                    )
+                } else {
+                    run(
+                        input,
+                        &labeller,
+                        Output::new(&mut *writer, format, label_properties)?,
+                    )
+                }
+            })
+        })
+        .and_then(|()| stats::finish());
+    progress::finish();
+    if let Err(err) = result {
+        eprintln!("polylabel: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(
+    input: &str,
+    labeller: &Labeller,
+    mut output: Output,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let geojson = read_geojson(input)?;
+    for labelled in label_features(&geojson, labeller) {
+        output.write(&labelled)?;
+    }
+    output.finish()?;
+    Ok(())
+}
+
+// label a stream of features
+fn run_seq(
+    input: &str,
+    labeller: &Labeller,
+    mut output: Output,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    map_lines(
+        input,
+        |number, line| {
+            Ok(parse_line(line)?
+                .and_then(|feature| progress::record(label_feature(&feature, number, labeller))))
+        },
+        |labelled| {
+            labelled
+                .iter()
+                .try_for_each(|labelled| output.write(labelled))
+        },
+    )?;
+    output.finish()?;
+    Ok(())
+}
+
+/// Map each line of a stream, and its line number, a chunk of lines at a time, so memory use doesn't depend on
+/// the input's size, writing the results in input order
+pub(crate) fn map_lines<T, M, W>(
+    input: &str,
+    map: M,
+    mut write: W,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    T: Send,
+    M: Fn(usize, &str) -> serde_json::Result<Option<T>> + Sync,
+    W: FnMut(T) -> io::Result<()>,
+{
+    let mut lines = reader(input)?.lines().enumerate();
+    loop {
+        let chunk = lines
+            .by_ref()
+            .take(CHUNK_SIZE)
+            .map(|(i, line)| line.map(|line| (i, line)))
+            .collect::<io::Result<Vec<_>>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let mapped = chunk
+            .par_iter()
+            .map(|(i, line)| map(i + 1, line).map_err(|err| format!("line {}: {}", i + 1, err)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for item in mapped.into_iter().flatten() {
+            write(item)?;
+        }
+    }
+    Ok(())
+}
+
+// the input file, or stdin for -
+fn reader(input: &str) -> Result<Box<dyn BufRead>, Box<dyn Error + Send + Sync>> {
+    let reader: Box<dyn BufRead> = match input {
+        "-" => Box::new(io::stdin().lock()),
+        path => Box::new(BufReader::new(
+            fs::File::open(path).map_err(|err| format!("{}: {}", path, err))?,
+        )),
+    };
+    Ok(progress::track(reader))
+}
+
+// the output file, or stdout for -
+fn writer(output: &str) -> Result<Box<dyn Write>, Box<dyn Error + Send + Sync>> {
+    Ok(match output {
+        "-" => Box::new(BufWriter::new(io::stdout().lock())),
+        path => Box::new(BufWriter::new(fs::File::create(path)?)),
+    })
+}
+
+/// Read a GeoJSON document from the input file, or stdin, or a shapefile's records as a FeatureCollection
+pub(crate) fn read_geojson(input: &str) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    if shapefile::is_shapefile(input) {
+        return shapefile::read_features(input);
+    }
+    let mut geojson = String::new();
+    reader(input)?.read_to_string(&mut geojson)?;
+    Ok(serde_json::from_str(&geojson)?)
+}
+
+/// Parse a line of a GeoJSON text sequence, which is `None` if it's blank
+pub(crate) fn parse_line(line: &str) -> serde_json::Result<Option<Value>> {
+    // GeoJSON text sequences prefix each feature with a record separator
+    let line = line.trim_start_matches('\u{1e}').trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(line).map(Some)
+}
+
+/// How each feature is labelled
+pub(crate) struct Labeller<'a> {
+    pub(crate) tolerance: Tolerance<'a>,
+    /// The conditions features must meet to be labelled
+    pub(crate) filter: Filter,
+    /// Which parts of MultiPolygons are labelled
+    pub(crate) parts: Parts,
+    /// Whether features are projected from longitude and latitude, so tolerances and distances are in metres
+    pub(crate) metres: bool,
+}
+
+impl Labeller<'_> {
+    /// The label positions of a GeoJSON Polygon or MultiPolygon feature's parts, if it meets the filter's
+    /// conditions and has any, given its number in the input
+    pub(crate) fn label(&self, feature: &Value, number: usize) -> Option<Vec<Label<f64>>> {
+        if !self.filter.matches_feature(feature) {
+            return None;
+        }
+        let tolerance = self.tolerance.of(feature.get("properties"));
+        let multi = geojson_multi_polygon(&feature.to_string()).ok()?;
+        self.label_multi(&multi, tolerance, number, feature.get("id"))
+    }
+
+    /// The label positions of a WKT Polygon or MultiPolygon's parts, if it has any, given its number in the input
+    pub(crate) fn label_wkt(
+        &self,
+        wkt: &str,
+        tolerance: f64,
+        number: usize,
+    ) -> Option<Vec<Label<f64>>> {
+        self.label_multi(&wkt_multi_polygon(wkt)?, tolerance, number, None)
+    }
+
+    // label a MultiPolygon's parts, recording statistics about their searches
+    fn label_multi(
+        &self,
+        multi: &MultiPolygon,
+        tolerance: f64,
+        number: usize,
+        id: Option<&Value>,
+    ) -> Option<Vec<Label<f64>>> {
+        let started = Instant::now();
+        let options = PolylabelOptions::new(tolerance);
+        let labels = if self.metres {
+            let (projected, projection) = project::project(multi)?;
+            polylabel_multi_with_stats(&projected, &options, self.parts)
+                .ok()?
+                .into_iter()
+                .map(|(label, stats)| (projection.inverse_label(label), stats))
+                .collect()
+        } else {
+            polylabel_multi_with_stats(multi, &options, self.parts).ok()?
+        };
+        stats::record(
+            number,
+            id,
+            started.elapsed(),
+            labels.iter().map(|&(_, stats)| stats),
+        );
+        let labels: Vec<_> = labels.into_iter().map(|(label, _)| label).collect();
+        Some(labels).filter(|labels| !labels.is_empty())
+    }
+}
+
+/// The tolerance used to label each feature
+pub(crate) struct Tolerance<'a> {
+    /// The tolerance of features without a valid tolerance property
+    pub(crate) default: f64,
+    /// The name of the property holding each feature's tolerance
+    pub(crate) property: Option<&'a str>,
+}
+
+impl Tolerance<'_> {
+    // the tolerance of a feature with the given properties
+    fn of(&self, properties: Option<&Value>) -> f64 {
+        let tolerance = match self.property.and_then(|name| properties?.get(name)) {
+            // JSON numbers are always finite
+            Some(Value::Number(tolerance)) => {
+                tolerance.as_f64().filter(|&tolerance| tolerance > 0.0)
+            }
+            Some(Value::String(tolerance)) => parse_tolerance(tolerance).ok(),
+            _ => None,
+        };
+        tolerance.unwrap_or(self.default)
+    }
+}
+
+pub(crate) fn parse_tolerance(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(tolerance) if tolerance.is_finite() && tolerance > 0.0 => Ok(tolerance),
+        _ => Err("must be a positive number".to_string()),
+    }
+}
+
+/// The label position of each Polygon or MultiPolygon feature in `geojson`, with the feature's properties and id
+///
+/// A bare geometry is treated as a Feature without properties. Other features, and features which don't meet
+/// the filter's conditions, are skipped. With [`Parts::All`], each part of a MultiPolygon feature is labelled.
+fn label_features(geojson: &Value, labeller: &Labeller) -> Vec<Labelled> {
+    let features = match geojson.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => geojson
+            .get("features")
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice),
+        _ => std::slice::from_ref(geojson),
+    };
+    progress::set_total(features.len());
+    // features are labelled using the current thread pool, in input order
+    features
+        .par_iter()
+        .enumerate()
+        .filter_map(|(i, feature)| progress::record(label_feature(feature, i + 1, labeller)))
+        .flatten()
+        .collect()
+}
+
+/// A Polygon or MultiPolygon feature's label positions, with its properties and id, given its number in the input
+fn label_feature(feature: &Value, number: usize, labeller: &Labeller) -> Option<Vec<Labelled>> {
+    let labels = labeller.label(feature, number)?;
+    let id = feature.get("id");
+    let properties = feature.get("properties").unwrap_or(&Value::Null);
+    Some(
+        labels
+            .into_iter()
+            .map(|label| Labelled {
+                id: id.cloned(),
+                properties: properties.clone(),
+                label,
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label_features, parse_line, parse_tolerance, Labeller, Tolerance};
+    use crate::filter::{parse_condition, Filter};
+    use geo::Point;
+    use polylabel::Parts;
+    use serde_json::json;
+
+    /// Label features with a tolerance of 0.1
+    pub(crate) fn labeller(filter: Filter, parts: Parts) -> Labeller<'static> {
+        Labeller {
+            tolerance: Tolerance {
+                default: 0.1,
+                property: None,
+            },
+            filter,
+            parts,
+            metres: false,
+        }
+    }
+
+    #[test]
+    fn test_label_features() {
+        let square = json!({
+            "type": "Polygon",
+            "coordinates": [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]]
+        });
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "id": 7, "properties": {"name": "square"}, "geometry": square},
+                {"type": "Feature", "properties": null, "geometry": {"type": "Point", "coordinates": [0.0, 0.0]}}
+            ]
+        });
+        let largest = labeller(Filter::default(), Parts::Largest);
+        let labelled = label_features(&collection, &largest);
+        assert_eq!(labelled.len(), 1);
+        assert_eq!(labelled[0].id, Some(json!(7)));
+        assert_eq!(labelled[0].properties, json!({"name": "square"}));
+        assert_eq!(labelled[0].label.point, Point::new(2.0, 2.0));
+        assert_eq!(label_features(&square, &largest)[0].properties, json!(null));
+        assert_eq!(
+            parse_line("\u{1e}{\"type\": \"Point\"}").unwrap(),
+            Some(json!({"type": "Point"}))
+        );
+        assert_eq!(parse_line(" ").unwrap(), None);
+        assert!(parse_line("{").is_err());
+        assert!(parse_tolerance("0.5").is_ok());
+        assert!(parse_tolerance("0").is_err());
+        assert!(parse_tolerance("NaN").is_err());
+
+        let filter = Filter {
+            conditions: vec![parse_condition("name!=square").unwrap()],
+            geometry_types: vec![],
+        };
+        assert!(label_features(&collection, &labeller(filter, Parts::Largest)).is_empty());
+    }
+
+    #[test]
+    fn test_parts() {
+        let squares = json!({
+            "type": "Feature",
+            "id": "squares",
+            "properties": {},
+            "geometry": {
+                "type": "MultiPolygon",
+                "coordinates": [
+                    [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]],
+                    [[[4.0, 0.0], [6.0, 0.0], [6.0, 4.0], [4.0, 4.0], [4.0, 0.0]]]
+                ]
+            }
+        });
+        let label = |parts| label_features(&squares, &labeller(Filter::default(), parts));
+        assert_eq!(label(Parts::Largest).len(), 1);
+        let all = label(Parts::All);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].id, Some(json!("squares")));
+        // the adjacent parts are dissolved into a 6 by 4 rectangle
+        let union = label(Parts::Union);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].label.distance, 2.0);
+    }
+
+    #[test]
+    fn test_tolerance_property() {
+        let tolerance = Tolerance {
+            default: 0.1,
+            property: Some("tolerance"),
+        };
+        let of = |properties| tolerance.of(Some(&properties));
+        assert_eq!(of(json!({"tolerance": 2.0})), 2.0);
+        assert_eq!(of(json!({"tolerance": "2"})), 2.0);
+        assert_eq!(of(json!({"tolerance": -2.0})), 0.1);
+        assert_eq!(of(json!({"tolerance": null})), 0.1);
+        assert_eq!(of(json!({})), 0.1);
+        assert_eq!(tolerance.of(None), 0.1);
+    }
+}
@@ -0,0 +1,203 @@
+//! Writing label positions in each output format
+
+use polylabel::Label;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+/// The format label positions are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// A GeoJSON FeatureCollection of Point features
+    GeoJson,
+    /// Newline-delimited GeoJSON Point features
+    GeoJsonSeq,
+    /// A CSV file with `id`, `label_x`, `label_y`, and `properties` columns
+    Csv,
+    /// A WKT `POINT` per line
+    Wkt,
+}
+
+/// A feature's label position, and the id and properties it's written with
+pub(crate) struct Labelled {
+    pub(crate) id: Option<Value>,
+    pub(crate) properties: Value,
+    pub(crate) label: Label<f64>,
+}
+
+/// Writes label positions in a format
+pub(crate) struct Output<'a> {
+    writer: &'a mut dyn Write,
+    format: Format,
+    label_properties: bool,
+    written: usize,
+}
+
+impl<'a> Output<'a> {
+    /// Start writing label positions, writing any header the format has
+    ///
+    /// If `label_properties` is set, each label position's distance from its feature's edges and error bound
+    /// are written as `label_radius` and `label_error_bound` properties (or CSV columns). WKT has no properties.
+    pub(crate) fn new(
+        writer: &'a mut dyn Write,
+        format: Format,
+        label_properties: bool,
+    ) -> io::Result<Self> {
+        match format {
+            Format::GeoJson => write!(writer, r#"{{"type":"FeatureCollection","features":["#)?,
+            Format::Csv if label_properties => writeln!(
+                writer,
+                "id,label_x,label_y,label_radius,label_error_bound,properties"
+            )?,
+            Format::Csv => writeln!(writer, "id,label_x,label_y,properties")?,
+            Format::GeoJsonSeq | Format::Wkt => (),
+        }
+        Ok(Self {
+            writer,
+            format,
+            label_properties,
+            written: 0,
+        })
+    }
+
+    pub(crate) fn write(&mut self, labelled: &Labelled) -> io::Result<()> {
+        let (x, y) = labelled.label.point.x_y();
+        match self.format {
+            Format::GeoJson | Format::GeoJsonSeq => {
+                let mut feature = json!({
+                    "type": "Feature",
+                    "properties": labelled.properties,
+                    "geometry": {"type": "Point", "coordinates": [x, y]},
+                });
+                if self.label_properties {
+                    let properties = &mut feature["properties"];
+                    if !properties.is_object() {
+                        *properties = json!({});
+                    }
+                    properties["label_radius"] = json!(labelled.label.distance);
+                    properties["label_error_bound"] = json!(labelled.label.bound);
+                }
+                if let Some(id) = &labelled.id {
+                    feature["id"] = id.clone();
+                }
+                match self.format {
+                    Format::GeoJson if self.written > 0 => write!(self.writer, ",{}", feature)?,
+                    Format::GeoJson => write!(self.writer, "{}", feature)?,
+                    _ => writeln!(self.writer, "{}", feature)?,
+                }
+            }
+            Format::Csv => {
+                let id = match &labelled.id {
+                    Some(Value::String(id)) => quote(id),
+                    Some(id) => quote(&id.to_string()),
+                    None => String::new(),
+                };
+                let properties = match &labelled.properties {
+                    Value::Null => String::new(),
+                    properties => quote(&properties.to_string()),
+                };
+                if self.label_properties {
+                    writeln!(
+                        self.writer,
+                        "{},{},{},{},{},{}",
+                        id, x, y, labelled.label.distance, labelled.label.bound, properties
+                    )?;
+                } else {
+                    writeln!(self.writer, "{},{},{},{}", id, x, y, properties)?;
+                }
+            }
+            Format::Wkt => writeln!(self.writer, "POINT({} {})", x, y)?,
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Finish writing label positions, writing any footer the format has
+    pub(crate) fn finish(self) -> io::Result<()> {
+        if self.format == Format::GeoJson {
+            writeln!(self.writer, "]}}")?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or line break
+pub(crate) fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote, Format, Labelled, Output};
+    use geo::Point;
+    use polylabel::Label;
+    use serde_json::json;
+
+    fn write(format: Format, label_properties: bool) -> String {
+        let label = Label {
+            point: Point::new(2.0, 2.5),
+            distance: 2.0,
+            bound: 0.25,
+        };
+        let labelled = [
+            Labelled {
+                id: Some(json!(7)),
+                properties: json!({"name": "a, b"}),
+                label,
+            },
+            Labelled {
+                id: None,
+                properties: json!(null),
+                label,
+            },
+        ];
+        let mut bytes = vec![];
+        let mut output = Output::new(&mut bytes, format, label_properties).unwrap();
+        for labelled in &labelled {
+            output.write(labelled).unwrap();
+        }
+        output.finish().unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_formats() {
+        let collection: serde_json::Value =
+            serde_json::from_str(&write(Format::GeoJson, false)).unwrap();
+        assert_eq!(collection["features"][0]["id"], json!(7));
+        assert_eq!(
+            collection["features"][1]["geometry"]["coordinates"],
+            json!([2.0, 2.5])
+        );
+        assert_eq!(write(Format::GeoJsonSeq, false).lines().count(), 2);
+        assert_eq!(
+            write(Format::Csv, false),
+            "id,label_x,label_y,properties\n7,2,2.5,\"{\"\"name\"\":\"\"a, b\"\"}\"\n,2,2.5,\n"
+        );
+        assert_eq!(write(Format::Wkt, false), "POINT(2 2.5)\nPOINT(2 2.5)\n");
+        assert_eq!(quote("a"), "a");
+    }
+
+    #[test]
+    fn test_label_properties() {
+        let lines: Vec<_> = write(Format::GeoJsonSeq, true)
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines[0]["properties"],
+            json!({"name": "a, b", "label_radius": 2.0, "label_error_bound": 0.25})
+        );
+        assert_eq!(
+            lines[1]["properties"],
+            json!({"label_radius": 2.0, "label_error_bound": 0.25})
+        );
+        assert_eq!(
+            write(Format::Csv, true).lines().nth(2),
+            Some(",2,2.5,2,0.25,")
+        );
+    }
+}
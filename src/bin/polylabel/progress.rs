@@ -0,0 +1,155 @@
+//! Progress reporting on stderr
+//!
+//! Reporting is disabled unless [`start`] is called, so the other functions do nothing by default.
+
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static PROGRESS: OnceLock<Progress> = OnceLock::new();
+
+// how often progress is redrawn
+const INTERVAL: Duration = Duration::from_millis(200);
+
+struct Progress {
+    started: Instant,
+    drawn: Mutex<Instant>,
+    labelled: AtomicUsize,
+    skipped: AtomicUsize,
+    // the number of features, once known
+    total: AtomicUsize,
+    // the number of bytes read from the input, and its size, if it's a file
+    read: AtomicU64,
+    size: Option<u64>,
+}
+
+impl Progress {
+    // draw the progress line, which is only redrawn periodically, and only on a terminal, until the summary
+    fn draw(&self, last: bool) {
+        let mut stderr = io::stderr().lock();
+        let terminal = stderr.is_terminal();
+        let now = Instant::now();
+        if !last {
+            match self.drawn.try_lock() {
+                Ok(mut drawn) if terminal && now.duration_since(*drawn) >= INTERVAL => *drawn = now,
+                _ => return,
+            }
+        }
+        let labelled = self.labelled.load(Ordering::Relaxed);
+        let skipped = self.skipped.load(Ordering::Relaxed);
+        let done = labelled + skipped;
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let line = if last {
+            format!(
+                "{} features labelled, {} skipped (filtered out, not Polygons or MultiPolygons, or unlabellable), in {:.1}s",
+                labelled, skipped, elapsed
+            )
+        } else {
+            let mut line = format!("{} features, {:.0}/s", done, done as f64 / elapsed);
+            if let Some(fraction) = self.fraction(done).filter(|&fraction| fraction > 0.0) {
+                let eta = elapsed * (1.0 - fraction) / fraction;
+                line.push_str(&format!(", {:.0}%, ETA {:.0}s", fraction * 100.0, eta));
+            }
+            line
+        };
+        if terminal {
+            // overwrite the previous line
+            let _ = write!(stderr, "\r\x1b[K");
+        }
+        let _ = write!(stderr, "{}", line);
+        if last {
+            let _ = writeln!(stderr);
+        }
+    }
+
+    // the fraction of the input which has been processed, if it can be known
+    fn fraction(&self, done: usize) -> Option<f64> {
+        match self.total.load(Ordering::Relaxed) {
+            0 => self
+                .size
+                .filter(|&size| size > 0)
+                .map(|size| self.read.load(Ordering::Relaxed) as f64 / size as f64),
+            total => Some(done as f64 / total as f64),
+        }
+    }
+}
+
+/// Start reporting progress through the input file, or stdin for -
+pub(crate) fn start(input: &str) {
+    let size = match input {
+        "-" => None,
+        path => fs::metadata(path).ok().map(|metadata| metadata.len()),
+    };
+    let _ = PROGRESS.set(Progress {
+        started: Instant::now(),
+        drawn: Mutex::new(Instant::now()),
+        labelled: AtomicUsize::new(0),
+        skipped: AtomicUsize::new(0),
+        total: AtomicUsize::new(0),
+        read: AtomicU64::new(0),
+        size,
+    });
+}
+
+/// Count a feature as labelled if it has a result, or skipped if it doesn't, passing the result through
+pub(crate) fn record<T>(result: Option<T>) -> Option<T> {
+    if let Some(progress) = PROGRESS.get() {
+        let count = match result {
+            Some(_) => &progress.labelled,
+            None => &progress.skipped,
+        };
+        count.fetch_add(1, Ordering::Relaxed);
+        progress.draw(false);
+    }
+    result
+}
+
+/// Set the number of features, once the whole input has been read
+pub(crate) fn set_total(total: usize) {
+    if let Some(progress) = PROGRESS.get() {
+        progress.total.store(total, Ordering::Relaxed);
+    }
+}
+
+/// Count the bytes read from an input
+pub(crate) fn track(reader: Box<dyn BufRead>) -> Box<dyn BufRead> {
+    match PROGRESS.get() {
+        Some(progress) => Box::new(Tracked { reader, progress }),
+        None => reader,
+    }
+}
+
+/// Write a summary of the labelled and skipped features
+pub(crate) fn finish() {
+    if let Some(progress) = PROGRESS.get() {
+        progress.draw(true);
+    }
+}
+
+struct Tracked {
+    reader: Box<dyn BufRead>,
+    progress: &'static Progress,
+}
+
+impl Read for Tracked {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.progress.read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+impl BufRead for Tracked {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.progress
+            .read
+            .fetch_add(amount as u64, Ordering::Relaxed);
+        self.reader.consume(amount)
+    }
+}
@@ -0,0 +1,93 @@
+//! Projecting longitude and latitude geometries, so they can be labelled with a tolerance in metres
+//!
+//! Each geometry is projected using a spherical Lambert azimuthal equal-area projection centred on its bounding
+//! box, which keeps distortion low for all but continent-sized geometries.
+
+use geo::{BoundingRect, Coord, MapCoords, MultiPolygon};
+use polylabel::Label;
+
+// the radius of a sphere with the same surface area as the WGS84 ellipsoid, in metres
+const RADIUS: f64 = 6_371_007.2;
+
+/// A Lambert azimuthal equal-area projection, centred on a longitude and latitude
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Projection {
+    lon: f64,
+    lat: f64,
+}
+
+impl Projection {
+    /// A projection centred on a longitude and latitude
+    fn centred(Coord { x, y }: Coord) -> Self {
+        Self {
+            lon: x.to_radians(),
+            lat: y.to_radians(),
+        }
+    }
+
+    /// Project a longitude and latitude to metres
+    pub(crate) fn forward(&self, (lon, lat): (f64, f64)) -> (f64, f64) {
+        let (lon, lat) = (lon.to_radians() - self.lon, lat.to_radians());
+        let k = (2.0 / (1.0 + self.lat.sin() * lat.sin() + self.lat.cos() * lat.cos() * lon.cos()))
+            .sqrt();
+        (
+            RADIUS * k * lat.cos() * lon.sin(),
+            RADIUS * k * (self.lat.cos() * lat.sin() - self.lat.sin() * lat.cos() * lon.cos()),
+        )
+    }
+
+    /// Project metres back to a longitude and latitude
+    pub(crate) fn inverse(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let rho = x.hypot(y);
+        if rho == 0.0 {
+            return (self.lon.to_degrees(), self.lat.to_degrees());
+        }
+        let c = 2.0 * (rho / (2.0 * RADIUS)).min(1.0).asin();
+        let lat = (c.cos() * self.lat.sin() + y * c.sin() * self.lat.cos() / rho).asin();
+        let lon = self.lon
+            + (x * c.sin()).atan2(rho * self.lat.cos() * c.cos() - y * self.lat.sin() * c.sin());
+        (lon.to_degrees(), lat.to_degrees())
+    }
+
+    /// Project a label position in metres back to a longitude and latitude, leaving its distances in metres
+    pub(crate) fn inverse_label(&self, label: Label<f64>) -> Label<f64> {
+        Label {
+            point: self.inverse(label.point.x_y()).into(),
+            ..label
+        }
+    }
+}
+
+/// Project a longitude and latitude MultiPolygon to metres, returning it and its projection, unless it's empty
+pub(crate) fn project(multi: &MultiPolygon) -> Option<(MultiPolygon, Projection)> {
+    let projection = Projection::centred(multi.bounding_rect()?.center());
+    let projected = multi.map_coords(|Coord { x, y }| projection.forward((x, y)).into());
+    Some((projected, projection))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project, Projection};
+    use geo::{Coord, MultiPolygon, Rect};
+
+    #[test]
+    fn test_projection() {
+        let projection = Projection::centred(Coord { x: 0.0, y: 51.0 });
+        let (x, y) = projection.forward((0.0, 51.0));
+        assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+        let (x, y) = projection.forward((1.0, 51.0));
+        // a degree of longitude at 51° is about 70 km, and parallels curve towards the pole
+        assert!((x - 69_977.0).abs() < 100.0 && y > 0.0);
+        let (lon, lat) = projection.inverse((x, y));
+        assert!((lon - 1.0).abs() < 1e-9 && (lat - 51.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_project() {
+        let square = MultiPolygon::new(vec![Rect::new((-1.0, 50.0), (1.0, 52.0)).to_polygon()]);
+        let (projected, projection) = project(&square).unwrap();
+        let (x, y) = projection.forward((1.0, 52.0));
+        assert!(projected.0[0].exterior().0.contains(&Coord { x, y }));
+        assert!(project(&MultiPolygon::new(vec![])).is_none());
+    }
+}
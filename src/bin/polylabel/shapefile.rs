@@ -0,0 +1,235 @@
+//! Reading polygon shapefiles, and their attributes, as GeoJSON features
+
+use geo::{LineString, MultiPolygon};
+use polylabel::read_shapefile;
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// the terminator of a .dbf file's field descriptors
+const FIELDS_END: u8 = 0x0d;
+
+/// Whether the input is a shapefile, judging by its extension
+pub(crate) fn is_shapefile(input: &str) -> bool {
+    Path::new(input)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("shp"))
+}
+
+/// Read a polygon shapefile as a GeoJSON FeatureCollection
+///
+/// Each record becomes a Feature whose id is its record number, and whose properties are its attributes from the
+/// `.dbf` file, if there is one. Null records have `null` geometries.
+pub(crate) fn read_features(input: &str) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let error = |err: io::Error| format!("{}: {}", input, err);
+    let dbf = Path::new(input).with_extension("dbf");
+    let attributes = match fs::read(&dbf) {
+        Ok(dbf) => read_attributes(&dbf).map_err(error)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+        Err(err) => return Err(format!("{}: {}", dbf.display(), err).into()),
+    };
+    let mut attributes = attributes.into_iter();
+    let features = read_shapefile(input)
+        .map_err(error)?
+        .enumerate()
+        .map(|(i, record)| {
+            Ok(json!({
+                "type": "Feature",
+                "id": i + 1,
+                "properties": attributes.next().map_or(Value::Null, Value::Object),
+                "geometry": record.map_err(error)?.as_ref().map(geometry),
+            }))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(json!({"type": "FeatureCollection", "features": features}))
+}
+
+// a MultiPolygon as a GeoJSON geometry
+fn geometry(multi: &MultiPolygon) -> Value {
+    let ring = |ring: &LineString| -> Value { ring.0.iter().map(|c| json!([c.x, c.y])).collect() };
+    let polygons: Vec<Value> = multi
+        .0
+        .iter()
+        .map(|polygon| {
+            std::iter::once(polygon.exterior())
+                .chain(polygon.interiors())
+                .map(ring)
+                .collect()
+        })
+        .collect();
+    json!({"type": "MultiPolygon", "coordinates": polygons})
+}
+
+/// Read the attributes of each record in a dBASE (`.dbf`) file
+///
+/// Numeric fields become numbers, logical fields booleans, dates `YYYY-MM-DD` strings, and other fields strings.
+/// Blank values are `null`. Text is decoded as UTF-8 if it's valid, and as Latin-1 otherwise.
+fn read_attributes(dbf: &[u8]) -> io::Result<Vec<Map<String, Value>>> {
+    let n_records = u32::from_le_bytes(bytes(dbf, 4)?) as usize;
+    let header_length = u16::from_le_bytes(bytes(dbf, 8)?) as usize;
+    let record_length = u16::from_le_bytes(bytes(dbf, 10)?) as usize;
+    // each field descriptor is 32 bytes, and a record's fields follow its deletion flag
+    let mut fields = vec![];
+    let mut offset = 1;
+    for descriptor in dbf
+        .get(32..header_length)
+        .ok_or_else(|| invalid("the .dbf file's header is truncated"))?
+        .chunks_exact(32)
+        .take_while(|descriptor| descriptor[0] != FIELDS_END)
+    {
+        let name = descriptor[..11].split(|&byte| byte == 0).next().unwrap();
+        let length = descriptor[16] as usize;
+        fields.push(Field {
+            name: text(name).trim().to_string(),
+            kind: descriptor[11],
+            decimals: descriptor[17],
+            range: offset..offset + length,
+        });
+        offset += length;
+    }
+    (0..n_records)
+        .map(|i| {
+            let start = header_length + i * record_length;
+            let record = dbf
+                .get(start..start + record_length)
+                .ok_or_else(|| invalid("the .dbf file is truncated"))?;
+            fields
+                .iter()
+                .map(|field| {
+                    let value = record
+                        .get(field.range.clone())
+                        .ok_or_else(|| invalid("a .dbf record is shorter than its fields"))?;
+                    Ok((field.name.clone(), field.value(value)))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// a .dbf file's field descriptor
+struct Field {
+    name: String,
+    // the field's type, e.g. C for character, or N for numeric
+    kind: u8,
+    decimals: u8,
+    // the field's position within each record
+    range: std::ops::Range<usize>,
+}
+
+impl Field {
+    fn value(&self, bytes: &[u8]) -> Value {
+        let value = text(bytes);
+        let value = value.trim_matches(|c: char| c == ' ' || c == '\0');
+        if value.is_empty() {
+            return Value::Null;
+        }
+        match self.kind {
+            b'N' | b'F' if self.decimals == 0 => value
+                .parse::<i64>()
+                .map(Value::from)
+                .or_else(|_| value.parse::<f64>().map(Value::from))
+                .unwrap_or(Value::Null),
+            // overflowing values are filled with asterisks
+            b'N' | b'F' => value.parse::<f64>().map_or(Value::Null, Value::from),
+            b'L' => match value {
+                "T" | "t" | "Y" | "y" => Value::Bool(true),
+                "F" | "f" | "N" | "n" => Value::Bool(false),
+                _ => Value::Null,
+            },
+            b'D' if value.len() == 8 && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+                Value::from(format!("{}-{}-{}", &value[..4], &value[4..6], &value[6..]))
+            }
+            _ => Value::from(value),
+        }
+    }
+}
+
+// decode text as UTF-8, or Latin-1 if it isn't valid UTF-8
+fn text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&byte| char::from(byte)).collect(),
+    }
+}
+
+fn bytes<const N: usize>(dbf: &[u8], offset: usize) -> io::Result<[u8; N]> {
+    dbf.get(offset..offset + N)
+        .and_then(|field| field.try_into().ok())
+        .ok_or_else(|| invalid("the .dbf file's header is truncated"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{geometry, is_shapefile, read_attributes};
+    use geo::{MultiPolygon, Rect};
+    use serde_json::{json, Value};
+
+    // encode a .dbf file, given its fields' names, types, lengths, and decimal counts, and its records
+    fn dbf(fields: &[(&str, u8, u8, u8)], records: &[&str]) -> Vec<u8> {
+        let header_length = 32 + fields.len() * 32 + 1;
+        let record_length = 1 + fields.iter().map(|field| field.2 as usize).sum::<usize>();
+        let mut dbf = vec![3, 126, 1, 1];
+        dbf.extend((records.len() as u32).to_le_bytes());
+        dbf.extend((header_length as u16).to_le_bytes());
+        dbf.extend((record_length as u16).to_le_bytes());
+        dbf.extend([0; 20]);
+        for &(name, kind, length, decimals) in fields {
+            let mut descriptor = [0; 32];
+            descriptor[..name.len()].copy_from_slice(name.as_bytes());
+            descriptor[11] = kind;
+            descriptor[16] = length;
+            descriptor[17] = decimals;
+            dbf.extend(descriptor);
+        }
+        dbf.push(0x0d);
+        for record in records {
+            dbf.push(b' ');
+            dbf.extend(record.as_bytes());
+        }
+        dbf.push(0x1a);
+        dbf
+    }
+
+    #[test]
+    fn test_read_attributes() {
+        let fields = [
+            ("NAME", b'C', 7, 0),
+            ("POP", b'N', 6, 0),
+            ("AREA", b'N', 5, 2),
+            ("CITY", b'L', 1, 0),
+            ("FOUNDED", b'D', 8, 0),
+        ];
+        // "Zürich" is 7 bytes long in UTF-8
+        let records = [
+            ["Zürich", "   402", "87.88", "T", "12180101"].concat(),
+            ["       ", "******", "     ", "?", "        "].concat(),
+        ];
+        let dbf = dbf(&fields, &[&records[0], &records[1]]);
+        let attributes = read_attributes(&dbf).unwrap();
+        assert_eq!(
+            Value::from(attributes[0].clone()),
+            json!({"NAME": "Zürich", "POP": 402, "AREA": 87.88, "CITY": true, "FOUNDED": "1218-01-01"})
+        );
+        assert!(attributes[1].values().all(Value::is_null));
+        assert!(read_attributes(&dbf[..dbf.len() - 10]).is_err());
+    }
+
+    #[test]
+    fn test_geometry() {
+        let square = MultiPolygon::new(vec![Rect::new((0.0, 0.0), (1.0, 1.0)).to_polygon()]);
+        let geometry = geometry(&square);
+        assert_eq!(geometry["type"], "MultiPolygon");
+        assert_eq!(
+            polylabel::geojson_multi_polygon(&geometry.to_string()),
+            Ok(square)
+        );
+        assert!(is_shapefile("parcels.SHP"));
+        assert!(!is_shapefile("parcels.geojson"));
+    }
+}
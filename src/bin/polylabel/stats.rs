@@ -0,0 +1,145 @@
+//! Per-feature search statistics, for finding the features which dominate a run's time
+//!
+//! Statistics are only gathered once [`start`] is called, so the other functions do nothing by default.
+
+use crate::output::quote;
+use polylabel::SearchStats;
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static STATS: OnceLock<Stats> = OnceLock::new();
+
+struct Stats {
+    started: Instant,
+    // where the statistics are written, or - for stderr
+    output: String,
+    features: Mutex<Vec<FeatureStats>>,
+}
+
+// a feature's statistics, summed over the parts which were searched
+struct FeatureStats {
+    number: usize,
+    id: Option<Value>,
+    time: Duration,
+    parts: usize,
+    cells: usize,
+    max_queue: usize,
+}
+
+/// Start gathering statistics, which are written to the output file, or stderr for -
+pub(crate) fn start(output: &str) {
+    let _ = STATS.set(Stats {
+        started: Instant::now(),
+        output: output.to_string(),
+        features: Mutex::new(vec![]),
+    });
+}
+
+/// Record the time taken to label a feature, given its number in the input, and its parts' search statistics
+pub(crate) fn record(
+    number: usize,
+    id: Option<&Value>,
+    time: Duration,
+    parts: impl IntoIterator<Item = SearchStats>,
+) {
+    if let Some(stats) = STATS.get() {
+        let mut feature = FeatureStats {
+            number,
+            id: id.cloned(),
+            time,
+            parts: 0,
+            cells: 0,
+            max_queue: 0,
+        };
+        for part in parts {
+            feature.parts += 1;
+            feature.cells += part.cells;
+            feature.max_queue = feature.max_queue.max(part.max_queue);
+        }
+        stats.features.lock().unwrap().push(feature);
+    }
+}
+
+/// Write the statistics as CSV, slowest feature first, and a summary including the total time on stderr
+pub(crate) fn finish() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let stats = match STATS.get() {
+        Some(stats) => stats,
+        None => return Ok(()),
+    };
+    let elapsed = stats.started.elapsed();
+    let mut features = std::mem::take(&mut *stats.features.lock().unwrap());
+    features.sort_by(|a, b| b.time.cmp(&a.time).then(a.number.cmp(&b.number)));
+    let mut writer: Box<dyn Write> = match stats.output.as_str() {
+        "-" => Box::new(io::stderr().lock()),
+        path => Box::new(BufWriter::new(
+            fs::File::create(path).map_err(|err| format!("{}: {}", path, err))?,
+        )),
+    };
+    write_stats(&mut writer, &features)?;
+    writer.flush()?;
+    let mut summary = format!(
+        "{} features searched in {:.1}s",
+        features.len(),
+        elapsed.as_secs_f64()
+    );
+    if let Some(slowest) = features.first() {
+        summary.push_str(&format!(
+            "; the slowest, feature {}, took {:.1} ms",
+            slowest.number,
+            slowest.time.as_secs_f64() * 1000.0
+        ));
+    }
+    eprintln!("{}", summary);
+    Ok(())
+}
+
+fn write_stats(writer: &mut dyn Write, features: &[FeatureStats]) -> io::Result<()> {
+    writeln!(writer, "feature,id,time_ms,parts,cells,max_queue")?;
+    for feature in features {
+        let id = match &feature.id {
+            Some(Value::String(id)) => quote(id),
+            Some(id) => quote(&id.to_string()),
+            None => String::new(),
+        };
+        writeln!(
+            writer,
+            "{},{},{:.3},{},{},{}",
+            feature.number,
+            id,
+            feature.time.as_secs_f64() * 1000.0,
+            feature.parts,
+            feature.cells,
+            feature.max_queue
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_stats, FeatureStats};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn test_write_stats() {
+        let features = [FeatureStats {
+            number: 3,
+            id: Some(json!("a,b")),
+            time: Duration::from_micros(1500),
+            parts: 2,
+            cells: 40,
+            max_queue: 12,
+        }];
+        let mut output = vec![];
+        write_stats(&mut output, &features).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "feature,id,time_ms,parts,cells,max_queue\n3,\"a,b\",1.500,2,40,12\n"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Labelling tilesets: directories of Mapbox Vector Tiles, laid out as `{z}/{x}/{y}.pbf`
+
+use polylabel::{encode_label_layer, label_tile};
+use rayon::prelude::*;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the extensions of tile files
+const EXTENSIONS: [&str; 2] = ["pbf", "mvt"];
+
+/// Label the polygon features in each tile of the `input` tileset, writing the label positions to a tileset of
+/// the same tiles in the `output` directory
+///
+/// Each output tile holds a point layer for each of its input tile's polygon layers, named after it with a
+/// `_label` suffix. Label positions are in tile coordinates, and those further than `buffer` tile units outside
+/// a tile are discarded, so features which are in several tiles' buffers aren't labelled in all of them. Tiles
+/// without polygon layers aren't written. Tiles are labelled in parallel, using the current thread pool.
+pub(crate) fn run_tiles(
+    input: &str,
+    output: &str,
+    tolerance: f64,
+    buffer: u32,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if output == "-" {
+        return Err("tilesets must be written to an output directory".into());
+    }
+    let tiles = find_tiles(Path::new(input)).map_err(|err| format!("{}: {}", input, err))?;
+    tiles.par_iter().try_for_each(|tile| {
+        label_tile_file(
+            &Path::new(input).join(tile),
+            &Path::new(output).join(tile),
+            tolerance,
+            buffer,
+        )
+        .map_err(|err| format!("{}: {}", tile.display(), err))
+    })?;
+    Ok(())
+}
+
+// label a tile file, writing its label positions to the output file if it has any polygon layers
+fn label_tile_file(
+    input: &Path,
+    output: &Path,
+    tolerance: f64,
+    buffer: u32,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(labels) = label_tile_bytes(&fs::read(input)?, tolerance, buffer)? {
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(output, labels)?;
+    }
+    Ok(())
+}
+
+// the paths of a tileset's tiles, relative to its directory, in zoom, column, and row order
+fn find_tiles(tileset: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut tiles = vec![];
+    for z in numbered(tileset, None)? {
+        for x in numbered(&tileset.join(&z), None)? {
+            for y in numbered(&tileset.join(&z).join(&x), Some(&EXTENSIONS))? {
+                tiles.push([&z, &x, &y].iter().collect());
+            }
+        }
+    }
+    Ok(tiles)
+}
+
+// the names of a directory's entries whose names (or stems, for files with one of the extensions) are numbers,
+// in numeric order. Directories are listed if `extensions` is `None`, and files otherwise
+fn numbered(directory: &Path, extensions: Option<&[&str]>) -> std::io::Result<Vec<String>> {
+    let mut entries = vec![];
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        let number = match extensions {
+            None if path.is_dir() => path.file_name(),
+            Some(extensions)
+                if path.is_file()
+                    && path.extension().is_some_and(|extension| {
+                        extensions
+                            .iter()
+                            .any(|&wanted| extension.eq_ignore_ascii_case(wanted))
+                    }) =>
+            {
+                path.file_stem()
+            }
+            _ => None,
+        };
+        if let Some(number) = number
+            .and_then(|number| number.to_str())
+            .and_then(|number| number.parse::<u32>().ok())
+        {
+            entries.push((number, entry.file_name().to_string_lossy().into_owned()));
+        }
+    }
+    entries.sort();
+    Ok(entries.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Encode the label positions of a tile's polygon layers as a tile, or `None` if it has no polygon layers
+fn label_tile_bytes(tile: &[u8], tolerance: f64, buffer: u32) -> Result<Option<Vec<u8>>, String> {
+    // gzip's magic number
+    if tile.starts_with(&[0x1f, 0x8b]) {
+        return Err(
+            "gzip-compressed tiles aren't supported; decompress the tileset first".to_string(),
+        );
+    }
+    let layers = label_tile(tile, tolerance, buffer).ok_or("the file isn't a vector tile")?;
+    if layers.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        layers
+            .iter()
+            .flat_map(|layer| encode_label_layer(layer, &format!("{}_label", layer.name)))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_tiles, label_tile_bytes};
+    use polylabel::label_tile;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // a tile holding a "buildings" layer, whose only feature is a square
+    const TILE: [u8; 37] = [
+        0x1a, 0x23, 0x78, 0x02, 0x0a, 0x09, 0x62, 0x75, 0x69, 0x6c, 0x64, 0x69, 0x6e, 0x67, 0x73,
+        0x12, 0x11, 0x18, 0x03, 0x22, 0x0d, 0x09, 0x00, 0x00, 0x1a, 0x80, 0x01, 0x00, 0x00, 0x80,
+        0x01, 0x7f, 0x00, 0x0f, 0x28, 0x80, 0x20,
+    ];
+
+    #[test]
+    fn test_label_tile_bytes() {
+        let labels = label_tile_bytes(&TILE, 1.0, 0).unwrap().unwrap();
+        // the label tile holds a point layer, which holds no polygons
+        assert!(label_tile(&labels, 1.0, 0).unwrap().is_empty());
+        assert!(labels.windows(15).any(|name| name == b"buildings_label"));
+        assert!(label_tile_bytes(&[], 1.0, 0).unwrap().is_none());
+        assert!(label_tile_bytes(&[0x1f, 0x8b, 0x08], 1.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_find_tiles() {
+        let tileset = std::env::temp_dir().join(format!("polylabel-tiles-{}", std::process::id()));
+        for path in [
+            "2/10/3.pbf",
+            "2/10/12.pbf",
+            "2/3/1.mvt",
+            "10/0/0.pbf",
+            "2/3/notes.txt",
+        ] {
+            let path = tileset.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, TILE).unwrap();
+        }
+        let tiles = find_tiles(&tileset);
+        fs::remove_dir_all(&tileset).unwrap();
+        let expected: Vec<PathBuf> = ["2/3/1.mvt", "2/10/3.pbf", "2/10/12.pbf", "10/0/0.pbf"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(tiles.unwrap(), expected);
+    }
+}
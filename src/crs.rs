@@ -0,0 +1,143 @@
+//! Geometries tagged with the kind of coordinates they're in, so their units can't be mistaken
+//!
+//! Labelling a longitude and latitude Polygon with Euclidean distances treats degrees as lengths, which distorts
+//! label positions away from the equator, and makes the tolerance a number of degrees rather than metres. Tagging
+//! a geometry as [`Geographic`] means its label position can only be calculated with an explicit
+//! [`Metric`](enum.Metric.html).
+
+use crate::errors::PolylabelError;
+use crate::options::{Label, Metric, PolylabelOptions};
+use crate::pole::PoleOfInaccessibility;
+use crate::{polylabel_with_options, PreparedPolygon};
+use geo::{GeoFloat, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+/// A geometry whose coordinates are projected, in linear units such as metres
+///
+/// Its label position is calculated using Euclidean distances, with a tolerance in its own units. It implements
+/// [`PoleOfInaccessibility`](trait.PoleOfInaccessibility.html) if the wrapped geometry does.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{PoleOfInaccessibility, Projected};
+/// use geo::{Point, Rect};
+///
+/// let square = Projected(Rect::new((500_000.0, 0.0), (500_004.0, 4.0)).to_polygon());
+/// assert_eq!(square.label(&0.1).unwrap().distance, 2.0);
+/// assert_eq!(square.pole_of_inaccessibility(&0.1).unwrap(), Point::new(500_002.0, 2.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projected<G>(pub G);
+
+/// A geometry whose coordinates are longitude and latitude, in degrees
+///
+/// Its label position can only be calculated with an explicit [`Metric`](enum.Metric.html):
+/// [`Metric::Haversine`](enum.Metric.html#variant.Haversine) (or `Metric::Geodesic`) to measure distances in
+/// metres, or one of the planar metrics to measure them in degrees, knowingly. It doesn't implement
+/// [`PoleOfInaccessibility`](trait.PoleOfInaccessibility.html), which measures in the coordinates' units.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{Geographic, Metric};
+/// use geo::{Contains, Rect};
+///
+/// let poly = Geographic(Rect::new((10.0, 65.0), (30.0, 70.0)).to_polygon());
+/// // a tolerance of 1 km
+/// let label = poly.label(&1000.0, Metric::Haversine).unwrap();
+/// assert!(poly.0.contains(&label.point));
+/// assert!(label.distance > 270_000.0 && label.distance < 280_000.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geographic<G>(pub G);
+
+impl<T> Projected<Polygon<T>>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    /// Calculate the Polygon's ideal label position, its distance from the Polygon's edges, and a bound on the
+    /// error of that distance, in the Polygon's units
+    pub fn label(&self, tolerance: &T) -> Result<Label<T>, PolylabelError> {
+        polylabel_with_options(&self.0, &PolylabelOptions::new(*tolerance))
+    }
+
+    /// Prepare the Polygon for repeated label position calculation
+    pub fn prepare(&self) -> Result<PreparedPolygon<T>, PolylabelError> {
+        PreparedPolygon::new(&self.0)
+    }
+}
+
+impl<T> Geographic<Polygon<T>>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    /// Calculate the Polygon's ideal label position, its distance from the Polygon's edges, and a bound on the
+    /// error of that distance, measuring distances using a metric
+    ///
+    /// See [`Metric`](enum.Metric.html) for the units of the tolerance and the label's distances.
+    pub fn label(&self, tolerance: &T, metric: Metric) -> Result<Label<T>, PolylabelError> {
+        polylabel_with_options(&self.0, &PolylabelOptions::new(*tolerance).metric(metric))
+    }
+
+    /// Prepare the Polygon for repeated label position calculation, measuring distances using a metric
+    pub fn prepare(&self, metric: Metric) -> Result<PreparedPolygon<T>, PolylabelError> {
+        PreparedPolygon::with_metric(&self.0, metric)
+    }
+}
+
+impl<T, G> PoleOfInaccessibility<T> for Projected<G>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    G: PoleOfInaccessibility<T>,
+{
+    type Output = G::Output;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        self.0.pole_of_inaccessibility(tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Geographic, Projected};
+    use crate::{polylabel, Metric, PoleOfInaccessibility};
+    use geo::{MultiPolygon, Polygon, Rect};
+
+    #[test]
+    fn test_tagged_polygons() {
+        // an L shape at 70°N, whose eastern arm is much wider than its northern arm in metres
+        let coords = vec![
+            (0.0, 70.0),
+            (12.0, 70.0),
+            (12.0, 72.0),
+            (2.0, 72.0),
+            (2.0, 78.0),
+            (0.0, 78.0),
+            (0.0, 70.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let projected = Projected(poly.clone());
+        assert_eq!(
+            projected.pole_of_inaccessibility(&0.1).unwrap(),
+            polylabel(&poly, &0.1).unwrap()
+        );
+        assert_eq!(
+            projected.label(&0.1).unwrap(),
+            projected.prepare().unwrap().label(&0.1)
+        );
+        let geographic = Geographic(poly);
+        let label = geographic.label(&100.0, Metric::Haversine).unwrap();
+        assert!(label.point.x() > 4.0);
+        assert_eq!(
+            label,
+            geographic.prepare(Metric::Haversine).unwrap().label(&100.0)
+        );
+        // other wrapped geometries forward to their own implementations
+        let multi = Projected(MultiPolygon::new(vec![
+            Rect::new((0.0, 0.0), (2.0, 2.0)).to_polygon()
+        ]));
+        assert!(multi.pole_of_inaccessibility(&0.1).unwrap().is_some());
+    }
+}
@@ -3,11 +3,22 @@
 use thiserror::Error;
 
 /// Possible Polylabel errors
+///
+/// New errors may be added in minor releases, so matches on a `PolylabelError` need a wildcard arm.
 #[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 #[error("{0}")]
 pub enum PolylabelError {
     #[error("Couldn't calculate a centroid for the input Polygon")]
     CentroidCalculation,
     #[error("Couldn't calculate a bounding box for the input Polygon")]
     RectCalculation,
+    #[error("The affine transform isn't invertible")]
+    NonInvertibleTransform,
+    #[error("The aspect ratio must be positive and finite")]
+    InvalidAspectRatio,
+    #[error("Each Polygon needs a label size")]
+    LabelSizeMismatch,
+    #[error("The GeoJSON isn't valid, or isn't of the expected type")]
+    InvalidGeoJson,
 }
@@ -1,91 +1,2140 @@
-use crate::polylabel;
-use geo::{GeoFloat, LineString, Point, Polygon};
-use libc::{c_double, c_void, size_t};
+#[cfg(feature = "arrow")]
+use crate::arrow::{ArrowArray, ArrowSchema};
+use crate::errors::PolylabelError;
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use crate::prepared::PreparedPolygon;
+use geo::{Coord, GeoFloat, LineString, MultiPolygon, Point, Polygon};
+use libc::c_char;
+use libc::{c_double, c_float, c_int, c_void, size_t};
+use num_traits::FromPrimitive;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::cell::RefCell;
 use std::f64;
+#[cfg(any(feature = "geojson", feature = "wkt"))]
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::fmt::{Debug, Display};
+use std::iter::{self, Sum};
+use std::mem::{self, MaybeUninit};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The version of the FFI's ABI, which is incremented whenever a struct, enum, or function signature changes
+/// incompatibly. Compare it to [`polylabel_abi_version`](fn.polylabel_abi_version.html) when loading the library.
+pub const POLYLABEL_ABI_VERSION: u32 = 1;
 
 /// Wrapper for a void pointer to a sequence of [`Array`](struct.Array.html)s, and the sequence length. Used for FFI.
 ///
-/// Each sequence entry represents an inner Polygon ring.
-#[repr(C)]
-#[derive(Copy, Clone)]
-pub struct WrapperArray {
-    pub data: *const Array,
-    pub len: size_t,
+/// Each sequence entry represents an inner Polygon ring. `data` may be null if `len` is 0.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct WrapperArray {
+    pub data: *const Array,
+    pub len: size_t,
+}
+
+/// Wrapper for a void pointer to a sequence of 2-element arrays representing points, and the sequence length. Used for FFI.
+///
+/// Used for the outer Polygon shell. `data` is a `Vec<[c_double; 2]>`, and may only be null if `len` is 0.
+#[repr(C)]
+pub struct Array {
+    pub data: *const c_void,
+    pub len: size_t,
+}
+
+/// Single-precision equivalent of [`WrapperArray`](struct.WrapperArray.html). Used for FFI.
+///
+/// Each sequence entry represents an inner Polygon ring. `data` may be null if `len` is 0.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct WrapperArrayF32 {
+    pub data: *const ArrayF32,
+    pub len: size_t,
+}
+
+/// Single-precision equivalent of [`Array`](struct.Array.html). Used for FFI.
+///
+/// `data` is a `Vec<[c_float; 2]>`, and may only be null if `len` is 0.
+#[repr(C)]
+pub struct ArrayF32 {
+    pub data: *const c_void,
+    pub len: size_t,
+}
+
+/// Severity of a message passed to a [`PolylabelLogCallback`](type.PolylabelLogCallback.html)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PolylabelLogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+/// A logging callback, called with a message's severity, the message as a NUL-terminated string (which is
+/// only valid during the call), and the `user_data` pointer passed to
+/// [`polylabel_set_log_callback`](fn.polylabel_set_log_callback.html)
+pub type PolylabelLogCallback =
+    extern "C" fn(level: PolylabelLogLevel, message: *const c_char, user_data: *mut c_void);
+
+/// FFI struct representing a Polygon: an exterior ring, and zero or more interior rings. Used for batch FFI.
+#[repr(C)]
+pub struct PolygonFFI {
+    pub outer: Array,
+    pub inners: WrapperArray,
+}
+
+/// FFI struct for returned optimum Polygon label position
+#[repr(C)]
+pub struct Position {
+    pub x_pos: c_double,
+    pub y_pos: c_double,
+}
+
+/// FFI struct for a returned single-precision optimum Polygon label position
+#[repr(C)]
+pub struct PositionF32 {
+    pub x_pos: c_float,
+    pub y_pos: c_float,
+}
+
+impl From<Point<c_float>> for PositionF32 {
+    fn from(point: Point<c_float>) -> PositionF32 {
+        PositionF32 {
+            x_pos: point.x(),
+            y_pos: point.y(),
+        }
+    }
+}
+
+/// Outcome of an FFI label position calculation
+///
+/// Other than `Ok`, `NullPointer`, `InvalidLength`, `InvalidInput`, `Panic`, and `Cancelled`, these mirror the
+/// variants of [`PolylabelError`](errors/enum.PolylabelError.html).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PolylabelStatus {
+    /// The label position was calculated successfully
+    Ok = 0,
+    /// Couldn't calculate a centroid for the input Polygon
+    CentroidCalculation = 1,
+    /// Couldn't calculate a bounding box for the input Polygon
+    RectCalculation = 2,
+    /// A required pointer argument was null
+    NullPointer = 3,
+    /// A length or offset argument was inconsistent
+    InvalidLength = 4,
+    /// The input geometry couldn't be parsed, or wasn't a Polygon or MultiPolygon
+    InvalidInput = 5,
+    /// An internal error (a Rust panic) occurred
+    Panic = 6,
+    /// The calculation was cancelled before it finished
+    Cancelled = 7,
+}
+
+// Converting an error records its description as the thread's last error
+impl From<PolylabelError> for PolylabelStatus {
+    fn from(err: PolylabelError) -> PolylabelStatus {
+        set_last_error(&err);
+        match err {
+            PolylabelError::CentroidCalculation => PolylabelStatus::CentroidCalculation,
+            PolylabelError::RectCalculation => PolylabelStatus::RectCalculation,
+            // transforms, rectangles, and label placements can't be requested across the FFI boundary, which
+            // parses GeoJSON itself
+            PolylabelError::NonInvertibleTransform
+            | PolylabelError::InvalidAspectRatio
+            | PolylabelError::LabelSizeMismatch
+            | PolylabelError::InvalidGeoJson => PolylabelStatus::InvalidInput,
+        }
+    }
+}
+
+/// Which parts of a MultiPolygon to calculate label positions for. Mirrors [`Parts`](enum.Parts.html).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PolylabelParts {
+    /// Only the part with the largest area
+    Largest = 0,
+    /// Every part
+    All = 1,
+}
+
+/// FFI struct for a returned optimum Polygon label position, its distance from the Polygon's edges,
+/// and the outcome of the calculation
+///
+/// If `status` is `Cancelled`, the other fields hold the best label position found before the calculation
+/// was cancelled. Otherwise, if it isn't `Ok`, they're `NaN`.
+///
+/// Its layout is fixed: three `double`s, followed by the status as a 32-bit `int` and four bytes of padding, 32
+/// bytes in all. It can be mirrored directly (e.g. by a Dart `Struct` with three `@Double()` fields and an
+/// `@Int32()` field), and written to caller-allocated memory by functions such as
+/// [`polylabel_compute`](fn.polylabel_compute.html), so repeated calculations needn't allocate.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PositionResult {
+    pub x: c_double,
+    pub y: c_double,
+    pub distance: c_double,
+    pub status: PolylabelStatus,
+}
+
+impl PositionResult {
+    fn error(status: PolylabelStatus) -> Self {
+        PositionResult {
+            x: f64::NAN,
+            y: f64::NAN,
+            distance: f64::NAN,
+            status,
+        }
+    }
+}
+
+/// A sequence of [`PositionResult`](struct.PositionResult.html)s allocated by the library, and the sequence
+/// length. Used for FFI functions which return a variable number of label positions.
+///
+/// Must be freed using [`polylabel_position_array_free`](fn.polylabel_position_array_free.html). `data` is
+/// null if `len` is 0.
+#[repr(C)]
+pub struct PositionArray {
+    pub data: *mut PositionResult,
+    pub len: size_t,
+}
+
+impl From<Vec<PositionResult>> for PositionArray {
+    fn from(results: Vec<PositionResult>) -> Self {
+        let len = results.len();
+        let data = if len == 0 {
+            ptr::null_mut()
+        } else {
+            Box::into_raw(results.into_boxed_slice()) as *mut PositionResult
+        };
+        PositionArray { data, len }
+    }
+}
+
+impl From<Label<c_double>> for Position {
+    fn from(label: Label<c_double>) -> Position {
+        label.point.into()
+    }
+}
+
+impl From<Label<c_double>> for PositionResult {
+    fn from(label: Label<c_double>) -> PositionResult {
+        PositionResult {
+            x: label.point.x(),
+            y: label.point.y(),
+            distance: label.distance,
+            status: PolylabelStatus::Ok,
+        }
+    }
+}
+
+// convert a Polylabel result Point into values that can be sent across the FFI boundary
+impl<T> From<Point<T>> for Position
+where
+    T: GeoFloat,
+{
+    fn from(point: Point<T>) -> Position {
+        Position {
+            x_pos: point.x().to_f64().unwrap() as c_double,
+            y_pos: point.y().to_f64().unwrap() as c_double,
+        }
+    }
+}
+
+thread_local! {
+    // A description of the most recent failure on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Display) {
+    // NULs would truncate the message, so they can't be part of it
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+// The thread's last error, for passing to another thread
+fn last_error() -> Option<CString> {
+    LAST_ERROR.with(|last| last.borrow().clone())
+}
+
+// Record a description of a failure as the thread's last error, returning its status
+fn fail(status: PolylabelStatus, message: impl Display) -> PolylabelStatus {
+    set_last_error(message);
+    status
+}
+
+// Prefix the thread's last error with the context in which it occurred, returning the failure's status
+fn context(status: PolylabelStatus, context: impl Display) -> PolylabelStatus {
+    let message = last_error().map_or_else(String::new, |last| last.to_string_lossy().into_owned());
+    fail(status, format_args!("{}: {}", context, message))
+}
+
+// The callback which receives log messages, and its user data
+#[derive(Copy, Clone)]
+struct Logger {
+    callback: PolylabelLogCallback,
+    user_data: *mut c_void,
+}
+
+// The caller is responsible for the user data being usable from any thread
+unsafe impl Send for Logger {}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+// Pass a message to the logging callback, if one has been set. The message is only formatted if it's needed
+fn log(level: PolylabelLogLevel, message: impl Display) {
+    let logger = *LOGGER.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(Logger {
+        callback,
+        user_data,
+    }) = logger
+    {
+        if let Ok(message) = CString::new(message.to_string()) {
+            callback(level, message.as_ptr(), user_data);
+        }
+    }
+}
+
+// Polygon::new closes unclosed rings, which callers may not expect. Ring 0 is the exterior
+fn warn_unclosed(ring: usize, closed: bool) {
+    if closed {
+        return;
+    }
+    match ring {
+        0 => log(
+            PolylabelLogLevel::Warn,
+            "the exterior ring wasn't closed, so it's been closed",
+        ),
+        i => log(
+            PolylabelLogLevel::Warn,
+            format_args!("interior ring {} wasn't closed, so it's been closed", i - 1),
+        ),
+    }
+}
+
+// A tolerance which the coordinates aren't precise enough to achieve is raised by the search
+fn warn_tolerance<T>(prepared: &PreparedPolygon<T>, tolerance: T)
+where
+    T: GeoFloat + FromPrimitive + Sum + Debug,
+{
+    let used = prepared.tolerance(tolerance);
+    if used > tolerance {
+        log(
+            PolylabelLogLevel::Warn,
+            format_args!(
+                "a tolerance of {:?} is finer than the coordinates' precision, so {:?} has been used",
+                tolerance, used
+            ),
+        );
+    }
+}
+
+// Check that a caller-supplied sequence's pointer isn't null (unless it's empty), and that its
+// length can be addressed, without creating a reference to its contents
+fn check<T>(data: *const T, len: size_t) -> Result<(), PolylabelStatus> {
+    if len == 0 {
+        return Ok(());
+    }
+    if data.is_null() {
+        return Err(fail(
+            PolylabelStatus::NullPointer,
+            format_args!("null pointer with a length of {}", len),
+        ));
+    }
+    match len.checked_mul(mem::size_of::<T>()) {
+        Some(size) if size <= isize::MAX as usize => Ok(()),
+        _ => Err(fail(
+            PolylabelStatus::InvalidLength,
+            format_args!("a length of {} is too large to be addressed", len),
+        )),
+    }
+}
+
+// Borrow a caller-supplied sequence, checking that its pointer isn't null (unless it's empty),
+// and that its length can be addressed
+unsafe fn borrow<'a, T>(data: *const T, len: size_t) -> Result<&'a [T], PolylabelStatus> {
+    check(data, len)?;
+    if len == 0 {
+        return Ok(&[]);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+// Borrow a ring's points from the caller, without copying them
+fn reconstitute(arr: &Array) -> Result<&[[f64; 2]], PolylabelStatus> {
+    unsafe { borrow(arr.data as *const [f64; 2], arr.len) }
+}
+
+fn reconstitute2<'a>(arr: WrapperArray) -> Result<Vec<&'a [[f64; 2]]>, PolylabelStatus> {
+    let arrays: &'a [Array] =
+        unsafe { borrow(arr.data, arr.len) }.map_err(|status| context(status, "interior rings"))?;
+    arrays
+        .iter()
+        .enumerate()
+        .map(|(i, arr)| {
+            reconstitute(arr).map_err(|status| context(status, format_args!("interior ring {}", i)))
+        })
+        .collect()
+}
+
+fn reconstitute_f32(arr: &ArrayF32) -> Result<&[[f32; 2]], PolylabelStatus> {
+    unsafe { borrow(arr.data as *const [f32; 2], arr.len) }
+}
+
+fn reconstitute2_f32<'a>(arr: WrapperArrayF32) -> Result<Vec<&'a [[f32; 2]]>, PolylabelStatus> {
+    let arrays: &'a [ArrayF32] =
+        unsafe { borrow(arr.data, arr.len) }.map_err(|status| context(status, "interior rings"))?;
+    arrays
+        .iter()
+        .enumerate()
+        .map(|(i, arr)| {
+            reconstitute_f32(arr)
+                .map_err(|status| context(status, format_args!("interior ring {}", i)))
+        })
+        .collect()
+}
+
+/// A Polygon's rings, borrowed from its FFI representation
+///
+/// Unlike raw pointers, borrowed rings can be shared between threads, so Polygons can be
+/// built where (and only when) they're needed. This isn't zero-copy: building a Polygon copies
+/// its coordinates once, and indexing it copies its segments again.
+struct Rings<'a, T = c_double> {
+    exterior: &'a [[T; 2]],
+    interiors: Vec<&'a [[T; 2]]>,
+}
+
+impl<'a> Rings<'a> {
+    fn new(outer: &'a Array, inners: WrapperArray) -> Result<Self, PolylabelStatus> {
+        let exterior = reconstitute(outer).map_err(|status| context(status, "exterior ring"))?;
+        Rings::from_parts(exterior, || reconstitute2(inners))
+    }
+}
+
+impl<'a> Rings<'a, c_float> {
+    fn new_f32(outer: &'a ArrayF32, inners: WrapperArrayF32) -> Result<Self, PolylabelStatus> {
+        let exterior =
+            reconstitute_f32(outer).map_err(|status| context(status, "exterior ring"))?;
+        Rings::from_parts(exterior, || reconstitute2_f32(inners))
+    }
+}
+
+impl<'a, T> Rings<'a, T>
+where
+    T: GeoFloat,
+{
+    // the exterior mustn't be empty
+    fn from_parts<F>(exterior: &'a [[T; 2]], interiors: F) -> Result<Self, PolylabelStatus>
+    where
+        F: FnOnce() -> Result<Vec<&'a [[T; 2]]>, PolylabelStatus>,
+    {
+        if exterior.is_empty() {
+            return Err(fail(
+                PolylabelStatus::InvalidLength,
+                "the exterior ring is empty",
+            ));
+        }
+        Ok(Rings {
+            exterior,
+            interiors: interiors()?,
+        })
+    }
+
+    // Each ring is copied directly from the caller's buffer, in a single pass, rather than being
+    // copied into a Vec and then into a LineString
+    fn polygon(&self) -> Polygon<T> {
+        let ring = |i, points: &[[T; 2]]| {
+            warn_unclosed(i, points.first() == points.last());
+            points.iter().copied().map(Coord::from).collect()
+        };
+        Polygon::new(
+            ring(0, self.exterior),
+            self.interiors
+                .iter()
+                .enumerate()
+                .map(|(i, points)| ring(i + 1, points))
+                .collect(),
+        )
+    }
+}
+
+// Run the body of an FFI function, falling back to `on_panic` if it panics, so that a panic can't unwind
+// across the FFI boundary
+fn catch<R>(body: impl FnOnce() -> R, on_panic: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        set_last_error(format_args!("internal error: {}", message));
+        on_panic()
+    })
+}
+
+// write an error result to `out` if it isn't null, returning the status
+unsafe fn write_error(out: *mut PositionResult, status: PolylabelStatus) -> PolylabelStatus {
+    if !out.is_null() {
+        *out = PositionResult::error(status);
+    }
+    status
+}
+
+// build a Polygon from its FFI representation, which must have a non-empty exterior
+fn polygon(outer: &Array, inners: WrapperArray) -> Result<Polygon<c_double>, PolylabelStatus> {
+    Rings::new(outer, inners).map(|rings| rings.polygon())
+}
+
+// Index a Polygon, consuming it so that its coordinates are released before the search begins.
+// Only the index is needed to calculate a label position
+fn prepare(poly: Polygon<c_double>) -> Result<PreparedPolygon<c_double>, PolylabelError> {
+    PreparedPolygon::new(&poly)
+}
+
+// build a Polygon from offsets delimiting its rings, and a function looking up each point.
+// The offsets mustn't decrease, and the exterior mustn't be empty
+fn offset_polygon<F>(
+    ring_offsets: &[size_t],
+    point: F,
+) -> Result<Polygon<c_double>, PolylabelStatus>
+where
+    F: Fn(usize) -> Coord<c_double>,
+{
+    if ring_offsets.len() < 2 {
+        return Err(fail(PolylabelStatus::InvalidLength, "there are no rings"));
+    }
+    if ring_offsets[0] == ring_offsets[1] {
+        return Err(fail(
+            PolylabelStatus::InvalidLength,
+            "the exterior ring is empty",
+        ));
+    }
+    if let Some(i) = ring_offsets
+        .windows(2)
+        .position(|bounds| bounds[0] > bounds[1])
+    {
+        return Err(fail(
+            PolylabelStatus::InvalidLength,
+            format_args!(
+                "offset {} ({}) is greater than offset {} ({})",
+                i,
+                ring_offsets[i],
+                i + 1,
+                ring_offsets[i + 1]
+            ),
+        ));
+    }
+    let mut rings = ring_offsets.windows(2).enumerate().map(|(i, bounds)| {
+        let ring = (bounds[0]..bounds[1])
+            .map(&point)
+            .collect::<LineString<_>>();
+        warn_unclosed(i, ring.is_closed());
+        ring
+    });
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    Ok(Polygon::new(exterior, rings.collect()))
+}
+
+// label a Polygon built from caller-supplied data
+fn label_ext(
+    poly: Result<Polygon<c_double>, PolylabelStatus>,
+    tolerance: c_double,
+) -> PositionResult {
+    match poly {
+        Ok(poly) => prepare(poly).map_or_else(
+            |err| PositionResult::error(err.into()),
+            |prepared| {
+                warn_tolerance(&prepared, tolerance);
+                prepared.label(&tolerance).into()
+            },
+        ),
+        Err(status) => PositionResult::error(status),
+    }
+}
+
+fn label<O>(poly: Polygon<c_double>, tolerance: c_double) -> Result<O, PolylabelError>
+where
+    O: From<Label<c_double>>,
+{
+    prepare(poly).map(|prepared| {
+        warn_tolerance(&prepared, tolerance);
+        prepared.label(&tolerance).into()
+    })
+}
+
+// label the MultiPolygon part with the largest area
+fn label_largest(multi: &MultiPolygon<c_double>, tolerance: c_double) -> PositionResult {
+    match polylabel_multi(multi, &PolylabelOptions::new(tolerance), Parts::Largest) {
+        Ok(labels) => labels.first().map_or_else(
+            || {
+                PositionResult::error(fail(
+                    PolylabelStatus::InvalidInput,
+                    "the MultiPolygon has no parts",
+                ))
+            },
+            |&label| label.into(),
+        ),
+        Err(err) => PositionResult::error(err.into()),
+    }
+}
+
+impl Position {
+    fn error() -> Self {
+        Point::new(f64::NAN, f64::NAN).into()
+    }
+}
+
+/// The crate's version, as a static, NUL-terminated string (e.g. `"4.0.0"`), which mustn't be freed
+#[no_mangle]
+pub extern "C" fn polylabel_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// The version of the library's FFI ABI
+///
+/// If this differs from the [`POLYLABEL_ABI_VERSION`](constant.POLYLABEL_ABI_VERSION.html) in the header
+/// you compiled against, the library's structs or functions are incompatible with your code.
+#[no_mangle]
+pub extern "C" fn polylabel_abi_version() -> u32 {
+    POLYLABEL_ABI_VERSION
+}
+
+/// Set a callback which receives the library's log messages, or pass null to remove it
+///
+/// Warnings are logged when the library silently adjusts its input: e.g. when a ring which isn't closed is
+/// closed, or when a tolerance which is finer than the coordinates' precision is raised. `user_data` is passed to
+/// the callback unchanged. The callback is shared by every thread, and may be called from any thread (including
+/// the batch functions' worker threads), so it (and `user_data`) must be thread-safe. It mustn't unwind.
+///
+/// # Nullability
+///
+/// `callback` and `user_data` are nullable.
+#[no_mangle]
+pub extern "C" fn polylabel_set_log_callback(
+    callback: Option<PolylabelLogCallback>,
+    user_data: *mut c_void,
+) {
+    *LOGGER.lock().unwrap_or_else(PoisonError::into_inner) = callback.map(|callback| Logger {
+        callback,
+        user_data,
+    });
+}
+
+/// A description of the most recent failure on the calling thread, as a NUL-terminated string
+///
+/// Functions which return a [`PolylabelStatus`](enum.PolylabelStatus.html) other than `Ok` (or `Cancelled`),
+/// or a `NaN` label position, record a description of the failure, e.g. `"interior ring 2: null pointer with a
+/// length of 5"`. Successful calls don't clear it. Returns null if there hasn't been a failure on this thread since
+/// the last call to [`polylabel_clear_last_error`](fn.polylabel_clear_last_error.html).
+///
+/// The string is owned by the library, and remains valid until the next failure on the same thread, or until
+/// the description is cleared.
+#[no_mangle]
+pub extern "C" fn polylabel_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |last| last.as_ptr())
+    })
+}
+
+/// Clear the calling thread's most recent failure description
+#[no_mangle]
+pub extern "C" fn polylabel_clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// FFI access to the [`polylabel`](fn.polylabel.html) function
+///
+/// Accepts three arguments:
+///
+/// - an exterior ring representing a Polygon shell or closed LineString
+/// - zero or more interior rings representing Polygon holes
+/// - a tolerance `c_double`.
+///
+/// If an error occurs while attempting to calculate the label position, the resulting point coordinates
+/// will be `NaN, NaN`. Use [`polylabel_ext_ffi`](fn.polylabel_ext_ffi.html) to find out which error occurred.
+#[no_mangle]
+pub extern "C" fn polylabel_ffi(
+    outer: Array,
+    inners: WrapperArray,
+    tolerance: c_double,
+) -> Position {
+    catch(
+        || {
+            polygon(&outer, inners)
+                .ok()
+                .and_then(|poly| label::<Position>(poly, tolerance).ok())
+                .unwrap_or_else(Position::error)
+        },
+        Position::error,
+    )
+}
+
+/// Single-precision FFI access to the [`polylabel`](fn.polylabel.html) function
+///
+/// Accepts the same arguments as [`polylabel_ffi`](fn.polylabel_ffi.html), but its rings hold `c_float`
+/// coordinates, its tolerance is a `c_float`, and it returns a [`PositionF32`](struct.PositionF32.html).
+/// The label position is calculated in single precision, and the tolerance is raised if necessary to
+/// one which the coordinates are precise enough to achieve.
+///
+/// If an error occurs while attempting to calculate the label position, the resulting point coordinates
+/// will be `NaN, NaN`.
+#[no_mangle]
+pub extern "C" fn polylabel_f32_ffi(
+    outer: ArrayF32,
+    inners: WrapperArrayF32,
+    tolerance: c_float,
+) -> PositionF32 {
+    let error = || Point::new(f32::NAN, f32::NAN).into();
+    catch(
+        || {
+            Rings::new_f32(&outer, inners)
+                .ok()
+                .and_then(|rings| PreparedPolygon::new(&rings.polygon()).ok())
+                .map_or_else(error, |prepared| {
+                    warn_tolerance(&prepared, tolerance);
+                    prepared.polylabel(&tolerance).into()
+                })
+        },
+        error,
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function
+///
+/// Accepts the same arguments as [`polylabel_ffi`](fn.polylabel_ffi.html), but returns a
+/// [`PositionResult`](struct.PositionResult.html), which also holds the label position's distance from
+/// the Polygon's edges, and a status indicating whether the calculation succeeded.
+///
+/// The status is `InvalidLength` if the exterior ring is empty, or a ring's length is too large to be
+/// addressed, and `NullPointer` if a ring with a nonzero length has a null `data` pointer.
+#[no_mangle]
+pub extern "C" fn polylabel_ext_ffi(
+    outer: Array,
+    inners: WrapperArray,
+    tolerance: c_double,
+) -> PositionResult {
+    catch(
+        || label_ext(polygon(&outer, inners), tolerance),
+        || PositionResult::error(PolylabelStatus::Panic),
+    )
+}
+
+/// A progress callback, called with the distance of the best label position found so far, an upper bound on
+/// the distance of the ideal label position, and the caller's `user_data` pointer
+pub type PolylabelProgress =
+    extern "C" fn(best_distance: c_double, bound: c_double, user_data: *mut c_void);
+
+/// FFI access to the [`PreparedPolygon::label_with_progress`](struct.PreparedPolygon.html#method.label_with_progress)
+/// method
+///
+/// Accepts the same arguments as [`polylabel_ext_ffi`](fn.polylabel_ext_ffi.html), followed by:
+///
+/// - a [`PolylabelProgress`](type.PolylabelProgress.html) callback, which is called periodically during the
+///   search, and once when it finishes. May be null
+/// - a `user_data` pointer, which is passed to the callback unchanged. May be null.
+///
+/// The search finishes once the callback's two distances are within the tolerance of each other, so they can be
+/// used to display its progress. Returns a [`PositionResult`](struct.PositionResult.html). The callback is called
+/// on the calling thread, and mustn't unwind.
+///
+/// # Nullability
+///
+/// `progress` and `user_data` are nullable.
+#[no_mangle]
+pub extern "C" fn polylabel_progress_ffi(
+    outer: Array,
+    inners: WrapperArray,
+    tolerance: c_double,
+    progress: Option<PolylabelProgress>,
+    user_data: *mut c_void,
+) -> PositionResult {
+    catch(
+        || match polygon(&outer, inners).and_then(|poly| prepare(poly).map_err(From::from)) {
+            Ok(prepared) => {
+                warn_tolerance(&prepared, tolerance);
+                prepared
+                    .label_with_progress(&tolerance, |best_distance, bound| {
+                        if let Some(progress) = progress {
+                            progress(best_distance, bound, user_data)
+                        }
+                    })
+                    .into()
+            }
+            Err(status) => PositionResult::error(status),
+        },
+        || PositionResult::error(PolylabelStatus::Panic),
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, with cancellation
+///
+/// Accepts the same arguments as [`polylabel_ext_ffi`](fn.polylabel_ext_ffi.html), followed by a pointer to
+/// an `int` cancellation flag, which may be null. If another thread sets the flag to a nonzero value while the
+/// label position is being calculated, the calculation stops, and the returned
+/// [`PositionResult`](struct.PositionResult.html) holds the best label position found so far, with a
+/// `Cancelled` status. Its distance may be further than the tolerance from the ideal label position's.
+///
+/// The flag should be written atomically (e.g. using C11 `atomic_store`) or through a `volatile` pointer.
+///
+/// # Nullability
+///
+/// `cancel` is nullable.
+///
+/// # Safety
+///
+/// `cancel` must be null, or point to an `int` which remains valid until the function returns.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_cancellable_ffi(
+    outer: Array,
+    inners: WrapperArray,
+    tolerance: c_double,
+    cancel: *const c_int,
+) -> PositionResult {
+    // c_int is an i32 on every supported platform, and AtomicI32 has the same layout
+    let cancel = (cancel as *const AtomicI32).as_ref();
+    let cancelled = || cancel.is_some_and(|flag| flag.load(Ordering::Relaxed) != 0);
+    catch(
+        || match polygon(&outer, inners).and_then(|poly| prepare(poly).map_err(From::from)) {
+            Ok(prepared) => {
+                warn_tolerance(&prepared, tolerance);
+                match prepared.search(&tolerance, |_, _| (), cancelled) {
+                    (label, true, _) => label.into(),
+                    (label, false, _) => PositionResult {
+                        status: PolylabelStatus::Cancelled,
+                        ..label.into()
+                    },
+                }
+            }
+            Err(status) => PositionResult::error(status),
+        },
+        || PositionResult::error(PolylabelStatus::Panic),
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a Polygon
+/// held in a single interleaved coordinate buffer
+///
+/// Accepts four arguments:
+///
+/// - a pointer to a buffer of interleaved `x, y` `c_double` coordinates
+/// - a pointer to a sequence of `n_rings + 1` `size_t` ring offsets. Ring `i` consists of points
+///   `ring_offsets[i]` up to (but not including) `ring_offsets[i + 1]`. The first ring is the exterior,
+///   and the remainder are interior rings. The buffer must hold `ring_offsets[n_rings]` points
+/// - the number of rings, a `size_t`
+/// - a tolerance `c_double`.
+///
+/// Returns a [`PositionResult`](struct.PositionResult.html). Its status is `InvalidLength` if there are no rings,
+/// or the offsets decrease, and `NullPointer` if either pointer is null.
+///
+/// # Nullability
+///
+/// `coords` and `ring_offsets` are non-null.
+///
+/// # Safety
+///
+/// `ring_offsets` must point to `n_rings + 1` `size_t`s, and `coords` must point to `2 * ring_offsets[n_rings]`
+/// `c_double`s.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_flat_ffi(
+    coords: *const c_double,
+    ring_offsets: *const size_t,
+    n_rings: size_t,
+    tolerance: c_double,
+) -> PositionResult {
+    catch(
+        || flat(coords, ring_offsets, n_rings, tolerance),
+        || PositionResult::error(PolylabelStatus::Panic),
+    )
+}
+
+unsafe fn flat(
+    coords: *const c_double,
+    ring_offsets: *const size_t,
+    n_rings: size_t,
+    tolerance: c_double,
+) -> PositionResult {
+    label_ext(flat_polygon(coords, ring_offsets, n_rings), tolerance)
+}
+
+// build a Polygon from an interleaved coordinate buffer and its ring offsets
+unsafe fn flat_polygon(
+    coords: *const c_double,
+    ring_offsets: *const size_t,
+    n_rings: size_t,
+) -> Result<Polygon<c_double>, PolylabelStatus> {
+    let n_offsets = n_rings.checked_add(1).ok_or_else(|| {
+        fail(
+            PolylabelStatus::InvalidLength,
+            format_args!("{} rings can't be addressed", n_rings),
+        )
+    })?;
+    let ring_offsets =
+        borrow(ring_offsets, n_offsets).map_err(|status| context(status, "ring offsets"))?;
+    // there's at least one offset
+    let n_points = ring_offsets[n_rings];
+    let coords = borrow(coords as *const [c_double; 2], n_points)
+        .map_err(|status| context(status, "coordinates"))?;
+    offset_polygon(ring_offsets, |i| coords[i].into())
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a Polygon
+/// whose coordinates are held in separate `x` and `y` arrays
+///
+/// Accepts six arguments:
+///
+/// - a pointer to a sequence of `n` `x` `c_double` coordinates
+/// - a pointer to a sequence of `n` `y` `c_double` coordinates
+/// - the number of points, a `size_t`
+/// - a pointer to a sequence of `n_holes` `size_t` hole offsets: the index of each interior ring's first point.
+///   The exterior ring consists of the points preceding the first hole. May be null if there are no holes
+/// - the number of holes, a `size_t`
+/// - a tolerance `c_double`.
+///
+/// Returns a [`PositionResult`](struct.PositionResult.html). Its status is `InvalidLength` if there are no points,
+/// or the hole offsets decrease or exceed `n`, and `NullPointer` if a required pointer is null.
+///
+/// # Nullability
+///
+/// `xs` and `ys` are non-null. `hole_offsets` is nullable if `n_holes` is 0.
+///
+/// # Safety
+///
+/// `xs` and `ys` must point to `n` `c_double`s, and `hole_offsets` must point to `n_holes` `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_xy_ffi(
+    xs: *const c_double,
+    ys: *const c_double,
+    n: size_t,
+    hole_offsets: *const size_t,
+    n_holes: size_t,
+    tolerance: c_double,
+) -> PositionResult {
+    catch(
+        || xy(xs, ys, n, hole_offsets, n_holes, tolerance),
+        || PositionResult::error(PolylabelStatus::Panic),
+    )
+}
+
+unsafe fn xy(
+    xs: *const c_double,
+    ys: *const c_double,
+    n: size_t,
+    hole_offsets: *const size_t,
+    n_holes: size_t,
+    tolerance: c_double,
+) -> PositionResult {
+    let poly = (|| {
+        let xs = borrow(xs, n).map_err(|status| context(status, "x coordinates"))?;
+        let ys = borrow(ys, n).map_err(|status| context(status, "y coordinates"))?;
+        let hole_offsets =
+            borrow(hole_offsets, n_holes).map_err(|status| context(status, "hole offsets"))?;
+        let ring_offsets: Vec<size_t> = iter::once(0)
+            .chain(hole_offsets.iter().copied())
+            .chain(iter::once(n))
+            .collect();
+        offset_polygon(&ring_offsets, |i| Coord { x: xs[i], y: ys[i] })
+    })();
+    label_ext(poly, tolerance)
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, using only
+/// primitive arrays
+///
+/// Intended for callers which can't easily build structs, such as JNI code using arrays from
+/// `GetDoubleArrayElements` and `GetIntArrayElements`. Accepts six arguments:
+///
+/// - a pointer to a buffer of interleaved `x, y` `c_double` coordinates
+/// - the number of `c_double`s in the buffer, a `c_int`
+/// - a pointer to a sequence of `c_int` ring offsets. As for [`polylabel_flat_ffi`](fn.polylabel_flat_ffi.html),
+///   ring `i` consists of points `ring_offsets[i]` up to (but not including) `ring_offsets[i + 1]`, and the first
+///   ring is the exterior
+/// - the number of ring offsets (one more than the number of rings), a `c_int`
+/// - a tolerance `c_double`
+/// - a pointer to three `c_double`s, which receive the label position's `x` and `y` coordinates, and its distance
+///   from the Polygon's edges. They're `NaN` if the label position couldn't be calculated.
+///
+/// Returns a [`PolylabelStatus`](enum.PolylabelStatus.html), which is a C `int`. It's `InvalidLength` if a length
+/// or offset is negative, the offsets decrease, or the buffer is too short, and `NullPointer` if a pointer is null.
+///
+/// # Nullability
+///
+/// `coords`, `ring_offsets`, and `out` are non-null.
+///
+/// # Safety
+///
+/// `coords` must point to `coords_len` `c_double`s, `ring_offsets` must point to `n_offsets` `c_int`s, and `out`
+/// must point to three writable `c_double`s.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_primitive_ffi(
+    coords: *const c_double,
+    coords_len: c_int,
+    ring_offsets: *const c_int,
+    n_offsets: c_int,
+    tolerance: c_double,
+    out: *mut c_double,
+) -> PolylabelStatus {
+    catch(
+        || {
+            if out.is_null() {
+                return fail(PolylabelStatus::NullPointer, "null output pointer");
+            }
+            let poly = (|| {
+                let coords = borrow_int(coords, coords_len)
+                    .map_err(|status| context(status, "coordinates"))?;
+                let ring_offsets = borrow_int(ring_offsets, n_offsets)
+                    .and_then(offsets)
+                    .map_err(|status| context(status, "ring offsets"))?;
+                primitive_polygon(coords, &ring_offsets)
+            })();
+            write_primitive(out, label_ext(poly, tolerance))
+        },
+        || PolylabelStatus::Panic,
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function for many Polygons in a
+/// single call, using only primitive arrays
+///
+/// Accepts the same arguments as [`polylabel_primitive_ffi`](fn.polylabel_primitive_ffi.html), with the ring
+/// offsets of every Polygon in a single sequence, followed by:
+///
+/// - a pointer to a sequence of `c_int` Polygon offsets: Polygon `i` consists of rings `polygon_offsets[i]` up to
+///   (but not including) `polygon_offsets[i + 1]`
+/// - the number of Polygon offsets (one more than the number of Polygons), a `c_int`
+/// - a tolerance `c_double`
+/// - a pointer to three `c_double`s for each Polygon, which receive its label position's `x` and `y` coordinates,
+///   and its distance, in order.
+///
+/// Returns `Ok` if every label position was calculated, or the status of the first Polygon whose label position
+/// couldn't be. Polygons are labelled on the calling thread.
+///
+/// # Nullability
+///
+/// `coords`, `ring_offsets`, and `polygon_offsets` are non-null. `out` is nullable if
+/// there are no Polygons.
+///
+/// # Safety
+///
+/// `coords` must point to `coords_len` `c_double`s, `ring_offsets` must point to `n_ring_offsets` `c_int`s,
+/// `polygon_offsets` must point to `n_polygon_offsets` `c_int`s, and `out` must point to three writable
+/// `c_double`s for each Polygon.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn polylabel_primitive_batch_ffi(
+    coords: *const c_double,
+    coords_len: c_int,
+    ring_offsets: *const c_int,
+    n_ring_offsets: c_int,
+    polygon_offsets: *const c_int,
+    n_polygon_offsets: c_int,
+    tolerance: c_double,
+    out: *mut c_double,
+) -> PolylabelStatus {
+    catch(
+        || {
+            let coords =
+                borrow_int(coords, coords_len).map_err(|status| context(status, "coordinates"));
+            let ring_offsets = borrow_int(ring_offsets, n_ring_offsets)
+                .and_then(offsets)
+                .map_err(|status| context(status, "ring offsets"));
+            let polygon_offsets = borrow_int(polygon_offsets, n_polygon_offsets)
+                .and_then(offsets)
+                .map_err(|status| context(status, "polygon offsets"));
+            let (coords, ring_offsets, polygon_offsets) =
+                match (coords, ring_offsets, polygon_offsets) {
+                    (Ok(coords), Ok(ring_offsets), Ok(polygon_offsets)) => {
+                        (coords, ring_offsets, polygon_offsets)
+                    }
+                    (Err(status), _, _) | (_, Err(status), _) | (_, _, Err(status)) => {
+                        return status
+                    }
+                };
+            let n_polygons = polygon_offsets.len().saturating_sub(1);
+            if n_polygons > 0 && out.is_null() {
+                return fail(PolylabelStatus::NullPointer, "null output pointer");
+            }
+            let mut first_failure = None;
+            for (i, bounds) in polygon_offsets.windows(2).enumerate() {
+                let poly = ring_offsets
+                    .get(bounds[0]..=bounds[1])
+                    .ok_or_else(|| {
+                        fail(
+                            PolylabelStatus::InvalidLength,
+                            format_args!(
+                                "its rings ({}..{}) aren't within the ring offsets",
+                                bounds[0], bounds[1]
+                            ),
+                        )
+                    })
+                    .and_then(|ring_offsets| primitive_polygon(coords, ring_offsets));
+                // a panic while labelling one Polygon doesn't prevent the others being labelled
+                let result = catch(
+                    || label_ext(poly, tolerance),
+                    || PositionResult::error(PolylabelStatus::Panic),
+                );
+                let status = write_primitive(out.add(i * 3), result);
+                if status != PolylabelStatus::Ok && first_failure.is_none() {
+                    let status = context(status, format_args!("polygon {}", i));
+                    first_failure = Some((status, last_error()));
+                }
+            }
+            // Report the first failure
+            match first_failure {
+                Some((status, message)) => {
+                    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+                    status
+                }
+                None => PolylabelStatus::Ok,
+            }
+        },
+        || PolylabelStatus::Panic,
+    )
+}
+
+// Borrow a caller-supplied primitive array, whose length is a c_int
+unsafe fn borrow_int<'a, T>(data: *const T, len: c_int) -> Result<&'a [T], PolylabelStatus> {
+    let len = usize::try_from(len).map_err(|_| {
+        fail(
+            PolylabelStatus::InvalidLength,
+            format_args!("a length of {} is negative", len),
+        )
+    })?;
+    borrow(data, len)
+}
+
+// c_int offsets as indices, which mustn't be negative
+fn offsets(offsets: &[c_int]) -> Result<Vec<usize>, PolylabelStatus> {
+    offsets
+        .iter()
+        .enumerate()
+        .map(|(i, &offset)| {
+            usize::try_from(offset).map_err(|_| {
+                fail(
+                    PolylabelStatus::InvalidLength,
+                    format_args!("offset {} ({}) is negative", i, offset),
+                )
+            })
+        })
+        .collect()
+}
+
+// build a Polygon from interleaved coordinates and ring offsets, which mustn't exceed the number of points
+fn primitive_polygon(
+    coords: &[c_double],
+    ring_offsets: &[usize],
+) -> Result<Polygon<c_double>, PolylabelStatus> {
+    let n_points = coords.len() / 2;
+    if let Some(&last) = ring_offsets.last().filter(|&&last| last > n_points) {
+        return Err(fail(
+            PolylabelStatus::InvalidLength,
+            format_args!(
+                "the last ring offset ({}) is greater than the number of points ({})",
+                last, n_points
+            ),
+        ));
+    }
+    offset_polygon(ring_offsets, |i| Coord {
+        x: coords[i * 2],
+        y: coords[i * 2 + 1],
+    })
+}
+
+// write a result's position and distance to three doubles, returning its status
+unsafe fn write_primitive(out: *mut c_double, result: PositionResult) -> PolylabelStatus {
+    ptr::copy_nonoverlapping([result.x, result.y, result.distance].as_ptr(), out, 3);
+    result.status
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a
+/// GeoJSON Polygon or MultiPolygon geometry
+///
+/// Accepts three arguments:
+///
+/// - a pointer to a NUL-terminated, UTF-8 GeoJSON geometry object string
+/// - a tolerance `c_double`
+/// - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+///
+/// The label position of a MultiPolygon is that of its part with the largest area.
+///
+/// Returns the result's status, which is `InvalidInput` if the string can't be parsed as a Polygon or
+/// MultiPolygon geometry, and `NullPointer` if either pointer is null. Requires the `geojson` feature.
+///
+/// # Nullability
+///
+/// `geojson` and `out` are non-null.
+///
+/// # Safety
+///
+/// `geojson` must point to a NUL-terminated string, and `out` must point to a writable `PositionResult`.
+#[cfg(feature = "geojson")]
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_geojson_ffi(
+    geojson: *const c_char,
+    tolerance: c_double,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    catch(
+        || label_parsed(parse_text(geojson, crate::geojson::parse), tolerance, out),
+        || write_error(out, PolylabelStatus::Panic),
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a
+/// WKT `POLYGON` or `MULTIPOLYGON`
+///
+/// Accepts three arguments:
+///
+/// - a pointer to a NUL-terminated, UTF-8 WKT string. `Z`, `M`, and `ZM` coordinates, and an EWKT `SRID=…;`
+///   prefix, are accepted
+/// - a tolerance `c_double`
+/// - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+///
+/// The label position of a MultiPolygon is that of its part with the largest area.
+///
+/// Returns the result's status, which is `InvalidInput` if the string can't be parsed as a `POLYGON` or
+/// `MULTIPOLYGON`, or is `EMPTY`, and `NullPointer` if either pointer is null. Requires the `wkt` feature.
+///
+/// # Nullability
+///
+/// `wkt` and `out` are non-null.
+///
+/// # Safety
+///
+/// `wkt` must point to a NUL-terminated string, and `out` must point to a writable `PositionResult`.
+#[cfg(feature = "wkt")]
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_wkt_ffi(
+    wkt: *const c_char,
+    tolerance: c_double,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    catch(
+        || label_parsed(parse_text(wkt, crate::wkt::parse), tolerance, out),
+        || write_error(out, PolylabelStatus::Panic),
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function, for a
+/// WKB or EWKB Polygon or MultiPolygon
+///
+/// Accepts four arguments:
+///
+/// - a pointer to a WKB buffer. Either byte order, ISO and EWKB `Z` and `M` coordinates, and an EWKB SRID
+///   are accepted
+/// - the length of the buffer in bytes, a `size_t`
+/// - a tolerance `c_double`
+/// - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+///
+/// The label position of a MultiPolygon is that of its part with the largest area.
+///
+/// Returns the result's status, which is `InvalidInput` if the buffer can't be parsed as a Polygon or
+/// MultiPolygon, or is empty, and `NullPointer` if either pointer is null. Requires the `wkb` feature.
+///
+/// # Nullability
+///
+/// `wkb` and `out` are non-null.
+///
+/// # Safety
+///
+/// `wkb` must point to `len` bytes, and `out` must point to a writable `PositionResult`.
+#[cfg(feature = "wkb")]
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_wkb_ffi(
+    wkb: *const u8,
+    len: size_t,
+    tolerance: c_double,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    catch(
+        || {
+            let geometry = match borrow(wkb, len) {
+                Ok([]) => Err(fail(
+                    PolylabelStatus::InvalidInput,
+                    "the WKB buffer is empty",
+                )),
+                Ok(wkb) => crate::wkb::parse(wkb).ok_or_else(|| {
+                    fail(
+                        PolylabelStatus::InvalidInput,
+                        "the WKB buffer isn't a valid Polygon or MultiPolygon",
+                    )
+                }),
+                Err(status) => Err(context(status, "WKB buffer")),
+            };
+            label_parsed(geometry, tolerance, out)
+        },
+        || write_error(out, PolylabelStatus::Panic),
+    )
+}
+
+/// Calculate label positions for a whole array of Polygons or MultiPolygons, using the
+/// [Arrow C Data Interface](https://arrow.apache.org/docs/format/CDataInterface.html)
+///
+/// Accepts five arguments:
+///
+/// - a pointer to an `ArrowArray`, and a pointer to its `ArrowSchema`. The array may be a WKB array (`binary` or
+///   `large_binary`), or a GeoArrow `polygon` or `multipolygon` array with interleaved or separated coordinates.
+///   They're borrowed, and aren't released
+/// - a tolerance `c_double`
+/// - a pointer to an `ArrowArray`, and a pointer to an `ArrowSchema`, which receive a GeoArrow `point` array with
+///   interleaved coordinates, which the caller must release.
+///
+/// The output holds each geometry's label position, in order. The label position of a MultiPolygon is that of its
+/// part with the largest area. Null geometries, and geometries whose label position couldn't be calculated, are
+/// null. Geometries are labelled on rayon's global thread pool.
+///
+/// Returns `Ok` if every label position was calculated, or the status of the first geometry whose label position
+/// couldn't be. `InvalidInput` is returned if the array isn't supported, and `NullPointer` if any pointer is null,
+/// in which case nothing is written to the output. Requires the `arrow` feature.
+///
+/// # Nullability
+///
+/// `array`, `schema`, `out_array`, and `out_schema` are non-null.
+///
+/// # Safety
+///
+/// `array` and `schema` must follow the Arrow C Data Interface specification, and `out_array` and `out_schema`
+/// must point to writable structures, which the caller hasn't initialised or has already released.
+#[cfg(feature = "arrow")]
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_arrow_ffi(
+    array: *const ArrowArray,
+    schema: *const ArrowSchema,
+    tolerance: c_double,
+    out_array: *mut ArrowArray,
+    out_schema: *mut ArrowSchema,
+) -> PolylabelStatus {
+    catch(
+        || arrow(array, schema, tolerance, out_array, out_schema),
+        || PolylabelStatus::Panic,
+    )
+}
+
+#[cfg(feature = "arrow")]
+unsafe fn arrow(
+    array: *const ArrowArray,
+    schema: *const ArrowSchema,
+    tolerance: c_double,
+    out_array: *mut ArrowArray,
+    out_schema: *mut ArrowSchema,
+) -> PolylabelStatus {
+    let (array, schema) = match (array.as_ref(), schema.as_ref()) {
+        (Some(array), Some(schema)) if !out_array.is_null() && !out_schema.is_null() => {
+            (array, schema)
+        }
+        _ => {
+            return fail(
+                PolylabelStatus::NullPointer,
+                "null array, schema, or output pointer",
+            )
+        }
+    };
+    let geometries = match crate::arrow::import(array, schema) {
+        Ok(geometries) => geometries,
+        Err(err) => return fail(PolylabelStatus::InvalidInput, err),
+    };
+    // A failure's status, and its description, which has to be passed back to the calling thread
+    let failure = |i: usize, status| {
+        let status = context(status, format_args!("geometry {}", i));
+        (status, last_error())
+    };
+    let labels: Vec<_> = (0..geometries.len())
+        .into_par_iter()
+        .map(|i| match geometries.get(i) {
+            None => Ok(None),
+            Some(Ok(multi)) => {
+                // a panic while labelling one geometry doesn't prevent the others being labelled
+                let result = catch(
+                    || label_largest(&multi, tolerance),
+                    || PositionResult::error(PolylabelStatus::Panic),
+                );
+                match result.status {
+                    PolylabelStatus::Ok => Ok(Some(Coord {
+                        x: result.x,
+                        y: result.y,
+                    })),
+                    status => Err(failure(i, status)),
+                }
+            }
+            Some(Err(err)) => Err(failure(i, fail(PolylabelStatus::InvalidInput, err))),
+        })
+        .collect();
+    let points: Vec<_> = labels
+        .iter()
+        .map(|label| label.as_ref().ok().copied().flatten())
+        .collect();
+    (*out_array, *out_schema) = crate::arrow::export(&points);
+    // Report the first failure
+    match labels.into_iter().find_map(Result::err) {
+        Some((status, message)) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = message);
+            status
+        }
+        None => PolylabelStatus::Ok,
+    }
+}
+
+// parse a geometry held in a C string
+#[cfg(any(feature = "geojson", feature = "wkt"))]
+unsafe fn parse_text(
+    text: *const c_char,
+    parse: fn(&str) -> Option<MultiPolygon<c_double>>,
+) -> Result<MultiPolygon<c_double>, PolylabelStatus> {
+    if text.is_null() {
+        return Err(fail(PolylabelStatus::NullPointer, "null geometry string"));
+    }
+    let text = CStr::from_ptr(text).to_str().map_err(|err| {
+        fail(
+            PolylabelStatus::InvalidInput,
+            format_args!("the geometry string isn't valid UTF-8: {}", err),
+        )
+    })?;
+    parse(text).ok_or_else(|| {
+        fail(
+            PolylabelStatus::InvalidInput,
+            "the geometry string isn't a valid Polygon or MultiPolygon",
+        )
+    })
+}
+
+// label a parsed geometry, writing the result to `out` and returning its status
+#[cfg(any(feature = "geojson", feature = "wkt", feature = "wkb"))]
+unsafe fn label_parsed(
+    geometry: Result<MultiPolygon<c_double>, PolylabelStatus>,
+    tolerance: c_double,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    if out.is_null() {
+        return fail(PolylabelStatus::NullPointer, "null output pointer");
+    }
+    let result = geometry.map_or_else(PositionResult::error, |multi| {
+        label_largest(&multi, tolerance)
+    });
+    let status = result.status;
+    *out = result;
+    status
+}
+
+/// FFI access to the [`polylabel_multi`](fn.polylabel_multi.html) function
+///
+/// Accepts five arguments:
+///
+/// - a pointer to a sequence of [`PolygonFFI`](struct.PolygonFFI.html)s: the MultiPolygon's parts
+/// - the number of parts, a `size_t`
+/// - a tolerance `c_double`
+/// - a [`PolylabelParts`](enum.PolylabelParts.html) specifying which parts to calculate label positions for
+/// - a pointer to a caller-allocated sequence of [`PositionResult`](struct.PositionResult.html)s: one
+///   if `parts` is `Largest`, or `len` if it's `All`.
+///
+/// With `Largest`, the label position of the part with the largest area is written to `out`, and its status is
+/// `InvalidInput` if there are no parts. With `All`, each part's label position is written to the `out` entry with
+/// the same index.
+///
+/// Returns `Ok` if every label position was calculated, or the status of the first that couldn't be.
+/// `NullPointer` is returned if `polygons` (when `len` isn't 0) or `out` is null.
+///
+/// # Nullability
+///
+/// `polygons` is nullable if `len` is 0. `out` is non-null.
+///
+/// # Safety
+///
+/// `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to enough writable `PositionResult`s.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_multi_ffi(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    parts: PolylabelParts,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    catch(
+        || multi(polygons, len, tolerance, parts, out),
+        || PolylabelStatus::Panic,
+    )
+}
+
+unsafe fn multi(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    parts: PolylabelParts,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    if out.is_null() {
+        return fail(PolylabelStatus::NullPointer, "null output pointer");
+    }
+    match label_parts(polygons, len, tolerance, parts) {
+        Ok((results, status)) => {
+            for (i, result) in results.into_iter().enumerate() {
+                *out.add(i) = result;
+            }
+            status
+        }
+        Err(status) => write_error(out, status),
+    }
+}
+
+// label a MultiPolygon's parts, returning their results and the status of the first that couldn't be labelled,
+// or the status of invalid input
+unsafe fn label_parts(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    parts: PolylabelParts,
+) -> Result<(Vec<PositionResult>, PolylabelStatus), PolylabelStatus> {
+    let parts_rings = borrow(polygons, len)
+        .map_err(|status| context(status, "parts"))
+        .and_then(|polygons| {
+            polygons
+                .iter()
+                .enumerate()
+                .map(|(i, poly)| {
+                    Rings::new(&poly.outer, poly.inners)
+                        .map_err(|status| context(status, format_args!("part {}", i)))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        });
+    let parts_rings = parts_rings?;
+    match parts {
+        PolylabelParts::Largest => {
+            let multi = parts_rings.iter().map(Rings::polygon).collect();
+            let result = label_largest(&multi, tolerance);
+            let status = result.status;
+            Ok((vec![result], status))
+        }
+        PolylabelParts::All => {
+            let mut first_failure = None;
+            let results = parts_rings
+                .iter()
+                .enumerate()
+                .map(|(i, rings)| {
+                    // a panic while labelling one part doesn't prevent the others being labelled
+                    let result = catch(
+                        || label_ext(Ok(rings.polygon()), tolerance),
+                        || PositionResult::error(PolylabelStatus::Panic),
+                    );
+                    if result.status != PolylabelStatus::Ok && first_failure.is_none() {
+                        let status = context(result.status, format_args!("part {}", i));
+                        first_failure = Some((status, last_error()));
+                    }
+                    result
+                })
+                .collect();
+            // Report the first failure
+            let status = match first_failure {
+                Some((status, message)) => {
+                    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+                    status
+                }
+                None => PolylabelStatus::Ok,
+            };
+            Ok((results, status))
+        }
+    }
+}
+
+/// FFI access to the [`polylabel_multi`](fn.polylabel_multi.html) function, returning a library-allocated
+/// sequence of label positions
+///
+/// Accepts the same arguments as [`polylabel_multi_ffi`](fn.polylabel_multi_ffi.html), but `out` points to a
+/// single [`PositionArray`](struct.PositionArray.html), which receives one label position if `parts` is
+/// `Largest`, or one for each part if it's `All`. It receives an empty `PositionArray` if the parts are invalid.
+///
+/// Returns `Ok` if every label position was calculated, or the status of the first that couldn't be.
+/// `NullPointer` is returned if `polygons` (when `len` isn't 0) or `out` is null.
+///
+/// # Nullability
+///
+/// `polygons` is nullable if `len` is 0. `out` is non-null.
+///
+/// # Safety
+///
+/// `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to a writable `PositionArray`, whose
+/// contents must be freed using [`polylabel_position_array_free`](fn.polylabel_position_array_free.html).
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_multi_array_ffi(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    parts: PolylabelParts,
+    out: *mut PositionArray,
+) -> PolylabelStatus {
+    if out.is_null() {
+        return fail(PolylabelStatus::NullPointer, "null output pointer");
+    }
+    *out = PositionArray::from(vec![]);
+    catch(
+        || match label_parts(polygons, len, tolerance, parts) {
+            Ok((results, status)) => {
+                *out = results.into();
+                status
+            }
+            Err(status) => status,
+        },
+        || PolylabelStatus::Panic,
+    )
+}
+
+/// Free a [`PositionArray`](struct.PositionArray.html) allocated by the library
+///
+/// # Safety
+///
+/// `array` must have been returned by the library, and not already freed. An empty `PositionArray` (whose
+/// `data` is null) may be freed, and nothing happens.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_position_array_free(array: PositionArray) {
+    if !array.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            array.data, array.len,
+        )));
+    }
+}
+
+/// An opaque handle to a sequence of [`PositionResult`](struct.PositionResult.html)s, for callers which can't
+/// safely receive library-allocated pointers inside structs (e.g. C# using P/Invoke, which can wrap it in a
+/// `SafeHandle`)
+///
+/// Created by [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html), read using
+/// [`polylabel_results_len`](fn.polylabel_results_len.html) and [`polylabel_results_get`](fn.polylabel_results_get.html),
+/// and destroyed by [`polylabel_results_free`](fn.polylabel_results_free.html).
+pub struct PolylabelResults(Vec<PositionResult>);
+
+/// FFI access to the [`polylabel_multi`](fn.polylabel_multi.html) function, returning an opaque handle to the
+/// label positions
+///
+/// Accepts the same first four arguments as [`polylabel_multi_ffi`](fn.polylabel_multi_ffi.html), followed by a
+/// pointer to a [`PolylabelStatus`](enum.PolylabelStatus.html), which receives `Ok` if every label position was
+/// calculated, or the status of the first part whose label position couldn't be. May be null.
+///
+/// Returns a pointer to a [`PolylabelResults`](struct.PolylabelResults.html), which must be freed using
+/// [`polylabel_results_free`](fn.polylabel_results_free.html), or null if the input is invalid.
+///
+/// # Nullability
+///
+/// `polygons` is nullable if `len` is 0. `status` is nullable.
+///
+/// # Safety
+///
+/// `polygons` must point to `len` valid [`PolygonFFI`](struct.PolygonFFI.html)s, and `status` must be null,
+/// or point to a writable `PolylabelStatus`.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_multi_results_ffi(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    parts: PolylabelParts,
+    status: *mut PolylabelStatus,
+) -> *mut PolylabelResults {
+    let (results, outcome) = catch(
+        || match label_parts(polygons, len, tolerance, parts) {
+            Ok((results, status)) => (Box::into_raw(Box::new(PolylabelResults(results))), status),
+            Err(status) => (ptr::null_mut(), status),
+        },
+        || (ptr::null_mut(), PolylabelStatus::Panic),
+    );
+    if !status.is_null() {
+        *status = outcome;
+    }
+    results
+}
+
+/// The number of label positions held by a [`PolylabelResults`](struct.PolylabelResults.html), which is 0 if
+/// `results` is null
+///
+/// # Nullability
+///
+/// `results` is nullable.
+///
+/// # Safety
+///
+/// `results` must be null, or a live handle returned by
+/// [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html).
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_results_len(results: *const PolylabelResults) -> size_t {
+    results.as_ref().map_or(0, |results| results.0.len())
+}
+
+/// Copy a label position held by a [`PolylabelResults`](struct.PolylabelResults.html)
+///
+/// Accepts three arguments:
+///
+/// - a pointer to a `PolylabelResults`
+/// - the label position's index, a `size_t`
+/// - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the label position.
+///
+/// Returns the label position's status, `InvalidLength` if `index` is out of bounds, or `NullPointer` if either
+/// pointer is null.
+///
+/// # Nullability
+///
+/// `results` and `out` are non-null.
+///
+/// # Safety
+///
+/// `results` must be a live handle returned by
+/// [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html), and `out` must point to a writable
+/// `PositionResult`.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_results_get(
+    results: *const PolylabelResults,
+    index: size_t,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    if results.is_null() || out.is_null() {
+        return fail(
+            PolylabelStatus::NullPointer,
+            "null results or output pointer",
+        );
+    }
+    let results = &(*results).0;
+    match results.get(index) {
+        Some(&result) => {
+            *out = result;
+            result.status
+        }
+        None => write_error(
+            out,
+            fail(
+                PolylabelStatus::InvalidLength,
+                format_args!(
+                    "index {} is out of bounds for {} results",
+                    index,
+                    results.len()
+                ),
+            ),
+        ),
+    }
+}
+
+/// Free a [`PolylabelResults`](struct.PolylabelResults.html). Passing null is a no-op.
+///
+/// # Nullability
+///
+/// `results` is nullable.
+///
+/// # Safety
+///
+/// `results` must be null, or a handle returned by
+/// [`polylabel_multi_results_ffi`](fn.polylabel_multi_results_ffi.html) which hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_results_free(results: *mut PolylabelResults) {
+    if !results.is_null() {
+        drop(Box::from_raw(results));
+    }
+}
+
+/// An opaque handle to a [`PreparedPolygon`](struct.PreparedPolygon.html), for repeated label position
+/// calculations across the FFI boundary
+///
+/// Created by [`polylabel_prepare`](fn.polylabel_prepare.html) or [`polylabel_create`](fn.polylabel_create.html),
+/// used by [`polylabel_compute`](fn.polylabel_compute.html), and destroyed by [`polylabel_free`](fn.polylabel_free.html)
+/// or [`polylabel_destroy`](fn.polylabel_destroy.html).
+pub struct PolylabelHandle(PreparedPolygon<c_double>);
+
+/// Prepare a Polygon for repeated label position calculation
+///
+/// Accepts three arguments:
+///
+/// - an exterior ring representing a Polygon shell or closed LineString
+/// - zero or more interior rings representing Polygon holes
+/// - a pointer to a [`PolylabelStatus`](enum.PolylabelStatus.html), which receives the outcome. May be null.
+///
+/// Returns a pointer to a [`PolylabelHandle`](struct.PolylabelHandle.html), which must be freed using
+/// [`polylabel_free`](fn.polylabel_free.html), or null if the Polygon couldn't be prepared.
+///
+/// # Nullability
+///
+/// `status` is nullable.
+///
+/// # Safety
+///
+/// `status` must be null, or point to a writable `PolylabelStatus`.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_prepare(
+    outer: Array,
+    inners: WrapperArray,
+    status: *mut PolylabelStatus,
+) -> *mut PolylabelHandle {
+    handle(|| polygon(&outer, inners), status)
 }
 
-/// Wrapper for a void pointer to a sequence of 2-element arrays representing points, and the sequence length. Used for FFI.
+/// Prepare a Polygon held in a single interleaved coordinate buffer for repeated label position calculation
 ///
-/// Used for the outer Polygon shell. `data` is a `Vec<[c_double; 2]>`.
-#[repr(C)]
-pub struct Array {
-    pub data: *const c_void,
-    pub len: size_t,
+/// Accepts four arguments:
+///
+/// - a pointer to a buffer of interleaved `x, y` `c_double` coordinates
+/// - a pointer to a sequence of `n_rings + 1` `size_t` ring offsets, as for
+///   [`polylabel_flat_ffi`](fn.polylabel_flat_ffi.html)
+/// - the number of rings, a `size_t`
+/// - a pointer to a [`PolylabelStatus`](enum.PolylabelStatus.html), which receives the outcome. May be null.
+///
+/// Returns a pointer to a [`PolylabelHandle`](struct.PolylabelHandle.html), which must be freed using
+/// [`polylabel_destroy`](fn.polylabel_destroy.html) (or [`polylabel_free`](fn.polylabel_free.html)), or null if
+/// the Polygon couldn't be prepared. The buffers are copied, so they may be freed as soon as this returns.
+///
+/// Together with `polylabel_destroy`, this suits garbage-collected callers such as Dart: fill a single native
+/// buffer, create a handle, and attach `polylabel_destroy` to the wrapping object using a `NativeFinalizer`.
+///
+/// # Nullability
+///
+/// `coords` and `ring_offsets` are non-null. `status` is nullable.
+///
+/// # Safety
+///
+/// `ring_offsets` must point to `n_rings + 1` `size_t`s, `coords` must point to `2 * ring_offsets[n_rings]`
+/// `c_double`s, and `status` must be null, or point to a writable `PolylabelStatus`.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_create(
+    coords: *const c_double,
+    ring_offsets: *const size_t,
+    n_rings: size_t,
+    status: *mut PolylabelStatus,
+) -> *mut PolylabelHandle {
+    handle(|| flat_polygon(coords, ring_offsets, n_rings), status)
 }
 
-/// FFI struct for returned optimum Polygon label position
-#[repr(C)]
-pub struct Position {
-    pub x_pos: c_double,
-    pub y_pos: c_double,
+// prepare a Polygon, returning a handle to it (or null), and writing the outcome to status if it isn't null
+unsafe fn handle(
+    poly: impl FnOnce() -> Result<Polygon<c_double>, PolylabelStatus>,
+    status: *mut PolylabelStatus,
+) -> *mut PolylabelHandle {
+    let (handle, outcome) = catch(
+        || match poly().map(prepare) {
+            Ok(Ok(prepared)) => (
+                Box::into_raw(Box::new(PolylabelHandle(prepared))),
+                PolylabelStatus::Ok,
+            ),
+            Ok(Err(err)) => (ptr::null_mut(), err.into()),
+            Err(status) => (ptr::null_mut(), status),
+        },
+        || (ptr::null_mut(), PolylabelStatus::Panic),
+    );
+    if !status.is_null() {
+        *status = outcome;
+    }
+    handle
 }
 
-// convert a Polylabel result Point into values that can be sent across the FFI boundary
-impl<T> From<Point<T>> for Position
-where
-    T: GeoFloat,
-{
-    fn from(point: Point<T>) -> Position {
-        Position {
-            x_pos: point.x().to_f64().unwrap() as c_double,
-            y_pos: point.y().to_f64().unwrap() as c_double,
-        }
+/// Calculate a prepared Polygon's label position
+///
+/// Accepts three arguments:
+///
+/// - a pointer to a [`PolylabelHandle`](struct.PolylabelHandle.html) returned by
+///   [`polylabel_prepare`](fn.polylabel_prepare.html)
+/// - a tolerance `c_double`
+/// - a pointer to a [`PositionResult`](struct.PositionResult.html), which receives the result.
+///
+/// Returns the result's status, which is `NullPointer` if either pointer is null.
+///
+/// A handle may be used from several threads at once.
+///
+/// # Nullability
+///
+/// `handle` and `out` are non-null.
+///
+/// # Safety
+///
+/// `handle` must be a live handle returned by `polylabel_prepare`, and `out` must point to a writable
+/// `PositionResult`.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_compute(
+    handle: *const PolylabelHandle,
+    tolerance: c_double,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    if handle.is_null() || out.is_null() {
+        return fail(
+            PolylabelStatus::NullPointer,
+            "null handle or output pointer",
+        );
     }
+    catch(
+        || {
+            warn_tolerance(&(*handle).0, tolerance);
+            *out = (*handle).0.label(&tolerance).into();
+            PolylabelStatus::Ok
+        },
+        || write_error(out, PolylabelStatus::Panic),
+    )
 }
 
-fn reconstitute(arr: &Array) -> Vec<[f64; 2]> {
-    unsafe { slice::from_raw_parts(arr.data as *mut [f64; 2], arr.len).to_vec() }
+/// Free a [`PolylabelHandle`](struct.PolylabelHandle.html). Passing null is a no-op.
+///
+/// # Nullability
+///
+/// `handle` is nullable.
+///
+/// # Safety
+///
+/// `handle` must be null, or a handle returned by [`polylabel_prepare`](fn.polylabel_prepare.html)
+/// which hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_free(handle: *mut PolylabelHandle) {
+    if !handle.is_null() {
+        catch(|| drop(Box::from_raw(handle)), || ());
+    }
 }
 
-fn reconstitute2(arr: WrapperArray) -> Vec<Vec<[f64; 2]>> {
-    let arrays = unsafe { slice::from_raw_parts(arr.data as *mut Array, arr.len) };
-    arrays.iter().map(reconstitute).collect()
+/// Free a [`PolylabelHandle`](struct.PolylabelHandle.html) created by
+/// [`polylabel_create`](fn.polylabel_create.html) or [`polylabel_prepare`](fn.polylabel_prepare.html).
+/// Passing null is a no-op.
+///
+/// Equivalent to [`polylabel_free`](fn.polylabel_free.html), but takes a `void` pointer, so it matches the
+/// `void (*)(void *)` signature required of finalizers, such as Dart's `NativeFinalizer`.
+///
+/// # Nullability
+///
+/// `handle` is nullable.
+///
+/// # Safety
+///
+/// `handle` must be null, or a handle which hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_destroy(handle: *mut c_void) {
+    polylabel_free(handle as *mut PolylabelHandle)
 }
 
-/// FFI access to the [`polylabel`](fn.polylabel.html) function
+/// FFI access to the [`polylabel`](fn.polylabel.html) function for many Polygons in a single call
 ///
-/// Accepts three arguments:
+/// Accepts five arguments:
 ///
-/// - an exterior ring representing a Polygon shell or closed LineString
-/// - zero or more interior rings representing Polygon holes
-/// - a tolerance `c_double`.
-/// If an error occurs while attempting to calculate the label position, the resulting point coordinates
+/// - a pointer to a sequence of [`PolygonFFI`](struct.PolygonFFI.html)s
+/// - the length of the sequence, a `size_t`
+/// - a tolerance `c_double`
+/// - the number of threads to calculate label positions on, a `size_t`. 0 uses one thread per available core
+/// - a pointer to a caller-allocated sequence of `len` [`Position`](struct.Position.html)s.
+///
+/// Each Polygon's label position is written to the `out` entry with the same index. Polygons are labelled
+/// by an internal thread pool, which is kept between calls, so repeated calls don't have to spawn new threads.
+/// If an error occurs while attempting to calculate a label position, that entry's point coordinates
 /// will be `NaN, NaN`.
+///
+/// Returns `Ok` if every label position was calculated, or the [`PolylabelStatus`](enum.PolylabelStatus.html)
+/// of the first Polygon whose label position couldn't be. `NullPointer` is returned if `polygons` or `out` is null.
+///
+/// # Nullability
+///
+/// `polygons` is nullable if `len` is 0. `out` is non-null.
+///
+/// # Safety
+///
+/// `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to `len` writable `Position`s.
 #[no_mangle]
-pub extern "C" fn polylabel_ffi(
-    outer: Array,
-    inners: WrapperArray,
+pub unsafe extern "C" fn polylabel_batch_ffi(
+    polygons: *const PolygonFFI,
+    len: size_t,
     tolerance: c_double,
-) -> Position {
-    let exterior: LineString<_> = unsafe {
-        slice::from_raw_parts(outer.data as *mut [c_double; 2], outer.len)
-            .to_vec()
-            .into()
+    threads: size_t,
+    out: *mut Position,
+) -> PolylabelStatus {
+    catch(
+        || batch(polygons, len, tolerance, threads, out),
+        || PolylabelStatus::Panic,
+    )
+}
+
+/// FFI access to the [`polylabel_with_options`](fn.polylabel_with_options.html) function for many Polygons in a
+/// single call, writing each result to a caller-allocated [`PositionResult`](struct.PositionResult.html)
+///
+/// Accepts the same arguments as [`polylabel_batch_ffi`](fn.polylabel_batch_ffi.html), but `out` must point to a
+/// caller-allocated sequence of `len` `PositionResult`s, so each Polygon's status and distance are available.
+/// The library doesn't allocate any output storage, so `out` can be reused between calls.
+///
+/// Returns `Ok` if every label position was calculated, or the [`PolylabelStatus`](enum.PolylabelStatus.html)
+/// of the first Polygon whose label position couldn't be. `NullPointer` is returned if `polygons` or `out` is null.
+///
+/// # Nullability
+///
+/// `polygons` is nullable if `len` is 0. `out` is non-null.
+///
+/// # Safety
+///
+/// `polygons` must point to `len` valid `PolygonFFI`s, and `out` must point to `len` writable `PositionResult`s.
+#[no_mangle]
+pub unsafe extern "C" fn polylabel_batch_ext_ffi(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    threads: size_t,
+    out: *mut PositionResult,
+) -> PolylabelStatus {
+    catch(
+        || batch(polygons, len, tolerance, threads, out),
+        || PolylabelStatus::Panic,
+    )
+}
+
+// A batch call's output buffer, which the caller may not have initialised, so it's written through
+// a raw pointer rather than borrowed as a slice
+struct Output<O>(*mut MaybeUninit<O>);
+
+// Each output is written by the single thread labelling its Polygon
+unsafe impl<O: Send> Send for Output<O> {}
+unsafe impl<O: Send> Sync for Output<O> {}
+
+impl<O> Output<O> {
+    // `i` must be within the buffer, and no other thread may write the same output
+    unsafe fn write(&self, i: usize, value: O) {
+        (*self.0.add(i)).write(value);
+    }
+}
+
+// The output of a batch call for a single Polygon
+trait BatchOutput: From<Label<c_double>> + Send {
+    // The output for a Polygon whose label position couldn't be calculated
+    fn failed(status: PolylabelStatus) -> Self;
+}
+
+impl BatchOutput for Position {
+    fn failed(_: PolylabelStatus) -> Self {
+        Position::error()
+    }
+}
+
+impl BatchOutput for PositionResult {
+    fn failed(status: PolylabelStatus) -> Self {
+        PositionResult::error(status)
+    }
+}
+
+unsafe fn batch<O>(
+    polygons: *const PolygonFFI,
+    len: size_t,
+    tolerance: c_double,
+    threads: size_t,
+    out: *mut O,
+) -> PolylabelStatus
+where
+    O: BatchOutput,
+{
+    if len == 0 {
+        return PolylabelStatus::Ok;
+    }
+    let polygons = borrow(polygons, len).map_err(|status| context(status, "polygons"));
+    let output = check(out, len).map_err(|status| context(status, "output"));
+    let polygons = match (polygons, output) {
+        (Ok(polygons), Ok(_)) => polygons,
+        (Err(status), _) | (_, Err(status)) => return status,
+    };
+    // A failure's status, and its description, which has to be passed back to the calling thread
+    type Failure = (PolylabelStatus, Option<CString>);
+    let failure = |i: usize, status| {
+        let status = context(status, format_args!("polygon {}", i));
+        (status, last_error())
     };
-    let interior: Vec<Vec<[f64; 2]>> = reconstitute2(inners);
-    let ls_int: Vec<LineString<c_double>> = interior.into_iter().map(|vec| vec.into()).collect();
-    let poly = Polygon::new(exterior, ls_int);
-    polylabel(&poly, &tolerance)
-        .unwrap_or_else(|_| Point::new(f64::NAN, f64::NAN))
-        .into()
+    // The caller's pointers can't be shared between threads, so borrow the rings up front.
+    // Each Polygon is built by the thread labelling it, so only one per thread exists at a time
+    let polygons: Vec<Result<Rings, Failure>> = polygons
+        .iter()
+        .enumerate()
+        .map(|(i, poly)| Rings::new(&poly.outer, poly.inners).map_err(|status| failure(i, status)))
+        .collect();
+    let out = Output(out as *mut MaybeUninit<O>);
+    let label_all = move || {
+        polygons
+            .par_iter()
+            .enumerate()
+            .filter_map(|(i, poly)| {
+                // a panic while labelling one Polygon doesn't prevent the others being labelled
+                let result = poly.as_ref().map_err(Clone::clone).and_then(|rings| {
+                    catch(
+                        || label(rings.polygon(), tolerance).map_err(PolylabelStatus::from),
+                        || Err(PolylabelStatus::Panic),
+                    )
+                    .map_err(|status| failure(i, status))
+                });
+                match result {
+                    Ok(result) => {
+                        out.write(i, result);
+                        None
+                    }
+                    Err(failure) => {
+                        out.write(i, O::failed(failure.0));
+                        Some((i, failure))
+                    }
+                }
+            })
+            // Report the first failure
+            .min_by_key(|&(i, _)| i)
+    };
+    let first_failure = match thread_pool(threads) {
+        Some(pool) => pool.install(label_all),
+        None => label_all(),
+    };
+    match first_failure {
+        Some((_, (status, message))) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = message);
+            status
+        }
+        None => PolylabelStatus::Ok,
+    }
+}
+
+// The thread pool used by the most recent batch call with an explicit thread count. It's kept so that
+// repeated calls with the same thread count don't have to spawn new threads
+static POOL: Mutex<Option<(usize, Arc<ThreadPool>)>> = Mutex::new(None);
+
+// A pool with the given number of threads, or None to use rayon's global pool (one thread per available core)
+fn thread_pool(threads: size_t) -> Option<Arc<ThreadPool>> {
+    if threads == 0 {
+        return None;
+    }
+    let mut pool = POOL.lock().unwrap_or_else(PoisonError::into_inner);
+    if !matches!(&*pool, Some((n, _)) if *n == threads) {
+        // If the pool can't be built, fall back to the global pool
+        *pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("polylabel-{}", i))
+            .build()
+            .ok()
+            .map(|built| (threads, Arc::new(built)));
+    }
+    pool.as_ref().map(|(_, pool)| Arc::clone(pool))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ffi::{polylabel_ffi, reconstitute2, Array, WrapperArray};
+    #[cfg(feature = "arrow")]
+    use crate::arrow::{ArrowArray, ArrowSchema};
+    use crate::errors::PolylabelError;
+    #[cfg(feature = "arrow")]
+    use crate::ffi::polylabel_arrow_ffi;
+    #[cfg(feature = "geojson")]
+    use crate::ffi::polylabel_geojson_ffi;
+    #[cfg(feature = "wkb")]
+    use crate::ffi::polylabel_wkb_ffi;
+    #[cfg(feature = "wkt")]
+    use crate::ffi::polylabel_wkt_ffi;
+    use crate::ffi::{
+        catch, last_error, polylabel_abi_version, polylabel_batch_ext_ffi, polylabel_batch_ffi,
+        polylabel_cancellable_ffi, polylabel_clear_last_error, polylabel_compute, polylabel_create,
+        polylabel_destroy, polylabel_ext_ffi, polylabel_f32_ffi, polylabel_ffi, polylabel_flat_ffi,
+        polylabel_free, polylabel_last_error_message, polylabel_multi_array_ffi,
+        polylabel_multi_ffi, polylabel_multi_results_ffi, polylabel_position_array_free,
+        polylabel_prepare, polylabel_primitive_batch_ffi, polylabel_primitive_ffi,
+        polylabel_progress_ffi, polylabel_results_free, polylabel_results_get,
+        polylabel_results_len, polylabel_set_log_callback, polylabel_version, polylabel_xy_ffi,
+        reconstitute2, Array, ArrayF32, PolygonFFI, PolylabelLogLevel, PolylabelParts,
+        PolylabelStatus, Position, PositionArray, PositionResult, WrapperArray, WrapperArrayF32,
+        POLYLABEL_ABI_VERSION,
+    };
     use geo::Point;
-    use libc::{c_void, size_t};
-    use std::mem;
+    use libc::{c_char, c_int, c_void, size_t};
+    use std::ffi::CStr;
+    #[cfg(any(feature = "geojson", feature = "wkt"))]
+    use std::ffi::CString;
+    use std::mem::{self, MaybeUninit};
+    use std::ptr;
+    use std::slice;
+    use std::sync::Mutex;
 
     // Only used for testing
     fn gen_array(v: Vec<[f64; 2]>) -> Array {
@@ -112,8 +2161,11 @@ mod tests {
         let i_b = vec![[0.55, 0.55], [0.8, 0.8], [1.2, 0.55]];
         let inners = vec![i_a, i_b];
         let array = gen_wrapperarray(inners);
-        let rec_inners = reconstitute2(array);
-        assert_eq!(rec_inners[0][2], [1.5, 0.5])
+        let rec_inners = reconstitute2(array).unwrap();
+        assert_eq!(rec_inners[0][2], [1.5, 0.5]);
+        // the rings are borrowed, not copied
+        let first = unsafe { &*array.data };
+        assert_eq!(rec_inners[0].as_ptr() as *const c_void, first.data);
     }
     #[test]
     fn test_ffi() {
@@ -137,6 +2189,895 @@ mod tests {
         let inners = gen_wrapperarray(int_vec);
         let res = polylabel_ffi(outer, inners, 0.1);
         let res_point = Point::new(res.x_pos, res.y_pos);
-        assert_eq!(res_point, Point::new(3.125, 2.875));
+        // within the tolerance of the pole, which is 0.499 from the nearest edge, near (3.2, 1.5)
+        assert_eq!(res_point, Point::new(2.515625, 2.828125));
+    }
+    #[test]
+    fn test_batch_ffi() {
+        let square = |offset: f64, size: f64| {
+            vec![
+                [offset, offset],
+                [offset + size, offset],
+                [offset + size, offset + size],
+                [offset, offset + size],
+                [offset, offset],
+            ]
+        };
+        let polygons: Vec<PolygonFFI> = (0..10)
+            .map(|i| PolygonFFI {
+                outer: gen_array(square(i as f64, 2.0)),
+                inners: gen_wrapperarray(vec![]),
+            })
+            .collect();
+        // the second call with 3 threads reuses the first call's pool
+        for threads in [0, 1, 3, 3] {
+            let mut out: Vec<Position> = (0..polygons.len())
+                .map(|_| Position {
+                    x_pos: 0.0,
+                    y_pos: 0.0,
+                })
+                .collect();
+            let status = unsafe {
+                polylabel_batch_ffi(
+                    polygons.as_ptr(),
+                    polygons.len(),
+                    0.1,
+                    threads,
+                    out.as_mut_ptr(),
+                )
+            };
+            assert_eq!(status, PolylabelStatus::Ok);
+            for (i, res) in out.iter().enumerate() {
+                let expected = i as f64 + 1.0;
+                assert_eq!(
+                    Point::new(res.x_pos, res.y_pos),
+                    Point::new(expected, expected)
+                );
+            }
+        }
+    }
+    #[test]
+    fn test_batch_uninit() {
+        // the output buffer needn't be initialised
+        let polygons = [
+            PolygonFFI {
+                outer: gen_array(vec![
+                    [0.0, 0.0],
+                    [2.0, 0.0],
+                    [2.0, 2.0],
+                    [0.0, 2.0],
+                    [0.0, 0.0],
+                ]),
+                inners: gen_wrapperarray(vec![]),
+            },
+            PolygonFFI {
+                outer: gen_array(vec![]),
+                inners: gen_wrapperarray(vec![]),
+            },
+        ];
+        let mut out: Vec<MaybeUninit<PositionResult>> =
+            (0..2).map(|_| MaybeUninit::uninit()).collect();
+        let status = unsafe {
+            polylabel_batch_ext_ffi(polygons.as_ptr(), 2, 0.1, 0, out.as_mut_ptr().cast())
+        };
+        assert_eq!(status, PolylabelStatus::InvalidLength);
+        let out: Vec<PositionResult> = out
+            .into_iter()
+            .map(|o| unsafe { o.assume_init() })
+            .collect();
+        assert_eq!(
+            (out[0].x, out[0].y, out[0].status),
+            (1.0, 1.0, PolylabelStatus::Ok)
+        );
+        assert_eq!(out[1].status, PolylabelStatus::InvalidLength);
+
+        let mut out: Vec<MaybeUninit<Position>> = (0..1).map(|_| MaybeUninit::uninit()).collect();
+        let status =
+            unsafe { polylabel_batch_ffi(polygons.as_ptr(), 1, 0.1, 0, out.as_mut_ptr().cast()) };
+        assert_eq!(status, PolylabelStatus::Ok);
+        let out = unsafe { out[0].assume_init_read() };
+        assert_eq!((out.x_pos, out.y_pos), (1.0, 1.0));
+    }
+    #[test]
+    fn test_batch_ext_ffi() {
+        let polygons = [
+            PolygonFFI {
+                outer: gen_array(vec![
+                    [0.0, 0.0],
+                    [2.0, 0.0],
+                    [2.0, 2.0],
+                    [0.0, 2.0],
+                    [0.0, 0.0],
+                ]),
+                inners: gen_wrapperarray(vec![]),
+            },
+            PolygonFFI {
+                outer: gen_array(vec![]),
+                inners: gen_wrapperarray(vec![]),
+            },
+        ];
+        // the output buffer is reused between calls
+        let mut out: Vec<PositionResult> = (0..2)
+            .map(|_| PositionResult::error(PolylabelStatus::Ok))
+            .collect();
+        for threads in [0, 1] {
+            let status = unsafe {
+                polylabel_batch_ext_ffi(polygons.as_ptr(), 2, 0.1, threads, out.as_mut_ptr())
+            };
+            assert_eq!(status, PolylabelStatus::InvalidLength);
+            assert_eq!((out[0].x, out[0].y, out[0].distance), (1.0, 1.0, 1.0));
+            assert_eq!(out[0].status, PolylabelStatus::Ok);
+            assert!(out[1].x.is_nan());
+            assert_eq!(out[1].status, PolylabelStatus::InvalidLength);
+        }
+        let status =
+            unsafe { polylabel_batch_ext_ffi(polygons.as_ptr(), 2, 0.1, 0, ptr::null_mut()) };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+    }
+    #[test]
+    fn test_ext_ffi() {
+        let outer = gen_array(vec![
+            [0.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 4.0],
+            [0.0, 4.0],
+            [0.0, 0.0],
+        ]);
+        let res = polylabel_ext_ffi(outer, gen_wrapperarray(vec![]), 0.1);
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert_eq!((res.x, res.y, res.distance), (0.5625, 0.5625, 0.5625));
+        // degenerate Polygons have a fixed label position
+        let line = gen_array(vec![[0.0, 0.0], [1.0, 1.0], [0.0, 0.0]]);
+        let res = polylabel_ext_ffi(line, gen_wrapperarray(vec![]), 0.1);
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert_eq!((res.x, res.y, res.distance), (0.0, 0.0, 0.0));
+    }
+    #[test]
+    fn test_f32_ffi() {
+        let coords: Vec<[f32; 2]> = vec![
+            [0.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 4.0],
+            [0.0, 4.0],
+            [0.0, 0.0],
+        ];
+        let outer = ArrayF32 {
+            data: coords.as_ptr() as *const c_void,
+            len: coords.len(),
+        };
+        let no_holes = WrapperArrayF32 {
+            data: ptr::null(),
+            len: 0,
+        };
+        let res = polylabel_f32_ffi(outer, no_holes, 0.1);
+        assert_eq!((res.x_pos, res.y_pos), (0.5625, 0.5625));
+        let empty = ArrayF32 {
+            data: ptr::null(),
+            len: 0,
+        };
+        assert!(polylabel_f32_ffi(empty, no_holes, 0.1).x_pos.is_nan());
+    }
+    #[test]
+    fn test_progress_ffi() {
+        extern "C" fn record(best_distance: f64, bound: f64, user_data: *mut c_void) {
+            let reports = unsafe { &mut *(user_data as *mut Vec<(f64, f64)>) };
+            reports.push((best_distance, bound));
+        }
+        let rings: Vec<Vec<(f64, f64)>> = include!("../tests/fixtures/lakes.rs");
+        let mut rings = rings
+            .into_iter()
+            .map(|ring| ring.into_iter().map(|(x, y)| [x, y]).collect());
+        let outer = gen_array(rings.next().unwrap());
+        let mut reports: Vec<(f64, f64)> = vec![];
+        let res = polylabel_progress_ffi(
+            outer,
+            gen_wrapperarray(rings.collect()),
+            1e-12,
+            Some(record),
+            &mut reports as *mut _ as *mut c_void,
+        );
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert!(reports.len() > 1);
+        // the best distance never decreases, and the bound never increases
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].0 <= pair[1].0 && pair[0].1 >= pair[1].1));
+        let &(best_distance, bound) = reports.last().unwrap();
+        assert_eq!(best_distance, res.distance);
+        assert!(bound - best_distance <= 1e-12);
+        // the callback is optional
+        let outer = gen_array(vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 0.0]]);
+        let res =
+            polylabel_progress_ffi(outer, gen_wrapperarray(vec![]), 0.1, None, ptr::null_mut());
+        assert_eq!(res.status, PolylabelStatus::Ok);
+    }
+    #[test]
+    fn test_cancellable_ffi() {
+        let square = || {
+            gen_array(vec![
+                [0.0, 0.0],
+                [4.0, 0.0],
+                [4.0, 4.0],
+                [0.0, 4.0],
+                [0.0, 0.0],
+            ])
+        };
+        let flag: c_int = 1;
+        let res =
+            unsafe { polylabel_cancellable_ffi(square(), gen_wrapperarray(vec![]), 0.1, &flag) };
+        assert_eq!(res.status, PolylabelStatus::Cancelled);
+        // the best position found so far is still inside the Polygon
+        assert!(res.distance > 0.0);
+        let flag: c_int = 0;
+        let res =
+            unsafe { polylabel_cancellable_ffi(square(), gen_wrapperarray(vec![]), 0.1, &flag) };
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert_eq!((res.x, res.y), (2.0, 2.0));
+        let res = unsafe {
+            polylabel_cancellable_ffi(square(), gen_wrapperarray(vec![]), 0.1, ptr::null())
+        };
+        assert_eq!(res.status, PolylabelStatus::Ok);
+    }
+    #[test]
+    fn test_version() {
+        let version = unsafe { CStr::from_ptr(polylabel_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(polylabel_abi_version(), POLYLABEL_ABI_VERSION);
+    }
+    #[test]
+    fn test_last_error() {
+        let message = || unsafe {
+            let message = polylabel_last_error_message();
+            (!message.is_null()).then(|| CStr::from_ptr(message).to_str().unwrap().to_owned())
+        };
+        polylabel_clear_last_error();
+        assert_eq!(message(), None);
+        let square = || {
+            gen_array(vec![
+                [0.0, 0.0],
+                [2.0, 0.0],
+                [2.0, 2.0],
+                [0.0, 2.0],
+                [0.0, 0.0],
+            ])
+        };
+        let holes = [
+            gen_array(vec![[0.5, 0.5], [1.0, 0.5], [1.0, 1.0], [0.5, 0.5]]),
+            Array {
+                data: ptr::null(),
+                len: 5,
+            },
+        ];
+        let inners = WrapperArray {
+            data: holes.as_ptr(),
+            len: holes.len(),
+        };
+        let res = polylabel_ext_ffi(square(), inners, 0.1);
+        assert_eq!(res.status, PolylabelStatus::NullPointer);
+        assert_eq!(
+            message().unwrap(),
+            "interior ring 1: null pointer with a length of 5"
+        );
+        // successful calls don't clear the message
+        let res = polylabel_ext_ffi(square(), gen_wrapperarray(vec![]), 0.1);
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert!(message().is_some());
+        polylabel_clear_last_error();
+        assert_eq!(message(), None);
+
+        let coords = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        unsafe { polylabel_flat_ffi(coords.as_ptr(), [0, 4, 3].as_ptr(), 2, 0.1) };
+        assert_eq!(
+            message().unwrap(),
+            "offset 1 (4) is greater than offset 2 (3)"
+        );
+        // the failures of batch calls are reported on the calling thread
+        let polygons = [
+            PolygonFFI {
+                outer: square(),
+                inners: gen_wrapperarray(vec![]),
+            },
+            PolygonFFI {
+                outer: gen_array(vec![]),
+                inners: gen_wrapperarray(vec![]),
+            },
+        ];
+        let mut out: Vec<Position> = (0..2)
+            .map(|_| Position {
+                x_pos: 0.0,
+                y_pos: 0.0,
+            })
+            .collect();
+        let status = unsafe { polylabel_batch_ffi(polygons.as_ptr(), 2, 0.1, 2, out.as_mut_ptr()) };
+        assert_eq!(status, PolylabelStatus::InvalidLength);
+        assert_eq!(message().unwrap(), "polygon 1: the exterior ring is empty");
+    }
+    #[test]
+    fn test_log_callback() {
+        extern "C" fn record(
+            level: PolylabelLogLevel,
+            message: *const c_char,
+            user_data: *mut c_void,
+        ) {
+            let messages =
+                unsafe { &*(user_data as *const Mutex<Vec<(PolylabelLogLevel, String)>>) };
+            let message = unsafe { CStr::from_ptr(message) }
+                .to_str()
+                .unwrap()
+                .to_owned();
+            messages.lock().unwrap().push((level, message));
+        }
+        // the callback is shared by every thread, so other tests' messages may also be recorded
+        let messages: &Mutex<Vec<(PolylabelLogLevel, String)>> =
+            Box::leak(Box::new(Mutex::new(vec![])));
+        polylabel_set_log_callback(Some(record), messages as *const _ as *mut c_void);
+        let unclosed = gen_array(vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+        let hole = vec![vec![[0.5, 0.5], [1.0, 0.5], [1.0, 1.0]]];
+        let res = polylabel_ext_ffi(unclosed, gen_wrapperarray(hole), 0.0);
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        polylabel_set_log_callback(None, ptr::null_mut());
+
+        let messages = messages.lock().unwrap();
+        let logged = |expected: &str| {
+            messages
+                .iter()
+                .any(|(level, message)| *level == PolylabelLogLevel::Warn && message == expected)
+        };
+        assert!(logged(
+            "the exterior ring wasn't closed, so it's been closed"
+        ));
+        assert!(logged("interior ring 0 wasn't closed, so it's been closed"));
+        assert!(logged(&format!(
+            "a tolerance of 0.0 is finer than the coordinates' precision, so {:?} has been used",
+            2.0 * f64::EPSILON * 16.0
+        )));
+    }
+    #[test]
+    fn test_status() {
+        assert_eq!(
+            PolylabelStatus::from(PolylabelError::RectCalculation),
+            PolylabelStatus::RectCalculation
+        );
+        let status = unsafe { polylabel_batch_ffi(ptr::null(), 1, 0.1, 0, ptr::null_mut()) };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+    }
+    #[test]
+    fn test_flat_ffi() {
+        // the same Polygon as test_ffi, with its rings laid end to end
+        let coords = [
+            4.0, 1.0, 5.0, 2.0, 5.0, 3.0, 4.0, 4.0, 3.0, 4.0, 2.0, 3.0, 2.0, 2.0, 3.0, 1.0, 4.0,
+            1.0, 3.5, 3.5, 4.4, 2.0, 2.6, 2.0, 3.5, 3.5, 4.0, 3.0, 4.0, 3.2, 4.5, 3.2, 4.0, 3.0,
+        ];
+        let offsets = [0, 9, 13, 17];
+        let res = unsafe { polylabel_flat_ffi(coords.as_ptr(), offsets.as_ptr(), 3, 0.1) };
+        let expected = polylabel_ext_ffi(
+            gen_array(coords[..18].chunks(2).map(|c| [c[0], c[1]]).collect()),
+            gen_wrapperarray(vec![
+                coords[18..26].chunks(2).map(|c| [c[0], c[1]]).collect(),
+                coords[26..].chunks(2).map(|c| [c[0], c[1]]).collect(),
+            ]),
+            0.1,
+        );
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert_eq!((res.x, res.y), (expected.x, expected.y));
+
+        let decreasing = [0, 9, 4];
+        let res = unsafe { polylabel_flat_ffi(coords.as_ptr(), decreasing.as_ptr(), 2, 0.1) };
+        assert_eq!(res.status, PolylabelStatus::InvalidLength);
+    }
+    #[test]
+    fn test_primitive_ffi() {
+        // the same Polygon as test_flat_ffi, with c_int offsets
+        let coords = [
+            4.0, 1.0, 5.0, 2.0, 5.0, 3.0, 4.0, 4.0, 3.0, 4.0, 2.0, 3.0, 2.0, 2.0, 3.0, 1.0, 4.0,
+            1.0, 3.5, 3.5, 4.4, 2.0, 2.6, 2.0, 3.5, 3.5, 4.0, 3.0, 4.0, 3.2, 4.5, 3.2, 4.0, 3.0,
+        ];
+        let expected =
+            unsafe { polylabel_flat_ffi(coords.as_ptr(), [0, 9, 13, 17].as_ptr(), 3, 0.1) };
+        let mut out = [0.0; 3];
+        let status = unsafe {
+            polylabel_primitive_ffi(
+                coords.as_ptr(),
+                34,
+                [0, 9, 13, 17].as_ptr(),
+                4,
+                0.1,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!(out, [expected.x, expected.y, expected.distance]);
+
+        // the last offset is beyond the coordinates
+        let status = unsafe {
+            polylabel_primitive_ffi(
+                coords.as_ptr(),
+                32,
+                [0, 9, 13, 17].as_ptr(),
+                4,
+                0.1,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::InvalidLength);
+        assert!(out.iter().all(|value| value.is_nan()));
+        let status = unsafe {
+            polylabel_primitive_ffi(
+                coords.as_ptr(),
+                34,
+                [0, -9].as_ptr(),
+                2,
+                0.1,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::InvalidLength);
+        let message = unsafe { CStr::from_ptr(polylabel_last_error_message()) };
+        assert_eq!(
+            message.to_str().unwrap(),
+            "ring offsets: offset 1 (-9) is negative"
+        );
+
+        // the Polygon twice, followed by a Polygon whose rings are out of bounds
+        let mut doubled = coords.to_vec();
+        doubled.extend_from_slice(&coords);
+        let ring_offsets = [0, 9, 13, 17, 26, 30, 34];
+        let mut out = [0.0; 9];
+        let status = unsafe {
+            polylabel_primitive_batch_ffi(
+                doubled.as_ptr(),
+                68,
+                ring_offsets.as_ptr(),
+                7,
+                [0, 3, 6].as_ptr(),
+                3,
+                0.1,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!(out[..3], out[3..6]);
+        let status = unsafe {
+            polylabel_primitive_batch_ffi(
+                doubled.as_ptr(),
+                68,
+                ring_offsets.as_ptr(),
+                7,
+                [0, 3, 6, 8].as_ptr(),
+                4,
+                0.1,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::InvalidLength);
+        let message = unsafe { CStr::from_ptr(polylabel_last_error_message()) };
+        assert!(message.to_str().unwrap().starts_with("polygon 2: "));
+        assert!(out[6..].iter().all(|value| value.is_nan()));
+    }
+    #[test]
+    fn test_xy_ffi() {
+        let xs = [
+            4.0, 5.0, 5.0, 4.0, 3.0, 2.0, 2.0, 3.0, 4.0, 3.5, 4.4, 2.6, 3.5, 4.0, 4.0, 4.5, 4.0,
+        ];
+        let ys = [
+            1.0, 2.0, 3.0, 4.0, 4.0, 3.0, 2.0, 1.0, 1.0, 3.5, 2.0, 2.0, 3.5, 3.0, 3.2, 3.2, 3.0,
+        ];
+        let coords: Vec<f64> = xs.iter().zip(&ys).flat_map(|(&x, &y)| [x, y]).collect();
+        let expected =
+            unsafe { polylabel_flat_ffi(coords.as_ptr(), [0, 9, 13, 17].as_ptr(), 3, 0.1) };
+        let holes = [9, 13];
+        let res = unsafe { polylabel_xy_ffi(xs.as_ptr(), ys.as_ptr(), 17, holes.as_ptr(), 2, 0.1) };
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        assert_eq!((res.x, res.y), (expected.x, expected.y));
+
+        // no holes
+        let res = unsafe { polylabel_xy_ffi(xs.as_ptr(), ys.as_ptr(), 9, ptr::null(), 0, 0.1) };
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        // a hole can't start beyond the last point
+        let res = unsafe { polylabel_xy_ffi(xs.as_ptr(), ys.as_ptr(), 9, holes.as_ptr(), 2, 0.1) };
+        assert_eq!(res.status, PolylabelStatus::InvalidLength);
+    }
+    #[test]
+    #[cfg(feature = "geojson")]
+    fn test_geojson_ffi() {
+        let mut out = PositionResult::error(PolylabelStatus::Ok);
+        let geojson = CString::new(
+            r#"{"type": "MultiPolygon", "coordinates": [
+                [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]],
+                [[[10, 10], [14, 10], [14, 14], [10, 14], [10, 10]]]
+            ]}"#,
+        )
+        .unwrap();
+        let status = unsafe { polylabel_geojson_ffi(geojson.as_ptr(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::Ok);
+        // the largest part is labelled
+        assert_eq!((out.x, out.y, out.distance), (12.0, 12.0, 2.0));
+
+        let geojson =
+            CString::new(r#"{"type": "LineString", "coordinates": [[0, 0], [1, 1]]}"#).unwrap();
+        let status = unsafe { polylabel_geojson_ffi(geojson.as_ptr(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::InvalidInput);
+        assert!(out.x.is_nan());
+    }
+    #[test]
+    #[cfg(feature = "wkt")]
+    fn test_wkt_ffi() {
+        let mut out = PositionResult::error(PolylabelStatus::Ok);
+        let wkt = CString::new(
+            "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 1, 0 0)), ((10 10, 14 10, 14 14, 10 14, 10 10)))",
+        )
+        .unwrap();
+        let status = unsafe { polylabel_wkt_ffi(wkt.as_ptr(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!((out.x, out.y, out.distance), (12.0, 12.0, 2.0));
+
+        let wkt = CString::new("POLYGON EMPTY").unwrap();
+        let status = unsafe { polylabel_wkt_ffi(wkt.as_ptr(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::InvalidInput);
+        let status = unsafe { polylabel_wkt_ffi(ptr::null(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+    }
+    #[test]
+    #[cfg(feature = "wkb")]
+    fn test_wkb_ffi() {
+        let mut out = PositionResult::error(PolylabelStatus::Ok);
+        let mut wkb = vec![1, 3, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0];
+        for value in [
+            10.0, 10.0, 14.0, 10.0, 14.0, 14.0, 10.0, 14.0, 10.0, 10.0f64,
+        ] {
+            wkb.extend(value.to_le_bytes());
+        }
+        let status = unsafe { polylabel_wkb_ffi(wkb.as_ptr(), wkb.len(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!((out.x, out.y, out.distance), (12.0, 12.0, 2.0));
+
+        let status = unsafe { polylabel_wkb_ffi(wkb.as_ptr(), 20, 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::InvalidInput);
+    }
+    #[test]
+    #[cfg(feature = "arrow")]
+    fn test_arrow_ffi() {
+        unsafe extern "C" fn release(array: *mut ArrowArray) {
+            (*array).release = None;
+        }
+        let mut wkb = vec![1, 3, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0];
+        for value in [
+            10.0, 10.0, 14.0, 10.0, 14.0, 14.0, 10.0, 14.0, 10.0, 10.0f64,
+        ] {
+            wkb.extend(value.to_le_bytes());
+        }
+        // a valid Polygon, a null, and a truncated Polygon
+        let offsets = [0, wkb.len() as i32, wkb.len() as i32, wkb.len() as i32 + 20];
+        wkb.extend_from_within(..20);
+        let validity = [0b101u8];
+        let mut buffers = [
+            validity.as_ptr() as *const c_void,
+            offsets.as_ptr() as *const c_void,
+            wkb.as_ptr() as *const c_void,
+        ];
+        let array = ArrowArray {
+            length: 3,
+            null_count: 1,
+            offset: 0,
+            n_buffers: 3,
+            n_children: 0,
+            buffers: buffers.as_mut_ptr(),
+            children: ptr::null_mut(),
+            dictionary: ptr::null_mut(),
+            release: Some(release),
+            private_data: ptr::null_mut(),
+        };
+        let mut schema = ArrowSchema {
+            format: b"z\0".as_ptr() as *const c_char,
+            name: ptr::null(),
+            metadata: ptr::null(),
+            flags: 2,
+            n_children: 0,
+            children: ptr::null_mut(),
+            dictionary: ptr::null_mut(),
+            release: None,
+            private_data: ptr::null_mut(),
+        };
+        let mut out_array: ArrowArray = unsafe { mem::zeroed() };
+        let mut out_schema: ArrowSchema = unsafe { mem::zeroed() };
+        let status =
+            unsafe { polylabel_arrow_ffi(&array, &schema, 0.1, &mut out_array, &mut out_schema) };
+        assert_eq!(status, PolylabelStatus::InvalidInput);
+        assert_eq!((out_array.length, out_array.null_count), (3, 2));
+        unsafe {
+            let values = *(**out_array.children).buffers.add(1) as *const f64;
+            assert_eq!((*values, *values.add(1)), (12.0, 12.0));
+            assert_eq!(*(*out_array.buffers as *const u8), 0b001);
+            let message = CStr::from_ptr(polylabel_last_error_message());
+            assert_eq!(
+                message.to_str().unwrap(),
+                "geometry 2: the WKB value isn't a valid Polygon or MultiPolygon"
+            );
+            out_array.release.unwrap()(&mut out_array);
+            out_schema.release.unwrap()(&mut out_schema);
+        }
+
+        // unsupported arrays aren't labelled
+        schema.format = b"u\0".as_ptr() as *const c_char;
+        let status =
+            unsafe { polylabel_arrow_ffi(&array, &schema, 0.1, &mut out_array, &mut out_schema) };
+        assert_eq!(status, PolylabelStatus::InvalidInput);
+        assert!(out_array.release.is_none());
+    }
+    #[test]
+    fn test_multi_ffi() {
+        let square = |offset: f64, size: f64| PolygonFFI {
+            outer: gen_array(vec![
+                [offset, offset],
+                [offset + size, offset],
+                [offset + size, offset + size],
+                [offset, offset + size],
+                [offset, offset],
+            ]),
+            inners: gen_wrapperarray(vec![]),
+        };
+        let parts = [square(0.0, 2.0), square(10.0, 4.0), square(20.0, 1.0)];
+        let mut out: Vec<PositionResult> = (0..3)
+            .map(|_| PositionResult::error(PolylabelStatus::Ok))
+            .collect();
+        let status = unsafe {
+            polylabel_multi_ffi(
+                parts.as_ptr(),
+                3,
+                0.1,
+                PolylabelParts::Largest,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!((out[0].x, out[0].y), (12.0, 12.0));
+        // only one result is written
+        assert_eq!(out[1].status, PolylabelStatus::Ok);
+        assert!(out[1].x.is_nan());
+
+        let status = unsafe {
+            polylabel_multi_ffi(
+                parts.as_ptr(),
+                3,
+                0.1,
+                PolylabelParts::All,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        let points: Vec<_> = out.iter().map(|res| (res.x, res.y)).collect();
+        assert_eq!(points, vec![(1.0, 1.0), (12.0, 12.0), (20.5, 20.5)]);
+
+        let status = unsafe {
+            polylabel_multi_ffi(
+                ptr::null(),
+                0,
+                0.1,
+                PolylabelParts::Largest,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(status, PolylabelStatus::InvalidInput);
+        let status = unsafe {
+            polylabel_multi_ffi(ptr::null(), 1, 0.1, PolylabelParts::All, out.as_mut_ptr())
+        };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+    }
+    #[test]
+    fn test_multi_array_ffi() {
+        let square = |offset: f64, size: f64| PolygonFFI {
+            outer: gen_array(vec![
+                [offset, offset],
+                [offset + size, offset],
+                [offset + size, offset + size],
+                [offset, offset + size],
+                [offset, offset],
+            ]),
+            inners: gen_wrapperarray(vec![]),
+        };
+        let parts = [square(0.0, 2.0), square(10.0, 4.0), square(20.0, 1.0)];
+        let mut out = PositionArray::from(vec![]);
+        let status = unsafe {
+            polylabel_multi_array_ffi(parts.as_ptr(), 3, 0.1, PolylabelParts::All, &mut out)
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        let results = unsafe { slice::from_raw_parts(out.data, out.len) };
+        let points: Vec<_> = results.iter().map(|res| (res.x, res.y)).collect();
+        assert_eq!(points, vec![(1.0, 1.0), (12.0, 12.0), (20.5, 20.5)]);
+        unsafe { polylabel_position_array_free(out) };
+
+        let mut out = PositionArray::from(vec![]);
+        let status = unsafe {
+            polylabel_multi_array_ffi(parts.as_ptr(), 3, 0.1, PolylabelParts::Largest, &mut out)
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!(out.len, 1);
+        assert_eq!(unsafe { ((*out.data).x, (*out.data).y) }, (12.0, 12.0));
+        unsafe { polylabel_position_array_free(out) };
+
+        // invalid parts produce an empty array, which can be freed
+        let mut out = PositionArray::from(vec![]);
+        let status = unsafe {
+            polylabel_multi_array_ffi(ptr::null(), 1, 0.1, PolylabelParts::All, &mut out)
+        };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+        assert!(out.data.is_null());
+        assert_eq!(out.len, 0);
+        unsafe { polylabel_position_array_free(out) };
+        let status = unsafe {
+            polylabel_multi_array_ffi(parts.as_ptr(), 3, 0.1, PolylabelParts::All, ptr::null_mut())
+        };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+    }
+    #[test]
+    fn test_multi_results_ffi() {
+        let square = |offset: f64, size: f64| PolygonFFI {
+            outer: gen_array(vec![
+                [offset, offset],
+                [offset + size, offset],
+                [offset + size, offset + size],
+                [offset, offset + size],
+                [offset, offset],
+            ]),
+            inners: gen_wrapperarray(vec![]),
+        };
+        let parts = [square(0.0, 2.0), square(10.0, 4.0)];
+        let mut status = PolylabelStatus::NullPointer;
+        let results = unsafe {
+            polylabel_multi_results_ffi(parts.as_ptr(), 2, 0.1, PolylabelParts::All, &mut status)
+        };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert_eq!(unsafe { polylabel_results_len(results) }, 2);
+        let mut out = PositionResult::error(PolylabelStatus::Ok);
+        let got = unsafe { polylabel_results_get(results, 1, &mut out) };
+        assert_eq!(got, PolylabelStatus::Ok);
+        assert_eq!((out.x, out.y), (12.0, 12.0));
+        let got = unsafe { polylabel_results_get(results, 2, &mut out) };
+        assert_eq!(got, PolylabelStatus::InvalidLength);
+        assert!(out.x.is_nan());
+        unsafe { polylabel_results_free(results) };
+
+        let results = unsafe {
+            polylabel_multi_results_ffi(ptr::null(), 1, 0.1, PolylabelParts::All, &mut status)
+        };
+        assert!(results.is_null());
+        assert_eq!(status, PolylabelStatus::NullPointer);
+        assert_eq!(unsafe { polylabel_results_len(results) }, 0);
+        unsafe { polylabel_results_free(results) };
+    }
+    #[test]
+    fn test_handle() {
+        let ext_vec = vec![
+            [0.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 4.0],
+            [0.0, 4.0],
+            [0.0, 0.0],
+        ];
+        let mut status = PolylabelStatus::NullPointer;
+        let handle =
+            unsafe { polylabel_prepare(gen_array(ext_vec), gen_wrapperarray(vec![]), &mut status) };
+        assert_eq!(status, PolylabelStatus::Ok);
+        assert!(!handle.is_null());
+        let mut out = PositionResult::error(PolylabelStatus::Ok);
+        for tolerance in [1.0, 0.1, 0.01] {
+            let status = unsafe { polylabel_compute(handle, tolerance, &mut out) };
+            assert_eq!(status, PolylabelStatus::Ok);
+        }
+        assert!((out.distance - (2.0 - 2f64.sqrt())).abs() < 0.01);
+        unsafe { polylabel_free(handle) };
+        unsafe { polylabel_free(ptr::null_mut()) };
+        let status = unsafe { polylabel_compute(ptr::null(), 0.1, &mut out) };
+        assert_eq!(status, PolylabelStatus::NullPointer);
+    }
+    #[test]
+    fn test_create_destroy() {
+        // the same L-shaped Polygon as test_handle, in a flat buffer
+        let coords = [
+            0.0, 0.0, 4.0, 0.0, 4.0, 1.0, 1.0, 1.0, 1.0, 4.0, 0.0, 4.0, 0.0, 0.0,
+        ];
+        let mut status = PolylabelStatus::NullPointer;
+        let handle = unsafe { polylabel_create(coords.as_ptr(), [0, 7].as_ptr(), 1, &mut status) };
+        assert_eq!(status, PolylabelStatus::Ok);
+        let mut out = PositionResult::error(PolylabelStatus::Ok);
+        let computed = unsafe { polylabel_compute(handle, 0.01, &mut out) };
+        assert_eq!(computed, PolylabelStatus::Ok);
+        assert!((out.distance - (2.0 - 2f64.sqrt())).abs() < 0.01);
+        unsafe { polylabel_destroy(handle as *mut c_void) };
+        unsafe { polylabel_destroy(ptr::null_mut()) };
+
+        let handle = unsafe { polylabel_create(coords.as_ptr(), [0, 7].as_ptr(), 0, &mut status) };
+        assert!(handle.is_null());
+        assert_eq!(status, PolylabelStatus::InvalidLength);
+
+        // the documented layout
+        assert_eq!(mem::size_of::<PositionResult>(), 32);
+        assert_eq!(mem::size_of::<PolylabelStatus>(), 4);
+    }
+    #[test]
+    fn test_panic() {
+        // a panic is caught, recorded as the last error, and replaced by the fallback
+        let status = catch(
+            || -> PolylabelStatus { panic!("boom") },
+            || PolylabelStatus::Panic,
+        );
+        assert_eq!(status, PolylabelStatus::Panic);
+        assert_eq!(
+            last_error().unwrap().to_str().unwrap(),
+            "internal error: boom"
+        );
+        let res = catch(
+            || -> PositionResult { panic!("part {}", 2) },
+            || PositionResult::error(PolylabelStatus::Panic),
+        );
+        assert_eq!(res.status, PolylabelStatus::Panic);
+        assert!(res.x.is_nan());
+        assert_eq!(
+            last_error().unwrap().to_str().unwrap(),
+            "internal error: part 2"
+        );
+        // otherwise, the body's result is returned
+        let status = catch(|| PolylabelStatus::Ok, || PolylabelStatus::Panic);
+        assert_eq!(status, PolylabelStatus::Ok);
+    }
+    #[test]
+    fn test_validation() {
+        let square = || {
+            gen_array(vec![
+                [0.0, 0.0],
+                [2.0, 0.0],
+                [2.0, 2.0],
+                [0.0, 2.0],
+                [0.0, 0.0],
+            ])
+        };
+        let null = |len| Array {
+            data: ptr::null(),
+            len,
+        };
+        let no_holes = || gen_wrapperarray(vec![]);
+        // an empty exterior
+        let res = polylabel_ext_ffi(null(0), no_holes(), 0.1);
+        assert_eq!(res.status, PolylabelStatus::InvalidLength);
+        // a null exterior
+        let res = polylabel_ext_ffi(null(5), no_holes(), 0.1);
+        assert_eq!(res.status, PolylabelStatus::NullPointer);
+        assert!(polylabel_ffi(null(5), no_holes(), 0.1).x_pos.is_nan());
+        // a null interior ring
+        let rings = [null(3)];
+        let holes = WrapperArray {
+            data: rings.as_ptr(),
+            len: 1,
+        };
+        let res = polylabel_ext_ffi(square(), holes, 0.1);
+        assert_eq!(res.status, PolylabelStatus::NullPointer);
+        // null interior rings, but none of them
+        let holes = WrapperArray {
+            data: ptr::null(),
+            len: 0,
+        };
+        let res = polylabel_ext_ffi(square(), holes, 0.1);
+        assert_eq!(res.status, PolylabelStatus::Ok);
+        // a length which can't be addressed
+        let point = [0.0f64; 2];
+        let huge = Array {
+            data: point.as_ptr() as *const c_void,
+            len: usize::MAX / 8,
+        };
+        let res = polylabel_ext_ffi(huge, no_holes(), 0.1);
+        assert_eq!(res.status, PolylabelStatus::InvalidLength);
+
+        let coords = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        let res = unsafe { polylabel_flat_ffi(coords.as_ptr(), [0, 4].as_ptr(), usize::MAX, 0.1) };
+        assert_eq!(res.status, PolylabelStatus::InvalidLength);
+        // an empty exterior, followed by a hole
+        let res = unsafe { polylabel_flat_ffi(coords.as_ptr(), [0, 0, 4].as_ptr(), 2, 0.1) };
+        assert_eq!(res.status, PolylabelStatus::InvalidLength);
     }
 }
@@ -0,0 +1,168 @@
+//! Geodesic distances on the WGS84 ellipsoid, using [geographiclib](https://docs.rs/geographiclib-rs)
+//!
+//! Enable the `geodesic` feature to use this functionality.
+//!
+//! Measuring a point's distance to a geodesic segment means finding the segment's nearest point, which is
+//! expensive on the ellipsoid, so each segment's great-circle distance is calculated first, and only the segments
+//! which could be nearest are measured on the ellipsoid.
+
+use crate::haversine::SphericalRing;
+use geo::{Coord, GeoFloat, Line, LineString, Point};
+use geographiclib_rs::{DirectGeodesic, Geodesic, InverseGeodesic};
+
+// WGS84's semi-major axis in metres, and flattening
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+// Great-circle distances (on a sphere with the Earth's mean radius) are within 0.6 % of geodesic distances, so
+// a segment can only be nearest if its great-circle distance is within this factor of the nearest one's
+const CANDIDATE_FACTOR: f64 = 1.02;
+
+// The greatest number of steps taken towards a segment's nearest point, and the step length in metres at which
+// it's considered found
+const MAX_STEPS: usize = 32;
+const CONVERGED: f64 = 1e-6;
+
+/// The greatest length of a degree of a meridian or the equator in metres, which is that of a meridian at a pole
+pub(crate) fn max_metres_per_degree<T: GeoFloat>() -> T {
+    // the meridian's radius of curvature at a pole is a / (1 - f)
+    T::from(SEMI_MAJOR_AXIS / (1.0 - FLATTENING)).unwrap() * T::one().to_radians()
+}
+
+/// Geodesic distance in metres from a point to a segment, treated as a geodesic
+pub(crate) fn segment_distance<T: GeoFloat>(point: Point<T>, segment: Line<T>) -> T {
+    let distance = nearest_distance(
+        &Geodesic::wgs84(),
+        lat_lon(segment.start),
+        lat_lon(segment.end),
+        lat_lon(point.into()),
+    );
+    T::from(distance).unwrap_or_else(T::nan)
+}
+
+/// A ring's segments, as geodesics
+pub(crate) struct GeodesicRing<T>
+where
+    T: GeoFloat,
+{
+    lines: Vec<Line<T>>,
+    spherical: SphericalRing<T>,
+    geodesic: Geodesic,
+}
+
+impl<T> GeodesicRing<T>
+where
+    T: GeoFloat,
+{
+    pub(crate) fn new(ring: &LineString<T>) -> Self {
+        Self {
+            lines: ring.lines().collect(),
+            spherical: SphericalRing::new(ring),
+            geodesic: Geodesic::wgs84(),
+        }
+    }
+
+    /// Geodesic distance in metres from a point to the nearest segment of the ring
+    ///
+    /// Empty rings are infinitely far away.
+    pub(crate) fn distance(&self, point: Point<T>) -> T {
+        let estimates: Vec<T> = self.spherical.distances(point).collect();
+        let nearest = estimates.iter().copied().fold(T::infinity(), T::min);
+        let limit = nearest * T::from(CANDIDATE_FACTOR).unwrap();
+        let point = lat_lon(point.into());
+        self.lines
+            .iter()
+            .zip(estimates)
+            .filter(|&(_, estimate)| estimate <= limit)
+            .filter_map(|(line, _)| {
+                T::from(nearest_distance(
+                    &self.geodesic,
+                    lat_lon(line.start),
+                    lat_lon(line.end),
+                    point,
+                ))
+            })
+            .fold(T::infinity(), T::min)
+    }
+}
+
+// the distance from a point to the nearest point on the geodesic between two points, found by stepping along
+// the geodesic by the projection of the distance to the point onto it, until the geodesic to the point
+// meets it at right angles
+fn nearest_distance(
+    geodesic: &Geodesic,
+    start: (f64, f64),
+    end: (f64, f64),
+    point: (f64, f64),
+) -> f64 {
+    let (length, azimuth, _, _): (f64, f64, f64, f64) =
+        geodesic.inverse(start.0, start.1, end.0, end.1);
+    let measure = |along: f64| -> (f64, f64) {
+        let (lat, lon, heading): (f64, f64, f64) =
+            geodesic.direct(start.0, start.1, azimuth, along);
+        let (distance, bearing, _, _): (f64, f64, f64, f64) =
+            geodesic.inverse(lat, lon, point.0, point.1);
+        (distance, distance * (bearing - heading).to_radians().cos())
+    };
+    let mut along = 0.0;
+    let (mut distance, mut step) = measure(along);
+    for _ in 0..MAX_STEPS {
+        let next = (along + step).clamp(0.0, length);
+        if (next - along).abs() < CONVERGED {
+            break;
+        }
+        along = next;
+        (distance, step) = measure(along);
+    }
+    distance
+}
+
+// a Coord's longitude and latitude, as a latitude and longitude in f64
+fn lat_lon<T: GeoFloat>(coord: Coord<T>) -> (f64, f64) {
+    (
+        coord.y.to_f64().unwrap_or(f64::NAN),
+        coord.x.to_f64().unwrap_or(f64::NAN),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{max_metres_per_degree, segment_distance, GeodesicRing};
+    use crate::haversine::SphericalRing;
+    use geo::{Line, LineString, Point};
+    use geographiclib_rs::{DirectGeodesic, Geodesic, InverseGeodesic};
+
+    #[test]
+    fn test_distance() {
+        let g = Geodesic::wgs84();
+        let equator = LineString::from(vec![(-1.0, 0.0), (1.0, 0.0)]);
+        let ring = GeodesicRing::new(&equator);
+        // north of the segment, where the nearest point is on the meridian, and beyond its end
+        let north: f64 = g.inverse(0.0, 0.0, 1.0, 0.0);
+        assert!((ring.distance(Point::new(0.0, 1.0)) - north).abs() < 1e-6);
+        let east: f64 = g.inverse(0.0, 1.0, 0.0, 3.0);
+        assert!((ring.distance(Point::new(3.0, 0.0)) - east).abs() < 1e-6);
+        // a diagonal segment, which differs from the great-circle distance
+        let diagonal = LineString::from(vec![(10.0, 40.0), (14.0, 44.0)]);
+        let point = Point::new(10.0, 44.0);
+        let distance: f64 = GeodesicRing::new(&diagonal).distance(point);
+        let spherical = SphericalRing::new(&diagonal).distance(point);
+        assert!((distance / spherical - 1.0).abs() < 0.006);
+        // no point along the segment is nearer
+        let (length, azimuth, _, _): (f64, f64, f64, f64) = g.inverse(40.0, 10.0, 44.0, 14.0);
+        let sampled = (0..=1000)
+            .map(|i| {
+                let (lat, lon): (f64, f64) =
+                    g.direct(40.0, 10.0, azimuth, length * i as f64 / 1000.0);
+                g.inverse(lat, lon, 44.0, 10.0)
+            })
+            .fold(f64::INFINITY, f64::min);
+        assert!(distance <= sampled && sampled - distance < 1.0);
+        let segment = Line::new((10.0, 40.0), (14.0, 44.0));
+        assert_eq!(segment_distance(point, segment), distance);
+        assert!(GeodesicRing::new(&LineString::<f64>::new(vec![]))
+            .distance(Point::new(0.0, 0.0))
+            .is_infinite());
+        assert!((max_metres_per_degree::<f64>() - 111_693.9).abs() < 1.0);
+    }
+}
@@ -0,0 +1,314 @@
+//! Label positions for GeoJSON Polygon and MultiPolygon geometries
+
+use crate::errors::PolylabelError;
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+use serde_json::{json, Value};
+
+/// Calculate the label position of a GeoJSON Polygon or MultiPolygon, returning it as a GeoJSON Point
+///
+/// `geojson` is a GeoJSON geometry object, or a Feature holding one. The label position of a MultiPolygon is
+/// that of its part with the largest area.
+///
+/// # Errors
+///
+/// [`PolylabelError::InvalidGeoJson`](errors/enum.PolylabelError.html#variant.InvalidGeoJson) is returned if
+/// `geojson` isn't a Polygon or MultiPolygon, and other errors if its label position can't be calculated.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_geojson;
+///
+/// let square = r#"{
+///     "type": "Polygon",
+///     "coordinates": [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]]
+/// }"#;
+/// let label = polylabel_geojson(square, 0.1).unwrap();
+/// assert_eq!(label, r#"{"coordinates":[2.0,2.0],"type":"Point"}"#);
+/// ```
+pub fn polylabel_geojson(geojson: &str, tolerance: f64) -> Result<String, PolylabelError> {
+    polylabel_geojson_label(geojson, tolerance).map(|label| point(&label).to_string())
+}
+
+/// Calculate the label position of a GeoJSON Polygon or MultiPolygon, and its distance from the geometry's edges
+///
+/// `geojson` is a GeoJSON geometry object, or a Feature holding one. The label position of a MultiPolygon is
+/// that of its part with the largest area. See [`polylabel_geojson`](fn.polylabel_geojson.html) for errors.
+pub fn polylabel_geojson_label(
+    geojson: &str,
+    tolerance: f64,
+) -> Result<Label<f64>, PolylabelError> {
+    polylabel_geojson_labels(geojson, tolerance, Parts::Largest)?
+        .into_iter()
+        .next()
+        // a MultiPolygon without any parts
+        .ok_or(PolylabelError::RectCalculation)
+}
+
+/// Calculate label positions for the parts of a GeoJSON Polygon or MultiPolygon
+///
+/// `geojson` is a GeoJSON geometry object, or a Feature holding one. A Polygon has a single part, and the
+/// label positions of a MultiPolygon's parts are calculated as in [`polylabel_multi`](fn.polylabel_multi.html).
+/// See [`polylabel_geojson`](fn.polylabel_geojson.html) for errors.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_geojson_labels, Parts};
+///
+/// let squares = r#"{
+///     "type": "MultiPolygon",
+///     "coordinates": [
+///         [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]],
+///         [[[10.0, 0.0], [12.0, 0.0], [12.0, 2.0], [10.0, 2.0], [10.0, 0.0]]]
+///     ]
+/// }"#;
+/// let labels = polylabel_geojson_labels(squares, 0.1, Parts::All).unwrap();
+/// assert_eq!(labels.len(), 2);
+/// ```
+pub fn polylabel_geojson_labels(
+    geojson: &str,
+    tolerance: f64,
+    parts: Parts,
+) -> Result<Vec<Label<f64>>, PolylabelError> {
+    let multi = geojson_multi_polygon(geojson)?;
+    polylabel_multi(&multi, &PolylabelOptions::new(tolerance), parts)
+}
+
+/// Convert a GeoJSON Polygon or MultiPolygon (or a Feature holding one) to a MultiPolygon
+///
+/// A Polygon becomes a single-part MultiPolygon, and positions' third (altitude) and later elements are ignored.
+/// [`PolylabelError::InvalidGeoJson`](errors/enum.PolylabelError.html#variant.InvalidGeoJson) is returned if
+/// `geojson` isn't a Polygon or MultiPolygon.
+pub fn geojson_multi_polygon(geojson: &str) -> Result<MultiPolygon<f64>, PolylabelError> {
+    serde_json::from_str(geojson)
+        .ok()
+        .as_ref()
+        .and_then(feature_multi_polygon)
+        .ok_or(PolylabelError::InvalidGeoJson)
+}
+
+/// Annotate each Polygon and MultiPolygon Feature in a GeoJSON FeatureCollection with its label position
+///
+/// The label position of each Feature whose geometry is a Polygon or MultiPolygon (see
+/// [`polylabel_geojson`](fn.polylabel_geojson.html)) is stored as a GeoJSON Point in its `label` property.
+/// Features are otherwise unchanged, and `null` properties become an object. Features whose label positions
+/// can't be calculated aren't labelled. Returns the annotated FeatureCollection, or
+/// [`PolylabelError::InvalidGeoJson`](errors/enum.PolylabelError.html#variant.InvalidGeoJson) if `geojson`
+/// isn't a FeatureCollection.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::label_feature_collection;
+///
+/// let collection = r#"{
+///     "type": "FeatureCollection",
+///     "features": [{
+///         "type": "Feature",
+///         "properties": {"name": "square"},
+///         "geometry": {
+///             "type": "Polygon",
+///             "coordinates": [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]]
+///         }
+///     }]
+/// }"#;
+/// let labelled = label_feature_collection(collection, 0.1).unwrap();
+/// assert!(labelled.contains(r#""label":{"coordinates":[2.0,2.0],"type":"Point"}"#));
+/// ```
+pub fn label_feature_collection(geojson: &str, tolerance: f64) -> Result<String, PolylabelError> {
+    let mut collection: Value =
+        serde_json::from_str(geojson).map_err(|_| PolylabelError::InvalidGeoJson)?;
+    if collection.get("type").and_then(Value::as_str) != Some("FeatureCollection") {
+        return Err(PolylabelError::InvalidGeoJson);
+    }
+    let features = collection
+        .get_mut("features")
+        .and_then(Value::as_array_mut)
+        .ok_or(PolylabelError::InvalidGeoJson)?;
+    for feature in features {
+        let label = match feature
+            .get("geometry")
+            .and_then(feature_multi_polygon)
+            .and_then(|multi| {
+                polylabel_multi(&multi, &PolylabelOptions::new(tolerance), Parts::Largest).ok()
+            })
+            .and_then(|labels| labels.into_iter().next())
+        {
+            Some(label) => point(&label),
+            None => continue,
+        };
+        let properties = match feature.get_mut("properties") {
+            Some(properties) if properties.is_object() => properties,
+            Some(properties) if properties.is_null() => {
+                *properties = json!({});
+                properties
+            }
+            _ => continue,
+        };
+        properties["label"] = label;
+    }
+    Ok(collection.to_string())
+}
+
+// A label position as a GeoJSON Point
+fn point(label: &Label<f64>) -> Value {
+    json!({
+        "type": "Point",
+        "coordinates": [label.point.x(), label.point.y()],
+    })
+}
+
+// A GeoJSON Polygon or MultiPolygon, or a Feature holding one, as a MultiPolygon
+fn feature_multi_polygon(geometry: &Value) -> Option<MultiPolygon<f64>> {
+    let geometry = match geometry.get("type")?.as_str()? {
+        "Feature" => geometry.get("geometry")?,
+        _ => geometry,
+    };
+    parse_value(geometry)
+}
+
+/// Parse a GeoJSON Polygon or MultiPolygon geometry object into a MultiPolygon
+///
+/// A Polygon becomes a single-part MultiPolygon. Positions' third (altitude) and later elements are ignored.
+/// Returns `None` if the string isn't valid JSON, or isn't a Polygon or MultiPolygon geometry object.
+#[cfg(feature = "ffi")]
+pub(crate) fn parse(geojson: &str) -> Option<MultiPolygon<f64>> {
+    parse_value(&serde_json::from_str(geojson).ok()?)
+}
+
+fn parse_value(geometry: &Value) -> Option<MultiPolygon<f64>> {
+    let coordinates = geometry.get("coordinates")?;
+    match geometry.get("type")?.as_str()? {
+        "Polygon" => polygon(coordinates).map(|poly| MultiPolygon::new(vec![poly])),
+        "MultiPolygon" => coordinates
+            .as_array()?
+            .iter()
+            .map(polygon)
+            .collect::<Option<_>>()
+            .map(MultiPolygon::new),
+        _ => None,
+    }
+}
+
+fn polygon(rings: &Value) -> Option<Polygon<f64>> {
+    let mut rings = rings.as_array()?.iter().map(ring);
+    let exterior = rings.next()??;
+    Some(Polygon::new(exterior, rings.collect::<Option<_>>()?))
+}
+
+fn ring(positions: &Value) -> Option<LineString<f64>> {
+    positions.as_array()?.iter().map(position).collect()
+}
+
+fn position(position: &Value) -> Option<Coord<f64>> {
+    let position = position.as_array()?;
+    Some(Coord {
+        x: position.first()?.as_f64()?,
+        y: position.get(1)?.as_f64()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        label_feature_collection, parse_value, polylabel_geojson, polylabel_geojson_label,
+        polylabel_geojson_labels,
+    };
+    use crate::errors::PolylabelError;
+    use crate::multi::Parts;
+    use serde_json::{json, Value};
+
+    fn parse(geojson: &str) -> Option<geo::MultiPolygon<f64>> {
+        parse_value(&serde_json::from_str(geojson).ok()?)
+    }
+
+    #[test]
+    fn test_parse() {
+        let poly = parse(r#"{"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 4, 10], [0, 0]], [[1, 1], [2, 1], [2, 2], [1, 1]]]}"#).unwrap();
+        assert_eq!(poly.0.len(), 1);
+        assert_eq!(poly.0[0].exterior().0[2].x, 4.0);
+        assert_eq!(poly.0[0].interiors().len(), 1);
+        let multi = parse(r#"{"type": "MultiPolygon", "coordinates": [[[[0, 0], [1, 0], [1, 1], [0, 0]]], [[[5, 5], [6, 5], [6, 6], [5, 5]]]]}"#).unwrap();
+        assert_eq!(multi.0.len(), 2);
+        assert!(parse(r#"{"type": "Point", "coordinates": [0, 0]}"#).is_none());
+        assert!(parse(r#"{"type": "Polygon", "coordinates": [[[0, 0], [1]]]}"#).is_none());
+        assert!(parse("not json").is_none());
+    }
+
+    #[test]
+    fn test_polylabel_geojson() {
+        let multi = json!({
+            "type": "MultiPolygon",
+            "coordinates": [
+                [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]],
+                [[[10, 10], [14, 10], [14, 14], [10, 14], [10, 10]]]
+            ]
+        });
+        let expected = json!({"type": "Point", "coordinates": [12.0, 12.0]});
+        let label = polylabel_geojson(&multi.to_string(), 0.1).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&label).unwrap(), expected);
+        let feature = json!({"type": "Feature", "properties": null, "geometry": multi}).to_string();
+        assert_eq!(polylabel_geojson(&feature, 0.1).unwrap(), label);
+        assert_eq!(
+            polylabel_geojson_label(&feature, 0.1).unwrap().distance,
+            2.0
+        );
+        assert_eq!(
+            polylabel_geojson_labels(&feature, 0.1, Parts::All)
+                .unwrap()
+                .len(),
+            2
+        );
+        // errors
+        let point = json!({"type": "Point", "coordinates": [0, 0]}).to_string();
+        assert_eq!(
+            polylabel_geojson(&point, 0.1),
+            Err(PolylabelError::InvalidGeoJson)
+        );
+        assert_eq!(
+            polylabel_geojson("{", 0.1),
+            Err(PolylabelError::InvalidGeoJson)
+        );
+        let empty = json!({"type": "MultiPolygon", "coordinates": []}).to_string();
+        assert_eq!(
+            polylabel_geojson(&empty, 0.1),
+            Err(PolylabelError::RectCalculation)
+        );
+    }
+
+    #[test]
+    fn test_label_feature_collection() {
+        let square = json!({
+            "type": "Polygon",
+            "coordinates": [[[0, 0], [2, 0], [2, 2], [0, 2], [0, 0]]]
+        });
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "properties": null, "geometry": square},
+                {"type": "Feature", "properties": {"a": 1}, "geometry": {"type": "Point", "coordinates": [0, 0]}},
+                {"type": "Feature", "properties": {"a": 2}, "geometry": null}
+            ]
+        });
+        let labelled = label_feature_collection(&collection.to_string(), 0.1).unwrap();
+        let labelled: Value = serde_json::from_str(&labelled).unwrap();
+        assert_eq!(
+            labelled["features"][0]["properties"]["label"]["coordinates"],
+            json!([1.0, 1.0])
+        );
+        assert_eq!(labelled["features"][1]["properties"], json!({"a": 1}));
+        assert_eq!(labelled["features"][2]["properties"], json!({"a": 2}));
+        // only FeatureCollections can be annotated
+        assert_eq!(
+            label_feature_collection(&square.to_string(), 0.1),
+            Err(PolylabelError::InvalidGeoJson)
+        );
+        assert_eq!(
+            label_feature_collection("null", 0.1),
+            Err(PolylabelError::InvalidGeoJson)
+        );
+    }
+}
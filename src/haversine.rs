@@ -0,0 +1,218 @@
+//! Great-circle distances, for labelling longitude and latitude Polygons with distances in metres
+//!
+//! Coordinates are `(longitude, latitude)` in degrees, on a sphere with the Earth's mean radius. Each ring
+//! segment is measured as the great-circle arc between its endpoints, which are converted to unit vectors once.
+
+use geo::{GeoFloat, Line, LineString, Point};
+
+// the Earth's mean radius, in metres
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+type Vector<T> = [T; 3];
+
+/// The Earth's mean radius in metres
+pub(crate) fn radius<T: GeoFloat>() -> T {
+    T::from(EARTH_RADIUS).unwrap()
+}
+
+/// The number of metres in a degree of a great circle
+pub(crate) fn metres_per_degree<T: GeoFloat>() -> T {
+    radius::<T>() * T::one().to_radians()
+}
+
+/// The greatest distance in metres from a cell's centroid to any point within it, given the greatest length of a
+/// degree of a meridian or the equator
+///
+/// A point within the cell can be reached by moving along the centroid's meridian to the point's latitude, then
+/// along that parallel, whose length per degree is greatest at the latitude closest to the equator.
+pub(crate) fn cell_radius<T: GeoFloat>(centroid: Point<T>, half_extent: T, degree: T) -> T {
+    let nearest = (centroid.y().abs() - half_extent).max(T::zero());
+    half_extent * degree * (T::one() + nearest.to_radians().cos().max(T::zero()))
+}
+
+/// Great-circle distance in metres from a point to a segment, treated as a great-circle arc
+pub(crate) fn segment_distance<T: GeoFloat>(point: Point<T>, segment: Line<T>) -> T {
+    Arc::new(segment).angle(unit(point)) * radius()
+}
+
+/// A ring's segments, as great-circle arcs
+pub(crate) struct SphericalRing<T>
+where
+    T: GeoFloat,
+{
+    arcs: Vec<Arc<T>>,
+}
+
+// a great-circle arc between two unit vectors, with the unit normal of its great circle's plane, or `None` if its
+// endpoints coincide
+struct Arc<T>
+where
+    T: GeoFloat,
+{
+    start: Vector<T>,
+    end: Vector<T>,
+    normal: Option<Vector<T>>,
+}
+
+impl<T> SphericalRing<T>
+where
+    T: GeoFloat,
+{
+    pub(crate) fn new(ring: &LineString<T>) -> Self {
+        Self {
+            arcs: ring.lines().map(Arc::new).collect(),
+        }
+    }
+
+    /// Great-circle distance in metres from a point to the nearest segment of the ring
+    ///
+    /// Empty rings are infinitely far away.
+    pub(crate) fn distance(&self, point: Point<T>) -> T {
+        self.distances(point).fold(T::infinity(), T::min)
+    }
+
+    /// The number of times the ring winds anticlockwise around a point, seen from above it, as an angle in
+    /// radians: ±2π if the ring encircles the point or its antipode, and 0 otherwise
+    pub(crate) fn winding(&self, point: Point<T>) -> T {
+        let point = unit(point);
+        self.arcs
+            .iter()
+            .map(|arc| {
+                // the angle around the point between its directions to the arc's ends
+                let sine = dot(point, cross(arc.start, arc.end));
+                let cosine = dot(arc.start, arc.end) - dot(arc.start, point) * dot(arc.end, point);
+                sine.atan2(cosine)
+            })
+            .fold(T::zero(), |total, angle| total + angle)
+    }
+
+    /// The total angle in radians by which the ring turns left at its vertices, which is 2π less the area on
+    /// its left, on a unit sphere
+    pub(crate) fn turning(&self) -> T {
+        // arcs whose ends coincide have no direction
+        let arcs: Vec<&Arc<T>> = self
+            .arcs
+            .iter()
+            .filter(|arc| arc.start != arc.end)
+            .collect();
+        arcs.iter()
+            .zip(arcs.iter().cycle().skip(1))
+            .map(|(incoming, outgoing)| {
+                let (vertex, previous, next) = (incoming.end, incoming.start, outgoing.end);
+                // the directions the ring arrives at and leaves the vertex in
+                let arriving = [0, 1, 2].map(|i| dot(previous, vertex) * vertex[i] - previous[i]);
+                let leaving = [0, 1, 2].map(|i| next[i] - dot(next, vertex) * vertex[i]);
+                dot(vertex, cross(arriving, leaving)).atan2(dot(arriving, leaving))
+            })
+            .fold(T::zero(), |total, angle| total + angle)
+    }
+
+    /// Great-circle distances in metres from a point to each segment of the ring
+    pub(crate) fn distances(&self, point: Point<T>) -> impl Iterator<Item = T> + '_ {
+        let point = unit(point);
+        self.arcs.iter().map(move |arc| arc.angle(point) * radius())
+    }
+}
+
+impl<T> Arc<T>
+where
+    T: GeoFloat,
+{
+    fn new(line: Line<T>) -> Self {
+        let (start, end) = (unit(line.start.into()), unit(line.end.into()));
+        let normal = cross(start, end);
+        let length = norm(normal);
+        Self {
+            start,
+            end,
+            normal: (length > T::epsilon()).then(|| normal.map(|n| n / length)),
+        }
+    }
+
+    // the angle in radians between a unit vector and the nearest point on the arc
+    fn angle(&self, point: Vector<T>) -> T {
+        if let Some(normal) = self.normal {
+            let height = dot(point, normal);
+            // the point's projection onto the arc's great circle
+            let projected = [0, 1, 2].map(|i| point[i] - height * normal[i]);
+            if dot(cross(self.start, projected), normal) >= T::zero()
+                && dot(cross(projected, self.end), normal) >= T::zero()
+            {
+                return height.abs().atan2(norm(projected));
+            }
+        }
+        angle(point, self.start).min(angle(point, self.end))
+    }
+}
+
+// a longitude and latitude in degrees as a unit vector
+fn unit<T: GeoFloat>(point: Point<T>) -> Vector<T> {
+    let (lon, lat) = (point.x().to_radians(), point.y().to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+// the angle between two unit vectors, which is accurate for small and large angles alike
+fn angle<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> T {
+    norm(cross(a, b)).atan2(dot(a, b))
+}
+
+fn cross<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> Vector<T> {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> T {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn norm<T: GeoFloat>(a: Vector<T>) -> T {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cell_radius, metres_per_degree, segment_distance, SphericalRing};
+    use geo::{Line, LineString, Point};
+
+    #[test]
+    fn test_distance() {
+        let degree = metres_per_degree::<f64>();
+        let equator = SphericalRing::new(&LineString::from(vec![(-1.0, 0.0), (1.0, 0.0)]));
+        // north of the segment, and beyond its end
+        assert!((equator.distance(Point::new(0.0, 1.0)) - degree).abs() < 1e-6);
+        assert!((equator.distance(Point::new(3.0, 0.0)) - 2.0 * degree).abs() < 1e-6);
+        // a degree of longitude is half as long at 60°
+        let meridian = SphericalRing::new(&LineString::from(vec![(0.0, 59.0), (0.0, 61.0)]));
+        let distance = meridian.distance(Point::new(1.0, 60.0));
+        assert!((distance / degree - 0.5).abs() < 1e-3);
+        let segment = Line::new((0.0, 59.0), (0.0, 61.0));
+        assert_eq!(segment_distance(Point::new(1.0, 60.0), segment), distance);
+        assert!(SphericalRing::new(&LineString::<f64>::new(vec![]))
+            .distance(Point::new(0.0, 0.0))
+            .is_infinite());
+    }
+
+    #[test]
+    fn test_cell_radius() {
+        let degree = metres_per_degree::<f64>();
+        // a cell's corners are within its radius
+        for &(centroid, half_extent) in
+            &[((10.0, 0.0), 1.0), ((10.0, 70.0), 0.5), ((0.0, 87.0), 2.0)]
+        {
+            let centroid = Point::from(centroid);
+            let radius = cell_radius(centroid, half_extent, degree);
+            let ring = SphericalRing::new(&LineString::from(vec![centroid, centroid]));
+            for (dx, dy) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                let corner = Point::new(
+                    centroid.x() + dx * half_extent,
+                    centroid.y() + dy * half_extent,
+                );
+                assert!(ring.distance(corner) <= radius);
+            }
+        }
+        assert_eq!(cell_radius(Point::new(0.0, 0.0), 1.0, degree), 2.0 * degree);
+    }
+}
@@ -0,0 +1,391 @@
+//! Spatial indexing of Polygon rings, used to accelerate signed distance queries
+//!
+//! Each ring's segments are bulk-loaded into an R-tree once, so that both the
+//! nearest-segment query and the point-in-ring test performed for every cell
+//! are O(log n) rather than O(n) in the number of vertices. Rings with only a few
+//! segments are scanned linearly instead, which is faster than querying a tree.
+//!
+//! Distances are compared squared, and only square-rooted once per query.
+//!
+//! With [`Metric::Haversine`] (or `Metric::Geodesic`), the R-trees are only used for the point-in-ring test, and
+//! distances on the Earth's surface are measured to every segment. So are [`Metric::Chebyshev`] and
+//! [`Metric::Manhattan`] distances.
+
+#[cfg(feature = "geodesic")]
+use crate::geodesic::GeodesicRing;
+use crate::haversine::SphericalRing;
+use crate::options::Metric;
+use crate::{DistanceField, DistanceMetric};
+use geo::{GeoFloat, Line, LineString, Point, Polygon};
+use rstar::{Envelope, PointDistance, RTree, RTreeObject, AABB};
+
+#[cfg(feature = "autovec")]
+use crate::autovec::{Segments as Lanes, MAX_SEGMENTS as MAX_LANE_SEGMENTS};
+
+/// A ring segment, measured by squared distance
+struct Segment<T>(Line<T>)
+where
+    T: GeoFloat;
+
+impl<T> RTreeObject for Segment<T>
+where
+    T: GeoFloat,
+{
+    type Envelope = AABB<Point<T>>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(self.0.start_point(), self.0.end_point())
+    }
+}
+
+impl<T> PointDistance for Segment<T>
+where
+    T: GeoFloat,
+{
+    fn distance_2(&self, point: &Point<T>) -> T {
+        let Line { start, end } = self.0;
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        let (px, py) = (point.x() - start.x, point.y() - start.y);
+        let length_2 = dx * dx + dy * dy;
+        // position of the point's projection along the segment
+        let r = if length_2 == T::zero() {
+            T::zero()
+        } else {
+            (px * dx + py * dy) / length_2
+        };
+        if r <= T::zero() {
+            px * px + py * py
+        } else if r >= T::one() {
+            let (qx, qy) = (point.x() - end.x, point.y() - end.y);
+            qx * qx + qy * qy
+        } else {
+            let cross = px * dy - py * dx;
+            cross * cross / length_2
+        }
+    }
+}
+
+// Rings with at most this many segments are measured using a linear scan, as querying an R-tree costs more than
+// measuring a few segments directly
+const MAX_SCAN_SEGMENTS: usize = 256;
+
+// Rings measured using the lane-chunked kernel are also scanned, so they don't build an R-tree
+#[cfg(feature = "autovec")]
+const _: () = assert!(MAX_LANE_SEGMENTS <= MAX_SCAN_SEGMENTS);
+
+/// A ring's segments, scanned linearly if there are few of them, or in an R-tree
+enum Segments<T>
+where
+    T: GeoFloat,
+{
+    Scan(Vec<Segment<T>>),
+    Tree(RTree<Segment<T>>),
+}
+
+/// The segments of a single closed ring, indexed if there are many of them
+pub(crate) struct RingIndex<T>
+where
+    T: GeoFloat,
+{
+    segments: Segments<T>,
+    // The ring's bounding box, if it isn't empty
+    envelope: Option<AABB<Point<T>>>,
+    // Short rings are measured using the lane-chunked kernel
+    #[cfg(feature = "autovec")]
+    lanes: Option<Lanes<T>>,
+}
+
+impl<T> RingIndex<T>
+where
+    T: GeoFloat,
+{
+    pub(crate) fn new(ring: &LineString<T>) -> Self {
+        let lines: Vec<Segment<T>> = ring.lines().map(Segment).collect();
+        let envelope = lines
+            .iter()
+            .map(RTreeObject::envelope)
+            .reduce(|a, b| a.merged(&b));
+        let segments = if lines.len() <= MAX_SCAN_SEGMENTS {
+            Segments::Scan(lines)
+        } else {
+            Segments::Tree(RTree::bulk_load(lines))
+        };
+        Self {
+            segments,
+            envelope,
+            #[cfg(feature = "autovec")]
+            lanes: Lanes::new(ring),
+        }
+    }
+
+    /// Squared distance from a point to the nearest segment of the ring
+    ///
+    /// Empty rings are infinitely far away.
+    pub(crate) fn distance_2(&self, point: Point<T>) -> T {
+        #[cfg(feature = "autovec")]
+        if let Some(lanes) = &self.lanes {
+            return lanes.distance_2(point);
+        }
+        match &self.segments {
+            Segments::Scan(segments) => segments
+                .iter()
+                .map(|segment| segment.distance_2(&point))
+                .fold(T::infinity(), T::min),
+            Segments::Tree(tree) => tree
+                .nearest_neighbor(&point)
+                .map_or_else(T::infinity, |segment| segment.distance_2(&point)),
+        }
+    }
+
+    /// Could any part of the ring lie within the given squared distance of the point?
+    ///
+    /// Compares the point against the ring's bounding box, expanded by the distance.
+    pub(crate) fn may_be_within(&self, point: Point<T>, distance_2: T) -> bool {
+        self.envelope
+            .is_some_and(|envelope| envelope.distance_2(&point) <= distance_2)
+    }
+
+    /// Is the point inside the ring? Uses the crossing-number test, only
+    /// visiting segments that straddle a ray cast from the point in the +x direction
+    pub(crate) fn contains(&self, point: Point<T>) -> bool {
+        let Some(envelope) = self.envelope else {
+            return false;
+        };
+        let max_x = envelope.upper().x();
+        if point.x() > max_x {
+            return false;
+        }
+        let crossings = match &self.segments {
+            Segments::Scan(segments) => crossings(segments.iter(), point),
+            Segments::Tree(tree) => {
+                let ray = AABB::from_corners(point, Point::new(max_x, point.y()));
+                crossings(tree.locate_in_envelope_intersecting(&ray), point)
+            }
+        };
+        crossings % 2 == 1
+    }
+}
+
+// the number of segments crossed by a ray cast from the point in the +x direction
+fn crossings<'a, T, I>(segments: I, point: Point<T>) -> usize
+where
+    T: GeoFloat + 'a,
+    I: Iterator<Item = &'a Segment<T>>,
+{
+    segments
+        .map(|segment| segment.0)
+        .filter(|line| (line.start.y > point.y()) != (line.end.y > point.y()))
+        .filter(|line| {
+            let crossing = line.start.x
+                + (point.y() - line.start.y) * (line.end.x - line.start.x)
+                    / (line.end.y - line.start.y);
+            point.x() < crossing
+        })
+        .count()
+}
+
+/// Per-ring indices for a Polygon's exterior and interior rings
+pub(crate) struct PolygonIndex<T>
+where
+    T: GeoFloat,
+{
+    exterior: RingIndex<T>,
+    interiors: Vec<RingIndex<T>>,
+    // The rings, exterior first, if distances aren't Euclidean
+    surface: Option<Surface<T>>,
+    metric: Metric,
+}
+
+/// A Polygon's rings, measured on the Earth's surface, or their segments, measured in the plane
+enum Surface<T>
+where
+    T: GeoFloat,
+{
+    Plane(Vec<Line<T>>),
+    Sphere(Vec<SphericalRing<T>>),
+    #[cfg(feature = "geodesic")]
+    Ellipsoid(Vec<GeodesicRing<T>>),
+}
+
+impl<T> PolygonIndex<T>
+where
+    T: GeoFloat,
+{
+    pub(crate) fn new(polygon: &Polygon<T>, metric: Metric) -> Self {
+        let rings = || std::iter::once(polygon.exterior()).chain(polygon.interiors());
+        let surface = match metric {
+            Metric::Euclidean => None,
+            Metric::Chebyshev | Metric::Manhattan => Some(Surface::Plane(
+                rings().flat_map(|ring| ring.lines()).collect(),
+            )),
+            Metric::Haversine => Some(Surface::Sphere(rings().map(SphericalRing::new).collect())),
+            #[cfg(feature = "geodesic")]
+            Metric::Geodesic => Some(Surface::Ellipsoid(rings().map(GeodesicRing::new).collect())),
+        };
+        Self {
+            exterior: RingIndex::new(polygon.exterior()),
+            interiors: polygon.interiors().iter().map(RingIndex::new).collect(),
+            surface,
+            metric,
+        }
+    }
+
+    /// The greatest distance from a cell's centroid to any point within it
+    pub(crate) fn cell_radius(&self, centroid: Point<T>, half_extent: T) -> T {
+        self.metric.cell_radius(centroid, half_extent)
+    }
+
+    /// Signed distance from a point to the Polygon's outline
+    /// Returned value is negative if the point is outside the polygon's exterior ring
+    ///
+    /// Interior rings whose bounding box is further away than the nearest ring
+    /// found so far can neither be closer nor contain the point, so they're skipped.
+    pub(crate) fn signed_distance(&self, point: Point<T>) -> T {
+        if let Some(surface) = &self.surface {
+            let inside = self.exterior.contains(point)
+                && !self.interiors.iter().any(|ring| ring.contains(point));
+            let distance = match surface {
+                Surface::Plane(segments) => segments
+                    .iter()
+                    .map(|&segment| self.metric.segment_distance(point, segment))
+                    .fold(T::infinity(), T::min),
+                Surface::Sphere(rings) => rings
+                    .iter()
+                    .map(|ring| ring.distance(point))
+                    .fold(T::infinity(), T::min),
+                #[cfg(feature = "geodesic")]
+                Surface::Ellipsoid(rings) => rings
+                    .iter()
+                    .map(|ring| ring.distance(point))
+                    .fold(T::infinity(), T::min),
+            };
+            return if inside { distance } else { -distance };
+        }
+        let mut inside = self.exterior.contains(point);
+        let mut distance_2 = self.exterior.distance_2(point);
+        for ring in &self.interiors {
+            if !ring.may_be_within(point, distance_2) {
+                continue;
+            }
+            distance_2 = distance_2.min(ring.distance_2(point));
+            if inside && ring.contains(point) {
+                inside = false;
+            }
+        }
+        let distance = distance_2.sqrt();
+
+        if inside {
+            distance
+        } else {
+            -distance
+        }
+    }
+}
+
+impl<T> DistanceField<T> for PolygonIndex<T>
+where
+    T: GeoFloat,
+{
+    fn signed_distance(&self, point: Point<T>) -> T {
+        PolygonIndex::signed_distance(self, point)
+    }
+
+    fn cell_radius(&self, centroid: Point<T>, half_extent: T) -> T {
+        PolygonIndex::cell_radius(self, centroid, half_extent)
+    }
+
+    fn length(&self, _: Point<T>, length: T) -> T {
+        self.metric
+            .degree()
+            .map_or(length, |degree| length * degree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PolygonIndex, RingIndex, MAX_SCAN_SEGMENTS};
+    use crate::options::Metric;
+    use geo::prelude::*;
+    use geo::{Euclidean, LineString, Point, Polygon};
+
+    #[test]
+    fn test_signed_distance() {
+        let outer = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let inner = vec![(6.0, 6.0), (6.0, 8.0), (8.0, 8.0), (8.0, 6.0)];
+        let poly = Polygon::new(LineString::from(outer), vec![LineString::from(inner)]);
+        let index = PolygonIndex::new(&poly, Metric::Euclidean);
+        // inside, nearest to the exterior
+        assert_eq!(index.signed_distance(Point::new(2.0, 3.0)), 2.0);
+        // inside, nearest to the hole
+        assert_eq!(index.signed_distance(Point::new(5.0, 7.0)), 1.0);
+        // inside the hole
+        assert_eq!(index.signed_distance(Point::new(7.0, 7.5)), -0.5);
+        // outside the exterior
+        assert_eq!(index.signed_distance(Point::new(-3.0, 5.0)), -3.0);
+    }
+    #[test]
+    // pruning interior rings by bounding box mustn't change the result
+    fn test_many_holes() {
+        // a 100 x 100 square containing a 10 x 10 grid of small diamond-shaped "lakes"
+        let exterior = vec![(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        let interiors = (0..10)
+            .flat_map(|x| (0..10).map(move |y| (x as f64 * 10.0 + 5.0, y as f64 * 10.0 + 5.0)))
+            .map(|(cx, cy)| {
+                LineString::from(vec![
+                    (cx - 2.0, cy),
+                    (cx, cy - 2.0),
+                    (cx + 2.0, cy),
+                    (cx, cy + 2.0),
+                ])
+            })
+            .collect();
+        let poly = Polygon::new(LineString::from(exterior), interiors);
+        let index = PolygonIndex::new(&poly, Metric::Euclidean);
+        for x in 0..40 {
+            for y in 0..40 {
+                let point = Point::new(x as f64 * 2.5 + 0.3, y as f64 * 2.5 + 0.7);
+                let expected = poly
+                    .interiors()
+                    .iter()
+                    .map(|ring| Euclidean::distance(&point, ring))
+                    .fold(Euclidean::distance(&point, poly.exterior()), f64::min);
+                let expected = if poly.contains(&point) {
+                    expected
+                } else {
+                    -expected
+                };
+                assert!((index.signed_distance(point) - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    // short rings are scanned, and long rings are indexed, with the same results
+    fn test_scan_and_tree() {
+        for n in [MAX_SCAN_SEGMENTS / 2, MAX_SCAN_SEGMENTS * 2] {
+            let ring: LineString<f64> = (0..=n)
+                .map(|i| i as f64 / n as f64 * std::f64::consts::TAU)
+                .map(|a| (10.0 * a.cos() + (5.0 * a).sin(), 10.0 * a.sin()))
+                .collect::<Vec<_>>()
+                .into();
+            let poly = Polygon::new(ring.clone(), vec![]);
+            let index = RingIndex::new(&ring);
+            for (x, y) in [
+                (0.0, 0.0),
+                (9.5, 0.5),
+                (10.5, -0.5),
+                (-20.0, 3.0),
+                (0.1, 9.99),
+            ] {
+                let point = Point::new(x, y);
+                let expected = Euclidean::distance(&point, &ring);
+                assert!((index.distance_2(point).sqrt() - expected).abs() < 1e-9);
+                assert_eq!(index.contains(point), poly.contains(&point));
+            }
+        }
+        let empty = RingIndex::new(&LineString::<f64>::new(vec![]));
+        assert!(empty.distance_2(Point::new(0.0, 0.0)).is_infinite());
+        assert!(!empty.contains(Point::new(0.0, 0.0)));
+        assert!(!empty.may_be_within(Point::new(0.0, 0.0), f64::INFINITY));
+    }
+}
@@ -6,23 +6,184 @@
 //! for finding the optimum position of a polygon label.
 //!
 //! ffi bindings are provided: enable the `ffi` and `headers` features when building the crate.
-use geo::{prelude::*, Coord, Rect};
+//!
+//! Parallel batch processing, and parallel refinement of single large Polygons, is provided: enable the `rayon`
+//! feature.
+//!
+//! GeoJSON, WKT, and WKB input and output is provided: enable the `geojson`, `wkt`, and `wkb` features.
+//!
+//! Polygon shapefiles can be read and labelled: enable the `shapefile` feature.
+//!
+//! Longitude and latitude Polygons can be labelled using geodesic distances on the WGS84 ellipsoid: enable the
+//! `geodesic` feature.
+//!
+//! Polygon features in Mapbox Vector Tiles can be labelled: enable the `mvt` feature.
+//!
+//! JavaScript bindings are provided: enable the `wasm` feature, and build for the `wasm32-unknown-unknown` target.
+//!
+//! A `polylabel` command is provided: enable the `cli` feature. The library needs Rust 1.70 or later, but the
+//! command and its dependencies need Rust 1.85 or later.
+use geo::{prelude::*, Coord, Euclidean, Rect};
 use geo::{GeoFloat, Point, Polygon};
 use num_traits::FromPrimitive;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::iter::Sum;
-use std::ops::{Deref, DerefMut};
 
 pub mod errors;
 use errors::PolylabelError;
 
+mod antimeridian;
+#[cfg(feature = "geodesic")]
+mod geodesic;
+mod haversine;
+mod index;
+mod periodic;
+use index::PolygonIndex;
+#[cfg(feature = "autovec")]
+mod autovec;
+
+mod prepared;
+pub use crate::prepared::PreparedPolygon;
+
+mod options;
+pub use crate::options::{Label, Metric, PolylabelOptions, SearchStats};
+
+mod metric;
+pub use crate::metric::{polylabel_with_distance_fn, polylabel_with_metric, DistanceMetric};
+
+mod mercator;
+pub use crate::mercator::web_mercator_tolerance;
+
+mod spherical;
+pub use crate::spherical::polylabel_spherical;
+
+mod rect;
+pub use crate::rect::{
+    fit_text_box, max_inscribed_rect, max_inscribed_rect_oriented, InscribedRect, TextFit,
+};
+
+mod spread;
+pub use crate::spread::polylabel_n;
+
+mod obstacles;
+pub use crate::obstacles::polylabel_with_obstacles;
+
+mod penalty;
+pub use crate::penalty::{polylabel_with_penalty, PenaltyRaster};
+
+mod placement;
+pub use crate::placement::{place_labels, Placement};
+
+mod plane;
+pub use crate::plane::{polylabel_3d, PlanarLabel};
+
+mod multi;
+pub use crate::multi::{polylabel_multi, polylabel_multi_with_stats, Parts};
+
+mod pole;
+pub use crate::pole::PoleOfInaccessibility;
+
+mod crs;
+pub use crate::crs::{Geographic, Projected};
+
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wasm")]
+pub use crate::wasm::{polylabel_flat_js, polylabel_geojson_js, polylabel_js, PolylabelJs};
+
+#[cfg(feature = "geojson")]
+mod geojson;
+#[cfg(feature = "geojson")]
+pub use crate::geojson::{
+    geojson_multi_polygon, label_feature_collection, polylabel_geojson, polylabel_geojson_label,
+    polylabel_geojson_labels,
+};
+
+#[cfg(feature = "wkt")]
+mod wkt;
+#[cfg(feature = "wkt")]
+pub use crate::wkt::{polylabel_wkt, polylabel_wkt_label, polylabel_wkt_labels, wkt_multi_polygon};
+
+#[cfg(feature = "wkb")]
+mod wkb;
+#[cfg(feature = "wkb")]
+pub use crate::wkb::{polylabel_wkb, polylabel_wkb_label};
+
+#[cfg(feature = "mvt")]
+mod mvt;
+#[cfg(feature = "mvt")]
+pub use crate::mvt::{add_label_layers, encode_label_layer, label_tile, MvtLabel, MvtLayerLabels};
+#[cfg(feature = "postgis")]
+mod postgis;
+#[cfg(feature = "postgis")]
+pub use crate::postgis::polylabel_hex_ewkb;
+#[cfg(feature = "shapefile")]
+mod shapefile;
+#[cfg(feature = "shapefile")]
+pub use crate::shapefile::{label_shapefile, read_shapefile, ShapefileRecords};
+
+#[cfg(feature = "rayon")]
+mod batch;
+#[cfg(feature = "rayon")]
+pub use crate::batch::{polylabel_batch, LabelFn, ParallelPolylabel};
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "rayon")]
+pub use crate::parallel::polylabel_parallel;
+
+#[cfg(all(feature = "ffi", feature = "arrow"))]
+mod arrow;
+
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(feature = "ffi")]
 mod ffi;
 #[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ffi", feature = "arrow"))]
+pub use crate::arrow::{ArrowArray, ArrowSchema};
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ffi", feature = "arrow"))]
+pub use crate::ffi::polylabel_arrow_ffi;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ffi", feature = "geojson"))]
+pub use crate::ffi::polylabel_geojson_ffi;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ffi", feature = "wkb"))]
+pub use crate::ffi::polylabel_wkb_ffi;
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "ffi", feature = "wkt"))]
+pub use crate::ffi::polylabel_wkt_ffi;
+#[cfg(not(target_arch = "wasm32"))]
 #[cfg(feature = "ffi")]
-pub use crate::ffi::{polylabel_ffi, Array, Position, WrapperArray};
+pub use crate::ffi::{
+    polylabel_abi_version, polylabel_batch_ext_ffi, polylabel_batch_ffi, polylabel_cancellable_ffi,
+    polylabel_clear_last_error, polylabel_compute, polylabel_create, polylabel_destroy,
+    polylabel_ext_ffi, polylabel_f32_ffi, polylabel_ffi, polylabel_flat_ffi, polylabel_free,
+    polylabel_last_error_message, polylabel_multi_array_ffi, polylabel_multi_ffi,
+    polylabel_multi_results_ffi, polylabel_position_array_free, polylabel_prepare,
+    polylabel_primitive_batch_ffi, polylabel_primitive_ffi, polylabel_progress_ffi,
+    polylabel_results_free, polylabel_results_get, polylabel_results_len,
+    polylabel_set_log_callback, polylabel_version, polylabel_xy_ffi, Array, ArrayF32, PolygonFFI,
+    PolylabelHandle, PolylabelLogCallback, PolylabelLogLevel, PolylabelParts, PolylabelProgress,
+    PolylabelResults, PolylabelStatus, Position, PositionArray, PositionF32, PositionResult,
+    WrapperArray, WrapperArrayF32, POLYLABEL_ABI_VERSION,
+};
+
+/// A signed distance field whose maximum is searched for: positive inside the Polygon, and negative outside it
+pub(crate) trait DistanceField<T>
+where
+    T: GeoFloat,
+{
+    /// Signed distance from a point to the Polygon's outline
+    fn signed_distance(&self, point: Point<T>) -> T;
+
+    /// The greatest distance from a cell's centroid to any point within it, which bounds how much greater
+    /// the field can be anywhere in the cell
+    fn cell_radius(&self, centroid: Point<T>, half_extent: T) -> T;
+
+    /// The distance spanned by a length in the Polygon's coordinates near a point, in the field's units
+    fn length(&self, at: Point<T>, length: T) -> T;
+}
 
 /// Represention of a Quadtree node's cells. A node contains four Qcells.
 #[derive(Debug, Copy, Clone)]
@@ -44,10 +205,12 @@ impl<T> Qcell<T>
 where
     T: GeoFloat,
 {
-    fn new(centroid: Point<T>, half_extent: T, polygon: &Polygon<T>) -> Self {
-        let two = T::one() + T::one();
-        let distance = signed_distance(centroid, polygon);
-        let max_distance = distance + half_extent * two.sqrt();
+    fn new<F>(centroid: Point<T>, half_extent: T, polygon: &F) -> Self
+    where
+        F: DistanceField<T> + ?Sized,
+    {
+        let distance = polygon.signed_distance(centroid);
+        let max_distance = distance + polygon.cell_radius(centroid, half_extent);
         Self {
             centroid,
             half_extent,
@@ -55,6 +218,32 @@ where
             max_distance,
         }
     }
+
+    /// Could any part of this cell lie inside the Polygon?
+    ///
+    /// A cell whose centroid is further inside an interior ring (or further beyond the exterior)
+    /// than its half-diagonal lies entirely outside the Polygon, so it can never contain the pole,
+    /// and there's no point in queueing or subdividing it.
+    fn may_be_inside(&self) -> bool {
+        self.max_distance > T::zero()
+    }
+
+    /// The four cells subdividing this cell, each with the given half extent
+    fn quad<F>(&self, half_extent: T, polygon: &F) -> [Qcell<T>; 4]
+    where
+        F: DistanceField<T> + ?Sized,
+    {
+        [
+            (-T::one(), -T::one()),
+            (T::one(), -T::one()),
+            (-T::one(), T::one()),
+            (T::one(), T::one()),
+        ]
+        .map(|(sign_x, sign_y)| (sign_x * half_extent, sign_y * half_extent))
+        .map(|(dx, dy)| Point::new(dx, dy))
+        .map(|delta| self.centroid + delta)
+        .map(|centroid| Qcell::new(centroid, half_extent, polygon))
+    }
 }
 
 impl<T> Ord for Qcell<T>
@@ -86,47 +275,77 @@ where
     }
 }
 
-/// Signed distance from a Qcell's centroid to a Polygon's outline
-/// Returned value is negative if the point is outside the polygon's exterior ring
-fn signed_distance<T>(point: Point<T>, polygon: &Polygon<T>) -> T
+// The queue length below which pruning isn't worthwhile
+const MIN_PRUNE_LEN: usize = 1024;
+
+/// A priority queue entry: a cell's maximum potential distance, and the slot holding its data
+#[derive(Debug, Copy, Clone)]
+struct Entry<T>
 where
     T: GeoFloat,
 {
-    let inside = polygon.contains(&point);
-    // Use LineString distance, because Polygon distance returns 0.0 for inside
-    let exterior_distance = point.euclidean_distance(polygon.exterior());
-    let distance = polygon
-        .interiors()
-        .iter()
-        .map(|x| point.euclidean_distance(x))
-        .fold(exterior_distance, T::min);
+    max_distance: T,
+    slot: usize,
+}
 
-    if inside {
-        distance
-    } else {
-        -distance
+impl<T> Ord for Entry<T>
+where
+    T: GeoFloat,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.max_distance.partial_cmp(&other.max_distance).unwrap()
     }
 }
-
-struct QuadTree<T>(pub BinaryHeap<Qcell<T>>)
+impl<T> PartialOrd for Entry<T>
 where
-    T: GeoFloat;
-
-impl<T> Deref for QuadTree<T>
+    T: GeoFloat,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Eq for Entry<T> where T: GeoFloat {}
+impl<T> PartialEq for Entry<T>
 where
     T: GeoFloat,
 {
-    type Target = BinaryHeap<Qcell<T>>;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    fn eq(&self, other: &Self) -> bool {
+        self.max_distance == other.max_distance
     }
 }
-impl<T> DerefMut for QuadTree<T>
+
+/// Priority queue of Qcells, ordered by their maximum potential distance
+///
+/// Cell data is stored column-wise, in parallel arrays addressed by slot, and only small
+/// `(max_distance, slot)` entries are moved around by the heap. Slots vacated by popped or
+/// pruned cells are reused.
+struct QuadTree<T>
+where
+    T: GeoFloat,
+{
+    centroids: Vec<Point<T>>,
+    half_extents: Vec<T>,
+    distances: Vec<T>,
+    // Slots which are no longer in use
+    free: Vec<usize>,
+    heap: BinaryHeap<Entry<T>>,
+    // The queue length at which it will next be pruned
+    prune_at: usize,
+}
+
+impl<T> Default for QuadTree<T>
 where
     T: GeoFloat,
 {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    fn default() -> Self {
+        Self {
+            centroids: Vec::new(),
+            half_extents: Vec::new(),
+            distances: Vec::new(),
+            free: Vec::new(),
+            heap: BinaryHeap::new(),
+            prune_at: MIN_PRUNE_LEN,
+        }
     }
 }
 
@@ -134,8 +353,17 @@ impl<T> QuadTree<T>
 where
     T: GeoFloat,
 {
-    pub fn new(bbox: Rect<T>, half_extent: T, polygon: &Polygon<T>) -> Self {
-        let mut cell_queue: BinaryHeap<Qcell<T>> = BinaryHeap::new();
+    /// Empty the queue, then fill it with cells covering the bounding box
+    pub fn reset<F>(&mut self, bbox: Rect<T>, half_extent: T, polygon: &F)
+    where
+        F: DistanceField<T> + ?Sized,
+    {
+        self.centroids.clear();
+        self.half_extents.clear();
+        self.distances.clear();
+        self.free.clear();
+        self.heap.clear();
+        self.prune_at = MIN_PRUNE_LEN;
 
         let two = T::one() + T::one();
         let cell_size = half_extent * two;
@@ -153,27 +381,87 @@ where
                 .map(|(x, y)| Coord { x, y } * cell_size)
                 .map(|delta_cell| origin + delta_cell + delta_mid)
                 .map(Point::from)
-                .map(|centroid| Qcell::new(centroid, half_extent, polygon));
-            cell_queue.extend(inital_points);
+                .map(|centroid| Qcell::new(centroid, half_extent, polygon))
+                .filter(Qcell::may_be_inside);
+            self.extend(inital_points);
         } else {
             // Do nothing, maybe error instead?
         }
+    }
 
-        Self(cell_queue)
+    pub fn len(&self) -> usize {
+        self.heap.len()
     }
 
-    pub fn add_quad(&mut self, cell: &Qcell<T>, half_extent: T, polygon: &Polygon<T>) {
-        let new_cells = [
-            (-T::one(), -T::one()),
-            (T::one(), -T::one()),
-            (-T::one(), T::one()),
-            (T::one(), T::one()),
-        ]
-        .map(|(sign_x, sign_y)| (sign_x * half_extent, sign_y * half_extent))
-        .map(|(dx, dy)| Point::new(dx, dy))
-        .map(|delta| cell.centroid + delta)
-        .map(|centroid| Qcell::new(centroid, half_extent, polygon));
-        self.extend(new_cells);
+    /// The number of cells the queue can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    pub fn push(&mut self, cell: Qcell<T>) {
+        let slot = if let Some(slot) = self.free.pop() {
+            self.centroids[slot] = cell.centroid;
+            self.half_extents[slot] = cell.half_extent;
+            self.distances[slot] = cell.distance;
+            slot
+        } else {
+            self.centroids.push(cell.centroid);
+            self.half_extents.push(cell.half_extent);
+            self.distances.push(cell.distance);
+            self.centroids.len() - 1
+        };
+        self.heap.push(Entry {
+            max_distance: cell.max_distance,
+            slot,
+        });
+    }
+
+    /// Remove the cell with the greatest maximum potential distance
+    pub fn pop(&mut self) -> Option<Qcell<T>> {
+        let Entry { max_distance, slot } = self.heap.pop()?;
+        self.free.push(slot);
+        Some(Qcell {
+            centroid: self.centroids[slot],
+            half_extent: self.half_extents[slot],
+            distance: self.distances[slot],
+            max_distance,
+        })
+    }
+
+    pub fn add_quad<F>(&mut self, cell: &Qcell<T>, half_extent: T, polygon: &F)
+    where
+        F: DistanceField<T> + ?Sized,
+    {
+        let cells = cell.quad(half_extent, polygon);
+        self.extend(cells.into_iter().filter(Qcell::may_be_inside));
+    }
+
+    /// Remove cells which can't contain a better solution than the given distance
+    ///
+    /// Pruning is only carried out once the queue has grown to twice its size after the
+    /// previous pruning, so the cost is amortised over insertions.
+    pub fn prune(&mut self, best_distance: T) {
+        if self.len() < self.prune_at {
+            return;
+        }
+        let free = &mut self.free;
+        self.heap.retain(|entry| {
+            let keep = entry.max_distance > best_distance;
+            if !keep {
+                free.push(entry.slot);
+            }
+            keep
+        });
+        self.prune_at = MIN_PRUNE_LEN.max(self.len() * 2);
+    }
+}
+
+impl<T> Extend<Qcell<T>> for QuadTree<T>
+where
+    T: GeoFloat,
+{
+    fn extend<I: IntoIterator<Item = Qcell<T>>>(&mut self, cells: I) {
+        cells.into_iter().for_each(|cell| self.push(cell));
     }
 }
 
@@ -181,6 +469,18 @@ where
 ///
 /// The calculation uses an [iterative grid-based algorithm](https://github.com/mapbox/polylabel#how-the-algorithm-works).
 ///
+/// # Precision
+///
+/// Both `f64` and `f32` coordinates are supported. The tolerance can't usefully be smaller than the precision
+/// of the Polygon's coordinates: it's raised to 16 [ULP](https://en.wikipedia.org/wiki/Unit_in_the_last_place)
+/// of the largest coordinate magnitude if necessary. This matters most for `f32`, which has ~7 significant digits:
+/// e.g. for projected coordinates around 500 000 m, the effective minimum tolerance is ~0.5 m.
+///
+/// The minimum doesn't keep a tiny tolerance fast, however. If a Polygon has a ridge of equally good label
+/// positions, such as a long rectangle's midline, every cell along it must be subdivided until it's smaller than
+/// the tolerance: for a rotated 10 × 1 rectangle, a tolerance of `0.01` takes ~1 ms, but a tolerance of `0.0`
+/// takes more than 20 s.
+///
 /// # Examples
 ///
 /// ```
@@ -213,68 +513,156 @@ pub fn polylabel<T>(polygon: &Polygon<T>, tolerance: &T) -> Result<Point<T>, Pol
 where
     T: GeoFloat + FromPrimitive + Sum,
 {
-    // special case for degenerate polygons
-    if polygon.signed_area() == T::zero() {
-        return Ok(Point::new(T::zero(), T::zero()));
-    }
+    Ok(PreparedPolygon::new(polygon)?.polylabel(tolerance))
+}
 
-    let bbox = polygon
-        .bounding_rect()
-        .ok_or(PolylabelError::RectCalculation)?;
-    let cell_size = bbox.width().min(bbox.height());
-    // Special case for degenerate polygons
-    if cell_size == T::zero() {
-        return Ok(Point::from(bbox.min()));
-    }
+/// Calculate a Polygon's ideal label position using the given options, returning the position along with its
+/// distance from the Polygon's edges and an error bound
+///
+/// See [`PolylabelOptions`](struct.PolylabelOptions.html) for the available options.
+///
+/// # Errors
+///
+/// The options' aspect ratio must be positive and finite, or
+/// [`PolylabelError::InvalidAspectRatio`](errors/enum.PolylabelError.html#variant.InvalidAspectRatio) is returned.
+pub fn polylabel_with_options<T>(
+    polygon: &Polygon<T>,
+    options: &PolylabelOptions<T>,
+) -> Result<Label<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    polylabel_with_stats(polygon, options).map(|(label, _)| label)
+}
 
+/// Calculate a Polygon's ideal label position using the given options, like
+/// [`polylabel_with_options`](fn.polylabel_with_options.html), along with statistics about the search
+///
+/// The statistics (the number of cells examined, and the greatest size of the cell queue) show why a Polygon
+/// is slow to label, e.g. to choose a coarser tolerance for it. A Polygon with no area isn't searched, so its
+/// statistics are zero.
+pub fn polylabel_with_stats<T>(
+    polygon: &Polygon<T>,
+    options: &PolylabelOptions<T>,
+) -> Result<(Label<T>, SearchStats), PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    let ratio = options.aspect_ratio;
+    if !(ratio > T::zero() && ratio.is_finite()) {
+        return Err(PolylabelError::InvalidAspectRatio);
+    }
+    let period = options.period;
+    if period != (None, None) {
+        let options = PolylabelOptions {
+            period: (None, None),
+            ..*options
+        };
+        let (label, stats) = polylabel_with_stats(&periodic::unwrap(polygon, period), &options)?;
+        let point = periodic::wrap(label.point, period);
+        return Ok((Label { point, ..label }, stats));
+    }
+    if options.antimeridian && antimeridian::crosses(polygon) {
+        let options = PolylabelOptions {
+            antimeridian: false,
+            ..*options
+        };
+        let (label, stats) = polylabel_with_stats(&antimeridian::shift(polygon), &options)?;
+        let point = antimeridian::unshift(label.point);
+        return Ok((Label { point, ..label }, stats));
+    }
+    if let Some(transform) = options
+        .transform
+        .filter(|transform| !transform.is_identity())
+    {
+        let inverse = transform
+            .inverse()
+            .ok_or(PolylabelError::NonInvertibleTransform)?;
+        let options = PolylabelOptions {
+            transform: None,
+            ..*options
+        };
+        let (label, stats) = polylabel_with_stats(&polygon.affine_transform(&transform), &options)?;
+        let point = inverse.apply(label.point.into()).into();
+        return Ok((Label { point, ..label }, stats));
+    }
+    let metric = options.metric;
+    if metric.degree::<T>().is_none() && ratio != T::one() {
+        // a wide label's ellipse is a circle once the Polygon is stretched vertically by the aspect ratio
+        let stretched = polygon.map_coords(|c| Coord {
+            x: c.x,
+            y: c.y * ratio,
+        });
+        let options = PolylabelOptions {
+            aspect_ratio: T::one(),
+            ..*options
+        };
+        let (label, stats) = polylabel_with_stats(&stretched, &options)?;
+        let point = Point::new(label.point.x(), label.point.y() / ratio);
+        return Ok((Label { point, ..label }, stats));
+    }
+    if !options.simplify {
+        return Ok(
+            PreparedPolygon::with_metric(polygon, metric)?.label_with_stats(&options.tolerance)
+        );
+    }
     let two = T::one() + T::one();
-    let mut half_extent = cell_size / two;
-
-    // initial best guess using centroid
-    let centroid = polygon
-        .centroid()
-        .ok_or(PolylabelError::CentroidCalculation)?;
-    let centroid_cell = Qcell::new(centroid, T::zero(), polygon);
-
-    // special case guess for rectangular polygons
-    let bbox_cell = Qcell::new(bbox.centroid(), T::zero(), polygon);
+    let epsilon = options.tolerance / (two * two);
+    // Polygons are simplified in their own units
+    let simplified = polygon.simplify(&match metric.degree() {
+        // Manhattan distances are up to √2 times Euclidean distances
+        None if metric == Metric::Manhattan => epsilon / two.sqrt(),
+        None => epsilon,
+        Some(degree) => epsilon / degree,
+    });
+    // Simplification can collapse the exterior entirely, in which case there's nothing to gain
+    if simplified.signed_area() == T::zero() {
+        return Ok(
+            PreparedPolygon::with_metric(polygon, metric)?.label_with_stats(&options.tolerance)
+        );
+    }
+    let (label, stats) = PreparedPolygon::with_metric(&simplified, metric)?
+        .label_with_stats(&(options.tolerance / two));
 
-    // deciding which initial guess was better
-    let mut best_cell = if bbox_cell.distance < centroid_cell.distance {
-        bbox_cell
-    } else {
-        centroid_cell
+    // Every point's distance to the simplified outline is within epsilon of its distance to the
+    // original outline, so the true pole can be at most epsilon further away than the simplified one
+    let distance = signed_distance(label.point, polygon, metric);
+    let label = Label {
+        point: label.point,
+        distance,
+        bound: (label.distance + label.bound + epsilon - distance).max(T::zero()),
     };
+    Ok((label, stats))
+}
 
-    // setup priority queue
-    let mut cell_queue = QuadTree::<T>::new(bbox, half_extent, polygon);
-
-    // Now try to find better solutions
-    while let Some(cell) = cell_queue.pop() {
-        // Update the best cell if we find a cell with greater distance
-        if cell.distance > best_cell.distance {
-            best_cell = Qcell { ..cell };
-        }
-
-        // Bail out of this iteration if we can't find a better solution
-        if cell.max_distance - best_cell.distance <= *tolerance {
-            continue;
-        }
-
-        // Otherwise, add a new quadtree node and start again
-        half_extent = cell.half_extent / two;
-        cell_queue.add_quad(&cell, half_extent, polygon);
+/// Signed distance from a point to a Polygon's outline, measured directly
+pub(crate) fn signed_distance<T>(point: Point<T>, polygon: &Polygon<T>, metric: Metric) -> T
+where
+    T: GeoFloat,
+{
+    if metric != Metric::Euclidean {
+        return PolygonIndex::new(polygon, metric).signed_distance(point);
+    }
+    let distance = polygon
+        .interiors()
+        .iter()
+        .map(|ring| Euclidean::distance(&point, ring))
+        .fold(Euclidean::distance(&point, polygon.exterior()), T::min);
+    if polygon.contains(&point) {
+        distance
+    } else {
+        -distance
     }
-
-    // We've exhausted the queue, so return the best solution we've found
-    Ok(best_cell.centroid)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{polylabel, Qcell};
+    use super::{
+        polylabel, polylabel_with_options, Metric, PolygonIndex, PolylabelError, PolylabelOptions,
+        Qcell, QuadTree, MIN_PRUNE_LEN,
+    };
     use geo::prelude::*;
-    use geo::{LineString, Point, Polygon};
+    use geo::{AffineTransform, Euclidean, LineString, Point, Polygon, Rect};
     use std::collections::BinaryHeap;
     #[test]
     // polygons are those used in Shapely's tests
@@ -371,4 +759,261 @@ mod tests {
         assert_eq!(q.pop().unwrap().max_distance, 8.0);
         assert_eq!(q.pop().unwrap().max_distance, 7.0);
     }
+    #[test]
+    // Pruning only removes cells which can't beat the best distance, and only once the queue is large
+    fn test_prune() {
+        let cell = |max_distance| Qcell {
+            centroid: Point::new(1.0, 2.0),
+            half_extent: 3.0,
+            distance: 0.0,
+            max_distance,
+        };
+        let mut q = QuadTree::default();
+        q.extend([cell(1.0), cell(5.0)]);
+        q.prune(2.0);
+        assert_eq!(q.len(), 2);
+        q.extend((0..MIN_PRUNE_LEN).map(|i| cell(i as f64 / 100.0)));
+        q.prune(2.0);
+        assert_eq!(q.len(), MIN_PRUNE_LEN - 200);
+        assert_eq!(q.prune_at, (MIN_PRUNE_LEN - 200) * 2);
+        // pruned slots are reused rather than growing the columns
+        let columns = q.centroids.len();
+        q.extend((0..100).map(|_| cell(3.0)));
+        assert_eq!(q.centroids.len(), columns);
+        while let Some(c) = q.pop() {
+            assert!(c.max_distance > 2.0);
+        }
+    }
+    #[test]
+    fn polygon_l_test_f32() {
+        let coords: Vec<(f32, f32)> = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let res = polylabel(&poly, &0.10).unwrap();
+        assert_eq!(res, Point::new(0.5625f32, 0.5625));
+    }
+    #[test]
+    // single-precision results must be within tolerance of double-precision results
+    fn test_london_f32() {
+        let coords: Vec<(f64, f64)> = include!("../tests/fixtures/poly3.rs");
+        let poly = Polygon::new(coords.clone().into(), vec![]);
+        let coords_f32: Vec<(f32, f32)> = coords
+            .into_iter()
+            .map(|(x, y)| (x as f32, y as f32))
+            .collect();
+        let poly_f32 = Polygon::new(coords_f32.into(), vec![]);
+        let res = polylabel(&poly, &0.001).unwrap();
+        let res_f32 = polylabel(&poly_f32, &0.001).unwrap();
+        assert!(poly_f32.contains(&res_f32));
+        let distance: f64 = Euclidean::distance(&res, poly.exterior());
+        let distance_f32: f32 = Euclidean::distance(&res_f32, poly_f32.exterior());
+        assert!((distance - distance_f32 as f64).abs() <= 0.001);
+    }
+    #[test]
+    // tolerances below f32 precision are raised, rather than refining forever
+    fn test_tiny_tolerance_f32() {
+        let coords: Vec<(f32, f32)> = include!("../tests/fixtures/poly1.rs")
+            .into_iter()
+            .map(|(x, y): (f64, f64)| (x as f32 + 500_000.0, y as f32))
+            .collect();
+        let poly = Polygon::new(coords.into(), vec![]);
+        let res = polylabel(&poly, &1e-9).unwrap();
+        assert!(poly.contains(&res));
+    }
+    #[test]
+    fn label_test() {
+        let coords = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let res = polylabel_with_options(&poly, &PolylabelOptions::new(0.1)).unwrap();
+        assert_eq!(res.point, Point::new(0.5625, 0.5625));
+        assert_eq!(res.distance, 0.5625);
+        // the true pole is 2 - √2 from the edges
+        assert!(res.distance + res.bound >= 2.0 - 2f64.sqrt());
+        assert!(res.bound <= 0.1);
+    }
+    #[test]
+    // simplification must still give a result within tolerance, and report an honest bound
+    fn simplify_test() {
+        let points = include!("../data/norway_main.rs");
+        let poly = Polygon::new(points.into(), vec![]);
+        let exact = polylabel_with_options(&poly, &PolylabelOptions::new(0.01)).unwrap();
+        let simplified =
+            polylabel_with_options(&poly, &PolylabelOptions::new(0.01).simplify(true)).unwrap();
+        assert!(poly.contains(&simplified.point));
+        assert!(simplified.bound <= 0.01);
+        assert!(exact.distance - simplified.distance <= 0.01);
+        assert!(simplified.distance + simplified.bound >= exact.distance);
+    }
+    #[test]
+    // a polygon with many holes: the label must avoid them all
+    fn lakes_test() {
+        let mut rings: Vec<Vec<(f64, f64)>> = include!("../tests/fixtures/lakes.rs");
+        let exterior = rings.remove(0);
+        let interiors = rings.into_iter().map(LineString::from).collect();
+        let poly = Polygon::new(LineString::from(exterior), interiors);
+        let res = polylabel(&poly, &0.1).unwrap();
+        assert!(poly.contains(&res));
+        assert_eq!(
+            res,
+            Point::new(-12.023_875_186_523_435, 78.380_815_708_007_83)
+        );
+    }
+    #[test]
+    // cells lying entirely inside a hole are never queued
+    fn hole_cell_test() {
+        let outer = vec![(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        let inner = vec![(20.0, 20.0), (20.0, 80.0), (80.0, 80.0), (80.0, 20.0)];
+        let poly = Polygon::new(LineString::from(outer), vec![LineString::from(inner)]);
+        let index = PolygonIndex::new(&poly, Metric::Euclidean);
+        let mut q = QuadTree::default();
+        // deep inside the hole: none of the children can be inside the polygon
+        q.add_quad(
+            &Qcell::new(Point::new(50.0, 50.0), 8.0, &index),
+            4.0,
+            &index,
+        );
+        assert_eq!(q.len(), 0);
+        // straddling the hole's edge: the children nearest the edge are kept
+        q.add_quad(
+            &Qcell::new(Point::new(22.0, 50.0), 8.0, &index),
+            4.0,
+            &index,
+        );
+        assert_eq!(q.len(), 2);
+    }
+    #[test]
+    // the Chebyshev pole is the centre of the largest square, and the Manhattan pole that of the largest diamond
+    fn test_planar_metrics() {
+        let triangle = Polygon::new(vec![(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)].into(), vec![]);
+        let label = |metric| {
+            polylabel_with_options(&triangle, &PolylabelOptions::new(0.001_f64).metric(metric))
+                .unwrap()
+        };
+        let chebyshev = label(Metric::Chebyshev);
+        assert!((chebyshev.distance - 1.0).abs() <= 0.001);
+        assert!(Euclidean::distance(&chebyshev.point, &Point::new(1.0, 1.0)) < 0.01);
+        let manhattan = label(Metric::Manhattan);
+        assert!((manhattan.distance - 4.0 / 3.0).abs() <= 0.001);
+        let euclidean = label(Metric::Euclidean);
+        assert!((euclidean.distance - (4.0 - 2.0 * 2f64.sqrt())).abs() <= 0.001);
+        // the largest 2:1 rectangle is 8 / 3 wide
+        let options = PolylabelOptions::new(0.001_f64)
+            .metric(Metric::Chebyshev)
+            .aspect_ratio(2.0);
+        let wide = polylabel_with_options(&triangle, &options).unwrap();
+        assert!((wide.distance - 4.0 / 3.0).abs() <= 0.001);
+        let simplified = polylabel_with_options(&triangle, &options.simplify(true)).unwrap();
+        assert!((simplified.distance - 4.0 / 3.0).abs() <= 0.001);
+    }
+    #[test]
+    // the label position is calculated in the transformed space, and transformed back
+    fn test_transform() {
+        let coords = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let shear = AffineTransform::skew(20.0, 10.0, Point::new(2.0, 2.0));
+        let label =
+            polylabel_with_options(&poly, &PolylabelOptions::new(0.1).transform(shear)).unwrap();
+        let sheared =
+            polylabel_with_options(&poly.affine_transform(&shear), &PolylabelOptions::new(0.1))
+                .unwrap();
+        let point = Point::from(shear.apply(label.point.into()));
+        assert!(Euclidean::distance(&point, &sheared.point) < 1e-12);
+        assert_eq!(label.distance, sheared.distance);
+        let flatten = AffineTransform::scale(1.0, 0.0, Point::new(0.0, 0.0));
+        assert_eq!(
+            polylabel_with_options(&poly, &PolylabelOptions::new(0.1).transform(flatten)),
+            Err(PolylabelError::NonInvertibleTransform)
+        );
+    }
+    #[test]
+    // a wide label's position is the centre of the widest ellipse of its shape
+    fn test_aspect_ratio() {
+        let rect = Rect::new((0.0, 0.0), (10.0, 2.0)).to_polygon();
+        let options = PolylabelOptions::new(0.01_f64).aspect_ratio(5.0);
+        let label = polylabel_with_options(&rect, &options).unwrap();
+        assert!((label.point.x() - 5.0).abs() <= 0.01 && (label.point.y() - 1.0).abs() <= 0.01);
+        assert!((label.distance - 5.0).abs() <= 0.01 && label.bound <= 0.01);
+        let simplified = polylabel_with_options(&rect, &options.simplify(true)).unwrap();
+        assert!((simplified.distance - 5.0).abs() <= 0.01);
+        // the aspect ratio only applies to Euclidean distances
+        let options = PolylabelOptions::new(100.0_f64).metric(Metric::Haversine);
+        assert_eq!(
+            polylabel_with_options(&rect, &options.aspect_ratio(5.0)).unwrap(),
+            polylabel_with_options(&rect, &options).unwrap()
+        );
+        // but it's checked for every metric
+        for ratio in [0.0, f64::NAN] {
+            for metric in [Metric::Euclidean, Metric::Haversine] {
+                let options = PolylabelOptions::new(0.01_f64).metric(metric);
+                assert_eq!(
+                    polylabel_with_options(&rect, &options.aspect_ratio(ratio)),
+                    Err(PolylabelError::InvalidAspectRatio)
+                );
+            }
+        }
+    }
+    #[test]
+    // at high latitudes, a degree of longitude is much shorter than a degree of latitude
+    fn test_haversine() {
+        // an L shape at 70°N, whose eastern arm is 12° of longitude by 2° of latitude, and whose northern arm is
+        // 2° of longitude by 8° of latitude: in metres, the eastern arm is much wider than the northern arm
+        let coords = vec![
+            (0.0, 70.0),
+            (12.0, 70.0),
+            (12.0, 72.0),
+            (2.0, 72.0),
+            (2.0, 78.0),
+            (0.0, 78.0),
+            (0.0, 70.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let options = PolylabelOptions::new(100.0_f64).metric(Metric::Haversine);
+        let label = polylabel_with_options(&poly, &options).unwrap();
+        assert!(poly.contains(&label.point));
+        // in degrees, the label position is in the corner, at the end of the narrow northern arm
+        assert!(polylabel(&poly, &0.01).unwrap().x() < 2.0);
+        assert!(label.point.x() > 4.0);
+        // a degree of latitude is about 111 km
+        assert!((label.distance - 111_000.0).abs() < 2000.0);
+        assert!(label.bound <= 100.0);
+        let simplified = polylabel_with_options(&poly, &options.simplify(true)).unwrap();
+        assert!((simplified.distance - label.distance).abs() <= 100.0);
+    }
+    #[test]
+    #[cfg(feature = "geodesic")]
+    fn test_geodesic() {
+        // a 10° square near the equator, whose label positions differ little between the sphere and the ellipsoid
+        let poly = geo::Rect::new((0.0, 0.0), (10.0, 10.0)).to_polygon();
+        let label = |metric| {
+            polylabel_with_options(&poly, &PolylabelOptions::new(100.0_f64).metric(metric)).unwrap()
+        };
+        let (geodesic, haversine) = (label(Metric::Geodesic), label(Metric::Haversine));
+        assert!(poly.contains(&geodesic.point));
+        assert!(geodesic.bound <= 100.0);
+        assert!((geodesic.distance / haversine.distance - 1.0).abs() < 0.006);
+        assert!((geodesic.point.x() - haversine.point.x()).abs() < 0.1);
+    }
 }
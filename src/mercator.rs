@@ -0,0 +1,75 @@
+//! Tolerances for Web Mercator Polygons
+//!
+//! Web Mercator (EPSG:3857) coordinates are nominally in metres, but they're only true to scale at the equator:
+//! a metre on the ground spans 1 / cos(latitude) units, so a tolerance given in ground metres is twice as coarse
+//! as intended at 60°.
+
+use geo::{BoundingRect, GeoFloat, Polygon};
+
+// Web Mercator's sphere's radius, in metres
+const RADIUS: f64 = 6_378_137.0;
+
+/// Convert a tolerance in metres on the ground to the equivalent tolerance in a Web Mercator Polygon's units
+///
+/// The scale varies with latitude, so the conversion uses the latitude of the Polygon's bounding box which is
+/// nearest the equator, where Web Mercator units are longest: the tolerance is then no coarser than `metres`
+/// anywhere within the Polygon. Returns `None` if the Polygon is empty.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel, web_mercator_tolerance};
+/// use geo::Rect;
+///
+/// // a 10 km square in Web Mercator units, around 60°N
+/// let poly = Rect::new((0.0, 8_399_737.9), (10_000.0, 8_409_737.9)).to_polygon();
+///
+/// // 1 m on the ground is about 2 units at 60°
+/// let tolerance = web_mercator_tolerance(&poly, 1.0_f64).unwrap();
+/// assert!((tolerance - 2.0).abs() < 0.01);
+/// assert!(polylabel(&poly, &tolerance).is_ok());
+/// ```
+pub fn web_mercator_tolerance<T>(polygon: &Polygon<T>, metres: T) -> Option<T>
+where
+    T: GeoFloat,
+{
+    let bbox = polygon.bounding_rect()?;
+    let (min, max) = (bbox.min().y, bbox.max().y);
+    // the y-coordinate nearest the equator
+    let y = if min <= T::zero() && max >= T::zero() {
+        T::zero()
+    } else {
+        min.abs().min(max.abs())
+    };
+    Some(metres / latitude(y).cos())
+}
+
+/// The latitude in radians of a Web Mercator y-coordinate
+fn latitude<T: GeoFloat>(y: T) -> T {
+    let two = T::one() + T::one();
+    let quarter = T::from(std::f64::consts::FRAC_PI_4).unwrap();
+    two * ((y / T::from(RADIUS).unwrap()).exp().atan() - quarter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{latitude, web_mercator_tolerance};
+    use geo::{LineString, Polygon, Rect};
+
+    #[test]
+    fn test_web_mercator_tolerance() {
+        assert_eq!(latitude(0.0), 0.0);
+        // EPSG:3857's northern limit is about 85.05°
+        assert!((latitude(20_037_508.34_f64).to_degrees() - 85.0511).abs() < 1e-4);
+        // the scale is the same north and south of the equator, and is 1 where a Polygon crosses it
+        let north = Rect::new((0.0, 8_399_737.9), (1.0, 8_500_000.0)).to_polygon();
+        let south = Rect::new((0.0, -8_500_000.0), (1.0, -8_399_737.9)).to_polygon();
+        let tolerance = web_mercator_tolerance(&north, 1.0_f64).unwrap();
+        assert!((tolerance - 2.0).abs() < 1e-6);
+        assert_eq!(web_mercator_tolerance(&south, 1.0), Some(tolerance));
+        let equator = Rect::new((0.0, -10.0), (1.0, 8_399_737.9)).to_polygon();
+        assert_eq!(web_mercator_tolerance(&equator, 1.0), Some(1.0));
+        let empty = Polygon::new(LineString::<f64>::new(vec![]), vec![]);
+        assert_eq!(web_mercator_tolerance(&empty, 1.0), None);
+    }
+}
@@ -0,0 +1,384 @@
+//! User-supplied distance metrics
+//!
+//! The label position search only needs a Polygon's signed distance field, and a bound on how much the field
+//! can change within a cell, so any metric which can measure a point's distance to a segment can be searched.
+
+use crate::errors::PolylabelError;
+use crate::options::{Label, Metric};
+use crate::prepared::search_field;
+use crate::{haversine, DistanceField};
+use geo::{Contains, Distance, Euclidean, GeoFloat, Line, Point, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+#[cfg(feature = "geodesic")]
+use crate::geodesic;
+
+/// A way of measuring distances from a label position to a Polygon's edges
+///
+/// Implement this to label Polygons using distances other than the built-in [`Metric`](enum.Metric.html)s,
+/// e.g. distances in a scaled or skewed space, or costs which vary across a map. A point's signed distance is its
+/// distance to the nearest segment of the Polygon's rings, which is positive if the Polygon contains it, and
+/// negative otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_with_metric, DistanceMetric};
+/// use geo::{Distance, Euclidean, Line, Point, Polygon, Rect};
+///
+/// // distances in a space whose y-axis is stretched
+/// struct Stretched(f64);
+///
+/// impl DistanceMetric<f64> for Stretched {
+///     fn segment_distance(&self, point: Point<f64>, segment: Line<f64>) -> f64 {
+///         let stretch = |p: Point<f64>| Point::new(p.x(), p.y() * self.0);
+///         let segment = Line::new(stretch(segment.start.into()), stretch(segment.end.into()));
+///         Euclidean::distance(&stretch(point), &segment)
+///     }
+///
+///     fn cell_radius(&self, _: Point<f64>, half_extent: f64) -> f64 {
+///         half_extent * (1.0 + self.0 * self.0).sqrt()
+///     }
+/// }
+///
+/// // a square is twice as wide as it is tall in the stretched space, so its label is as far from its
+/// // top and bottom edges as the stretched distance allows
+/// let square = Rect::new((0.0, 0.0), (4.0, 4.0)).to_polygon();
+/// let label = polylabel_with_metric(&square, &0.01, &Stretched(0.5)).unwrap();
+/// assert!((label.distance - 1.0).abs() < 0.01);
+/// ```
+pub trait DistanceMetric<T>
+where
+    T: GeoFloat,
+{
+    /// Distance from a point to a segment of one of the Polygon's rings
+    fn segment_distance(&self, point: Point<T>, segment: Line<T>) -> T;
+
+    /// Whether the Polygon contains a point, which decides the sign of its distance
+    ///
+    /// By default, this is planar containment.
+    fn contains(&self, polygon: &Polygon<T>, point: Point<T>) -> bool {
+        polygon.contains(&point)
+    }
+
+    /// The greatest distance from a square cell's centre to any point within it, given half of its width
+    ///
+    /// This bounds how much greater a point's distance can be anywhere within the cell, so it must not be an
+    /// underestimate, or the search may discard the cell holding the ideal label position. By default, it's the
+    /// Euclidean length of the cell's half-diagonal.
+    fn cell_radius(&self, _centre: Point<T>, half_extent: T) -> T {
+        half_extent * (T::one() + T::one()).sqrt()
+    }
+}
+
+impl<T> DistanceMetric<T> for Metric
+where
+    T: GeoFloat,
+{
+    fn segment_distance(&self, point: Point<T>, segment: Line<T>) -> T {
+        match self {
+            Metric::Euclidean => Euclidean::distance(&point, &segment),
+            Metric::Chebyshev => chebyshev_distance(point, segment),
+            Metric::Manhattan => manhattan_distance(point, segment),
+            Metric::Haversine => haversine::segment_distance(point, segment),
+            #[cfg(feature = "geodesic")]
+            Metric::Geodesic => geodesic::segment_distance(point, segment),
+        }
+    }
+
+    fn cell_radius(&self, centre: Point<T>, half_extent: T) -> T {
+        match self {
+            Metric::Chebyshev => half_extent,
+            Metric::Manhattan => half_extent + half_extent,
+            _ => match self.degree() {
+                None => half_extent * (T::one() + T::one()).sqrt(),
+                Some(degree) => haversine::cell_radius(centre, half_extent, degree),
+            },
+        }
+    }
+}
+
+// Chebyshev and Manhattan distances to a segment are convex and piecewise linear along it, so they're least at
+// one of its ends, or at a point where the piece changes
+
+/// Chebyshev distance from a point to a segment, which is least where the horizontal and vertical distances are
+/// equal, if it's not at one of the segment's ends
+fn chebyshev_distance<T: GeoFloat>(point: Point<T>, segment: Line<T>) -> T {
+    let (dx, dy) = (segment.dx(), segment.dy());
+    let (ax, ay) = (point.x() - segment.start.x, point.y() - segment.start.y);
+    let at = |t: T| (ax - t * dx).abs().max((ay - t * dy).abs());
+    [(ax - ay, dx - dy), (ax + ay, dx + dy)]
+        .iter()
+        .filter(|&&(_, denominator)| denominator != T::zero())
+        .map(|&(numerator, denominator)| numerator / denominator)
+        .filter(|&t| t > T::zero() && t < T::one())
+        .map(at)
+        .fold(at(T::zero()).min(at(T::one())), T::min)
+}
+
+/// Manhattan distance from a point to a segment, which is least level with the point, or directly above or below
+/// it, if it's not at one of the segment's ends
+fn manhattan_distance<T: GeoFloat>(point: Point<T>, segment: Line<T>) -> T {
+    let (dx, dy) = (segment.dx(), segment.dy());
+    let (ax, ay) = (point.x() - segment.start.x, point.y() - segment.start.y);
+    let at = |t: T| (ax - t * dx).abs() + (ay - t * dy).abs();
+    [(ax, dx), (ay, dy)]
+        .iter()
+        .filter(|&&(_, denominator)| denominator != T::zero())
+        .map(|&(numerator, denominator)| numerator / denominator)
+        .filter(|&t| t > T::zero() && t < T::one())
+        .map(at)
+        .fold(at(T::zero()).min(at(T::one())), T::min)
+}
+
+/// Calculate a Polygon's ideal label position, measuring distances using a
+/// [`DistanceMetric`](trait.DistanceMetric.html)
+///
+/// The tolerance, and the label's distance and bound, are in the metric's units. Every segment is measured for
+/// each point, so this is slower than [`polylabel_with_options`](fn.polylabel_with_options.html) for the built-in
+/// [`Metric`](enum.Metric.html)s.
+pub fn polylabel_with_metric<T, M>(
+    polygon: &Polygon<T>,
+    tolerance: &T,
+    metric: &M,
+) -> Result<Label<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    M: DistanceMetric<T> + ?Sized,
+{
+    let field = MetricField {
+        metric,
+        polygon,
+        segments: std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .flat_map(|ring| ring.lines())
+            .collect(),
+    };
+    search_field(polygon, &field, *tolerance).map(|(label, _)| label)
+}
+
+/// Calculate a Polygon's ideal label position, maximising a user-supplied signed distance function
+///
+/// The search maximises `distance`, which replaces the distance from the Polygon's outline, within the
+/// Polygon's bounding box. Like the outline's signed distance, it should be positive where a label position is
+/// acceptable, as areas where it's negative aren't searched, and it mustn't change faster than the Euclidean
+/// distance between points, or the search may discard the cell holding the ideal label position. The minimum of
+/// such functions doesn't either, so a distance can be wrapped by taking its minimum with the distance from other
+/// features to avoid, whereas adding a penalty can double its rate of change.
+/// [`PreparedPolygon::signed_distance`](struct.PreparedPolygon.html#method.signed_distance) measures the
+/// outline's signed distance.
+///
+/// The tolerance, and the label's distance and bound, are in the function's units.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_with_distance_fn, PreparedPolygon};
+/// use geo::{Distance, Euclidean, Point, Rect};
+///
+/// let square = Rect::new((0.0, 0.0), (4.0, 4.0)).to_polygon();
+/// let prepared = PreparedPolygon::new(&square).unwrap();
+///
+/// // keep the label clear of a symbol at the square's centre, with a radius of 1
+/// let symbol = Point::new(2.0, 2.0);
+/// let label = polylabel_with_distance_fn(&square, &0.01, |point: Point<f64>| {
+///     prepared
+///         .signed_distance(point)
+///         .min(Euclidean::distance(&point, &symbol) - 1.0)
+/// })
+/// .unwrap();
+/// assert!(Euclidean::distance(&label.point, &symbol) > 1.5);
+/// ```
+pub fn polylabel_with_distance_fn<T, F>(
+    polygon: &Polygon<T>,
+    tolerance: &T,
+    distance: F,
+) -> Result<Label<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    F: Fn(Point<T>) -> T,
+{
+    search_field(polygon, &FnField(distance), *tolerance).map(|(label, _)| label)
+}
+
+/// A signed distance field given by a function
+struct FnField<F>(F);
+
+impl<T, F> DistanceField<T> for FnField<F>
+where
+    T: GeoFloat,
+    F: Fn(Point<T>) -> T,
+{
+    fn signed_distance(&self, point: Point<T>) -> T {
+        (self.0)(point)
+    }
+
+    fn cell_radius(&self, _: Point<T>, half_extent: T) -> T {
+        half_extent * (T::one() + T::one()).sqrt()
+    }
+
+    fn length(&self, _: Point<T>, length: T) -> T {
+        length
+    }
+}
+
+/// A Polygon's signed distance field, measured using a metric
+struct MetricField<'a, T, M>
+where
+    T: GeoFloat,
+    M: ?Sized,
+{
+    metric: &'a M,
+    polygon: &'a Polygon<T>,
+    segments: Vec<Line<T>>,
+}
+
+impl<T, M> DistanceField<T> for MetricField<'_, T, M>
+where
+    T: GeoFloat,
+    M: DistanceMetric<T> + ?Sized,
+{
+    fn signed_distance(&self, point: Point<T>) -> T {
+        let distance = self
+            .segments
+            .iter()
+            .map(|&segment| self.metric.segment_distance(point, segment))
+            .fold(T::infinity(), T::min);
+        if self.metric.contains(self.polygon, point) {
+            distance
+        } else {
+            -distance
+        }
+    }
+
+    fn cell_radius(&self, centroid: Point<T>, half_extent: T) -> T {
+        self.metric.cell_radius(centroid, half_extent)
+    }
+
+    fn length(&self, at: Point<T>, length: T) -> T {
+        self.metric.cell_radius(at, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        chebyshev_distance, manhattan_distance, polylabel_with_distance_fn, polylabel_with_metric,
+        DistanceMetric,
+    };
+    use crate::{polylabel_with_options, Metric, PolylabelOptions, PreparedPolygon};
+    use geo::{Line, Point, Polygon, Rect};
+
+    // Manhattan distance, whose cells' corners are twice their half extent away
+    struct Manhattan;
+
+    impl DistanceMetric<f64> for Manhattan {
+        fn segment_distance(&self, point: Point<f64>, segment: Line<f64>) -> f64 {
+            // the nearest point of an axis-aligned segment
+            let x = point.x().clamp(
+                segment.start.x.min(segment.end.x),
+                segment.start.x.max(segment.end.x),
+            );
+            let y = point.y().clamp(
+                segment.start.y.min(segment.end.y),
+                segment.start.y.max(segment.end.y),
+            );
+            (point.x() - x).abs() + (point.y() - y).abs()
+        }
+
+        fn cell_radius(&self, _: Point<f64>, half_extent: f64) -> f64 {
+            2.0 * half_extent
+        }
+    }
+
+    #[test]
+    fn test_builtin_metrics() {
+        let coords = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let label = polylabel_with_metric(&poly, &0.1, &Metric::Euclidean).unwrap();
+        let indexed = polylabel_with_options(&poly, &PolylabelOptions::new(0.1)).unwrap();
+        assert_eq!(label, indexed);
+
+        let poly = Rect::new((10.0, 65.0), (30.0, 70.0)).to_polygon();
+        let options = PolylabelOptions::new(1000.0_f64).metric(Metric::Haversine);
+        let label = polylabel_with_metric(&poly, &1000.0, &Metric::Haversine).unwrap();
+        let indexed = polylabel_with_options(&poly, &options).unwrap();
+        assert!((label.distance - indexed.distance).abs() < 1000.0);
+    }
+
+    #[test]
+    // the distances to a segment are those to its nearest sampled point
+    fn test_segment_distances() {
+        let segments = [
+            Line::new((0.0, 0.0), (4.0, 1.0)),
+            Line::new((1.0, 3.0), (-2.0, -1.0)),
+            Line::new((2.0, 2.0), (2.0, 2.0)),
+        ];
+        for segment in segments {
+            for point in [(0.0, 2.0), (3.0, -1.0), (5.0, 5.0), (-1.0, 0.5)] {
+                let point = Point::from(point);
+                let samples =
+                    (0..=10_000).map(|i| segment.start + segment.delta() * (i as f64 / 1e4));
+                let sampled = |distance: fn(f64, f64) -> f64| {
+                    samples
+                        .clone()
+                        .map(|c| distance((c.x - point.x()).abs(), (c.y - point.y()).abs()))
+                        .fold(f64::INFINITY, f64::min)
+                };
+                let chebyshev = chebyshev_distance(point, segment);
+                assert!(chebyshev <= sampled(f64::max) && sampled(f64::max) - chebyshev < 1e-3);
+                let manhattan = manhattan_distance(point, segment);
+                let sum = |x: f64, y: f64| x + y;
+                assert!(manhattan <= sampled(sum) && sampled(sum) - manhattan < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_metric() {
+        let square = Rect::new((0.0, 0.0), (4.0, 4.0)).to_polygon();
+        let label = polylabel_with_metric(&square, &0.01, &Manhattan).unwrap();
+        assert!((label.distance - 2.0).abs() <= 0.01);
+        assert!(label.bound <= 0.01);
+        // degenerate Polygons aren't searched
+        let line = Polygon::new(vec![(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)].into(), vec![]);
+        let label = polylabel_with_metric(&line, &0.01, &Manhattan).unwrap();
+        assert_eq!(label.point, Point::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_distance_fn() {
+        let coords = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let prepared = PreparedPolygon::new(&poly).unwrap();
+        // the outline's distance gives the same label position
+        let label =
+            polylabel_with_distance_fn(&poly, &0.1, |p| prepared.signed_distance(p)).unwrap();
+        assert_eq!(label, prepared.label(&0.1));
+        // a distance function which is greatest at a point
+        let peak = Point::new(3.0, 2.0);
+        let label = polylabel_with_distance_fn(&poly, &0.001, |p: Point<f64>| {
+            1.0 - ((p.x() - peak.x()).powi(2) + (p.y() - peak.y()).powi(2)).sqrt()
+        })
+        .unwrap();
+        assert!((label.point.x() - 3.0).abs() < 0.01 && (label.point.y() - 2.0).abs() < 0.01);
+        assert!(label.distance <= 1.0 && label.distance > 0.999);
+    }
+}
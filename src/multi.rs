@@ -0,0 +1,175 @@
+//! Label position calculation for MultiPolygons
+
+use crate::antimeridian;
+use crate::errors::PolylabelError;
+use crate::options::{Label, PolylabelOptions, SearchStats};
+use crate::polylabel_with_stats;
+use geo::algorithm::bool_ops::BoolOpsNum;
+use geo::{Area, BooleanOps, GeoFloat, MultiPolygon, Polygon};
+use num_traits::FromPrimitive;
+use std::cmp::Ordering;
+use std::iter::Sum;
+
+/// Which parts of a MultiPolygon to calculate label positions for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parts {
+    /// Only the part with the largest area
+    Largest,
+    /// Every part
+    All,
+    /// The part with the largest area once the parts are dissolved, so overlapping and adjacent parts are
+    /// labelled as one
+    Union,
+}
+
+/// Calculate label positions for a MultiPolygon's parts using the given options
+///
+/// With [`Parts::Largest`](enum.Parts.html#variant.Largest), a single label position is calculated, for the
+/// part with the largest area. With [`Parts::All`](enum.Parts.html#variant.All), a label position is calculated
+/// for each part, in order. With [`Parts::Union`](enum.Parts.html#variant.Union), the parts are dissolved first,
+/// and a single label position is calculated for the largest part of their union. An empty MultiPolygon has no
+/// label positions.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_multi, Parts, PolylabelOptions};
+/// use geo::{MultiPolygon, Point, Rect};
+///
+/// let multi = MultiPolygon::new(vec![
+///     Rect::new((0.0, 0.0), (1.0, 1.0)).to_polygon(),
+///     Rect::new((10.0, 10.0), (14.0, 14.0)).to_polygon(),
+/// ]);
+/// let options = PolylabelOptions::new(0.1);
+///
+/// let largest = polylabel_multi(&multi, &options, Parts::Largest).unwrap();
+/// assert_eq!(largest[0].point, Point::new(12.0, 12.0));
+/// assert_eq!(polylabel_multi(&multi, &options, Parts::All).unwrap().len(), 2);
+///
+/// let adjacent = MultiPolygon::new(vec![
+///     Rect::new((0.0, 0.0), (4.0, 4.0)).to_polygon(),
+///     Rect::new((4.0, 0.0), (8.0, 4.0)).to_polygon(),
+/// ]);
+/// let union = polylabel_multi(&adjacent, &options, Parts::Union).unwrap();
+/// assert_eq!(union[0].distance, 2.0);
+/// ```
+pub fn polylabel_multi<T>(
+    multi: &MultiPolygon<T>,
+    options: &PolylabelOptions<T>,
+    parts: Parts,
+) -> Result<Vec<Label<T>>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum + BoolOpsNum,
+{
+    let labels = polylabel_multi_with_stats(multi, options, parts)?;
+    Ok(labels.into_iter().map(|(label, _)| label).collect())
+}
+
+/// Calculate label positions for a MultiPolygon's parts, like [`polylabel_multi`](fn.polylabel_multi.html),
+/// along with statistics about each part's search
+///
+/// See [`polylabel_with_stats`](fn.polylabel_with_stats.html) for the statistics.
+pub fn polylabel_multi_with_stats<T>(
+    multi: &MultiPolygon<T>,
+    options: &PolylabelOptions<T>,
+    parts: Parts,
+) -> Result<Vec<(Label<T>, SearchStats)>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum + BoolOpsNum,
+{
+    match parts {
+        Parts::Largest => largest(multi)
+            .map(|largest| polylabel_with_stats(largest, options))
+            .into_iter()
+            .collect(),
+        Parts::All => multi
+            .iter()
+            .map(|poly| polylabel_with_stats(poly, options))
+            .collect(),
+        Parts::Union if options.antimeridian && antimeridian::straddles(multi) => {
+            let labels = polylabel_multi_with_stats(&antimeridian::shift(multi), options, parts)?;
+            Ok(labels
+                .into_iter()
+                .map(|(label, stats)| {
+                    let point = antimeridian::unshift(label.point);
+                    (Label { point, ..label }, stats)
+                })
+                .collect())
+        }
+        Parts::Union => {
+            // the parts are unioned one at a time, as a MultiPolygon's parts mustn't overlap
+            let union = multi
+                .iter()
+                .fold(MultiPolygon::new(vec![]), |union, poly| union.union(poly));
+            largest(&union)
+                .map(|largest| polylabel_with_stats(largest, options))
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+// the part with the largest area
+pub(crate) fn largest<T: GeoFloat>(multi: &MultiPolygon<T>) -> Option<&Polygon<T>> {
+    multi.iter().max_by(|a, b| {
+        a.unsigned_area()
+            .partial_cmp(&b.unsigned_area())
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{polylabel_multi, polylabel_multi_with_stats, Parts};
+    use crate::options::PolylabelOptions;
+    use geo::{MultiPolygon, Point, Rect};
+
+    #[test]
+    fn test_parts() {
+        let multi = MultiPolygon::new(vec![
+            Rect::new((0.0, 0.0), (2.0, 2.0)).to_polygon(),
+            Rect::new((10.0, 10.0), (14.0, 14.0)).to_polygon(),
+            Rect::new((20.0, 0.0), (21.0, 1.0)).to_polygon(),
+        ]);
+        let options = PolylabelOptions::new(0.1);
+        let largest = polylabel_multi(&multi, &options, Parts::Largest).unwrap();
+        assert_eq!(largest.len(), 1);
+        assert_eq!(largest[0].point, Point::new(12.0, 12.0));
+        let all = polylabel_multi(&multi, &options, Parts::All).unwrap();
+        let points: Vec<_> = all.iter().map(|label| label.point).collect();
+        assert_eq!(
+            points,
+            vec![
+                Point::new(1.0, 1.0),
+                Point::new(12.0, 12.0),
+                Point::new(20.5, 0.5)
+            ]
+        );
+        let stats = polylabel_multi_with_stats(&multi, &options, Parts::All).unwrap();
+        assert!(stats.iter().all(|(_, stats)| stats.cells > 0));
+        // the overlapping parts are dissolved, and their union's label position is inside both
+        let overlapping = MultiPolygon::new(vec![
+            Rect::new((0.0, 0.0), (6.0, 4.0)).to_polygon(),
+            Rect::new((2.0, 0.0), (8.0, 4.0)).to_polygon(),
+        ]);
+        let union = polylabel_multi(&overlapping, &options, Parts::Union).unwrap();
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].distance, 2.0);
+        assert!((2.0..=6.0).contains(&union[0].point.x()));
+        // parts split at the antimeridian are labelled as one
+        let split = MultiPolygon::new(vec![
+            Rect::new((176.0_f64, 0.0), (180.0, 4.0)).to_polygon(),
+            Rect::new((-180.0, 0.0), (-176.0, 4.0)).to_polygon(),
+        ]);
+        let union = polylabel_multi(&split, &options.antimeridian(true), Parts::Union).unwrap();
+        assert_eq!(union[0].distance, 2.0);
+        assert!(union[0].point.x().abs() >= 178.0);
+        let empty = MultiPolygon::<f64>::new(vec![]);
+        assert!(polylabel_multi(&empty, &options, Parts::Largest)
+            .unwrap()
+            .is_empty());
+        assert!(polylabel_multi(&empty, &options, Parts::Union)
+            .unwrap()
+            .is_empty());
+    }
+}
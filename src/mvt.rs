@@ -0,0 +1,529 @@
+//! Label positions for Mapbox Vector Tile (MVT) polygon layers
+//!
+//! Tiles are decoded directly from their [protocol buffer encoding](https://github.com/mapbox/vector-tile-spec).
+
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+
+// field numbers
+const TILE_LAYER: u64 = 3;
+const LAYER_NAME: u64 = 1;
+const LAYER_FEATURE: u64 = 2;
+const LAYER_KEY: u64 = 3;
+const LAYER_VALUE: u64 = 4;
+const LAYER_EXTENT: u64 = 5;
+const LAYER_VERSION: u64 = 15;
+const FEATURE_ID: u64 = 1;
+const FEATURE_TAGS: u64 = 2;
+const FEATURE_TYPE: u64 = 3;
+const FEATURE_GEOMETRY: u64 = 4;
+const VALUE_DOUBLE: u64 = 3;
+const VALUE_UINT: u64 = 5;
+// geometry types and commands
+const POINT: u64 = 1;
+const POLYGON: u64 = 3;
+const MOVE_TO: u32 = 1;
+const LINE_TO: u32 = 2;
+const CLOSE_PATH: u32 = 7;
+const DEFAULT_EXTENT: u32 = 4096;
+
+/// The label positions of a tile layer's polygon features
+#[derive(Debug, Clone, PartialEq)]
+pub struct MvtLayerLabels {
+    /// The layer's name
+    pub name: String,
+    /// The layer's extent: the width and height of the tile, in tile coordinates
+    pub extent: u32,
+    /// The label positions of the layer's polygon features, in feature order
+    pub labels: Vec<MvtLabel>,
+}
+
+/// A tile feature's label position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MvtLabel {
+    /// The feature's id, if it has one
+    pub id: Option<u64>,
+    /// The feature's index in its layer
+    pub index: usize,
+    /// The label position, in tile coordinates, and its distance from the feature's edges
+    pub label: Label<f64>,
+}
+
+/// Calculate the label positions of the polygon features in a Mapbox Vector Tile
+///
+/// `tile` is an uncompressed tile buffer. Each feature's label position is calculated in tile coordinates (in
+/// which `y` increases downwards), and is that of its part with the largest area. Tiles usually include a buffer
+/// of geometry beyond their extent, so features which cross tile boundaries are labelled in several tiles:
+/// label positions further than `buffer` tile units outside the tile's extent are discarded, so a `buffer` of
+/// 0 keeps only those within the tile.
+///
+/// Returns the label positions of each layer which holds polygon features, in layer order. Features whose label
+/// position can't be calculated are omitted. Returns `None` if `tile` isn't a valid tile.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::label_tile;
+///
+/// // a tile holding a "buildings" layer, whose only feature is a square
+/// let tile = [
+///     0x1a, 0x23, 0x78, 0x02, 0x0a, 0x09, 0x62, 0x75, 0x69, 0x6c, 0x64, 0x69, 0x6e, 0x67, 0x73, 0x12,
+///     0x11, 0x18, 0x03, 0x22, 0x0d, 0x09, 0x00, 0x00, 0x1a, 0x80, 0x01, 0x00, 0x00, 0x80, 0x01, 0x7f,
+///     0x00, 0x0f, 0x28, 0x80, 0x20,
+/// ];
+/// let layers = label_tile(&tile, 1.0, 0).unwrap();
+/// assert_eq!(layers[0].name, "buildings");
+/// assert_eq!(layers[0].labels[0].label.point.x_y(), (32.0, 32.0));
+/// ```
+pub fn label_tile(tile: &[u8], tolerance: f64, buffer: u32) -> Option<Vec<MvtLayerLabels>> {
+    let mut layers = vec![];
+    fields(tile, |field, value| {
+        if let (TILE_LAYER, Value::Bytes(layer)) = (field, value) {
+            if let Some(layer) = label_layer(layer, tolerance, buffer)? {
+                layers.push(layer);
+            }
+        }
+        Some(())
+    })?;
+    Some(layers)
+}
+
+// label a layer's polygon features, or None if it has none
+fn label_layer(layer: &[u8], tolerance: f64, buffer: u32) -> Option<Option<MvtLayerLabels>> {
+    let (mut name, mut extent, mut features) = (None, DEFAULT_EXTENT, vec![]);
+    fields(layer, |field, value| {
+        match (field, value) {
+            (LAYER_NAME, Value::Bytes(bytes)) => {
+                name = Some(String::from_utf8(bytes.to_vec()).ok()?)
+            }
+            (LAYER_FEATURE, Value::Bytes(feature)) => features.push(feature),
+            (LAYER_EXTENT, Value::Varint(value)) => extent = u32::try_from(value).ok()?,
+            _ => (),
+        }
+        Some(())
+    })?;
+    let (min, max) = (-f64::from(buffer), f64::from(extent) + f64::from(buffer));
+    let mut polygons = false;
+    let mut labels = vec![];
+    for (index, feature) in features.into_iter().enumerate() {
+        let (id, polygon) = match feature_polygon(feature)? {
+            Some(feature) => feature,
+            None => continue,
+        };
+        polygons = true;
+        let label = polylabel_multi(&polygon, &PolylabelOptions::new(tolerance), Parts::Largest)
+            .ok()
+            .and_then(|labels| labels.into_iter().next())
+            .filter(|label| {
+                let (x, y) = label.point.x_y();
+                (min..=max).contains(&x) && (min..=max).contains(&y)
+            });
+        if let Some(label) = label {
+            labels.push(MvtLabel { id, index, label });
+        }
+    }
+    Some(polygons.then(|| MvtLayerLabels {
+        name: name.unwrap_or_default(),
+        extent,
+        labels,
+    }))
+}
+
+// a polygon feature's id and geometry, or None if it isn't a polygon
+fn feature_polygon(feature: &[u8]) -> Option<Option<(Option<u64>, MultiPolygon<f64>)>> {
+    let (mut id, mut kind, mut geometry) = (None, 0, None);
+    fields(feature, |field, value| {
+        match (field, value) {
+            (FEATURE_ID, Value::Varint(value)) => id = Some(value),
+            (FEATURE_TYPE, Value::Varint(value)) => kind = value,
+            (FEATURE_GEOMETRY, Value::Bytes(bytes)) => geometry = Some(bytes),
+            _ => (),
+        }
+        Some(())
+    })?;
+    if kind != POLYGON {
+        return Some(None);
+    }
+    let geometry = decode_polygon(geometry.unwrap_or_default())?;
+    Some(Some((id, geometry)))
+}
+
+/// Decode a polygon feature's geometry commands
+///
+/// Each ring with a positive area (clockwise, as `y` increases downwards) begins a new Polygon, and each ring with a
+/// negative area is an interior ring of the preceding Polygon. Rings with no area, and interior rings which don't
+/// follow an exterior, are ignored.
+fn decode_polygon(geometry: &[u8]) -> Option<MultiPolygon<f64>> {
+    let mut integers = Packed(geometry);
+    let mut cursor = (0i64, 0i64);
+    let mut polygons: Vec<Polygon<f64>> = vec![];
+    let mut ring = vec![];
+    while let Some(command) = integers.next() {
+        let command = u32::try_from(command?).ok()?;
+        let (id, count) = (command & 7, command >> 3);
+        match id {
+            MOVE_TO | LINE_TO => {
+                for _ in 0..count {
+                    let dx = zigzag(integers.next()??);
+                    let dy = zigzag(integers.next()??);
+                    cursor = (cursor.0.checked_add(dx)?, cursor.1.checked_add(dy)?);
+                    if id == MOVE_TO {
+                        ring.clear();
+                    }
+                    ring.push(Coord {
+                        x: cursor.0 as f64,
+                        y: cursor.1 as f64,
+                    });
+                }
+            }
+            CLOSE_PATH => {
+                let ring = LineString::from(std::mem::take(&mut ring));
+                let area = signed_area(&ring);
+                if area > 0.0 {
+                    polygons.push(Polygon::new(ring, vec![]));
+                } else if let Some(polygon) = polygons.last_mut().filter(|_| area < 0.0) {
+                    polygon.interiors_push(ring);
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(MultiPolygon::new(polygons))
+}
+
+// the surveyor's formula, which is positive for clockwise rings when y increases downwards
+fn signed_area(ring: &LineString<f64>) -> f64 {
+    ring.0
+        .iter()
+        .zip(ring.0.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Encode label positions as a point layer, which can be appended to a tile
+///
+/// The layer is named `name`, and has the extent of the layer the label positions were calculated for. Each label
+/// position becomes a point feature, rounded to the nearest tile coordinate, with a `radius` attribute holding its
+/// distance from its polygon's edges. If its source feature has an id, the point has the same id, and a
+/// `source_id` attribute holding it.
+///
+/// The returned bytes are an encoded tile holding the single layer. As tiles are protocol buffer messages, they
+/// can be appended to an (uncompressed) tile to add the layer to it.
+pub fn encode_label_layer(labels: &MvtLayerLabels, name: &str) -> Vec<u8> {
+    let mut layer = vec![];
+    varint_field(LAYER_VERSION, 2, &mut layer);
+    bytes_field(LAYER_NAME, name.as_bytes(), &mut layer);
+    // each feature has its own values, which are referred to by their index
+    let mut values = vec![];
+    for label in &labels.labels {
+        let mut feature = vec![];
+        let mut tags = vec![0, values.len() as u64];
+        let mut radius = vec![];
+        write_varint(VALUE_DOUBLE << 3 | 1, &mut radius);
+        radius.extend(label.label.distance.to_le_bytes());
+        values.push(radius);
+        if let Some(id) = label.id {
+            varint_field(FEATURE_ID, id, &mut feature);
+            let mut source_id = vec![];
+            varint_field(VALUE_UINT, id, &mut source_id);
+            tags.extend([1, values.len() as u64]);
+            values.push(source_id);
+        }
+        bytes_field(FEATURE_TAGS, &packed(&tags), &mut feature);
+        varint_field(FEATURE_TYPE, POINT, &mut feature);
+        let (x, y) = label.label.point.x_y();
+        let geometry = [
+            u64::from(MOVE_TO | 1 << 3),
+            unzigzag(x.round() as i64),
+            unzigzag(y.round() as i64),
+        ];
+        bytes_field(FEATURE_GEOMETRY, &packed(&geometry), &mut feature);
+        bytes_field(LAYER_FEATURE, &feature, &mut layer);
+    }
+    for key in ["radius", "source_id"] {
+        bytes_field(LAYER_KEY, key.as_bytes(), &mut layer);
+    }
+    for value in values {
+        bytes_field(LAYER_VALUE, &value, &mut layer);
+    }
+    varint_field(LAYER_EXTENT, u64::from(labels.extent), &mut layer);
+    let mut tile = vec![];
+    bytes_field(TILE_LAYER, &layer, &mut tile);
+    tile
+}
+
+/// Label the polygon features in a Mapbox Vector Tile, returning the tile with a layer of label positions added
+/// for each of its polygon layers
+///
+/// The label positions are calculated by [`label_tile`](fn.label_tile.html), and each layer of them is encoded by
+/// [`encode_label_layer`](fn.encode_label_layer.html), named after its source layer with a `_label` suffix.
+/// Returns `None` if `tile` isn't a valid tile.
+pub fn add_label_layers(tile: &[u8], tolerance: f64, buffer: u32) -> Option<Vec<u8>> {
+    let mut labelled = tile.to_vec();
+    for layer in label_tile(tile, tolerance, buffer)? {
+        labelled.extend(encode_label_layer(&layer, &format!("{}_label", layer.name)));
+    }
+    Some(labelled)
+}
+
+fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn unzigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// A protocol buffer field's value
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+// call `f` with each field of a message, failing if the message or `f` fails
+fn fields<'a>(
+    mut message: &'a [u8],
+    mut f: impl FnMut(u64, Value<'a>) -> Option<()>,
+) -> Option<()> {
+    while !message.is_empty() {
+        let key = varint(&mut message)?;
+        let value = match key & 7 {
+            0 => Value::Varint(varint(&mut message)?),
+            1 => {
+                message = message.get(8..)?;
+                Value::Fixed
+            }
+            2 => {
+                let len = usize::try_from(varint(&mut message)?).ok()?;
+                let (bytes, rest) = (message.get(..len)?, &message[len..]);
+                message = rest;
+                Value::Bytes(bytes)
+            }
+            5 => {
+                message = message.get(4..)?;
+                Value::Fixed
+            }
+            _ => return None,
+        };
+        f(key >> 3, value)?;
+    }
+    Some(())
+}
+
+fn varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn write_varint(mut value: u64, bytes: &mut Vec<u8>) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn varint_field(field: u64, value: u64, bytes: &mut Vec<u8>) {
+    write_varint(field << 3, bytes);
+    write_varint(value, bytes);
+}
+
+fn bytes_field(field: u64, value: &[u8], bytes: &mut Vec<u8>) {
+    write_varint(field << 3 | 2, bytes);
+    write_varint(value.len() as u64, bytes);
+    bytes.extend(value);
+}
+
+fn packed(values: &[u64]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in values {
+        write_varint(value, &mut bytes);
+    }
+    bytes
+}
+
+/// The varints of a packed repeated field
+struct Packed<'a>(&'a [u8]);
+
+impl Iterator for Packed<'_> {
+    type Item = Option<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (!self.0.is_empty()).then(|| varint(&mut self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_label_layers, bytes_field, decode_polygon, fields, label_tile, unzigzag, varint_field,
+        write_varint as varint, zigzag, Packed, Value,
+    };
+
+    // encode rings as geometry commands, each as a sequence of positions
+    fn geometry(rings: &[&[(i64, i64)]]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut cursor = (0, 0);
+        for ring in rings {
+            for (i, &(x, y)) in ring.iter().enumerate() {
+                match i {
+                    0 => varint(1 | 1 << 3, &mut bytes),
+                    1 => varint(2 | (ring.len() as u64 - 1) << 3, &mut bytes),
+                    _ => (),
+                }
+                varint(unzigzag(x - cursor.0), &mut bytes);
+                varint(unzigzag(y - cursor.1), &mut bytes);
+                cursor = (x, y);
+            }
+            varint(7 | 1 << 3, &mut bytes);
+        }
+        bytes
+    }
+
+    fn feature(id: u64, kind: u64, geometry: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        varint_field(1, id, &mut bytes);
+        varint_field(3, kind, &mut bytes);
+        bytes_field(4, geometry, &mut bytes);
+        bytes
+    }
+
+    fn layer(name: &str, extent: u64, features: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = vec![];
+        varint_field(15, 2, &mut bytes);
+        bytes_field(1, name.as_bytes(), &mut bytes);
+        for feature in features {
+            bytes_field(2, feature, &mut bytes);
+        }
+        varint_field(5, extent, &mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn test_zigzag() {
+        assert_eq!([0, 1, 2, 3, 4].map(zigzag), [0, -1, 1, -2, 2]);
+        assert_eq!([0, -1, 1, -2, 2].map(unzigzag), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_decode_polygon() {
+        // a clockwise exterior (as y increases downwards), an anticlockwise hole, and a second exterior
+        let exterior = [(0, 0), (10, 0), (10, 10), (0, 10)];
+        let hole = [(2, 2), (2, 4), (4, 4), (4, 2)];
+        let second = [(20, 0), (21, 0), (21, 1), (20, 1)];
+        let multi = decode_polygon(&geometry(&[&exterior, &hole, &second])).unwrap();
+        assert_eq!(multi.0.len(), 2);
+        assert_eq!(multi.0[0].interiors().len(), 1);
+        assert_eq!(multi.0[1].exterior().0[2], (21.0, 1.0).into());
+        // a LineTo without a MoveTo's parameters
+        assert!(decode_polygon(&[9, 0]).is_none());
+    }
+
+    #[test]
+    fn test_label_tile() {
+        let square = |offset: i64, size: i64| {
+            [
+                (offset, offset),
+                (offset + size, offset),
+                (offset + size, offset + size),
+                (offset, offset + size),
+            ]
+        };
+        let features = [
+            feature(7, 3, &geometry(&[&square(0, 64)])),
+            // a Point
+            feature(8, 1, &[9, 2, 2]),
+            // beyond the tile's extent and buffer
+            feature(9, 3, &geometry(&[&square(300, 64)])),
+        ];
+        let mut tile = vec![];
+        bytes_field(3, &layer("parcels", 256, &features), &mut tile);
+        bytes_field(3, &layer("roads", 256, &[feature(1, 2, &[])]), &mut tile);
+
+        let layers = label_tile(&tile, 0.5, 16).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(
+            (layers[0].name.as_str(), layers[0].extent),
+            ("parcels", 256)
+        );
+        assert_eq!(layers[0].labels.len(), 1);
+        let label = layers[0].labels[0];
+        assert_eq!((label.id, label.index), (Some(7), 0));
+        assert_eq!(label.label.point.x_y(), (32.0, 32.0));
+        // a larger buffer keeps the label beyond the extent
+        assert_eq!(label_tile(&tile, 0.5, 128).unwrap()[0].labels.len(), 2);
+
+        assert!(label_tile(&tile[..tile.len() - 1], 0.5, 16).is_none());
+    }
+
+    #[test]
+    fn test_add_label_layers() {
+        let square = [(0, 0), (64, 0), (64, 64), (0, 64)];
+        let mut tile = vec![];
+        bytes_field(
+            3,
+            &layer("parcels", 256, &[feature(7, 3, &geometry(&[&square]))]),
+            &mut tile,
+        );
+        let labelled = add_label_layers(&tile, 0.5, 0).unwrap();
+        assert_eq!(labelled[..tile.len()], tile[..]);
+
+        let mut layers = vec![];
+        fields(&labelled[tile.len()..], |_, value| {
+            if let Value::Bytes(layer) = value {
+                layers.push(layer);
+            }
+            Some(())
+        })
+        .unwrap();
+        assert_eq!(layers.len(), 1);
+        let (mut name, mut features, mut keys, mut values, mut extent) =
+            (&[][..], vec![], vec![], vec![], 0);
+        fields(layers[0], |field, value| {
+            match (field, value) {
+                (1, Value::Bytes(bytes)) => name = bytes,
+                (2, Value::Bytes(bytes)) => features.push(bytes),
+                (3, Value::Bytes(bytes)) => keys.push(bytes),
+                (4, Value::Bytes(bytes)) => values.push(bytes),
+                (5, Value::Varint(value)) => extent = value,
+                _ => (),
+            }
+            Some(())
+        })
+        .unwrap();
+        assert_eq!(name, b"parcels_label");
+        assert_eq!(keys, [&b"radius"[..], b"source_id"]);
+        assert_eq!(extent, 256);
+        // a double value and a uint value
+        let mut radius = vec![3 << 3 | 1];
+        radius.extend(32f64.to_le_bytes());
+        assert_eq!(values, [&radius[..], &[5 << 3, 7]]);
+
+        let (mut id, mut tags, mut kind, mut points) = (None, vec![], 0, vec![]);
+        fields(features[0], |field, value| {
+            match (field, value) {
+                (1, Value::Varint(value)) => id = Some(value),
+                (2, Value::Bytes(bytes)) => tags = Packed(bytes).collect(),
+                (3, Value::Varint(value)) => kind = value,
+                (4, Value::Bytes(bytes)) => points = Packed(bytes).collect(),
+                _ => (),
+            }
+            Some(())
+        })
+        .unwrap();
+        assert_eq!((id, kind), (Some(7), 1));
+        assert_eq!(tags, [Some(0), Some(0), Some(1), Some(1)]);
+        assert_eq!(points, [Some(9), Some(64), Some(64)]);
+    }
+}
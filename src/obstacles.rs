@@ -0,0 +1,128 @@
+//! Label positions which avoid obstacles inside a Polygon, such as existing labels, icons, or rivers
+
+use crate::errors::PolylabelError;
+use crate::metric::polylabel_with_distance_fn;
+use crate::options::{Label, Metric};
+use crate::prepared::PreparedPolygon;
+use crate::signed_distance;
+use geo::{Distance, Euclidean, GeoFloat, Geometry, Point, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+/// Calculate a Polygon's ideal label position, keeping clear of obstacles
+///
+/// The label position is the point inside the Polygon which is furthest from both the Polygon's edges and the
+/// obstacles, which may be any geometries. The label's distance is to the nearest edge or obstacle, and is
+/// negative inside an obstacle Polygon, so a label position is never inside one if there's room outside them.
+/// With no obstacles, this is the same as [`polylabel`](fn.polylabel.html).
+///
+/// Obstacles aren't spatially indexed, so each is measured at every point examined by the search.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_with_obstacles;
+/// use geo::{Geometry, LineString, Point, Rect};
+///
+/// let square = Rect::new((0.0, 0.0), (10.0, 10.0)).to_polygon();
+///
+/// // a river running north to south through the square, and an icon in the larger part to its west
+/// let river = LineString::from(vec![(6.0, 0.0), (6.0, 10.0)]);
+/// let icon = Point::new(3.0, 5.0);
+/// let obstacles = [Geometry::LineString(river), Geometry::Point(icon)];
+///
+/// let label = polylabel_with_obstacles(&square, &obstacles, &0.01_f64).unwrap();
+/// assert!(label.point.x() < 6.0);
+/// assert!(label.distance > 2.5 && label.distance < 3.0);
+/// ```
+pub fn polylabel_with_obstacles<T>(
+    polygon: &Polygon<T>,
+    obstacles: &[Geometry<T>],
+    tolerance: &T,
+) -> Result<Label<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    let prepared = PreparedPolygon::new(polygon)?;
+    if obstacles.is_empty() {
+        return Ok(prepared.label(tolerance));
+    }
+    polylabel_with_distance_fn(polygon, tolerance, |point: Point<T>| {
+        obstacles
+            .iter()
+            .map(|obstacle| clearance(point, obstacle))
+            .fold(prepared.signed_distance(point), T::min)
+    })
+}
+
+// the distance from a point to an obstacle, which is negative inside an obstacle with an area, so the search can
+// discard cells inside it
+fn clearance<T>(point: Point<T>, obstacle: &Geometry<T>) -> T
+where
+    T: GeoFloat,
+{
+    match obstacle {
+        Geometry::Polygon(polygon) => -signed_distance(point, polygon, Metric::Euclidean),
+        Geometry::MultiPolygon(polygons) => polygons
+            .iter()
+            .map(|polygon| -signed_distance(point, polygon, Metric::Euclidean))
+            .fold(T::infinity(), T::min),
+        Geometry::Rect(rect) => -signed_distance(point, &rect.to_polygon(), Metric::Euclidean),
+        Geometry::Triangle(triangle) => {
+            -signed_distance(point, &triangle.to_polygon(), Metric::Euclidean)
+        }
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .map(|obstacle| clearance(point, obstacle))
+            .fold(T::infinity(), T::min),
+        _ => Euclidean::distance(&point, obstacle),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clearance, polylabel_with_obstacles};
+    use crate::polylabel;
+    use geo::{Contains, Geometry, GeometryCollection, Point, Rect};
+
+    #[test]
+    fn test_polylabel_with_obstacles() {
+        let poly = Rect::new((0.0_f64, 0.0), (10.0, 4.0)).to_polygon();
+        // no obstacles
+        let label = polylabel_with_obstacles(&poly, &[], &0.01).unwrap();
+        assert_eq!(label.point, polylabel(&poly, &0.01).unwrap());
+        // an icon at the label position moves it along the strip
+        let icon = Geometry::Point(label.point);
+        let label = polylabel_with_obstacles(&poly, &[icon], &0.01).unwrap();
+        assert!((label.point.x() - 5.0).abs() >= 2.0 - 0.01);
+        assert!((label.distance - 2.0).abs() <= 0.01);
+        // an obstacle Polygon covering the western half of the strip excludes it
+        let existing = Geometry::Polygon(Rect::new((-1.0, -1.0), (6.0, 5.0)).to_polygon());
+        let label = polylabel_with_obstacles(&poly, &[existing], &0.01).unwrap();
+        assert!(label.point.x() > 6.0 && poly.contains(&label.point));
+        assert!((label.distance - 2.0).abs() <= 0.01);
+        // obstacles covering the whole Polygon leave no room for a label
+        let covering = Geometry::Rect(Rect::new((-1.0, -1.0), (11.0, 5.0)));
+        let label = polylabel_with_obstacles(&poly, &[covering], &0.01).unwrap();
+        assert!(label.distance <= 0.0);
+    }
+
+    #[test]
+    fn test_clearance() {
+        let point = Point::new(1.0_f64, 1.0);
+        let rect = Rect::new((0.0, 0.0), (4.0, 4.0));
+        assert_eq!(clearance(point, &Geometry::Rect(rect)), -1.0);
+        assert_eq!(
+            clearance(point, &Geometry::Point(Point::new(4.0, 1.0))),
+            3.0
+        );
+        let collection = GeometryCollection::new_from(vec![
+            Geometry::Point(Point::new(4.0, 1.0)),
+            Geometry::Polygon(Rect::new((-1.0, 3.0), (3.0, 5.0)).to_polygon()),
+        ]);
+        assert_eq!(
+            clearance(point, &Geometry::GeometryCollection(collection)),
+            2.0
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Options for, and results of, label position calculations
+
+#[cfg(feature = "geodesic")]
+use crate::geodesic;
+use crate::haversine;
+use geo::{AffineTransform, GeoFloat, Point};
+
+/// Options controlling a label position calculation
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_with_options, PolylabelOptions};
+/// use geo::Polygon;
+///
+/// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+/// let poly = Polygon::new(coords.into(), vec![]);
+///
+/// let options = PolylabelOptions::new(0.1).simplify(true);
+/// let label = polylabel_with_options(&poly, &options).unwrap();
+/// assert!(label.bound <= 0.1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolylabelOptions<T>
+where
+    T: GeoFloat,
+{
+    pub(crate) tolerance: T,
+    pub(crate) simplify: bool,
+    pub(crate) metric: Metric,
+    pub(crate) aspect_ratio: T,
+    pub(crate) antimeridian: bool,
+    pub(crate) period: (Option<T>, Option<T>),
+    pub(crate) transform: Option<AffineTransform<T>>,
+}
+
+impl<T> PolylabelOptions<T>
+where
+    T: GeoFloat,
+{
+    /// Default options, using the given tolerance
+    pub fn new(tolerance: T) -> Self {
+        Self {
+            tolerance,
+            simplify: false,
+            metric: Metric::Euclidean,
+            aspect_ratio: T::one(),
+            antimeridian: false,
+            period: (None, None),
+            transform: None,
+        }
+    }
+
+    /// Simplify the Polygon before searching it
+    ///
+    /// Rings are simplified using the Ramer–Douglas–Peucker algorithm, with an epsilon of a quarter of the
+    /// tolerance, and the search tolerance is halved to compensate, so the result is still within the
+    /// requested tolerance of the optimum. For detailed Polygons such as coastlines this is much faster.
+    pub fn simplify(mut self, simplify: bool) -> Self {
+        self.simplify = simplify;
+        self
+    }
+
+    /// Measure distances from the label position to the Polygon's edges using a metric
+    ///
+    /// With [`Metric::Haversine`], the tolerance, and the label's distance and bound, are in metres.
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Find the best position for a label which is `aspect_ratio` times wider than it is tall
+    ///
+    /// The best position for a circle isn't the best anchor for a wide text box, so vertical distances are
+    /// scaled by the aspect ratio, and the search finds the centre of the widest ellipse of that shape which
+    /// fits in the Polygon. The tolerance, and the label's distance and bound, are horizontal: the distance is
+    /// the ellipse's half-width. The aspect ratio only applies to the planar metrics: it's ignored for longitude
+    /// and latitude distances. With [`Metric::Chebyshev`], the search finds the centre of the largest rectangle of
+    /// that shape. The aspect ratio must be positive and finite, for any metric, or the search returns
+    /// [`PolylabelError::InvalidAspectRatio`](errors/enum.PolylabelError.html#variant.InvalidAspectRatio).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polylabel::{polylabel_with_options, PolylabelOptions};
+    /// use geo::{Point, Polygon};
+    ///
+    /// // a square with a wide arm to its right
+    /// let coords = vec![(0.0, 0.0), (20.0, 0.0), (20.0, 2.0), (3.0, 2.0), (3.0, 3.0), (0.0, 3.0), (0.0, 0.0)];
+    /// let poly = Polygon::new(coords.into(), vec![]);
+    ///
+    /// // a circle fits best in the square
+    /// let label = polylabel_with_options(&poly, &PolylabelOptions::new(0.01_f64)).unwrap();
+    /// assert!(label.point.x() < 3.0);
+    ///
+    /// // but a label five times wider than it is tall fits best along the arm
+    /// let options = PolylabelOptions::new(0.01_f64).aspect_ratio(5.0);
+    /// let label = polylabel_with_options(&poly, &options).unwrap();
+    /// assert!(label.point.x() > 3.0);
+    /// assert!(label.distance > 4.0);
+    /// ```
+    pub fn aspect_ratio(mut self, aspect_ratio: T) -> Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    /// Label longitude and latitude Polygons which cross the antimeridian (±180°) correctly
+    ///
+    /// A Polygon crossing the antimeridian has an edge spanning more than 180° of longitude. If it's detected,
+    /// the Polygon's western longitudes are shifted east by 360° so it's contiguous, and the label position is
+    /// shifted back into the range ±180°. With [`Parts::Union`](enum.Parts.html#variant.Union), a MultiPolygon
+    /// whose parts meet the antimeridian from both sides is shifted before its parts are dissolved, so features
+    /// split there are labelled as one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polylabel::{polylabel_with_options, Metric, PolylabelOptions};
+    /// use geo::{Point, Polygon};
+    ///
+    /// // a Polygon spanning 4° of longitude across the antimeridian
+    /// let coords = vec![(178.0, -18.0), (-178.0, -18.0), (-178.0, -16.0), (178.0, -16.0)];
+    /// let poly = Polygon::new(coords.into(), vec![]);
+    ///
+    /// let options = PolylabelOptions::new(100.0_f64).metric(Metric::Haversine);
+    /// let label = polylabel_with_options(&poly, &options.antimeridian(true)).unwrap();
+    /// assert!(label.point.x().abs() > 179.0);
+    /// ```
+    pub fn antimeridian(mut self, antimeridian: bool) -> Self {
+        self.antimeridian = antimeridian;
+        self
+    }
+
+    /// Label Polygons in a periodic domain, which wraps around in x and/or y with the given periods
+    ///
+    /// The domain starts at the origin, so x wraps around from `period_x` to 0, and likewise for y. A Polygon
+    /// straddling the domain's edge has its rings unwrapped, taking each edge to be the shorter way round, so
+    /// its label position is calculated correctly, and it's wrapped back into the domain. Polygons must be
+    /// smaller than the domain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polylabel::{polylabel_with_options, PolylabelOptions};
+    /// use geo::{Point, Polygon};
+    ///
+    /// // a 4 × 4 square straddling the right edge of a domain 10 wide
+    /// let coords = vec![(8.0, 0.0), (2.0, 0.0), (2.0, 4.0), (8.0, 4.0)];
+    /// let poly = Polygon::new(coords.into(), vec![]);
+    ///
+    /// let options = PolylabelOptions::new(0.1).periodic(Some(10.0), None);
+    /// let label = polylabel_with_options(&poly, &options).unwrap();
+    /// assert_eq!(label.point, Point::new(0.0, 2.0));
+    /// assert_eq!(label.distance, 2.0);
+    /// ```
+    pub fn periodic(mut self, period_x: Option<T>, period_y: Option<T>) -> Self {
+        self.period = (period_x, period_y);
+        self
+    }
+
+    /// Transform the Polygon using an affine transform before searching it, and the label position back
+    ///
+    /// Distances are measured in the transformed space, so this can search a rotated or sheared local
+    /// coordinate frame, or shape labels (e.g. rotating the Polygon to find the best position for a label
+    /// along an angle). The tolerance, and the label's distance and bound, are in the transformed space's
+    /// units. The transform must be invertible: otherwise, the calculation fails with
+    /// [`PolylabelError::NonInvertibleTransform`](errors/enum.PolylabelError.html#variant.NonInvertibleTransform).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polylabel::{polylabel_with_options, PolylabelOptions};
+    /// use geo::{AffineTransform, Point, Rect, Rotate};
+    ///
+    /// // an 8 × 2 rectangle, rotated by 45°
+    /// let origin = Point::new(0.0, 0.0);
+    /// let poly = Rect::new((0.0, 0.0), (8.0, 2.0)).to_polygon().rotate_around_point(45.0, origin);
+    ///
+    /// // the widest 4:1 label along the rectangle
+    /// let options = PolylabelOptions::new(0.01_f64)
+    ///     .transform(AffineTransform::rotate(-45.0, origin))
+    ///     .aspect_ratio(4.0);
+    /// let label = polylabel_with_options(&poly, &options).unwrap();
+    /// assert!((label.distance - 4.0).abs() < 0.01);
+    /// let centre = Point::new(3.0, 5.0) / 2f64.sqrt();
+    /// assert!((label.point.x() - centre.x()).abs() < 0.01 && (label.point.y() - centre.y()).abs() < 0.01);
+    /// ```
+    pub fn transform(mut self, transform: AffineTransform<T>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+}
+
+/// How distances from a label position to a Polygon's edges are measured
+///
+/// This enum is non-exhaustive: `Metric::Geodesic` only exists with the `geodesic` feature, and Cargo features
+/// are additive, so any crate in a build could add it. Matches on a `Metric` need a wildcard arm.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_with_options, Metric, PolylabelOptions};
+/// use geo::{Contains, Rect};
+///
+/// // 20° of longitude by 5° of latitude, in northern Scandinavia
+/// let poly = Rect::new((10.0, 65.0), (30.0, 70.0)).to_polygon();
+///
+/// // a tolerance of 1 km
+/// let options = PolylabelOptions::new(1000.0_f64).metric(Metric::Haversine);
+/// let label = polylabel_with_options(&poly, &options).unwrap();
+/// assert!(poly.contains(&label.point));
+/// // about half its height of 556 km, as 10° of longitude is longer than 2.5° of latitude even at 70°
+/// assert!(label.distance > 270_000.0 && label.distance < 280_000.0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Metric {
+    /// Straight-line distance, in the Polygon's units
+    #[default]
+    Euclidean,
+    /// Great-circle distance in metres, for Polygons whose coordinates are longitude and latitude in degrees
+    ///
+    /// Euclidean distances in degrees exaggerate east-west distances away from the equator, so they move label
+    /// positions towards the Polygon's north-south axis, increasingly so at high latitudes. Distances are
+    /// measured on a sphere with the Earth's mean radius, and each edge is treated as a great-circle arc. This
+    /// is slower than Euclidean distance, as edges aren't spatially indexed.
+    Haversine,
+    /// Geodesic distance in metres on the WGS84 ellipsoid, for Polygons whose coordinates are longitude and
+    /// latitude in degrees
+    ///
+    /// Spherical distances differ from ellipsoidal distances by up to 0.5 %, which matters for very large
+    /// Polygons such as exclusive economic zones or ice sheets. Each edge is treated as a geodesic, and
+    /// distances are calculated using [geographiclib](https://docs.rs/geographiclib-rs), accurate to
+    /// nanometres. This is much slower than [`Metric::Haversine`]: only the edges whose great-circle distance
+    /// is close to the nearest edge's are measured on the ellipsoid. Enable the `geodesic` feature to use it.
+    #[cfg(feature = "geodesic")]
+    Geodesic,
+    /// Chebyshev (L∞) distance, the greater of the horizontal and vertical distances, in the Polygon's units
+    ///
+    /// The label position is the centre of the largest axis-aligned square which fits in the Polygon, which is a
+    /// better anchor for a rectangular label than the largest circle's centre. Combine it with an
+    /// [aspect ratio](struct.PolylabelOptions.html#method.aspect_ratio) to find the largest rectangle of a
+    /// label's shape. Edges aren't spatially indexed.
+    Chebyshev,
+    /// Manhattan (L1) distance, the sum of the horizontal and vertical distances, in the Polygon's units
+    ///
+    /// The label position is the centre of the largest square rotated by 45° which fits in the Polygon. Edges
+    /// aren't spatially indexed.
+    Manhattan,
+}
+
+impl Metric {
+    /// The greatest length of a degree of a meridian or the equator in metres, if distances are in metres
+    pub(crate) fn degree<T: GeoFloat>(self) -> Option<T> {
+        match self {
+            Metric::Euclidean | Metric::Chebyshev | Metric::Manhattan => None,
+            Metric::Haversine => Some(haversine::metres_per_degree()),
+            #[cfg(feature = "geodesic")]
+            Metric::Geodesic => Some(geodesic::max_metres_per_degree()),
+        }
+    }
+}
+
+/// A calculated label position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Label<T>
+where
+    T: GeoFloat,
+{
+    /// The label position
+    pub point: Point<T>,
+    /// The distance from the label position to the Polygon's nearest edge: the radius of the largest
+    /// circle centred on the label position which fits inside the Polygon
+    pub distance: T,
+    /// An upper bound on how much further from the Polygon's edges the true pole of inaccessibility may be
+    ///
+    /// This is never more than the tolerance, and is often much less.
+    pub bound: T,
+}
+
+/// Statistics about a label position search, e.g. to find the Polygons which are slowest to label
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// The number of cells taken from the queue and examined
+    pub cells: usize,
+    /// The greatest number of cells in the queue at once
+    pub max_queue: usize,
+}
@@ -0,0 +1,109 @@
+//! Parallel refinement of a single Polygon's label position, using [rayon](https://docs.rs/rayon)
+//!
+//! Enable the `rayon` feature to use this functionality.
+
+use crate::errors::PolylabelError;
+use crate::prepared::{PreparedPolygon, Start};
+use crate::Qcell;
+use geo::{GeoFloat, Point, Polygon};
+use num_traits::FromPrimitive;
+use rayon::prelude::*;
+use std::iter::Sum;
+
+// Number of queued cells refined per thread in each round
+const CELLS_PER_THREAD: usize = 16;
+
+/// Calculate a Polygon's ideal label position, refining the search in parallel
+///
+/// Rather than refining one cell at a time, the most promising cells are taken from the queue in
+/// batches and their subdivisions are evaluated concurrently on rayon's global thread pool. This is
+/// only worthwhile for very large Polygons: for most inputs [`polylabel`](fn.polylabel.html) is
+/// faster, and [`polylabel_batch`](fn.polylabel_batch.html) is a better way to use many cores.
+///
+/// The result is within `tolerance` of the optimum, as with `polylabel`, but because cells are
+/// visited in a different order it may not be identical to `polylabel`'s result.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_parallel;
+/// use geo::{Contains, Polygon};
+///
+/// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+/// let poly = Polygon::new(coords.into(), vec![]);
+///
+/// let label_position = polylabel_parallel(&poly, &0.1).unwrap();
+/// assert!(poly.contains(&label_position));
+/// ```
+pub fn polylabel_parallel<T>(
+    polygon: &Polygon<T>,
+    tolerance: &T,
+) -> Result<Point<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum + Send + Sync,
+{
+    let prepared = PreparedPolygon::new(polygon)?;
+    let index = &prepared.index;
+    let tolerance = prepared.tolerance(*tolerance);
+    let (mut best_cell, mut cell_queue) = match prepared.start() {
+        Start::Degenerate(point) => return Ok(point),
+        Start::Search(best_cell, cell_queue) => (best_cell, cell_queue),
+    };
+    let two = T::one() + T::one();
+    let batch_size = rayon::current_num_threads() * CELLS_PER_THREAD;
+    let mut batch: Vec<Qcell<T>> = Vec::with_capacity(batch_size);
+
+    loop {
+        // Take the most promising cells, updating the best cell as we go
+        batch.clear();
+        while batch.len() < batch_size {
+            let Some(cell) = cell_queue.pop() else { break };
+            if cell.distance > best_cell.distance {
+                best_cell = cell;
+            }
+            batch.push(cell);
+        }
+        if batch.is_empty() {
+            break;
+        }
+
+        // Subdivide every cell that could still contain a better solution
+        let best_distance = best_cell.distance;
+        let children: Vec<Qcell<T>> = batch
+            .par_iter()
+            .filter(|cell| cell.max_distance - best_distance > tolerance)
+            .flat_map_iter(|cell| cell.quad(cell.half_extent / two, index))
+            .filter(Qcell::may_be_inside)
+            .collect();
+        cell_queue.extend(children);
+        cell_queue.prune(best_cell.distance);
+    }
+
+    Ok(best_cell.centroid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::polylabel_parallel;
+    use crate::polylabel;
+    use geo::prelude::*;
+    use geo::{Euclidean, Polygon};
+
+    #[test]
+    // the parallel result must be as good as the serial result, within the tolerance
+    fn test_parallel() {
+        for (coords, tolerance) in [
+            (include!("../tests/fixtures/poly1.rs"), 10.0),
+            (include!("../tests/fixtures/poly2.rs"), 1.0),
+            (include!("../tests/fixtures/poly3.rs"), 0.001),
+        ] {
+            let poly = Polygon::new(coords.into(), vec![]);
+            let serial = polylabel(&poly, &tolerance).unwrap();
+            let parallel = polylabel_parallel(&poly, &tolerance).unwrap();
+            assert!(poly.contains(&parallel));
+            let serial_distance: f64 = Euclidean::distance(&serial, poly.exterior());
+            let parallel_distance = Euclidean::distance(&parallel, poly.exterior());
+            assert!((serial_distance - parallel_distance).abs() <= tolerance);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Label positions which reflect cartographic preferences, as well as the Polygon's shape
+//!
+//! A penalty, e.g. for high elevation or population density, is subtracted from the distance to the Polygon's
+//! edges. The search's bound on the score within a cell holds if the cell's radius is scaled by the penalty's
+//! greatest rate of change, so the penalty's slope must be known: a raster's is calculated from its values.
+
+use crate::errors::PolylabelError;
+use crate::options::Label;
+use crate::prepared::{search_field, PreparedPolygon};
+use crate::DistanceField;
+use geo::{Coord, GeoFloat, Point, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+/// Calculate a Polygon's ideal label position, penalising some areas of the Polygon
+///
+/// The search maximises the distance from the Polygon's edges less `penalty`, which should be positive where
+/// positions are less desirable. `max_slope` is the penalty's greatest rate of change: how much it can differ
+/// between two points, per unit of distance between them. If it's too small, the search may discard the cell
+/// holding the ideal label position, and if it's larger than necessary, the search is slower. Areas whose
+/// penalty is at least their distance from the Polygon's edges aren't searched.
+///
+/// The label's distance is its penalised score, and its bound is on the greatest score, all in the Polygon's
+/// units.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_with_penalty;
+/// use geo::{Point, Rect};
+///
+/// let strip = Rect::new((0.0, 0.0), (10.0, 2.0)).to_polygon();
+///
+/// // prefer positions further west, where the penalty is lower
+/// let label = polylabel_with_penalty(&strip, &0.01, |point: Point<f64>| point.x() * 0.1, 0.1).unwrap();
+/// assert!(label.point.x() < 1.5);
+/// ```
+pub fn polylabel_with_penalty<T, F>(
+    polygon: &Polygon<T>,
+    tolerance: &T,
+    penalty: F,
+    max_slope: T,
+) -> Result<Label<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    F: Fn(Point<T>) -> T,
+{
+    let field = PenaltyField {
+        prepared: PreparedPolygon::new(polygon)?,
+        penalty,
+        max_slope: max_slope.max(T::zero()),
+    };
+    search_field(polygon, &field, *tolerance).map(|(label, _)| label)
+}
+
+/// A grid of penalties, e.g. elevations or population densities, for
+/// [`polylabel_with_penalty`](fn.polylabel_with_penalty.html)
+///
+/// Each value is the penalty at the centre of a square cell, and the penalty between cell centres is bilinearly
+/// interpolated. Beyond the grid's outermost cell centres, the nearest edge's penalties are extended.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{polylabel_with_penalty, PenaltyRaster};
+/// use geo::{Coord, Rect};
+///
+/// let strip = Rect::new((0.0, 0.0), (10.0, 2.0)).to_polygon();
+///
+/// // a 2 × 1 grid covering the strip, penalising its eastern half
+/// let raster = PenaltyRaster::new(Coord { x: 0.0, y: -4.0 }, 5.0, 2, vec![0.0, 0.5]).unwrap();
+/// assert_eq!(raster.sample(Coord { x: 5.0, y: 1.0 }.into()), 0.25);
+///
+/// let label = polylabel_with_penalty(&strip, &0.01, |p| raster.sample(p), raster.max_slope()).unwrap();
+/// assert!(label.point.x() < 5.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PenaltyRaster<T>
+where
+    T: GeoFloat,
+{
+    origin: Coord<T>,
+    cell_size: T,
+    columns: usize,
+    values: Vec<T>,
+}
+
+impl<T> PenaltyRaster<T>
+where
+    T: GeoFloat,
+{
+    /// A grid of penalties whose cells are `cell_size` wide, with its lower left corner at `origin`
+    ///
+    /// `values` holds the penalties of `columns` cells in each row, starting with the lowest row. Returns `None`
+    /// if there are no values, they don't fill a whole number of rows, or the cell size isn't positive and finite.
+    pub fn new(origin: Coord<T>, cell_size: T, columns: usize, values: Vec<T>) -> Option<Self> {
+        let valid = columns > 0
+            && !values.is_empty()
+            && values.len() % columns == 0
+            && cell_size > T::zero()
+            && cell_size.is_finite();
+        valid.then_some(Self {
+            origin,
+            cell_size,
+            columns,
+            values,
+        })
+    }
+
+    /// The penalty at a point, bilinearly interpolated between the nearest cell centres
+    pub fn sample(&self, point: Point<T>) -> T {
+        let rows = self.values.len() / self.columns;
+        let half = T::from(0.5).unwrap();
+        // a point's position in cells, relative to the first cell's centre
+        let position = |offset: T, count: usize| -> (usize, T) {
+            let last = T::from(count - 1).unwrap();
+            let position = (offset / self.cell_size - half).max(T::zero()).min(last);
+            let index = position.floor().to_usize().unwrap_or(0).min(count - 1);
+            (index, position - T::from(index).unwrap())
+        };
+        let (column, x) = position(point.x() - self.origin.x, self.columns);
+        let (row, y) = position(point.y() - self.origin.y, rows);
+        let value = |column: usize, row: usize| {
+            self.values[row.min(rows - 1) * self.columns + column.min(self.columns - 1)]
+        };
+        let lerp = |a: T, b: T, t: T| a + (b - a) * t;
+        lerp(
+            lerp(value(column, row), value(column + 1, row), x),
+            lerp(value(column, row + 1), value(column + 1, row + 1), x),
+            y,
+        )
+    }
+
+    /// The penalty's greatest rate of change, for use as
+    /// [`polylabel_with_penalty`](fn.polylabel_with_penalty.html)'s `max_slope`
+    pub fn max_slope(&self) -> T {
+        let horizontal = self
+            .values
+            .chunks(self.columns)
+            .flat_map(|row| row.windows(2).map(|pair| (pair[1] - pair[0]).abs()))
+            .fold(T::zero(), T::max);
+        let vertical = self
+            .values
+            .iter()
+            .zip(self.values.iter().skip(self.columns))
+            .map(|(&a, &b)| (b - a).abs())
+            .fold(T::zero(), T::max);
+        // the interpolated penalty's gradient is no steeper than the steepest differences in each direction
+        horizontal.hypot(vertical) / self.cell_size
+    }
+}
+
+/// A Polygon's signed distance field, less a penalty
+struct PenaltyField<T, F>
+where
+    T: GeoFloat,
+{
+    prepared: PreparedPolygon<T>,
+    penalty: F,
+    max_slope: T,
+}
+
+impl<T, F> DistanceField<T> for PenaltyField<T, F>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    F: Fn(Point<T>) -> T,
+{
+    fn signed_distance(&self, point: Point<T>) -> T {
+        self.prepared.signed_distance(point) - (self.penalty)(point)
+    }
+
+    fn cell_radius(&self, _: Point<T>, half_extent: T) -> T {
+        // the distance and the penalty can both change across the cell
+        half_extent * (T::one() + T::one()).sqrt() * (T::one() + self.max_slope)
+    }
+
+    fn length(&self, _: Point<T>, length: T) -> T {
+        length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{polylabel_with_penalty, PenaltyRaster};
+    use geo::{Contains, Coord, Point, Rect};
+
+    #[test]
+    fn test_polylabel_with_penalty() {
+        let square = Rect::new((0.0_f64, 0.0), (10.0, 10.0)).to_polygon();
+        // no penalty
+        let label = polylabel_with_penalty(&square, &0.01, |_| 0.0, 0.0).unwrap();
+        assert!((label.distance - 5.0).abs() <= 0.01);
+        // a penalty peaking at the centre pushes the label towards a corner's diagonal
+        let centre = Point::new(5.0, 5.0);
+        let penalty = |p: Point<f64>| (3.0 - (p.x() - 5.0).hypot(p.y() - 5.0)).max(0.0);
+        let label = polylabel_with_penalty(&square, &0.01, penalty, 1.0).unwrap();
+        assert!(square.contains(&label.point));
+        assert!((label.point.x() - centre.x()).hypot(label.point.y() - centre.y()) > 1.0);
+        // a brute-force search agrees
+        let best = (0..=100)
+            .flat_map(|i| (0..=100).map(move |j| Point::new(i as f64 / 10.0, j as f64 / 10.0)))
+            .map(|p| {
+                let edge = p.x().min(p.y()).min(10.0 - p.x()).min(10.0 - p.y());
+                edge - penalty(p)
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert!(label.distance >= best - 0.01 && label.distance <= best + 0.05);
+    }
+
+    #[test]
+    fn test_raster() {
+        // a 3 × 2 grid of 2-unit cells
+        let origin = Coord { x: 1.0_f64, y: 1.0 };
+        let raster =
+            PenaltyRaster::new(origin, 2.0, 3, vec![0.0, 1.0, 4.0, 2.0, 3.0, 6.0]).unwrap();
+        // cell centres, between them, and beyond the grid
+        assert_eq!(raster.sample(Point::new(2.0, 2.0)), 0.0);
+        assert_eq!(raster.sample(Point::new(6.0, 4.0)), 6.0);
+        assert_eq!(raster.sample(Point::new(3.0, 3.0)), 1.5);
+        assert_eq!(raster.sample(Point::new(-10.0, 20.0)), 2.0);
+        assert_eq!(raster.sample(Point::new(5.0, 2.0)), 2.5);
+        // the steepest differences are 3 across and 2 up, over 2 units
+        assert_eq!(raster.max_slope(), 13.0_f64.sqrt() / 2.0);
+        // invalid grids
+        assert!(PenaltyRaster::new(origin, 2.0, 3, vec![0.0; 4]).is_none());
+        assert!(PenaltyRaster::new(origin, 0.0, 3, vec![0.0; 3]).is_none());
+        assert!(PenaltyRaster::<f64>::new(origin, 2.0, 0, vec![]).is_none());
+        // a single cell
+        let single = PenaltyRaster::new(origin, 2.0, 1, vec![7.0]).unwrap();
+        assert_eq!(single.sample(Point::new(100.0, -3.0)), 7.0);
+        assert_eq!(single.max_slope(), 0.0);
+    }
+}
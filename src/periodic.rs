@@ -0,0 +1,100 @@
+//! Polygons in periodic domains, such as simulations with periodic boundary conditions
+//!
+//! A Polygon straddling the domain's edge wraps around to its opposite side, so its coordinates jump by nearly
+//! a period. Unwrapping its rings makes it contiguous: each edge is taken to be the shorter way round the domain.
+//! Its interior's distance from its own edges is less than from those of its periodic images, which lie outside
+//! it, so it can then be labelled as usual, and the label position wrapped back into the domain.
+
+use geo::{Coord, GeoFloat, LineString, Point, Polygon};
+
+/// Unwrap a Polygon's rings in each periodic dimension, keeping its interiors beside its exterior
+pub(crate) fn unwrap<T: GeoFloat>(
+    polygon: &Polygon<T>,
+    period: (Option<T>, Option<T>),
+) -> Polygon<T> {
+    let exterior = unwrap_ring(polygon.exterior(), period);
+    let interiors = polygon
+        .interiors()
+        .iter()
+        .map(|ring| {
+            let ring = unwrap_ring(ring, period);
+            // move each interior by whole periods to the copy nearest the exterior's first vertex
+            match (ring.0.first(), exterior.0.first()) {
+                (Some(&start), Some(&target)) => {
+                    let shift = Coord {
+                        x: nearest_shift(start.x, target.x, period.0),
+                        y: nearest_shift(start.y, target.y, period.1),
+                    };
+                    ring.0.iter().map(|&c| c + shift).collect()
+                }
+                _ => ring,
+            }
+        })
+        .collect();
+    Polygon::new(exterior, interiors)
+}
+
+/// Wrap a point into the domain, which starts at the origin
+pub(crate) fn wrap<T: GeoFloat>(point: Point<T>, period: (Option<T>, Option<T>)) -> Point<T> {
+    let wrap = |value: T, period: Option<T>| match period {
+        Some(period) => value - (value / period).floor() * period,
+        None => value,
+    };
+    Point::new(wrap(point.x(), period.0), wrap(point.y(), period.1))
+}
+
+// remove the jumps between consecutive vertices which are more than half a period apart
+fn unwrap_ring<T: GeoFloat>(ring: &LineString<T>, period: (Option<T>, Option<T>)) -> LineString<T> {
+    let mut previous: Option<Coord<T>> = None;
+    ring.0
+        .iter()
+        .map(|&c| {
+            let unwrapped = match previous {
+                None => c,
+                Some(previous) => Coord {
+                    x: c.x + nearest_shift(c.x, previous.x, period.0),
+                    y: c.y + nearest_shift(c.y, previous.y, period.1),
+                },
+            };
+            previous = Some(unwrapped);
+            unwrapped
+        })
+        .collect()
+}
+
+// the whole number of periods which moves a value nearest to a target
+fn nearest_shift<T: GeoFloat>(value: T, target: T, period: Option<T>) -> T {
+    match period {
+        Some(period) => ((target - value) / period).round() * period,
+        None => T::zero(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unwrap, wrap};
+    use geo::{Area, Point, Polygon};
+
+    #[test]
+    fn test_unwrap() {
+        // a square straddling the right edge of a domain 10 wide, with a hole on the far side
+        let exterior = vec![(8.0, 0.0), (2.0, 0.0), (2.0, 4.0), (8.0, 4.0)];
+        let hole = vec![(1.0, 1.0), (1.0, 2.0), (0.5, 2.0), (0.5, 1.0)];
+        let poly = Polygon::new(exterior.into(), vec![hole.into()]);
+        let unwrapped = unwrap(&poly, (Some(10.0), None));
+        assert_eq!(unwrapped.exterior()[1].x, 12.0);
+        assert_eq!(unwrapped.interiors()[0][0].x, 11.0);
+        assert_eq!(unwrapped.unsigned_area(), 16.0 - 0.5);
+        // Polygons which don't straddle an edge are unchanged
+        assert_eq!(unwrap(&unwrapped, (Some(10.0), None)), unwrapped);
+        assert_eq!(unwrap(&poly, (None, Some(10.0))), poly);
+        assert_eq!(
+            wrap(Point::new(11.0, -1.0), (Some(10.0), Some(5.0))),
+            Point::new(1.0, 4.0)
+        );
+        assert_eq!(
+            wrap(Point::new(11.0, -1.0), (None, None)),
+            Point::new(11.0, -1.0)
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Label placement across neighbouring Polygons, so that their labels don't overlap
+//!
+//! Polygons are labelled in order of their poles of inaccessibility's distances, so larger Polygons claim their
+//! ideal label positions first. Each later label is nudged to the point furthest from both its Polygon's edges and
+//! the boxes of the labels already placed, and is suppressed if its box can't be placed without overlapping them.
+
+use crate::errors::PolylabelError;
+use crate::metric::polylabel_with_distance_fn;
+use crate::prepared::PreparedPolygon;
+use geo::{GeoFloat, Point, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+/// A label's placement, calculated by [`place_labels`](fn.place_labels.html)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement<T>
+where
+    T: GeoFloat,
+{
+    /// The centre of the label's box
+    pub point: Point<T>,
+    /// Whether the label's box can't be placed inside its Polygon without overlapping another label's box
+    ///
+    /// A suppressed label's point is its Polygon's pole of inaccessibility.
+    pub suppressed: bool,
+}
+
+/// Place labels of the given sizes inside neighbouring Polygons, so their boxes don't overlap
+///
+/// `label_sizes` holds the width and height of each Polygon's label, whose box is centred on its placement. A label
+/// whose box doesn't overlap those already placed is placed at its Polygon's pole of inaccessibility. Otherwise,
+/// it's nudged to the point furthest from its Polygon's edges and the boxes of labels already placed, or
+/// suppressed if its box would overlap them wherever its centre is in the Polygon. The placements are in the same
+/// order as the Polygons.
+///
+/// `label_sizes` must hold a size for each Polygon, or
+/// [`PolylabelError::LabelSizeMismatch`](errors/enum.PolylabelError.html#variant.LabelSizeMismatch) is returned.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::place_labels;
+/// use geo::Rect;
+///
+/// // two neighbouring squares, whose labels overlap if they're centred in them
+/// let west = Rect::new((0.0, 0.0), (4.0, 4.0)).to_polygon();
+/// let east = Rect::new((4.0, 0.0), (8.0, 4.0)).to_polygon();
+///
+/// let placements = place_labels(&[west, east], &[(6.0, 1.0), (6.0_f64, 1.0)], &0.01).unwrap();
+/// assert!(!placements[0].suppressed && !placements[1].suppressed);
+/// // the second label is nudged above or below the first
+/// assert!((placements[1].point.y() - placements[0].point.y()).abs() >= 1.0);
+/// ```
+pub fn place_labels<T>(
+    polygons: &[Polygon<T>],
+    label_sizes: &[(T, T)],
+    tolerance: &T,
+) -> Result<Vec<Placement<T>>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    if polygons.len() != label_sizes.len() {
+        return Err(PolylabelError::LabelSizeMismatch);
+    }
+    let prepared = polygons
+        .iter()
+        .map(PreparedPolygon::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let poles: Vec<_> = prepared.iter().map(|p| p.label(tolerance)).collect();
+    let mut order: Vec<usize> = (0..polygons.len()).collect();
+    order.sort_by(|&a, &b| poles[b].distance.total_cmp(&poles[a].distance));
+
+    let mut placements: Vec<_> = poles
+        .iter()
+        .map(|pole| Placement {
+            point: pole.point,
+            suppressed: true,
+        })
+        .collect();
+    // the centres and sizes of the boxes placed so far
+    let mut placed: Vec<(Point<T>, (T, T))> = Vec::with_capacity(polygons.len());
+    for i in order {
+        let size = label_sizes[i];
+        // the distance to the nearest edge or box, which is positive only where the box fits
+        let clearance = |point: Point<T>| {
+            placed
+                .iter()
+                .map(|&(centre, other)| gap(point, size, centre, other))
+                .fold(prepared[i].signed_distance(point), T::min)
+        };
+        let point = if clearance(poles[i].point) > T::zero() {
+            poles[i].point
+        } else {
+            let label = polylabel_with_distance_fn(&polygons[i], tolerance, clearance)?;
+            if label.distance <= T::zero() {
+                continue;
+            }
+            label.point
+        };
+        placements[i] = Placement {
+            point,
+            suppressed: false,
+        };
+        placed.push((point, size));
+    }
+    Ok(placements)
+}
+
+// the Chebyshev distance between two boxes, given their centres and sizes, which is negative if they overlap
+fn gap<T: GeoFloat>(a: Point<T>, a_size: (T, T), b: Point<T>, b_size: (T, T)) -> T {
+    let two = T::one() + T::one();
+    let x = (a.x() - b.x()).abs() - (a_size.0 + b_size.0) / two;
+    let y = (a.y() - b.y()).abs() - (a_size.1 + b_size.1) / two;
+    x.max(y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gap, place_labels};
+    use crate::errors::PolylabelError;
+    use crate::polylabel;
+    use geo::{Contains, Point, Rect};
+
+    #[test]
+    fn test_gap() {
+        let a = Point::new(0.0_f64, 0.0);
+        assert_eq!(gap(a, (2.0, 2.0), Point::new(5.0, 0.0), (2.0, 4.0)), 3.0);
+        assert_eq!(gap(a, (2.0, 2.0), Point::new(1.0, 1.0), (2.0, 2.0)), -1.0);
+    }
+
+    #[test]
+    fn test_place_labels() {
+        // a large square, and two smaller squares along its eastern edge
+        let polygons = [
+            Rect::new((0.0_f64, 0.0), (10.0, 10.0)).to_polygon(),
+            Rect::new((10.0, 0.0), (14.0, 4.0)).to_polygon(),
+            Rect::new((10.0, 4.0), (14.0, 8.0)).to_polygon(),
+        ];
+        // the large square's label claims its pole, and the others are nudged clear of it and each other
+        let sizes = [(8.0, 2.0), (6.0, 1.0), (6.0, 1.0)];
+        let placements = place_labels(&polygons, &sizes, &0.01).unwrap();
+        assert_eq!(placements[0].point, polylabel(&polygons[0], &0.01).unwrap());
+        for (i, a) in placements.iter().enumerate() {
+            assert!(!a.suppressed && polygons[i].contains(&a.point));
+            for (j, b) in placements.iter().enumerate().skip(i + 1) {
+                assert!(gap(a.point, sizes[i], b.point, sizes[j]) >= 0.0);
+            }
+        }
+        // labels which don't overlap aren't nudged
+        let sizes = [(1.0, 1.0), (1.0, 1.0), (1.0, 1.0)];
+        let placements = place_labels(&polygons, &sizes, &0.01).unwrap();
+        for (polygon, placement) in polygons.iter().zip(&placements) {
+            assert_eq!(placement.point, polylabel(polygon, &0.01).unwrap());
+        }
+        // a label which can't avoid the large square's is suppressed, and its pole is returned
+        let sizes = [(9.0, 9.0), (10.0, 1.0), (2.0, 2.0)];
+        let placements = place_labels(&polygons, &sizes, &0.01).unwrap();
+        assert!(placements[1].suppressed && !placements[2].suppressed);
+        assert_eq!(placements[1].point, polylabel(&polygons[1], &0.01).unwrap());
+        // each Polygon needs a label size
+        assert_eq!(
+            place_labels(&polygons, &sizes[..2], &0.01),
+            Err(PolylabelError::LabelSizeMismatch)
+        );
+    }
+}
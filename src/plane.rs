@@ -0,0 +1,216 @@
+//! Label positions for planar Polygons in 3D space, such as CAD faces or geological surfaces
+//!
+//! The Polygon's plane is fitted using Newell's method, which is robust to concave rings and to vertices lying
+//! slightly off the plane. Its vertices are projected onto an orthonormal basis of the plane, so distances within
+//! it are unchanged, the label position is calculated in 2D, and then mapped back into 3D.
+
+use crate::errors::PolylabelError;
+use crate::options::PolylabelOptions;
+use crate::polylabel_with_options;
+use geo::{Coord, GeoFloat, LineString, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+type Vector<T> = [T; 3];
+
+/// A calculated label position for a planar Polygon in 3D space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlanarLabel<T>
+where
+    T: GeoFloat,
+{
+    /// The label position, on the Polygon's fitted plane
+    pub point: [T; 3],
+    /// The label position's distance from the Polygon's edges, within the plane
+    pub distance: T,
+    /// An upper bound on how much greater the ideal label position's distance could be
+    pub bound: T,
+    /// The greatest distance of any of the Polygon's vertices from the fitted plane, showing how planar it is
+    pub deviation: T,
+}
+
+/// Calculate the ideal label position of a planar Polygon in 3D space
+///
+/// `exterior` and `interiors` are the Polygon's rings, whose vertices lie (approximately) on a plane. The label
+/// position is calculated on the plane that fits them best, with distances and the tolerance in the vertices'
+/// units. A Polygon whose vertices don't span a plane has no area, and is labelled at its first vertex.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_3d;
+///
+/// // a 4 × 2 rectangle, tilted 45° about the x-axis
+/// let s = 2f64.sqrt();
+/// let exterior = [[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [4.0, s, s], [0.0, s, s]];
+/// let label = polylabel_3d(&exterior, &[], &0.01).unwrap();
+/// let [x, y, z] = label.point;
+/// assert!((x - 2.0).abs() < 0.01 && (y - s / 2.0).abs() < 0.01 && (z - s / 2.0).abs() < 0.01);
+/// assert!((label.distance - 1.0).abs() < 1e-9);
+/// assert!(label.deviation < 1e-9);
+/// ```
+pub fn polylabel_3d<T>(
+    exterior: &[[T; 3]],
+    interiors: &[Vec<[T; 3]>],
+    tolerance: &T,
+) -> Result<PlanarLabel<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    let rings = || std::iter::once(exterior).chain(interiors.iter().map(Vec::as_slice));
+    let vertices = || rings().flatten();
+    // Newell's normal: the sum of the cross products of each ring's consecutive vertices
+    let normal = rings()
+        .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+        .fold([T::zero(); 3], |normal, (&a, &b)| add(normal, cross(a, b)));
+    let length = norm(normal);
+    let count = T::from(vertices().count()).unwrap();
+    if length == T::zero() || count == T::zero() {
+        return Ok(PlanarLabel {
+            point: exterior.first().copied().unwrap_or([T::zero(); 3]),
+            distance: T::zero(),
+            bound: T::zero(),
+            deviation: T::zero(),
+        });
+    }
+    let normal = scale(normal, T::one() / length);
+    let origin = scale(
+        vertices().fold([T::zero(); 3], |sum, &vertex| add(sum, vertex)),
+        T::one() / count,
+    );
+    let (u, v) = basis(normal);
+    let project = |ring: &[[T; 3]]| -> LineString<T> {
+        ring.iter()
+            .map(|&vertex| {
+                let offset = sub(vertex, origin);
+                Coord {
+                    x: dot(offset, u),
+                    y: dot(offset, v),
+                }
+            })
+            .collect()
+    };
+    let polygon = Polygon::new(
+        project(exterior),
+        interiors.iter().map(|ring| project(ring)).collect(),
+    );
+    let label = polylabel_with_options(&polygon, &PolylabelOptions::new(*tolerance))?;
+    let point = add(
+        origin,
+        add(scale(u, label.point.x()), scale(v, label.point.y())),
+    );
+    let deviation = vertices()
+        .map(|&vertex| dot(sub(vertex, origin), normal).abs())
+        .fold(T::zero(), T::max);
+    Ok(PlanarLabel {
+        point,
+        distance: label.distance,
+        bound: label.bound,
+        deviation,
+    })
+}
+
+// two unit vectors which, with the unit normal, form a right-handed orthonormal basis, so rings keep their
+// orientation when they're projected onto the plane
+fn basis<T: GeoFloat>(normal: Vector<T>) -> (Vector<T>, Vector<T>) {
+    // the axis least aligned with the normal
+    let axis = if normal[0].abs() <= normal[1].abs() && normal[0].abs() <= normal[2].abs() {
+        [T::one(), T::zero(), T::zero()]
+    } else if normal[1].abs() <= normal[2].abs() {
+        [T::zero(), T::one(), T::zero()]
+    } else {
+        [T::zero(), T::zero(), T::one()]
+    };
+    let u = cross(axis, normal);
+    let u = scale(u, T::one() / norm(u));
+    (u, cross(normal, u))
+}
+
+fn add<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> Vector<T> {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> Vector<T> {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale<T: GeoFloat>(a: Vector<T>, factor: T) -> Vector<T> {
+    a.map(|component| component * factor)
+}
+
+fn cross<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> Vector<T> {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot<T: GeoFloat>(a: Vector<T>, b: Vector<T>) -> T {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn norm<T: GeoFloat>(a: Vector<T>) -> T {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{basis, cross, dot, polylabel_3d};
+    use crate::polylabel;
+    use geo::Polygon;
+
+    #[test]
+    fn test_basis() {
+        for normal in [[0.0_f64, 0.0, 1.0], [1.0, 0.0, 0.0], [0.6, 0.0, -0.8]] {
+            let (u, v) = basis(normal);
+            assert!(dot(u, v).abs() < 1e-12 && dot(u, normal).abs() < 1e-12);
+            assert!((dot(u, u) - 1.0).abs() < 1e-12 && (dot(v, v) - 1.0).abs() < 1e-12);
+            let w = cross(u, v);
+            assert!((0..3).all(|i| (w[i] - normal[i]).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn test_polylabel_3d() {
+        // an L shape in the plane z = 5, matches its 2D label position
+        let coords = vec![
+            (0.0_f64, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+            (0.0, 0.0),
+        ];
+        let flat = polylabel(&Polygon::new(coords.clone().into(), vec![]), &0.001).unwrap();
+        let exterior: Vec<_> = coords.iter().map(|&(x, y)| [x, y, 5.0]).collect();
+        let label = polylabel_3d(&exterior, &[], &0.001).unwrap();
+        assert!(
+            (label.point[0] - flat.x()).abs() < 0.01 && (label.point[1] - flat.y()).abs() < 0.01
+        );
+        assert!((label.point[2] - 5.0).abs() < 1e-9);
+        // the vertical plane x = 2, with a hole, and one vertex slightly off the plane
+        let exterior = [
+            [2.0_f64, 0.0, 0.0],
+            [2.0, 10.0, 0.0],
+            [2.0, 10.0, 10.0],
+            [2.0, 0.0, 10.0],
+        ];
+        let hole = vec![
+            [2.0, 1.0, 1.0],
+            [2.0, 1.0, 5.0],
+            [2.001, 5.0, 5.0],
+            [2.0, 5.0, 1.0],
+        ];
+        let label = polylabel_3d(&exterior, &[hole], &0.01).unwrap();
+        assert!((label.point[0] - 2.0).abs() < 0.001);
+        assert!(label.point[1] > 5.0 && label.point[2] > 5.0);
+        assert!(label.deviation > 0.0 && label.deviation < 0.001);
+        // collinear vertices don't span a plane
+        let line = [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [2.0, 2.0, 2.0]];
+        let label = polylabel_3d(&line, &[], &0.1).unwrap();
+        assert_eq!(label.point, [0.0, 0.0, 0.0]);
+        assert_eq!(label.distance, 0.0);
+    }
+}
@@ -0,0 +1,179 @@
+//! An algorithm trait for calculating label positions, in the style of `geo`'s algorithm traits
+
+use crate::errors::PolylabelError;
+use crate::multi::largest;
+use crate::polylabel;
+use geo::{GeoFloat, Geometry, GeometryCollection, MultiPolygon, Point, Polygon, Rect, Triangle};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+/// Calculation of a geometry's pole of inaccessibility: the optimum label position
+///
+/// This is implemented for the polygonal `geo` types, and for `Geometry` and `GeometryCollection`, so it can be
+/// used in generic code alongside `geo`'s `Centroid` and `InteriorPoint`. Polygons, Rects, and Triangles
+/// always have a pole of inaccessibility. The other types may not, as they can be empty, or have no polygonal
+/// parts, so their output is optional. A MultiPolygon's pole of inaccessibility is that of its part with the
+/// largest area, and a `GeometryCollection`'s is that of its polygonal member (or member's part) with the
+/// largest area.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::PoleOfInaccessibility;
+/// use geo::{Geometry, MultiPolygon, Point, Polygon, Rect};
+///
+/// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+/// let poly = Polygon::new(coords.into(), vec![]);
+/// assert_eq!(poly.pole_of_inaccessibility(&0.1).unwrap(), Point::new(0.5625, 0.5625));
+///
+/// let multi = MultiPolygon::new(vec![poly, Rect::new((10.0, 10.0), (14.0, 14.0)).to_polygon()]);
+/// assert_eq!(multi.pole_of_inaccessibility(&0.1).unwrap(), Some(Point::new(12.0, 12.0)));
+///
+/// let point = Geometry::Point(Point::new(1.0, 1.0));
+/// assert_eq!(point.pole_of_inaccessibility(&0.1).unwrap(), None);
+/// ```
+pub trait PoleOfInaccessibility<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output;
+
+    /// Calculate the geometry's pole of inaccessibility using the given tolerance
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output;
+}
+
+impl<T> PoleOfInaccessibility<T> for Polygon<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output = Result<Point<T>, PolylabelError>;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        polylabel(self, tolerance)
+    }
+}
+
+impl<T> PoleOfInaccessibility<T> for Rect<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output = Result<Point<T>, PolylabelError>;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        polylabel(&self.to_polygon(), tolerance)
+    }
+}
+
+impl<T> PoleOfInaccessibility<T> for Triangle<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output = Result<Point<T>, PolylabelError>;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        polylabel(&self.to_polygon(), tolerance)
+    }
+}
+
+impl<T> PoleOfInaccessibility<T> for MultiPolygon<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output = Result<Option<Point<T>>, PolylabelError>;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        largest(self)
+            .map(|largest| polylabel(largest, tolerance))
+            .transpose()
+    }
+}
+
+impl<T> PoleOfInaccessibility<T> for GeometryCollection<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output = Result<Option<Point<T>>, PolylabelError>;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        let mut polygons = vec![];
+        for geometry in self {
+            collect_polygons(geometry, &mut polygons);
+        }
+        MultiPolygon::new(polygons).pole_of_inaccessibility(tolerance)
+    }
+}
+
+impl<T> PoleOfInaccessibility<T> for Geometry<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    type Output = Result<Option<Point<T>>, PolylabelError>;
+
+    fn pole_of_inaccessibility(&self, tolerance: &T) -> Self::Output {
+        match self {
+            Geometry::Polygon(poly) => poly.pole_of_inaccessibility(tolerance).map(Some),
+            Geometry::MultiPolygon(multi) => multi.pole_of_inaccessibility(tolerance),
+            Geometry::Rect(rect) => rect.pole_of_inaccessibility(tolerance).map(Some),
+            Geometry::Triangle(triangle) => triangle.pole_of_inaccessibility(tolerance).map(Some),
+            Geometry::GeometryCollection(collection) => {
+                collection.pole_of_inaccessibility(tolerance)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// gather the polygonal parts of a geometry, including those of nested collections
+fn collect_polygons<T>(geometry: &Geometry<T>, polygons: &mut Vec<Polygon<T>>)
+where
+    T: GeoFloat,
+{
+    match geometry {
+        Geometry::Polygon(poly) => polygons.push(poly.clone()),
+        Geometry::MultiPolygon(multi) => polygons.extend(multi.iter().cloned()),
+        Geometry::Rect(rect) => polygons.push(rect.to_polygon()),
+        Geometry::Triangle(triangle) => polygons.push(triangle.to_polygon()),
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                collect_polygons(geometry, polygons);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PoleOfInaccessibility;
+    use geo::{Geometry, GeometryCollection, LineString, MultiPolygon, Point, Rect, Triangle};
+
+    #[test]
+    fn test_pole_of_inaccessibility() {
+        let small = Rect::new((0.0, 0.0), (2.0, 2.0));
+        let large = Rect::new((10.0, 10.0), (14.0, 14.0));
+        assert_eq!(
+            small.pole_of_inaccessibility(&0.1),
+            Ok(Point::new(1.0, 1.0))
+        );
+        let triangle = Triangle::from([(0.0, 0.0), (6.0, 0.0), (0.0, 6.0)]);
+        let pole: Point<f64> = triangle.pole_of_inaccessibility(&0.01).unwrap();
+        // the incentre, at (6 - 3√2) in each axis
+        assert!((pole.x() - 1.757).abs() < 0.01 && (pole.y() - 1.757).abs() < 0.01);
+
+        let empty = MultiPolygon::<f64>::new(vec![]);
+        assert_eq!(empty.pole_of_inaccessibility(&0.1), Ok(None));
+
+        // nested collections are searched, and non-polygonal members are ignored
+        let collection = GeometryCollection::new_from(vec![
+            Geometry::Rect(small),
+            Geometry::LineString(LineString::from(vec![(0.0, 0.0), (100.0, 100.0)])),
+            Geometry::GeometryCollection(GeometryCollection::new_from(vec![Geometry::Rect(large)])),
+        ]);
+        assert_eq!(
+            Geometry::GeometryCollection(collection).pole_of_inaccessibility(&0.1),
+            Ok(Some(Point::new(12.0, 12.0)))
+        );
+        let point = Geometry::Point(Point::new(1.0, 1.0));
+        assert_eq!(point.pole_of_inaccessibility(&0.1), Ok(None));
+    }
+}
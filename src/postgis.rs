@@ -0,0 +1,77 @@
+//! Label positions for PostGIS geometries, as returned by database drivers
+
+use crate::wkb::polylabel_wkb;
+
+/// Calculate the label position of a hex-encoded EWKB Polygon or MultiPolygon, returning it as a hex-encoded
+/// EWKB Point
+///
+/// This is the form in which PostGIS returns `geometry` values using the text protocol (e.g. when a
+/// `tokio-postgres` or `sqlx` row's `geometry` column is read as a `String`), and which it accepts as
+/// input, so the label position can be written back to a `geometry` column as it is. A `bytea` value's
+/// `\x` prefix is accepted, and the output is upper-case, as PostGIS writes it. The Point has the geometry's
+/// byte order and SRID. Values read as bytes (e.g. using `ST_AsEWKB`) can be passed to
+/// [`polylabel_wkb`](fn.polylabel_wkb.html) directly.
+///
+/// Returns `None` if `hex` isn't a hex-encoded Polygon or MultiPolygon, or its label position can't be
+/// calculated.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_hex_ewkb;
+///
+/// // SELECT 'SRID=4326;POLYGON((0 0,4 0,4 4,0 4,0 0))'::geometry
+/// let geometry = "0103000020E610000001000000050000000000000000000000000000000000000000000000000010400000000000000000000000000000104000000000000010400000000000000000000000000000104000000000000000000000000000000000";
+/// let label = polylabel_hex_ewkb(geometry, 0.1).unwrap();
+/// // SELECT 'SRID=4326;POINT(2 2)'::geometry
+/// assert_eq!(label, "0101000020E610000000000000000000400000000000000040");
+/// ```
+pub fn polylabel_hex_ewkb(hex: &str, tolerance: f64) -> Option<String> {
+    let hex = hex.strip_prefix("\\x").unwrap_or(hex);
+    polylabel_wkb(&decode(hex)?, tolerance).map(|point| encode(&point))
+}
+
+fn decode(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| {
+            // from_str_radix also accepts a sign
+            let digits = std::str::from_utf8(pair)
+                .ok()
+                .filter(|digits| digits.bytes().all(|digit| digit.is_ascii_hexdigit()))?;
+            u8::from_str_radix(digits, 16).ok()
+        })
+        .collect()
+}
+
+fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, polylabel_hex_ewkb};
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(decode("00ff1A"), Some(vec![0, 255, 26]));
+        assert_eq!(decode("0"), None);
+        assert_eq!(decode("zz"), None);
+        assert_eq!(decode("+1"), None);
+        assert_eq!(encode(&[0, 255, 26]), "00FF1A");
+    }
+
+    #[test]
+    fn test_polylabel_hex_ewkb() {
+        // POLYGON((0 0,0 2,2 2,2 0,0 0)), big-endian without an SRID, as a bytea
+        let geometry = "\\x000000000300000001000000050000000000000000000000000000000000000000000000004000000000000000400000000000000040000000000000004000000000000000000000000000000000000000000000000000000000000000";
+        assert_eq!(
+            polylabel_hex_ewkb(geometry, 0.1).as_deref(),
+            Some("00000000013FF00000000000003FF0000000000000")
+        );
+        assert!(polylabel_hex_ewkb("0101000000", 0.1).is_none());
+    }
+}
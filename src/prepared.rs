@@ -0,0 +1,475 @@
+//! Polygons prepared for repeated label position calculation
+
+use crate::errors::PolylabelError;
+use crate::index::PolygonIndex;
+use crate::options::{Label, Metric, SearchStats};
+use crate::{DistanceField, Qcell, QuadTree};
+use geo::prelude::*;
+use geo::{GeoFloat, Point, Polygon, Rect};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+use std::mem;
+use std::sync::Mutex;
+
+// The smallest usable tolerance, in units in the last place of the Polygon's largest coordinate
+const MIN_TOLERANCE_ULPS: u8 = 16;
+
+// The number of cells examined between progress reports
+const PROGRESS_INTERVAL: usize = 1024;
+
+/// A Polygon whose spatial index and initial label position guess have been calculated up front
+///
+/// Preparing a Polygon is the most expensive part of a label position calculation for small tolerances
+/// relative to its size, so if you need to calculate the same Polygon's label position repeatedly (e.g. using
+/// a different tolerance at each zoom level), prepare it once and call [`polylabel`](#method.polylabel) as needed.
+///
+/// The cell queue's storage is also kept between calls, so repeated calculations don't need to grow it from scratch.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::PreparedPolygon;
+/// use geo::{Contains, Point, Polygon};
+///
+/// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+/// let poly = Polygon::new(coords.into(), vec![]);
+///
+/// let prepared = PreparedPolygon::new(&poly).unwrap();
+/// assert_eq!(prepared.polylabel(&0.1), Point::new(0.5625, 0.5625));
+/// assert!(poly.contains(&prepared.polylabel(&0.01)));
+/// ```
+pub struct PreparedPolygon<T>
+where
+    T: GeoFloat,
+{
+    pub(crate) index: PolygonIndex<T>,
+    seed: Seed<T>,
+    // Reusable storage for the cell queue
+    pool: Mutex<QuadTree<T>>,
+}
+
+/// The starting point of a label position search
+enum Seed<T>
+where
+    T: GeoFloat,
+{
+    /// The Polygon is degenerate, and has a fixed label position
+    Degenerate(Point<T>),
+    /// The Polygon's bounding box, the initial cell size, the best initial guess,
+    /// and the smallest tolerance its coordinates' precision can support
+    Search {
+        bbox: Rect<T>,
+        half_extent: T,
+        best_cell: Qcell<T>,
+        min_tolerance: T,
+    },
+}
+
+/// The state of a label position search
+pub(crate) enum Start<T>
+where
+    T: GeoFloat,
+{
+    /// The Polygon is degenerate, and has a fixed label position
+    Degenerate(Point<T>),
+    /// An initial best guess, and a queue of cells covering the Polygon's bounding box
+    Search(Qcell<T>, QuadTree<T>),
+}
+
+impl<T> PreparedPolygon<T>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    /// Prepare a Polygon for label position calculation
+    pub fn new(polygon: &Polygon<T>) -> Result<Self, PolylabelError> {
+        Self::with_metric(polygon, Metric::Euclidean)
+    }
+
+    /// Prepare a Polygon for label position calculation, measuring distances using a metric
+    ///
+    /// See [`Metric`](enum.Metric.html) for the units of the tolerance and the label positions' distances.
+    pub fn with_metric(polygon: &Polygon<T>, metric: Metric) -> Result<Self, PolylabelError> {
+        // index the polygon's rings once, so each distance query is O(log n)
+        let index = PolygonIndex::new(polygon, metric);
+        let seed = seed(polygon, &index)?;
+        Ok(Self {
+            index,
+            seed,
+            pool: Mutex::new(QuadTree::default()),
+        })
+    }
+
+    /// Calculate the prepared Polygon's ideal label position
+    ///
+    /// See [`polylabel`](fn.polylabel.html) for details.
+    pub fn polylabel(&self, tolerance: &T) -> Point<T> {
+        self.label(tolerance).point
+    }
+
+    /// Calculate the prepared Polygon's ideal label position, its distance from the Polygon's edges,
+    /// and a bound on the error of that distance
+    pub fn label(&self, tolerance: &T) -> Label<T> {
+        self.label_with_progress(tolerance, |_, _| ())
+    }
+
+    /// Calculate the prepared Polygon's ideal label position, reporting the search's progress
+    ///
+    /// `progress` is called periodically during the search, and once when it finishes, with the distance of
+    /// the best label position found so far, and an upper bound on the distance of the ideal label position.
+    /// The search finishes once these are within the tolerance of each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polylabel::PreparedPolygon;
+    /// use geo::Polygon;
+    ///
+    /// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+    /// let prepared = PreparedPolygon::new(&Polygon::new(coords.into(), vec![])).unwrap();
+    ///
+    /// let mut reports = vec![];
+    /// let label = prepared.label_with_progress(&0.1, |best, bound| reports.push((best, bound)));
+    /// assert_eq!(reports.last(), Some(&(label.distance, label.distance + label.bound)));
+    /// ```
+    pub fn label_with_progress<F>(&self, tolerance: &T, progress: F) -> Label<T>
+    where
+        F: FnMut(T, T),
+    {
+        self.search(tolerance, progress, || false).0
+    }
+
+    /// Calculate the prepared Polygon's ideal label position, along with statistics about the search
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polylabel::PreparedPolygon;
+    /// use geo::Polygon;
+    ///
+    /// let coords = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+    /// let prepared = PreparedPolygon::new(&Polygon::new(coords.into(), vec![])).unwrap();
+    ///
+    /// let (label, stats) = prepared.label_with_stats(&0.1);
+    /// assert_eq!(label, prepared.label(&0.1));
+    /// assert!(stats.cells > 0 && stats.max_queue > 0);
+    /// ```
+    pub fn label_with_stats(&self, tolerance: &T) -> (Label<T>, SearchStats) {
+        let (label, _, stats) = self.search(tolerance, |_, _| (), || false);
+        (label, stats)
+    }
+
+    /// Signed distance from a point to the prepared Polygon's outline: positive inside the Polygon, and negative
+    /// outside it
+    ///
+    /// This is the distance the label position search maximises, measured using the Polygon's
+    /// [`Metric`](enum.Metric.html). It can be used to build a distance function for
+    /// [`polylabel_with_distance_fn`](fn.polylabel_with_distance_fn.html).
+    pub fn signed_distance(&self, point: Point<T>) -> T {
+        self.index.signed_distance(point)
+    }
+
+    /// Search for the ideal label position, reporting progress, and stopping early if `cancelled` returns `true`
+    ///
+    /// Returns the best label position found, whether the search finished, and statistics about the search.
+    /// If it didn't finish, the label's bound may exceed the tolerance.
+    pub(crate) fn search<F, C>(
+        &self,
+        tolerance: &T,
+        mut progress: F,
+        cancelled: C,
+    ) -> (Label<T>, bool, SearchStats)
+    where
+        F: FnMut(T, T),
+        C: Fn() -> bool,
+    {
+        let tolerance = self.tolerance(*tolerance);
+        let (best_cell, cell_queue) = match self.start() {
+            Start::Degenerate(point) => {
+                progress(T::zero(), T::zero());
+                let label = Label {
+                    point,
+                    distance: T::zero(),
+                    bound: T::zero(),
+                };
+                return (label, true, SearchStats::default());
+            }
+            Start::Search(best_cell, cell_queue) => (best_cell, cell_queue),
+        };
+        let (label, finished, stats, cell_queue) = refine(
+            &self.index,
+            best_cell,
+            cell_queue,
+            tolerance,
+            progress,
+            cancelled,
+        );
+        self.finish(cell_queue);
+        (label, finished, stats)
+    }
+
+    /// The tolerance to search with: the requested tolerance, raised if necessary to one
+    /// that the Polygon's coordinates are precise enough to achieve
+    pub(crate) fn tolerance(&self, tolerance: T) -> T {
+        match self.seed {
+            Seed::Degenerate(_) => tolerance,
+            Seed::Search { min_tolerance, .. } => tolerance.max(min_tolerance),
+        }
+    }
+
+    /// Begin a search, setting up the priority queue using pooled storage if it's available
+    pub(crate) fn start(&self) -> Start<T> {
+        match self.seed {
+            Seed::Degenerate(point) => Start::Degenerate(point),
+            Seed::Search {
+                bbox,
+                half_extent,
+                best_cell,
+                ..
+            } => {
+                // If another thread is using the pool, fall back to a fresh allocation
+                let mut cell_queue = self
+                    .pool
+                    .try_lock()
+                    .map(|mut pool| mem::take(&mut *pool))
+                    .unwrap_or_default();
+                cell_queue.reset(bbox, half_extent, &self.index);
+                Start::Search(best_cell, cell_queue)
+            }
+        }
+    }
+
+    /// Finish a search, returning the queue's storage to the pool
+    pub(crate) fn finish(&self, cell_queue: QuadTree<T>) {
+        if let Ok(mut pool) = self.pool.try_lock() {
+            if cell_queue.capacity() > pool.capacity() {
+                *pool = cell_queue;
+            }
+        }
+    }
+}
+
+/// Calculate the starting point of a label position search of a Polygon's distance field
+fn seed<T, D>(polygon: &Polygon<T>, field: &D) -> Result<Seed<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    D: DistanceField<T> + ?Sized,
+{
+    // special case for degenerate polygons
+    if polygon.signed_area() == T::zero() {
+        return Ok(Seed::Degenerate(Point::new(T::zero(), T::zero())));
+    }
+
+    let bbox = polygon
+        .bounding_rect()
+        .ok_or(PolylabelError::RectCalculation)?;
+    let cell_size = bbox.width().min(bbox.height());
+    // Special case for degenerate polygons
+    if cell_size == T::zero() {
+        return Ok(Seed::Degenerate(Point::from(bbox.min())));
+    }
+
+    let two = T::one() + T::one();
+    let half_extent = cell_size / two;
+
+    // initial best guess using centroid
+    let centroid = polygon
+        .centroid()
+        .ok_or(PolylabelError::CentroidCalculation)?;
+    let centroid_cell = Qcell::new(centroid, T::zero(), field);
+
+    // special case guess for rectangular polygons
+    let bbox_cell = Qcell::new(bbox.centroid(), T::zero(), field);
+
+    // deciding which initial guess was better
+    let best_cell = if bbox_cell.distance < centroid_cell.distance {
+        bbox_cell
+    } else {
+        centroid_cell
+    };
+
+    // Cells can't usefully be subdivided beyond the precision of the coordinates
+    // they're positioned in, so a smaller tolerance is raised to it. This bounds how small
+    // cells get, but not how many there are: if the optimum isn't unique, e.g. along a
+    // strip's midline, every cell along the ridge stays within a tiny tolerance of the best,
+    // and is subdivided down to that precision
+    let magnitude = [bbox.min(), bbox.max()]
+        .iter()
+        .map(|c| c.x.abs().max(c.y.abs()))
+        .fold(cell_size, T::max);
+    let precision = magnitude * T::epsilon() * T::from(MIN_TOLERANCE_ULPS).unwrap();
+    let min_tolerance = field.length(best_cell.centroid, precision);
+
+    Ok(Seed::Search {
+        bbox,
+        half_extent,
+        best_cell,
+        min_tolerance,
+    })
+}
+
+/// Search a Polygon's distance field for the ideal label position, without preparing the Polygon
+pub(crate) fn search_field<T, D>(
+    polygon: &Polygon<T>,
+    field: &D,
+    tolerance: T,
+) -> Result<(Label<T>, SearchStats), PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+    D: DistanceField<T> + ?Sized,
+{
+    match seed(polygon, field)? {
+        Seed::Degenerate(point) => Ok((
+            Label {
+                point,
+                distance: T::zero(),
+                bound: T::zero(),
+            },
+            SearchStats::default(),
+        )),
+        Seed::Search {
+            bbox,
+            half_extent,
+            best_cell,
+            min_tolerance,
+        } => Ok(search_cells(
+            field,
+            bbox,
+            half_extent,
+            best_cell,
+            tolerance.max(min_tolerance),
+        )),
+    }
+}
+
+/// Search a distance field for the ideal label position within the cells of a given size covering an area,
+/// starting from a best guess
+pub(crate) fn search_cells<T, D>(
+    field: &D,
+    bbox: Rect<T>,
+    half_extent: T,
+    best_cell: Qcell<T>,
+    tolerance: T,
+) -> (Label<T>, SearchStats)
+where
+    T: GeoFloat,
+    D: DistanceField<T> + ?Sized,
+{
+    let mut cell_queue = QuadTree::default();
+    cell_queue.reset(bbox, half_extent, field);
+    let (label, _, stats, _) = refine(field, best_cell, cell_queue, tolerance, |_, _| (), || false);
+    (label, stats)
+}
+
+/// Refine a search's best guess by subdividing the queued cells, until no cell could contain a label position
+/// better by more than the tolerance, reporting progress, and stopping early if `cancelled` returns `true`
+///
+/// Returns the best label position found, whether the search finished, statistics about the search, and the
+/// queue, so its storage can be reused.
+fn refine<T, D, F, C>(
+    field: &D,
+    mut best_cell: Qcell<T>,
+    mut cell_queue: QuadTree<T>,
+    tolerance: T,
+    mut progress: F,
+    cancelled: C,
+) -> (Label<T>, bool, SearchStats, QuadTree<T>)
+where
+    T: GeoFloat,
+    D: DistanceField<T> + ?Sized,
+    F: FnMut(T, T),
+    C: Fn() -> bool,
+{
+    let two = T::one() + T::one();
+    // The greatest distance that any discarded cell could contain
+    let mut upper = best_cell.distance;
+
+    let mut examined = 0;
+    let mut max_queue = cell_queue.len();
+    let mut finished = true;
+
+    // Now try to find better solutions
+    while let Some(cell) = cell_queue.pop() {
+        examined += 1;
+        if examined % PROGRESS_INTERVAL == 0 {
+            // No unexamined cell can contain a better solution than the queue's best candidate
+            let bound = upper.max(cell.max_distance);
+            progress(best_cell.distance.max(cell.distance), bound);
+        }
+        // Update the best cell if we find a cell with greater distance
+        if cell.distance > best_cell.distance {
+            best_cell = Qcell { ..cell };
+        }
+
+        if cancelled() {
+            // The abandoned cell is the queue's best candidate, so it bounds every unexamined cell
+            upper = upper.max(cell.max_distance);
+            finished = false;
+            break;
+        }
+
+        // Bail out of this iteration if we can't find a better solution
+        if cell.max_distance - best_cell.distance <= tolerance {
+            upper = upper.max(cell.max_distance);
+            continue;
+        }
+
+        // Otherwise, add a new quadtree node and start again
+        cell_queue.add_quad(&cell, cell.half_extent / two, field);
+        max_queue = max_queue.max(cell_queue.len());
+        // Drop cells that can no longer beat the best cell, keeping the queue bounded
+        cell_queue.prune(best_cell.distance);
+    }
+
+    // We've exhausted (or abandoned) the queue, so return the best solution we've found
+    let label = Label {
+        point: best_cell.centroid,
+        distance: best_cell.distance,
+        bound: (upper - best_cell.distance).max(T::zero()),
+    };
+    progress(label.distance, label.distance + label.bound);
+    let stats = SearchStats {
+        cells: examined,
+        max_queue,
+    };
+    (label, finished, stats, cell_queue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PreparedPolygon;
+    use crate::polylabel;
+    use geo::{Distance, Euclidean, Polygon};
+
+    #[test]
+    // repeated calculations reuse the queue's storage, and must match one-off calculations
+    fn test_reuse() {
+        let poly = Polygon::new(include!("../tests/fixtures/poly3.rs").into(), vec![]);
+        let prepared = PreparedPolygon::new(&poly).unwrap();
+        for tolerance in [0.1, 0.01, 0.001, 0.01] {
+            assert_eq!(
+                prepared.polylabel(&tolerance),
+                polylabel(&poly, &tolerance).unwrap()
+            );
+        }
+        assert!(prepared.pool.lock().unwrap().capacity() > 0);
+    }
+    #[test]
+    // a zero tolerance is raised to one the coordinates can support, so the search finishes
+    fn test_zero_tolerance() {
+        let coords = vec![
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let prepared = PreparedPolygon::new(&poly).unwrap();
+        assert_eq!(prepared.tolerance(0.0), 4.0 * f64::EPSILON * 16.0);
+        let res = prepared.polylabel(&0.0);
+        let distance: f64 = Euclidean::distance(&res, poly.exterior());
+        // the true pole is equidistant from the outer edges and the inner corner
+        assert!((distance - (2.0 - 2f64.sqrt())).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,347 @@
+//! The largest rectangle of a label's shape which fits inside a Polygon
+//!
+//! The Chebyshev distance from a point to a Polygon's edges is the half-width of the largest axis-aligned square
+//! centred on it which fits in the Polygon. Stretching the Polygon vertically by a label's aspect ratio turns the
+//! label's rectangle into a square, so the usual search finds the largest rectangle of that shape. Rotated
+//! rectangles are found by rotating the Polygon the other way.
+
+use crate::errors::PolylabelError;
+use crate::options::{Metric, PolylabelOptions};
+use crate::polylabel_with_options;
+use geo::{AffineTransform, BoundingRect, GeoFloat, Point, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+// The number of orientations sampled, and the number of the best which are refined
+const SAMPLES: u8 = 36;
+const CANDIDATES: usize = 3;
+
+/// The largest rectangle of a given shape which fits inside a Polygon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InscribedRect<T>
+where
+    T: GeoFloat,
+{
+    /// The rectangle's centre
+    pub center: Point<T>,
+    /// The rectangle's width
+    pub width: T,
+    /// The rectangle's height
+    pub height: T,
+    /// The rectangle's anticlockwise rotation in degrees, from 0 up to 180: zero for an axis-aligned rectangle
+    pub angle: T,
+    /// An upper bound on how much wider the largest rectangle of this shape could be
+    pub bound: T,
+}
+
+/// Find the largest axis-aligned rectangle which is `aspect` times wider than it is tall, and fits inside a Polygon
+///
+/// This answers whether a label will fit, rather than where its anchor should be: a label of this shape fits
+/// if it's no wider than the rectangle. The tolerance, and the rectangle's bound, are widths in the Polygon's
+/// units. A Polygon with no area has an empty rectangle.
+///
+/// # Errors
+///
+/// The aspect ratio must be positive and finite, or
+/// [`PolylabelError::InvalidAspectRatio`](errors/enum.PolylabelError.html#variant.InvalidAspectRatio) is returned.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::max_inscribed_rect;
+/// use geo::Polygon;
+///
+/// // a square with a wide arm to its right
+/// let coords = vec![(0.0, 0.0), (20.0, 0.0), (20.0, 2.0), (3.0, 2.0), (3.0, 3.0), (0.0, 3.0), (0.0, 0.0)];
+/// let poly = Polygon::new(coords.into(), vec![]);
+///
+/// // a 5:1 label fits best along the arm, which is 2 tall
+/// let rect = max_inscribed_rect(&poly, 5.0_f64, &0.01).unwrap();
+/// assert!(rect.center.x() > 3.0);
+/// assert!((rect.width - 10.0).abs() <= 0.01 && (rect.height - 2.0).abs() <= 0.01);
+/// ```
+pub fn max_inscribed_rect<T>(
+    polygon: &Polygon<T>,
+    aspect: T,
+    tolerance: &T,
+) -> Result<InscribedRect<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    if !(aspect > T::zero() && aspect.is_finite()) {
+        return Err(PolylabelError::InvalidAspectRatio);
+    }
+    fit(
+        polygon,
+        aspect,
+        *tolerance,
+        T::zero(),
+        Point::new(T::zero(), T::zero()),
+    )
+}
+
+/// Find the largest rectangle at any orientation which is `aspect` times wider than it is tall, and fits inside
+/// a Polygon
+///
+/// Like [`max_inscribed_rect`](fn.max_inscribed_rect.html), but the rectangle may be rotated, e.g. to label a
+/// road or a river along its length. Orientations are sampled every 5°, and the best few are refined until
+/// rotating the rectangle further would move it by less than the tolerance. The rectangle is within the
+/// tolerance of the largest at its angle, but, as the orientations are sampled, a narrow range of angles
+/// holding a larger rectangle can be missed.
+///
+/// Each orientation is searched using [`polylabel`](fn.polylabel.html)'s algorithm, so the rectangles of elongated
+/// Polygons, whose largest rectangles often have a ridge of equally good centres, are very slow to find with a
+/// tiny tolerance. See [`polylabel`](fn.polylabel.html#precision).
+///
+/// # Errors
+///
+/// The aspect ratio must be positive and finite, or
+/// [`PolylabelError::InvalidAspectRatio`](errors/enum.PolylabelError.html#variant.InvalidAspectRatio) is returned.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::{max_inscribed_rect, max_inscribed_rect_oriented};
+/// use geo::{Point, Rect, Rotate};
+///
+/// // a 10 × 1 strip, rotated by 30°
+/// let strip = Rect::new((0.0, 0.0), (10.0, 1.0)).to_polygon();
+/// let strip = strip.rotate_around_point(30.0_f64, Point::new(0.0, 0.0));
+///
+/// let rect = max_inscribed_rect_oriented(&strip, 10.0, &0.01).unwrap();
+/// assert!((rect.angle - 30.0).abs() < 0.1);
+/// assert!(rect.width > 9.9);
+/// // only a much smaller rectangle fits without rotating it
+/// assert!(max_inscribed_rect(&strip, 10.0, &0.01).unwrap().width < 3.0);
+/// ```
+pub fn max_inscribed_rect_oriented<T>(
+    polygon: &Polygon<T>,
+    aspect: T,
+    tolerance: &T,
+) -> Result<InscribedRect<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    if !(aspect > T::zero() && aspect.is_finite()) {
+        return Err(PolylabelError::InvalidAspectRatio);
+    }
+    let Some(bbox) = polygon.bounding_rect() else {
+        return max_inscribed_rect(polygon, aspect, tolerance);
+    };
+    let origin = bbox.center().into();
+    let two = T::one() + T::one();
+    let half_turn = T::from(180.0).unwrap();
+    let step = half_turn / T::from(SAMPLES).unwrap();
+    // rotating the rectangle by an angle in radians moves its corners by up to the angle times the Polygon's
+    // diagonal, which can change the width by up to the aspect ratio times as much
+    let diagonal = bbox.width().hypot(bbox.height());
+    let elongation = aspect.max(aspect.recip());
+    let min_step = (*tolerance / (diagonal * elongation)).to_degrees();
+    let at = |angle: T| fit(polygon, aspect, *tolerance, angle, origin);
+
+    let mut samples = (0..SAMPLES)
+        .map(|i| at(T::from(i).unwrap() * step))
+        .collect::<Result<Vec<_>, _>>()?;
+    samples.sort_by(|a, b| b.width.total_cmp(&a.width));
+    let mut best = samples[0];
+    for sample in samples.into_iter().take(CANDIDATES) {
+        // a pattern search: step to the better neighbouring angle if there is one, then halve the step
+        let mut current = sample;
+        let mut step = step / two;
+        while step > min_step {
+            for angle in [current.angle - step, current.angle + step] {
+                let rect = at(angle)?;
+                if rect.width > current.width {
+                    current = rect;
+                }
+            }
+            step = step / two;
+        }
+        if current.width > best.width {
+            best = current;
+        }
+    }
+    Ok(best)
+}
+
+/// The position and size at which a text box fits inside a Polygon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextFit<T>
+where
+    T: GeoFloat,
+{
+    /// The text box's centre
+    pub anchor: Point<T>,
+    /// The greatest factor by which the text box can be scaled while still fitting inside the Polygon
+    pub scale: T,
+}
+
+/// Find where a text box fits inside a Polygon, and the greatest scale at which it fits
+///
+/// `width` and `height` are the text box's size at a scale of 1, in the Polygon's units. The text box is centred
+/// on the anchor, and fits at any scale up to the returned scale, which is within `tolerance / width` of the
+/// greatest. If it doesn't fit anywhere at `min_scale`, e.g. the smallest readable size, `None` is returned.
+///
+/// # Errors
+///
+/// The text box's width and height must be positive and finite, or
+/// [`PolylabelError::InvalidAspectRatio`](errors/enum.PolylabelError.html#variant.InvalidAspectRatio) is returned.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::fit_text_box;
+/// use geo::Rect;
+///
+/// let poly = Rect::new((0.0, 0.0), (8.0, 2.0)).to_polygon();
+///
+/// // a 2 × 1 box fits at up to twice its size
+/// let fit = fit_text_box(&poly, 2.0_f64, 1.0, 0.5, &0.01).unwrap().unwrap();
+/// assert!((fit.scale - 2.0).abs() < 0.01);
+/// assert!((fit.anchor.y() - 1.0).abs() < 0.01);
+///
+/// // but can't be drawn three times as large
+/// assert!(fit_text_box(&poly, 2.0_f64, 1.0, 3.0, &0.01).unwrap().is_none());
+/// ```
+pub fn fit_text_box<T>(
+    polygon: &Polygon<T>,
+    width: T,
+    height: T,
+    min_scale: T,
+    tolerance: &T,
+) -> Result<Option<TextFit<T>>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    if !(width > T::zero() && width.is_finite() && height > T::zero() && height.is_finite()) {
+        return Err(PolylabelError::InvalidAspectRatio);
+    }
+    let rect = max_inscribed_rect(polygon, width / height, tolerance)?;
+    let scale = rect.width / width;
+    Ok((scale >= min_scale).then_some(TextFit {
+        anchor: rect.center,
+        scale,
+    }))
+}
+
+// the largest rectangle of the aspect ratio at an angle, found by rotating the Polygon the other way
+fn fit<T>(
+    polygon: &Polygon<T>,
+    aspect: T,
+    tolerance: T,
+    angle: T,
+    origin: Point<T>,
+) -> Result<InscribedRect<T>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    let two = T::one() + T::one();
+    // the search's distances are half-widths
+    let options = PolylabelOptions::new(tolerance / two)
+        .metric(Metric::Chebyshev)
+        .aspect_ratio(aspect)
+        .transform(AffineTransform::rotate(-angle, origin));
+    let label = polylabel_with_options(polygon, &options)?;
+    let width = label.distance.max(T::zero()) * two;
+    let half_turn = T::from(180.0).unwrap();
+    Ok(InscribedRect {
+        center: label.point,
+        width,
+        height: width / aspect,
+        angle: angle - (angle / half_turn).floor() * half_turn,
+        bound: label.bound * two,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_text_box, max_inscribed_rect, max_inscribed_rect_oriented};
+    use crate::errors::PolylabelError;
+    use geo::{Contains, Point, Polygon, Rect, Rotate};
+
+    #[test]
+    fn test_max_inscribed_rect() {
+        // an 8 × 2 rectangle holds a 4 × 2 rectangle, and a 2 × 1 rectangle's width is limited by its height
+        let poly = Rect::new((0.0_f64, 0.0), (8.0, 2.0)).to_polygon();
+        let rect = max_inscribed_rect(&poly, 2.0, &0.01).unwrap();
+        assert!((rect.width - 4.0).abs() <= 0.01 && (rect.height - 2.0).abs() <= 0.01);
+        let rect = max_inscribed_rect(&poly, 0.5, &0.01).unwrap();
+        assert!((rect.width - 1.0).abs() <= 0.01 && (rect.height - 2.0).abs() <= 0.01);
+        assert!(rect.bound <= 0.01);
+        // an L shape, with a hole in its corner
+        let coords = vec![
+            (0.0_f64, 0.0),
+            (10.0, 0.0),
+            (10.0, 4.0),
+            (4.0, 4.0),
+            (4.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ];
+        let hole = Rect::new((1.0, 1.0), (3.0, 3.0))
+            .to_polygon()
+            .exterior()
+            .clone();
+        let poly = Polygon::new(coords.into(), vec![hole]);
+        let rect = max_inscribed_rect(&poly, 1.0, &0.001).unwrap();
+        // the square fits in either arm, away from the hole
+        assert!(poly.contains(&rect.center));
+        assert!(rect.center.x() >= 4.0 || rect.center.y() >= 4.0);
+        assert!((rect.width - 4.0).abs() <= 0.001);
+        // a degenerate Polygon, and invalid aspect ratios
+        let line = Polygon::new(vec![(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)].into(), vec![]);
+        assert_eq!(max_inscribed_rect(&line, 1.0, &0.1).unwrap().width, 0.0);
+        for aspect in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            assert_eq!(
+                max_inscribed_rect(&poly, aspect, &0.1),
+                Err(PolylabelError::InvalidAspectRatio)
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_inscribed_rect_oriented() {
+        // a 4 × 1 strip at 123°, which isn't one of the sampled orientations
+        let strip = Rect::new((0.0_f64, 0.0), (4.0, 1.0))
+            .to_polygon()
+            .rotate_around_point(123.0, Point::new(0.0, 0.0));
+        let rect = max_inscribed_rect_oriented(&strip, 4.0, &0.01).unwrap();
+        assert!((rect.angle - 123.0).abs() < 0.5);
+        assert!(rect.width > 3.9 && rect.width <= 4.0 + 0.01);
+        assert!(strip.contains(&rect.center));
+        // a square fits an axis-aligned square best
+        let square = Rect::new((0.0_f64, 0.0), (2.0, 2.0)).to_polygon();
+        let rect = max_inscribed_rect_oriented(&square, 1.0, &0.01).unwrap();
+        assert!((rect.width - 2.0).abs() <= 0.01);
+        assert!(rect.angle.rem_euclid(90.0) < 0.5 || rect.angle.rem_euclid(90.0) > 89.5);
+        assert_eq!(
+            max_inscribed_rect_oriented(&square, 0.0, &0.1),
+            Err(PolylabelError::InvalidAspectRatio)
+        );
+        // axis-aligned rectangles aren't rotated
+        assert_eq!(max_inscribed_rect(&square, 1.0, &0.1).unwrap().angle, 0.0);
+    }
+
+    #[test]
+    fn test_fit_text_box() {
+        // a 3 × 1 box fits in the wide arm of an L shape at twice its size, but not in the tall arm
+        let coords = vec![
+            (0.0_f64, 0.0),
+            (10.0, 0.0),
+            (10.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let fit = fit_text_box(&poly, 3.0, 1.0, 1.0, &0.01).unwrap().unwrap();
+        assert!((fit.scale - 2.0).abs() <= 0.01 / 3.0);
+        assert!(fit.anchor.x() > 2.0 && (fit.anchor.y() - 1.0).abs() < 0.01);
+        assert!(fit_text_box(&poly, 3.0, 1.0, 2.1, &0.01).unwrap().is_none());
+        assert_eq!(
+            fit_text_box(&poly, 3.0, 0.0, 1.0, &0.01),
+            Err(PolylabelError::InvalidAspectRatio)
+        );
+    }
+}
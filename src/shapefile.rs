@@ -0,0 +1,443 @@
+//! Label positions for polygon shapefiles
+
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use geo::{Contains, Coord, LineString, MultiPolygon, Polygon, Winding};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const FILE_CODE: i32 = 9994;
+const VERSION: i32 = 1000;
+const NULL: i32 = 0;
+const POINT: i32 = 1;
+const POLYGON: i32 = 5;
+const POLYGON_Z: i32 = 15;
+const POLYGON_M: i32 = 25;
+// the length of a file header, in bytes
+const HEADER: usize = 100;
+
+/// Calculate the label position of each record in a polygon shapefile, writing them to a point shapefile
+///
+/// `input` and `output` are the paths of the `.shp` files; their extensions are replaced as required. Each
+/// record's label position is that of its part with the largest area. The input's `.dbf`, `.prj`, and `.cpg` files
+/// are copied alongside the output's `.shp` and `.shx` files if they exist, so each label position keeps its
+/// record's attributes, and the input's coordinate reference system. Records are read and written one at a time,
+/// so memory use doesn't depend on the size of the shapefile.
+///
+/// Null records, and those whose label position can't be calculated, are written as null shapes, so the
+/// records remain aligned with their attributes. Returns the number of records which were labelled.
+///
+/// # Errors
+///
+/// Returns any I/O error, or an error of kind `InvalidData` if `input` isn't a valid Polygon, PolygonZ,
+/// or PolygonM shapefile.
+pub fn label_shapefile<P, Q>(input: P, output: Q, tolerance: f64) -> io::Result<usize>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let input = input.as_ref().with_extension("shp");
+    let output = output.as_ref().with_extension("shp");
+    let shp = BufReader::new(File::open(&input)?);
+    let mut out_shp = BufWriter::new(File::create(&output)?);
+    let mut out_shx = BufWriter::new(File::create(output.with_extension("shx"))?);
+    let labelled = label_records(shp, &mut out_shp, &mut out_shx, tolerance)?;
+    out_shp.flush()?;
+    out_shx.flush()?;
+    for extension in ["dbf", "prj", "cpg"] {
+        match fs::copy(
+            input.with_extension(extension),
+            output.with_extension(extension),
+        ) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+    }
+    Ok(labelled)
+}
+
+// read polygon records from a .shp file, writing their label positions to .shp and .shx files
+fn label_records<R, W, X>(
+    shp: R,
+    out_shp: &mut W,
+    out_shx: &mut X,
+    tolerance: f64,
+) -> io::Result<usize>
+where
+    R: Read,
+    W: Write + Seek,
+    X: Write + Seek,
+{
+    let records_in = ShapefileRecords::new(shp)?;
+    // the headers are written once the bounding box and file lengths are known
+    out_shp.write_all(&[0; HEADER])?;
+    out_shx.write_all(&[0; HEADER])?;
+    let (mut offset, mut records, mut labelled) = (HEADER / 2, 0, 0);
+    let mut bounds: Option<[f64; 4]> = None;
+    for record in records_in {
+        records += 1;
+        let label = record?.and_then(|multi| label(&multi, tolerance));
+        // content lengths are in 16-bit words
+        let content_length: i32 = if label.is_some() { 10 } else { 2 };
+        out_shx.write_all(&int(offset)?.to_be_bytes())?;
+        out_shx.write_all(&content_length.to_be_bytes())?;
+        out_shp.write_all(&int(records)?.to_be_bytes())?;
+        out_shp.write_all(&content_length.to_be_bytes())?;
+        match label {
+            Some(label) => {
+                let (x, y) = label.point.x_y();
+                out_shp.write_all(&POINT.to_le_bytes())?;
+                out_shp.write_all(&x.to_le_bytes())?;
+                out_shp.write_all(&y.to_le_bytes())?;
+                bounds = Some(bounds.map_or([x, y, x, y], |[min_x, min_y, max_x, max_y]| {
+                    [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                }));
+                labelled += 1;
+            }
+            None => out_shp.write_all(&NULL.to_le_bytes())?,
+        }
+        offset += 4 + content_length as usize;
+    }
+    let bounds = bounds.unwrap_or_default();
+    out_shp.seek(SeekFrom::Start(0))?;
+    out_shp.write_all(&file_header(int(offset)?, bounds))?;
+    out_shx.seek(SeekFrom::Start(0))?;
+    out_shx.write_all(&file_header(int(HEADER / 2 + records * 4)?, bounds))?;
+    Ok(labelled)
+}
+
+/// Read the records of a polygon shapefile as MultiPolygons, one at a time
+///
+/// `input` is the path of the `.shp` file; its extension is replaced as required. Each item is a record's
+/// MultiPolygon, or `None` if it's a null record, in the order of the records' attributes in the `.dbf` file.
+/// Records are parsed as they're read, so memory use doesn't depend on the size of the shapefile.
+///
+/// # Errors
+///
+/// Returns any I/O error, or an error of kind `InvalidData` if `input` isn't a valid Polygon, PolygonZ,
+/// or PolygonM shapefile. Records which can't be read are errors too, after which there are no more items.
+pub fn read_shapefile<P: AsRef<Path>>(input: P) -> io::Result<ShapefileRecords<BufReader<File>>> {
+    ShapefileRecords::new(BufReader::new(File::open(
+        input.as_ref().with_extension("shp"),
+    )?))
+}
+
+/// An iterator over the records of a polygon shapefile, created by [`read_shapefile`]
+#[derive(Debug)]
+pub struct ShapefileRecords<R> {
+    shp: R,
+    // the number of bytes read, and the file's length
+    read: usize,
+    length: usize,
+}
+
+impl<R: Read> ShapefileRecords<R> {
+    // read and check a .shp file's header
+    fn new(mut shp: R) -> io::Result<Self> {
+        let mut header = [0; HEADER];
+        shp.read_exact(&mut header)?;
+        if be_i32(&header, 0)? != FILE_CODE {
+            return Err(invalid("the file isn't a shapefile"));
+        }
+        if ![NULL, POLYGON, POLYGON_Z, POLYGON_M].contains(&le_i32(&header, 32)?) {
+            return Err(invalid("the shapefile doesn't hold polygons"));
+        }
+        Ok(Self {
+            shp,
+            read: HEADER,
+            length: words(be_i32(&header, 24)?)?,
+        })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<MultiPolygon<f64>>> {
+        let mut record_header = [0; 8];
+        self.shp.read_exact(&mut record_header)?;
+        // the content's length is checked against what remains of the file before it's allocated
+        let length = words(be_i32(&record_header, 4)?)?;
+        if length > self.length.saturating_sub(self.read + record_header.len()) {
+            return Err(invalid("a record's length exceeds the file's length"));
+        }
+        let mut content = vec![0; length];
+        self.shp.read_exact(&mut content)?;
+        self.read += record_header.len() + content.len();
+        parse_record(&content)
+    }
+}
+
+impl<R: Read> Iterator for ShapefileRecords<R> {
+    type Item = io::Result<Option<MultiPolygon<f64>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.read >= self.length {
+            return None;
+        }
+        let record = self.read_record();
+        if record.is_err() {
+            // the rest of the file can't be found once a record can't be read
+            self.read = self.length;
+        }
+        Some(record)
+    }
+}
+
+fn label(multi: &MultiPolygon<f64>, tolerance: f64) -> Option<Label<f64>> {
+    polylabel_multi(multi, &PolylabelOptions::new(tolerance), Parts::Largest)
+        .ok()?
+        .into_iter()
+        .next()
+}
+
+// a point file's header, given its length in 16-bit words, and its bounding box
+fn file_header(length: i32, [min_x, min_y, max_x, max_y]: [f64; 4]) -> [u8; HEADER] {
+    let mut header = [0; HEADER];
+    header[..4].copy_from_slice(&FILE_CODE.to_be_bytes());
+    header[24..28].copy_from_slice(&length.to_be_bytes());
+    header[28..32].copy_from_slice(&VERSION.to_le_bytes());
+    header[32..36].copy_from_slice(&POINT.to_le_bytes());
+    for (i, value) in [min_x, min_y, max_x, max_y].into_iter().enumerate() {
+        header[36 + i * 8..44 + i * 8].copy_from_slice(&value.to_le_bytes());
+    }
+    header
+}
+
+/// Parse a shapefile record's content into a MultiPolygon, or `None` if it's a null record
+///
+/// Clockwise rings are exteriors, and each counter-clockwise ring is a hole in the first exterior which contains
+/// it. A counter-clockwise ring which isn't within an exterior is treated as an exterior. Any `z` and `m`
+/// values are ignored.
+fn parse_record(content: &[u8]) -> io::Result<Option<MultiPolygon<f64>>> {
+    match le_i32(content, 0)? {
+        NULL => return Ok(None),
+        POLYGON | POLYGON_Z | POLYGON_M => (),
+        _ => return Err(invalid("a record isn't a polygon")),
+    }
+    // the record's bounding box is skipped
+    let n_parts = count(le_i32(content, 36)?)?;
+    let n_points = count(le_i32(content, 40)?)?;
+    let points = 44 + n_parts * 4;
+    let mut offsets = (0..n_parts)
+        .map(|i| le_i32(content, 44 + i * 4).and_then(count))
+        .collect::<io::Result<Vec<_>>>()?;
+    offsets.push(n_points);
+    if offsets.windows(2).any(|bounds| bounds[0] > bounds[1]) {
+        return Err(invalid("a record's part offsets aren't in order"));
+    }
+    let mut exteriors: Vec<Polygon<f64>> = vec![];
+    let mut holes = vec![];
+    for bounds in offsets.windows(2) {
+        let ring = (bounds[0]..bounds[1])
+            .map(|i| {
+                Ok(Coord {
+                    x: le_f64(content, points + i * 16)?,
+                    y: le_f64(content, points + i * 16 + 8)?,
+                })
+            })
+            .collect::<io::Result<LineString<f64>>>()?;
+        match ring.0.first() {
+            None => continue,
+            Some(_) if ring.is_cw() => exteriors.push(Polygon::new(ring, vec![])),
+            Some(_) => holes.push(ring),
+        }
+    }
+    for hole in holes {
+        match exteriors
+            .iter_mut()
+            .find(|exterior| exterior.contains(&hole.0[0]))
+        {
+            Some(exterior) => exterior.interiors_push(hole),
+            None => exteriors.push(Polygon::new(hole, vec![])),
+        }
+    }
+    Ok(Some(MultiPolygon::new(exteriors)))
+}
+
+fn field<const N: usize>(bytes: &[u8], offset: usize) -> io::Result<[u8; N]> {
+    bytes
+        .get(offset..offset + N)
+        .and_then(|field| field.try_into().ok())
+        .ok_or_else(|| invalid("a record is truncated"))
+}
+
+fn be_i32(bytes: &[u8], offset: usize) -> io::Result<i32> {
+    field(bytes, offset).map(i32::from_be_bytes)
+}
+
+fn le_i32(bytes: &[u8], offset: usize) -> io::Result<i32> {
+    field(bytes, offset).map(i32::from_le_bytes)
+}
+
+fn le_f64(bytes: &[u8], offset: usize) -> io::Result<f64> {
+    field(bytes, offset).map(f64::from_le_bytes)
+}
+
+fn count(value: i32) -> io::Result<usize> {
+    usize::try_from(value).map_err(|_| invalid("a count or length is negative"))
+}
+
+// a length in 16-bit words, in bytes
+fn words(value: i32) -> io::Result<usize> {
+    count(value).map(|words| words * 2)
+}
+
+fn int(value: usize) -> io::Result<i32> {
+    i32::try_from(value).map_err(|_| invalid("the output is too large for a shapefile"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        be_i32, label_records, label_shapefile, le_f64, le_i32, ShapefileRecords, HEADER, POLYGON,
+    };
+    use std::fs;
+    use std::io::{Cursor, ErrorKind};
+    use std::path::Path;
+
+    // encode a Polygon record's content, given its rings
+    fn polygon(rings: &[&[(f64, f64)]]) -> Vec<u8> {
+        let n_points: usize = rings.iter().map(|ring| ring.len()).sum();
+        let mut content = POLYGON.to_le_bytes().to_vec();
+        content.extend([0; 32]);
+        content.extend((rings.len() as i32).to_le_bytes());
+        content.extend((n_points as i32).to_le_bytes());
+        let mut offset = 0;
+        for ring in rings {
+            content.extend((offset as i32).to_le_bytes());
+            offset += ring.len();
+        }
+        for &(x, y) in rings.iter().flat_map(|ring| ring.iter()) {
+            content.extend(x.to_le_bytes());
+            content.extend(y.to_le_bytes());
+        }
+        content
+    }
+
+    fn shapefile(records: &[Vec<u8>]) -> Vec<u8> {
+        let mut shp = vec![0; HEADER];
+        shp[..4].copy_from_slice(&9994i32.to_be_bytes());
+        shp[32..36].copy_from_slice(&POLYGON.to_le_bytes());
+        for (i, content) in records.iter().enumerate() {
+            shp.extend((i as i32 + 1).to_be_bytes());
+            shp.extend((content.len() as i32 / 2).to_be_bytes());
+            shp.extend(content);
+        }
+        let length = shp.len() as i32 / 2;
+        shp[24..28].copy_from_slice(&length.to_be_bytes());
+        shp
+    }
+
+    #[test]
+    fn test_label_records() {
+        // clockwise exteriors, and a counter-clockwise hole
+        let square = |offset: f64, size: f64| {
+            vec![
+                (offset, offset),
+                (offset, offset + size),
+                (offset + size, offset + size),
+                (offset + size, offset),
+                (offset, offset),
+            ]
+        };
+        let hole = [(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0), (1.0, 1.0)];
+        let records = [
+            polygon(&[&square(0.0, 4.0), &hole, &square(10.0, 1.0)]),
+            0i32.to_le_bytes().to_vec(),
+            polygon(&[&square(20.0, 2.0)]),
+        ];
+        let (mut out_shp, mut out_shx) = (Cursor::new(vec![]), Cursor::new(vec![]));
+        let labelled =
+            label_records(&shapefile(&records)[..], &mut out_shp, &mut out_shx, 0.01).unwrap();
+        assert_eq!(labelled, 2);
+        let (shp, shx) = (out_shp.into_inner(), out_shx.into_inner());
+        // two point records, and a null record
+        assert_eq!(shp.len(), HEADER + 28 + 12 + 28);
+        assert_eq!(be_i32(&shp, 24).unwrap() * 2, shp.len() as i32);
+        assert_eq!(le_i32(&shp, 32).unwrap(), 1);
+        // the first record is labelled away from its hole
+        let (x, y) = (le_f64(&shp, 112).unwrap(), le_f64(&shp, 120).unwrap());
+        assert!(x > 2.0 || y > 2.0);
+        // the null record, followed by the third record
+        assert_eq!(le_i32(&shp, 136).unwrap(), 0);
+        assert_eq!(
+            (le_f64(&shp, 152).unwrap(), le_f64(&shp, 160).unwrap()),
+            (21.0, 21.0)
+        );
+        // the bounding box's maximum
+        assert_eq!(le_f64(&shp, 52).unwrap(), 21.0);
+        // the index's offsets
+        assert_eq!(shx.len(), HEADER + 3 * 8);
+        assert_eq!(be_i32(&shx, 108).unwrap(), 64);
+        assert_eq!(be_i32(&shx, 116).unwrap(), 70);
+
+        let mut not_shapefile = shapefile(&records);
+        not_shapefile[3] = 0;
+        assert!(label_records(
+            &not_shapefile[..],
+            &mut Cursor::new(vec![]),
+            &mut Cursor::new(vec![]),
+            0.1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_shapefile_records() {
+        let square = [(0.0, 0.0), (0.0, 2.0), (2.0, 2.0), (2.0, 0.0), (0.0, 0.0)];
+        let records = [polygon(&[&square]), 0i32.to_le_bytes().to_vec()];
+        let shp = shapefile(&records);
+        let read = ShapefileRecords::new(&shp[..])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].as_ref().unwrap().0[0].exterior().0.len(), 5);
+        assert!(read[1].is_none());
+        // a truncated record is an error, which ends the records
+        let mut truncated = ShapefileRecords::new(&shp[..shp.len() - 8]).unwrap();
+        assert!(truncated.next().unwrap().is_ok());
+        assert!(truncated.next().unwrap().is_err());
+        assert!(truncated.next().is_none());
+        // a record's length can't exceed the file's length
+        let mut oversized = shp.clone();
+        oversized[104..108].copy_from_slice(&i32::MAX.to_be_bytes());
+        let mut oversized = ShapefileRecords::new(&oversized[..]).unwrap();
+        let err = oversized.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(oversized.next().is_none());
+    }
+
+    #[test]
+    fn test_label_shapefile() {
+        // a shapefile with a .shx, .dbf, and .prj, written to the ESRI specification independently of this module
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/shapefile/labels");
+        let output = std::env::temp_dir()
+            .join(format!("polylabel-shapefile-{}", std::process::id()))
+            .join("labels");
+        fs::create_dir_all(output.parent().unwrap()).unwrap();
+        assert_eq!(label_shapefile(&fixture, &output, 0.01).unwrap(), 2);
+        let shp = fs::read(output.with_extension("shp")).unwrap();
+        let shx = fs::read(output.with_extension("shx")).unwrap();
+        assert_eq!(shp.len(), HEADER + 28 + 12 + 28);
+        assert_eq!(shx.len(), HEADER + 3 * 8);
+        // the first record is labelled away from its hole, and the third within its largest part
+        let (x, y) = (le_f64(&shp, 112).unwrap(), le_f64(&shp, 120).unwrap());
+        assert!(x > 2.0 || y > 2.0);
+        assert_eq!(le_i32(&shp, 136).unwrap(), 0);
+        assert_eq!(
+            (le_f64(&shp, 152).unwrap(), le_f64(&shp, 160).unwrap()),
+            (21.0, 21.0)
+        );
+        // the attributes and coordinate reference system are copied
+        for extension in ["dbf", "prj"] {
+            assert_eq!(
+                fs::read(output.with_extension(extension)).unwrap(),
+                fs::read(fixture.with_extension(extension)).unwrap()
+            );
+        }
+        fs::remove_dir_all(output.parent().unwrap()).unwrap();
+    }
+}
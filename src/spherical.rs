@@ -0,0 +1,221 @@
+//! Label positions for Polygons on the sphere, whose rings are great-circle arcs
+//!
+//! Planar containment tests and searches of a Polygon's longitude and latitude bounding box break down for Polygons
+//! covering a large part of the globe, such as oceans, which may enclose a pole or cover more than a hemisphere.
+//! Here, a Polygon is the area to the left of each of its rings, and the whole sphere is searched.
+
+use crate::errors::PolylabelError;
+use crate::haversine::{self, SphericalRing};
+use crate::options::Label;
+use crate::prepared::search_cells;
+use crate::{DistanceField, Qcell};
+use geo::{Centroid, Coord, GeoFloat, Point, Polygon, Rect};
+
+// The smallest usable tolerance, in units in the last place of the largest longitude
+const MIN_TOLERANCE_ULPS: u8 = 16;
+
+/// Calculate the ideal label position of a Polygon on the sphere
+///
+/// The Polygon's coordinates are longitude and latitude in degrees, and its edges are great-circle arcs. Its
+/// interior is on the left of each of its rings, so its exterior must be anticlockwise and its interiors clockwise
+/// (as in GeoJSON): this is what distinguishes a Polygon from its complement, which has the same rings. The label
+/// position is the point whose great-circle distance from the Polygon's edges is greatest, on a sphere with the
+/// Earth's mean radius, so it's correct for Polygons covering a pole, or more than a hemisphere.
+///
+/// The tolerance, and the label's distance and bound, are in metres. The whole sphere is searched, and edges aren't
+/// spatially indexed, so this is slower than [`Metric::Haversine`](enum.Metric.html#variant.Haversine), which gives
+/// the same label position for Polygons that are small enough for their planar bounding boxes to contain them.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_spherical;
+/// use geo::Polygon;
+///
+/// // the equator, anticlockwise seen from above the north pole, encloses the northern hemisphere
+/// let equator = vec![(0.0, 0.0), (90.0, 0.0), (180.0, 0.0), (-90.0, 0.0), (0.0, 0.0)];
+/// let north = Polygon::new(equator.into(), vec![]);
+/// let label = polylabel_spherical(&north, &1000.0_f64).unwrap();
+/// assert!(label.point.y() > 89.9);
+/// // a quarter of a great circle
+/// assert!((label.distance - 10_007_543.0).abs() < 1000.0);
+/// ```
+pub fn polylabel_spherical<T>(
+    polygon: &Polygon<T>,
+    tolerance: &T,
+) -> Result<Label<T>, PolylabelError>
+where
+    T: GeoFloat,
+{
+    let exterior = &polygon.exterior().0;
+    // a ring needs three edges to enclose an area
+    if exterior
+        .windows(2)
+        .filter(|edge| edge[0] != edge[1])
+        .count()
+        < 3
+    {
+        let point = exterior.first().copied().unwrap_or(Coord {
+            x: T::zero(),
+            y: T::zero(),
+        });
+        return Ok(Label {
+            point: point.into(),
+            distance: T::zero(),
+            bound: T::zero(),
+        });
+    }
+    let field = SphericalField::new(polygon);
+    let half_turn = T::from(180.0).unwrap();
+    let quarter_turn = T::from(90.0).unwrap();
+    // two cells cover the sphere: the western and eastern hemispheres
+    let sphere = Rect::new(
+        Coord {
+            x: -half_turn,
+            y: -quarter_turn,
+        },
+        Coord {
+            x: half_turn,
+            y: quarter_turn,
+        },
+    );
+    // the planar centroid is a good initial guess for small Polygons
+    let guess = polygon
+        .centroid()
+        .ok_or(PolylabelError::CentroidCalculation)?;
+    let best_cell = Qcell::new(guess, T::zero(), &field);
+    let precision = half_turn * T::epsilon() * T::from(MIN_TOLERANCE_ULPS).unwrap();
+    let tolerance = tolerance.max(field.length(best_cell.centroid, precision));
+    let (label, _) = search_cells(&field, sphere, quarter_turn, best_cell, tolerance);
+    Ok(label)
+}
+
+/// A Polygon's signed great-circle distance field
+struct SphericalField<T>
+where
+    T: GeoFloat,
+{
+    // each ring, and whether the area on its left is larger than a hemisphere
+    rings: Vec<(SphericalRing<T>, bool)>,
+}
+
+impl<T> SphericalField<T>
+where
+    T: GeoFloat,
+{
+    fn new(polygon: &Polygon<T>) -> Self {
+        let rings = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(SphericalRing::new)
+            .map(|ring| {
+                // the area on the left is 2π less the turning angle, and a hemisphere's is 2π
+                let large = ring.turning() < T::zero();
+                (ring, large)
+            })
+            .collect();
+        Self { rings }
+    }
+
+    /// Whether a point is on the left of every ring
+    fn contains(&self, point: Point<T>) -> bool {
+        let pi = T::from(std::f64::consts::PI).unwrap();
+        self.rings.iter().all(|(ring, large)| {
+            // a ring encircling the point anticlockwise has it on its left, and one encircling it clockwise on its
+            // right. Otherwise, the ring encircles neither it nor its antipode, which are both on its left only if
+            // the area on its left is larger than a hemisphere
+            let winding = ring.winding(point);
+            winding > pi || (*large && winding.abs() < pi)
+        })
+    }
+}
+
+impl<T> DistanceField<T> for SphericalField<T>
+where
+    T: GeoFloat,
+{
+    fn signed_distance(&self, point: Point<T>) -> T {
+        let distance = self
+            .rings
+            .iter()
+            .map(|(ring, _)| ring.distance(point))
+            .fold(T::infinity(), T::min);
+        if self.contains(point) {
+            distance
+        } else {
+            -distance
+        }
+    }
+
+    fn cell_radius(&self, centroid: Point<T>, half_extent: T) -> T {
+        haversine::cell_radius(centroid, half_extent, haversine::metres_per_degree())
+    }
+
+    fn length(&self, _: Point<T>, length: T) -> T {
+        length * haversine::metres_per_degree()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{polylabel_spherical, SphericalField};
+    use crate::{polylabel_with_options, Metric, PolylabelOptions};
+    use geo::{Point, Polygon, Rect};
+
+    #[test]
+    fn test_contains() {
+        let square = Rect::new((0.0, 0.0), (2.0, 2.0)).to_polygon();
+        let field = SphericalField::new(&square);
+        assert!(field.contains(Point::new(1.0, 1.0)));
+        assert!(!field.contains(Point::new(3.0, 1.0)));
+        assert!(!field.contains(Point::new(-179.0, -1.0)));
+        // reversed, the square is a hole in the sphere
+        let mut complement = square.clone();
+        complement.exterior_mut(|ring| ring.0.reverse());
+        let field = SphericalField::new(&complement);
+        assert!(!field.contains(Point::new(1.0, 1.0)));
+        assert!(field.contains(Point::new(3.0, 1.0)));
+        assert!(field.contains(Point::new(-179.0, -1.0)));
+        assert!(field.contains(Point::new(0.0, 90.0)));
+    }
+
+    #[test]
+    fn test_polylabel_spherical() {
+        // small Polygons' label positions match the haversine metric's
+        let poly = Rect::new((10.0, 65.0), (30.0, 70.0)).to_polygon();
+        let label = polylabel_spherical(&poly, &1000.0_f64).unwrap();
+        let options = PolylabelOptions::new(1000.0_f64).metric(Metric::Haversine);
+        let haversine = polylabel_with_options(&poly, &options).unwrap();
+        assert!((label.distance - haversine.distance).abs() < 1000.0);
+        assert!(label.bound <= 1000.0);
+        // the complement of a small square is labelled at the square's antipode
+        let mut complement = Rect::new((0.0_f64, 0.0), (2.0, 2.0)).to_polygon();
+        complement.exterior_mut(|ring| ring.0.reverse());
+        let label = polylabel_spherical(&complement, &1000.0).unwrap();
+        assert!((label.point.x() + 179.0).abs() < 0.1 && (label.point.y() + 1.0).abs() < 0.1);
+        // the southern hemisphere, with a hole at the south pole
+        let equator = vec![
+            (0.0, 0.0),
+            (-90.0, 0.0),
+            (180.0, 0.0),
+            (90.0, 0.0),
+            (0.0, 0.0),
+        ];
+        let pole = vec![
+            (0.0, -80.0),
+            (90.0, -80.0),
+            (180.0, -80.0),
+            (-90.0, -80.0),
+            (0.0, -80.0),
+        ];
+        let south = Polygon::new(equator.into(), vec![pole.into()]);
+        let label = polylabel_spherical(&south, &1000.0).unwrap();
+        // midway between the equator and the hole's edges, which are nearest the pole midway between its vertices
+        let degree = crate::haversine::metres_per_degree::<f64>();
+        let nearest = (80f64.to_radians().tan() * 2f64.sqrt()).atan().to_degrees();
+        assert!((label.distance - nearest / 2.0 * degree).abs() < 1000.0);
+        assert!((label.point.x().rem_euclid(90.0) - 45.0).abs() < 0.1);
+        // degenerate Polygons aren't searched
+        let line = Polygon::new(vec![(1.0, 1.0), (2.0, 2.0), (1.0, 1.0)].into(), vec![]);
+        assert_eq!(polylabel_spherical(&line, &1.0).unwrap().distance, 0.0);
+    }
+}
@@ -0,0 +1,101 @@
+//! Several well-separated label positions within one Polygon, e.g. to repeat a country's name across its extent
+
+use crate::errors::PolylabelError;
+use crate::metric::polylabel_with_distance_fn;
+use crate::options::Label;
+use crate::prepared::PreparedPolygon;
+use geo::{Distance, Euclidean, GeoFloat, Point, Polygon};
+use num_traits::FromPrimitive;
+use std::iter::Sum;
+
+/// Calculate up to `n` well-separated label positions within a Polygon
+///
+/// The first label position is the Polygon's pole of inaccessibility, as calculated by
+/// [`polylabel`](fn.polylabel.html). Each of the others is placed greedily, as far as possible from both the
+/// Polygon's edges and discs with a radius of `min_separation` around the label positions already placed, so
+/// it's more than `min_separation` from all of them. Fewer than `n` label positions are returned if there's no
+/// room for more.
+///
+/// Each label's distance is from the Polygon's edges, and its bound is on its distance from the edges and discs.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_n;
+/// use geo::{Distance, Euclidean, Rect};
+///
+/// // a long strip has room for several labels more than 2 apart
+/// let strip = Rect::new((0.0, 0.0), (12.0, 2.0)).to_polygon();
+/// let labels = polylabel_n(&strip, 10, 2.0_f64, &0.01).unwrap();
+/// assert!(labels.len() > 2 && labels.len() < 10);
+/// assert!(Euclidean::distance(&labels[0].point, &labels[1].point) > 2.0);
+/// ```
+pub fn polylabel_n<T>(
+    polygon: &Polygon<T>,
+    n: usize,
+    min_separation: T,
+    tolerance: &T,
+) -> Result<Vec<Label<T>>, PolylabelError>
+where
+    T: GeoFloat + FromPrimitive + Sum,
+{
+    let mut labels = Vec::with_capacity(n);
+    if n == 0 {
+        return Ok(labels);
+    }
+    let prepared = PreparedPolygon::new(polygon)?;
+    labels.push(prepared.label(tolerance));
+    while labels.len() < n {
+        // the distance to the nearest edge or disc, which is positive only where there's room for a label
+        let label = polylabel_with_distance_fn(polygon, tolerance, |point: Point<T>| {
+            labels
+                .iter()
+                .map(|label: &Label<T>| Euclidean::distance(&point, &label.point) - min_separation)
+                .fold(prepared.signed_distance(point), T::min)
+        })?;
+        if label.distance <= T::zero() {
+            break;
+        }
+        labels.push(Label {
+            distance: prepared.signed_distance(label.point),
+            ..label
+        });
+    }
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::polylabel_n;
+    use crate::polylabel;
+    use geo::{Contains, Distance, Euclidean, Polygon};
+
+    #[test]
+    fn test_polylabel_n() {
+        // an L shape, whose arms each have room for labels
+        let coords = vec![
+            (0.0_f64, 0.0),
+            (20.0, 0.0),
+            (20.0, 4.0),
+            (4.0, 4.0),
+            (4.0, 20.0),
+            (0.0, 20.0),
+            (0.0, 0.0),
+        ];
+        let poly = Polygon::new(coords.into(), vec![]);
+        let labels = polylabel_n(&poly, 100, 5.0, &0.01).unwrap();
+        assert!(labels.len() > 4 && labels.len() < 100);
+        assert_eq!(labels[0].point, polylabel(&poly, &0.01).unwrap());
+        for (i, a) in labels.iter().enumerate() {
+            assert!(poly.contains(&a.point) && a.distance > 0.0);
+            for b in &labels[i + 1..] {
+                assert!(Euclidean::distance(&a.point, &b.point) > 5.0);
+            }
+        }
+        // the number of labels is limited
+        assert_eq!(polylabel_n(&poly, 2, 5.0, &0.01).unwrap().len(), 2);
+        assert!(polylabel_n(&poly, 0, 5.0, &0.01).unwrap().is_empty());
+        // a Polygon with no room for a second label
+        assert_eq!(polylabel_n(&poly, 10, 100.0, &0.01).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+//! JavaScript bindings, using [wasm-bindgen](https://docs.rs/wasm-bindgen)
+//!
+//! Enable the `wasm` feature, and build for the `wasm32-unknown-unknown` target.
+
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use crate::prepared::PreparedPolygon;
+use geo::{Area, Coord, LineString, MultiPolygon, Polygon};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Calculate a Polygon's ideal label position
+///
+/// `coords` holds the Polygon's rings, as arrays of `[x, y]` positions: its exterior, followed by zero or
+/// more holes, as in a GeoJSON `Polygon`'s coordinates. Like
+/// [mapbox/polylabel](https://github.com/mapbox/polylabel), returns the label position as an `[x, y]` array,
+/// whose `distance` property is its distance from the Polygon's edges.
+///
+/// Throws an error if `coords` isn't an array of rings, or if the label position can't be calculated.
+#[wasm_bindgen(js_name = polylabel)]
+pub fn polylabel_js(coords: &JsValue, tolerance: f64) -> Result<Array, JsError> {
+    position(label(&polygon(coords)?, tolerance)?)
+}
+
+/// Calculate the ideal label position of a GeoJSON `Polygon` or `MultiPolygon`
+///
+/// `geometry` is a GeoJSON geometry object, or a `Feature` holding one. The label position of a MultiPolygon is that
+/// of its part with the largest area. Returns the label position as a GeoJSON `Point` geometry object.
+///
+/// Throws an error if `geometry` isn't a Polygon or MultiPolygon, or if the label position can't be calculated.
+#[wasm_bindgen(js_name = polylabelGeoJSON)]
+pub fn polylabel_geojson_js(geometry: &JsValue, tolerance: f64) -> Result<Object, JsError> {
+    let multi = geojson(geometry)?;
+    let label = polylabel_multi(&multi, &PolylabelOptions::new(tolerance), Parts::Largest)
+        .map_err(|err| JsError::new(&err.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| JsError::new("the MultiPolygon has no parts"))?;
+    let point = Object::new();
+    let coordinates = Array::of2(&label.point.x().into(), &label.point.y().into());
+    for (key, value) in [
+        ("type", JsValue::from("Point")),
+        ("coordinates", coordinates.into()),
+    ] {
+        Reflect::set(&point, &key.into(), &value)
+            .map_err(|_| JsError::new("the Point couldn't be built"))?;
+    }
+    Ok(point)
+}
+
+/// Calculate the ideal label position of a Polygon held in a flat coordinate buffer
+///
+/// `coords` holds the Polygon's interleaved `x` and `y` coordinates, and `ring_offsets` delimits its rings: ring `i`
+/// holds the positions from `ring_offsets[i]` up to (but not including) `ring_offsets[i + 1]`, so there's one more
+/// offset than there are rings. The first ring is the exterior, and the rest are holes.
+///
+/// Passing typed arrays avoids converting each position to and from a JavaScript array. Returns a
+/// `Float64Array` holding the label position's `x` and `y` coordinates, and its distance from the Polygon's edges.
+///
+/// Throws an error if the offsets are invalid, or if the label position can't be calculated.
+#[wasm_bindgen(js_name = polylabelFlat)]
+pub fn polylabel_flat_js(
+    coords: &[f64],
+    ring_offsets: &[u32],
+    tolerance: f64,
+) -> Result<Vec<f64>, JsError> {
+    let polygon = flat_polygon(coords, ring_offsets).map_err(|err| JsError::new(&err))?;
+    let label = label(&polygon, tolerance)?;
+    Ok(vec![label.point.x(), label.point.y(), label.distance])
+}
+
+// build a Polygon from interleaved coordinates and ring offsets
+fn flat_polygon(coords: &[f64], ring_offsets: &[u32]) -> Result<Polygon<f64>, String> {
+    let positions = coords.len() / 2;
+    if coords.len() % 2 != 0 {
+        return Err("the coordinates must hold an even number of values".to_owned());
+    }
+    let ring_offsets: Vec<_> = ring_offsets.iter().map(|&offset| offset as usize).collect();
+    match *ring_offsets.as_slice() {
+        [] | [_] => return Err("there are no rings".to_owned()),
+        [start, end, ..] if start == end => return Err("the exterior ring is empty".to_owned()),
+        _ => (),
+    }
+    if let Some(i) = ring_offsets
+        .windows(2)
+        .position(|bounds| bounds[0] > bounds[1])
+    {
+        return Err(format!(
+            "offset {} ({}) is greater than offset {} ({})",
+            i,
+            ring_offsets[i],
+            i + 1,
+            ring_offsets[i + 1]
+        ));
+    }
+    if let Some(&last) = ring_offsets.last().filter(|&&last| last > positions) {
+        return Err(format!(
+            "the last offset ({}) is greater than the number of positions ({})",
+            last, positions
+        ));
+    }
+    let mut rings = ring_offsets.windows(2).map(|bounds| {
+        coords[bounds[0] * 2..bounds[1] * 2]
+            .chunks_exact(2)
+            .map(|xy| Coord { x: xy[0], y: xy[1] })
+            .collect::<LineString<_>>()
+    });
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    Ok(Polygon::new(exterior, rings.collect()))
+}
+
+/// A Polygon prepared for repeated label position calculation. See
+/// [`PreparedPolygon`](struct.PreparedPolygon.html) for details.
+///
+/// The Polygon is converted and indexed once, when it's constructed, so calling `compute` (e.g. with a finer
+/// tolerance as the map is zoomed) doesn't pass it from JavaScript again.
+#[wasm_bindgen(js_name = Polylabel)]
+pub struct PolylabelJs(PreparedPolygon<f64>);
+
+#[wasm_bindgen(js_class = Polylabel)]
+impl PolylabelJs {
+    /// Prepare a Polygon, given as an array of rings (see `polylabel`), or a GeoJSON `Polygon` or `MultiPolygon`
+    /// geometry object or `Feature` (see `polylabelGeoJSON`). Only a MultiPolygon's part with the largest area
+    /// is prepared.
+    ///
+    /// Throws an error if the Polygon is invalid, or can't be prepared.
+    #[wasm_bindgen(constructor)]
+    pub fn new(geometry: &JsValue) -> Result<PolylabelJs, JsError> {
+        let polygon = if Array::is_array(geometry) {
+            polygon(geometry)?
+        } else {
+            geojson(geometry)?
+                .into_iter()
+                .max_by(|a, b| a.unsigned_area().total_cmp(&b.unsigned_area()))
+                .ok_or_else(|| JsError::new("the MultiPolygon has no parts"))?
+        };
+        PreparedPolygon::new(&polygon)
+            .map(PolylabelJs)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// Calculate the prepared Polygon's ideal label position, returned as it is by `polylabel`
+    pub fn compute(&self, tolerance: f64) -> Result<Array, JsError> {
+        position(self.0.label(&tolerance))
+    }
+}
+
+// an [x, y] array with a distance property, as returned by mapbox/polylabel
+fn position(label: Label<f64>) -> Result<Array, JsError> {
+    let position = Array::of2(&label.point.x().into(), &label.point.y().into());
+    Reflect::set(&position, &"distance".into(), &label.distance.into())
+        .map_err(|_| JsError::new("the label's distance couldn't be set"))?;
+    Ok(position)
+}
+
+// build a MultiPolygon from a GeoJSON Polygon or MultiPolygon, or a Feature holding one
+fn geojson(geometry: &JsValue) -> Result<MultiPolygon<f64>, JsError> {
+    let geometry = match property(geometry, "type")?.as_string().as_deref() {
+        Some("Feature") => property(geometry, "geometry")?,
+        _ => geometry.clone(),
+    };
+    let coordinates = property(&geometry, "coordinates")?;
+    match property(&geometry, "type")?.as_string().as_deref() {
+        Some("Polygon") => Ok(MultiPolygon::new(vec![polygon(&coordinates)?])),
+        Some("MultiPolygon") => array(&coordinates, "a MultiPolygon's coordinates")?
+            .iter()
+            .map(|part| polygon(&part))
+            .collect(),
+        _ => Err(JsError::new(
+            "the geometry must be a Polygon or MultiPolygon",
+        )),
+    }
+}
+
+// calculate a Polygon's label, converting any error for JavaScript
+fn label(polygon: &Polygon<f64>, tolerance: f64) -> Result<Label<f64>, JsError> {
+    PreparedPolygon::new(polygon)
+        .map(|prepared| prepared.label(&tolerance))
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+// build a Polygon from an array of rings
+fn polygon(rings: &JsValue) -> Result<Polygon<f64>, JsError> {
+    let mut rings = array(rings, "a Polygon's coordinates")?.iter().map(|ring| {
+        array(&ring, "a ring")?
+            .iter()
+            .map(|position| coord(&position))
+            .collect::<Result<LineString<_>, _>>()
+    });
+    let exterior = rings
+        .next()
+        .ok_or_else(|| JsError::new("a Polygon must have an exterior ring"))??;
+    Ok(Polygon::new(exterior, rings.collect::<Result<_, _>>()?))
+}
+
+// an [x, y] position. Any later elements (e.g. an altitude) are ignored
+fn coord(position: &JsValue) -> Result<Coord<f64>, JsError> {
+    let position = array(position, "a position")?;
+    match (position.get(0).as_f64(), position.get(1).as_f64()) {
+        (Some(x), Some(y)) => Ok(Coord { x, y }),
+        _ => Err(JsError::new("a position must hold at least two numbers")),
+    }
+}
+
+fn property(object: &JsValue, key: &str) -> Result<JsValue, JsError> {
+    Reflect::get(object, &key.into())
+        .map_err(|_| JsError::new(&format!("the geometry's {} couldn't be read", key)))
+}
+
+fn array<'a>(value: &'a JsValue, what: &str) -> Result<&'a Array, JsError> {
+    value
+        .dyn_ref::<Array>()
+        .ok_or_else(|| JsError::new(&format!("{} must be an array", what)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::flat_polygon;
+
+    #[test]
+    fn test_flat_polygon() {
+        // a square with a triangular hole
+        let coords = [
+            0.0, 0.0, 4.0, 0.0, 4.0, 4.0, 0.0, 4.0, 0.0, 0.0, 1.0, 1.0, 2.0, 1.0, 2.0, 2.0, 1.0,
+            1.0,
+        ];
+        let polygon = flat_polygon(&coords, &[0, 5, 9]).unwrap();
+        assert_eq!(polygon.exterior().0.len(), 5);
+        assert_eq!(polygon.interiors()[0].0[2].x, 2.0);
+
+        assert!(flat_polygon(&coords[..17], &[0, 5, 9]).is_err());
+        assert_eq!(
+            flat_polygon(&coords, &[0]).unwrap_err(),
+            "there are no rings"
+        );
+        assert_eq!(
+            flat_polygon(&coords, &[0, 5, 4]).unwrap_err(),
+            "offset 1 (5) is greater than offset 2 (4)"
+        );
+        assert_eq!(
+            flat_polygon(&coords, &[0, 5, 10]).unwrap_err(),
+            "the last offset (10) is greater than the number of positions (9)"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Label positions for Well-Known Binary (WKB) Polygon and MultiPolygon geometries
+
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+
+const POINT: u32 = 1;
+const POLYGON: u32 = 3;
+const MULTIPOLYGON: u32 = 6;
+// EWKB flags, set in the high bits of the geometry type
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+/// Calculate the label position of a WKB or EWKB Polygon or MultiPolygon, returning it as a WKB Point
+///
+/// The label position of a MultiPolygon is that of its part with the largest area. The Point has the same byte
+/// order as the input, and if the input is EWKB with an SRID (as returned by PostGIS), the Point is EWKB with
+/// the same SRID. See [`polylabel_wkb_label`](fn.polylabel_wkb_label.html) for the accepted input.
+/// Returns `None` if `wkb` isn't a valid Polygon or MultiPolygon, or its label position can't be calculated.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_wkb;
+///
+/// // a little-endian EWKB Polygon with an SRID of 4326: a 4 × 4 square
+/// let mut ewkb = vec![1, 3, 0, 0, 0x20, 0xe6, 0x10, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0];
+/// for (x, y) in [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)] {
+///     ewkb.extend(f64::to_le_bytes(x));
+///     ewkb.extend(f64::to_le_bytes(y));
+/// }
+/// let point = polylabel_wkb(&ewkb, 0.1).unwrap();
+/// // an EWKB Point with the same SRID
+/// assert_eq!(point[..9], [1, 1, 0, 0, 0x20, 0xe6, 0x10, 0, 0]);
+/// assert_eq!(point[9..17], f64::to_le_bytes(2.0));
+/// ```
+pub fn polylabel_wkb(wkb: &[u8], tolerance: f64) -> Option<Vec<u8>> {
+    let (geometry, header) = read(wkb)?;
+    let label = largest(&geometry, tolerance)?;
+    let encode_u32 = |value: u32| {
+        if header.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        }
+    };
+    let encode_f64 = |value: f64| {
+        if header.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        }
+    };
+    let mut point = vec![header.little_endian as u8];
+    match header.srid {
+        Some(srid) => {
+            point.extend(encode_u32(POINT | EWKB_SRID));
+            point.extend(encode_u32(srid));
+        }
+        None => point.extend(encode_u32(POINT)),
+    }
+    point.extend(encode_f64(label.point.x()));
+    point.extend(encode_f64(label.point.y()));
+    Some(point)
+}
+
+/// Calculate the label position of a WKB or EWKB Polygon or MultiPolygon, and its distance from the geometry's
+/// edges, returning it with the geometry's SRID, if it has one
+///
+/// The label position of a MultiPolygon is that of its part with the largest area. Both byte orders, ISO and
+/// EWKB `Z` and `M` variants, and an EWKB SRID are accepted, but only `x` and `y` are used.
+/// Returns `None` if `wkb` isn't a valid Polygon or MultiPolygon, or its label position can't be calculated.
+pub fn polylabel_wkb_label(wkb: &[u8], tolerance: f64) -> Option<(Label<f64>, Option<u32>)> {
+    let (geometry, header) = read(wkb)?;
+    largest(&geometry, tolerance).map(|label| (label, header.srid))
+}
+
+fn largest(geometry: &MultiPolygon<f64>, tolerance: f64) -> Option<Label<f64>> {
+    polylabel_multi(geometry, &PolylabelOptions::new(tolerance), Parts::Largest)
+        .ok()?
+        .into_iter()
+        .next()
+}
+
+/// Parse a WKB or EWKB Polygon or MultiPolygon into a MultiPolygon
+///
+/// A Polygon becomes a single-part MultiPolygon. Both byte orders, ISO and EWKB `Z` and `M` variants,
+/// and an EWKB SRID are accepted, but only `x` and `y` are kept.
+/// Returns `None` if the bytes aren't a valid Polygon or MultiPolygon.
+#[cfg(feature = "ffi")]
+pub(crate) fn parse(wkb: &[u8]) -> Option<MultiPolygon<f64>> {
+    read(wkb).map(|(geometry, _)| geometry)
+}
+
+// parse a Polygon or MultiPolygon, returning it with its header
+fn read(wkb: &[u8]) -> Option<(MultiPolygon<f64>, Header)> {
+    let mut reader = Reader {
+        bytes: wkb,
+        little_endian: true,
+    };
+    let header = reader.header()?;
+    let geometry = match header {
+        Header {
+            kind: POLYGON,
+            dimensions,
+            ..
+        } => MultiPolygon::new(vec![reader.polygon(dimensions)?]),
+        Header {
+            kind: MULTIPOLYGON, ..
+        } => {
+            let parts = reader.count(9)?;
+            let polygons = (0..parts)
+                .map(|_| match reader.header()? {
+                    Header {
+                        kind: POLYGON,
+                        dimensions,
+                        ..
+                    } => reader.polygon(dimensions),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?;
+            MultiPolygon::new(polygons)
+        }
+        _ => return None,
+    };
+    reader.bytes.is_empty().then_some((geometry, header))
+}
+
+/// A geometry's type, the number of ordinates in each of its coordinates, its byte order, and its EWKB SRID
+struct Header {
+    kind: u32,
+    dimensions: usize,
+    little_endian: bool,
+    srid: Option<u32>,
+}
+
+/// A cursor over the remainder of a WKB buffer
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = (self.bytes.get(..N)?, &self.bytes[N..]);
+        self.bytes = rest;
+        head.try_into().ok()
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.take()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        let bytes = self.take()?;
+        Some(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    // a count of items, each of which is at least `min_size` bytes long, checked against the remaining bytes
+    // so that a corrupt count can't cause a huge allocation
+    fn count(&mut self, min_size: usize) -> Option<usize> {
+        let count = self.u32()? as usize;
+        (count.checked_mul(min_size)? <= self.bytes.len()).then_some(count)
+    }
+
+    // the byte order, type, and SRID of a geometry
+    fn header(&mut self) -> Option<Header> {
+        self.little_endian = match self.take::<1>()? {
+            [0] => false,
+            [1] => true,
+            _ => return None,
+        };
+        let kind = self.u32()?;
+        let srid = if kind & EWKB_SRID != 0 {
+            Some(self.u32()?)
+        } else {
+            None
+        };
+        let ewkb_dimensions = 2 + (kind & EWKB_Z != 0) as usize + (kind & EWKB_M != 0) as usize;
+        let kind = kind & !(EWKB_Z | EWKB_M | EWKB_SRID);
+        // ISO WKB adds 1000 for Z, 2000 for M, and 3000 for ZM
+        let dimensions = match kind / 1000 {
+            0 => ewkb_dimensions,
+            1 | 2 => 3,
+            3 => 4,
+            _ => return None,
+        };
+        Some(Header {
+            kind: kind % 1000,
+            dimensions,
+            little_endian: self.little_endian,
+            srid,
+        })
+    }
+
+    fn polygon(&mut self, dimensions: usize) -> Option<Polygon<f64>> {
+        let rings = self.count(4)?;
+        let mut rings = (0..rings).map(|_| self.ring(dimensions));
+        let exterior = rings
+            .next()
+            .unwrap_or_else(|| Some(LineString::new(vec![])))?;
+        Some(Polygon::new(exterior, rings.collect::<Option<_>>()?))
+    }
+
+    fn ring(&mut self, dimensions: usize) -> Option<LineString<f64>> {
+        let points = self.count(dimensions * 8)?;
+        (0..points)
+            .map(|_| {
+                let coord = Coord {
+                    x: self.f64()?,
+                    y: self.f64()?,
+                };
+                // skip any z and m ordinates
+                for _ in 2..dimensions {
+                    self.f64()?;
+                }
+                Some(coord)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{polylabel_wkb, polylabel_wkb_label, read};
+    use geo::MultiPolygon;
+
+    fn parse(wkb: &[u8]) -> Option<MultiPolygon<f64>> {
+        read(wkb).map(|(geometry, _)| geometry)
+    }
+
+    // encode a square ring of the given size as WKB, with the given number of ordinates per coordinate
+    fn square(little_endian: bool, size: f64, dimensions: usize) -> Vec<u8> {
+        let encode = |value: f64| {
+            if little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            }
+        };
+        let points = [
+            (0.0, 0.0),
+            (size, 0.0),
+            (size, size),
+            (0.0, size),
+            (0.0, 0.0),
+        ];
+        let mut bytes = if little_endian {
+            5u32.to_le_bytes().to_vec()
+        } else {
+            5u32.to_be_bytes().to_vec()
+        };
+        for (x, y) in points {
+            bytes.extend(encode(x));
+            bytes.extend(encode(y));
+            for _ in 2..dimensions {
+                bytes.extend(encode(1.0));
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse() {
+        // little-endian ISO Polygon
+        let mut wkb = vec![1, 3, 0, 0, 0, 1, 0, 0, 0];
+        wkb.extend(square(true, 4.0, 2));
+        let poly = parse(&wkb).unwrap();
+        assert_eq!(poly.0[0].exterior().0[2].x, 4.0);
+
+        // big-endian EWKB PolygonZ with an SRID
+        let mut ewkb = vec![0, 0xa0, 0, 0, 3, 0, 0, 0x10, 0xe6, 0, 0, 0, 1];
+        ewkb.extend(square(false, 2.0, 3));
+        let poly = parse(&ewkb).unwrap();
+        assert_eq!(poly.0[0].exterior().0[2].y, 2.0);
+
+        // little-endian ISO MultiPolygon of two PolygonZMs
+        let mut multi = vec![1, 6, 0, 0, 0, 2, 0, 0, 0];
+        for size in [1.0, 3.0] {
+            multi.extend([1, 0xbb, 0x0b, 0, 0, 1, 0, 0, 0]);
+            multi.extend(square(true, size, 4));
+        }
+        let multi = parse(&multi).unwrap();
+        assert_eq!(multi.0.len(), 2);
+        assert_eq!(multi.0[1].exterior().0[1].x, 3.0);
+
+        // truncated
+        assert!(parse(&wkb[..wkb.len() - 1]).is_none());
+        // a Point
+        assert!(
+            parse(&[1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).is_none()
+        );
+        // an impossibly large ring count
+        assert!(parse(&[1, 3, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]).is_none());
+    }
+
+    #[test]
+    fn test_polylabel_wkb() {
+        // big-endian EWKB Polygon with an SRID of 27700
+        let mut ewkb = vec![0, 0x20, 0, 0, 3, 0, 0, 0x6c, 0x34, 0, 0, 0, 1];
+        ewkb.extend(square(false, 2.0, 2));
+        let (label, srid) = polylabel_wkb_label(&ewkb, 0.1).unwrap();
+        assert_eq!((label.point.x(), label.point.y()), (1.0, 1.0));
+        assert_eq!(srid, Some(27700));
+        let point = polylabel_wkb(&ewkb, 0.1).unwrap();
+        let mut expected = vec![0, 0x20, 0, 0, 1, 0, 0, 0x6c, 0x34];
+        expected.extend(1f64.to_be_bytes());
+        expected.extend(1f64.to_be_bytes());
+        assert_eq!(point, expected);
+
+        // little-endian ISO Polygon, without an SRID
+        let mut wkb = vec![1, 3, 0, 0, 0, 1, 0, 0, 0];
+        wkb.extend(square(true, 4.0, 2));
+        assert_eq!(polylabel_wkb_label(&wkb, 0.1).unwrap().1, None);
+        assert_eq!(polylabel_wkb(&wkb, 0.1).unwrap()[..5], [1, 1, 0, 0, 0]);
+        assert!(polylabel_wkb(&wkb[..wkb.len() - 1], 0.1).is_none());
+    }
+}
@@ -0,0 +1,229 @@
+//! Label positions for Well-Known Text (WKT) Polygon and MultiPolygon geometries
+
+use crate::multi::{polylabel_multi, Parts};
+use crate::options::{Label, PolylabelOptions};
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+
+/// Calculate the label position of a WKT `POLYGON` or `MULTIPOLYGON`, returning it as a WKT `POINT`
+///
+/// The label position of a MultiPolygon is that of its part with the largest area. An EWKT `SRID=…;` prefix
+/// is copied to the `POINT`. See [`polylabel_wkt_label`](fn.polylabel_wkt_label.html) for the accepted input.
+/// Returns `None` if `wkt` isn't a valid `POLYGON` or `MULTIPOLYGON`, or its label position can't be calculated.
+///
+/// # Examples
+///
+/// ```
+/// use polylabel::polylabel_wkt;
+///
+/// let label = polylabel_wkt("SRID=4326;POLYGON ((0 0, 4 0, 4 4, 0 4, 0 0))", 0.1);
+/// assert_eq!(label.as_deref(), Some("SRID=4326;POINT(2 2)"));
+/// ```
+pub fn polylabel_wkt(wkt: &str, tolerance: f64) -> Option<String> {
+    let label = polylabel_wkt_label(wkt, tolerance)?;
+    let point = format!("POINT({} {})", label.point.x(), label.point.y());
+    Some(match split_srid(wkt) {
+        (Some(srid), _) => format!("{};{}", srid, point),
+        (None, _) => point,
+    })
+}
+
+/// Calculate the label position of a WKT `POLYGON` or `MULTIPOLYGON`, and its distance from the geometry's edges
+///
+/// The label position of a MultiPolygon is that of its part with the largest area.
+/// Keywords are case-insensitive, and `Z`, `M`, and `ZM` coordinates are accepted, but only `x` and `y` are used.
+/// An EWKT `SRID=…;` prefix is ignored.
+/// Returns `None` if `wkt` isn't a valid `POLYGON` or `MULTIPOLYGON`, or its label position can't be calculated.
+pub fn polylabel_wkt_label(wkt: &str, tolerance: f64) -> Option<Label<f64>> {
+    polylabel_wkt_labels(wkt, tolerance, Parts::Largest)?
+        .into_iter()
+        .next()
+}
+
+/// Calculate label positions for the parts of a WKT `POLYGON` or `MULTIPOLYGON`
+///
+/// A Polygon has a single part, and the label positions of a MultiPolygon's parts are calculated as in
+/// [`polylabel_multi`](fn.polylabel_multi.html). See [`polylabel_wkt_label`](fn.polylabel_wkt_label.html) for
+/// the accepted input.
+/// Returns `None` if `wkt` isn't a valid `POLYGON` or `MULTIPOLYGON`, or its label positions can't be calculated.
+pub fn polylabel_wkt_labels(wkt: &str, tolerance: f64, parts: Parts) -> Option<Vec<Label<f64>>> {
+    polylabel_multi(&parse(wkt)?, &PolylabelOptions::new(tolerance), parts).ok()
+}
+
+/// Convert a WKT `POLYGON` or `MULTIPOLYGON` to a MultiPolygon
+///
+/// A Polygon becomes a single-part MultiPolygon, and `EMPTY` geometries have no parts. See
+/// [`polylabel_wkt_label`](fn.polylabel_wkt_label.html) for the accepted input.
+/// Returns `None` if `wkt` isn't a valid `POLYGON` or `MULTIPOLYGON`.
+pub fn wkt_multi_polygon(wkt: &str) -> Option<MultiPolygon<f64>> {
+    parse(wkt)
+}
+
+// split an EWKT SRID=…; prefix from the WKT that follows it
+fn split_srid(wkt: &str) -> (Option<&str>, &str) {
+    match wkt.trim_start().split_once(';') {
+        Some((srid, rest))
+            if srid
+                .get(..5)
+                .is_some_and(|s| s.eq_ignore_ascii_case("SRID=")) =>
+        {
+            (Some(srid), rest)
+        }
+        _ => (None, wkt),
+    }
+}
+
+/// Parse a WKT `POLYGON` or `MULTIPOLYGON` into a MultiPolygon
+///
+/// A Polygon becomes a single-part MultiPolygon, and `EMPTY` geometries have no parts.
+/// Keywords are case-insensitive, and `Z`, `M`, and `ZM` coordinates are accepted, but only `x` and `y` are kept.
+/// An EWKT `SRID=…;` prefix is ignored.
+/// Returns `None` if the string isn't a valid `POLYGON` or `MULTIPOLYGON`.
+pub(crate) fn parse(wkt: &str) -> Option<MultiPolygon<f64>> {
+    let (_, wkt) = split_srid(wkt);
+    let mut parser = Parser(wkt);
+    let tag = parser.word()?;
+    let multi = if tag.eq_ignore_ascii_case("POLYGON") {
+        false
+    } else if tag.eq_ignore_ascii_case("MULTIPOLYGON") {
+        true
+    } else {
+        return None;
+    };
+    // the dimension is implied by the number of ordinates in each coordinate
+    let mut word = parser.word();
+    if word.is_some_and(|word| {
+        ["Z", "M", "ZM"]
+            .iter()
+            .any(|dim| word.eq_ignore_ascii_case(dim))
+    }) {
+        word = parser.word();
+    }
+    let geometry = match word {
+        Some(word) if word.eq_ignore_ascii_case("EMPTY") => MultiPolygon::new(vec![]),
+        Some(_) => return None,
+        None if multi => MultiPolygon::new(parser.list(Parser::polygon)?),
+        None => MultiPolygon::new(vec![parser.polygon()?]),
+    };
+    parser.0.trim().is_empty().then_some(geometry)
+}
+
+/// A cursor over the remainder of a WKT string
+struct Parser<'a>(&'a str);
+
+impl<'a> Parser<'a> {
+    fn word(&mut self) -> Option<&'a str> {
+        self.0 = self.0.trim_start();
+        let end = self
+            .0
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.0.len());
+        if end == 0 {
+            return None;
+        }
+        let (word, rest) = self.0.split_at(end);
+        self.0 = rest;
+        Some(word)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.0 = self.0.trim_start();
+        match self.0.strip_prefix(c) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        self.0 = self.0.trim_start();
+        let end = self
+            .0
+            .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+            .unwrap_or(self.0.len());
+        let (number, rest) = self.0.split_at(end);
+        let number = number.parse().ok()?;
+        self.0 = rest;
+        Some(number)
+    }
+
+    // a parenthesised, comma-separated list of items
+    fn list<T>(&mut self, item: fn(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        if !self.eat('(') {
+            return None;
+        }
+        let mut items = vec![item(self)?];
+        while self.eat(',') {
+            items.push(item(self)?);
+        }
+        self.eat(')').then_some(items)
+    }
+
+    fn polygon(&mut self) -> Option<Polygon<f64>> {
+        let mut rings = self.list(Parser::ring)?.into_iter();
+        let exterior = rings.next()?;
+        Some(Polygon::new(exterior, rings.collect()))
+    }
+
+    fn ring(&mut self) -> Option<LineString<f64>> {
+        self.list(Parser::coord).map(LineString::new)
+    }
+
+    fn coord(&mut self) -> Option<Coord<f64>> {
+        let coord = Coord {
+            x: self.number()?,
+            y: self.number()?,
+        };
+        // skip any z and m ordinates
+        while !self.0.trim_start().starts_with([',', ')']) {
+            self.number()?;
+        }
+        Some(coord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, polylabel_wkt, polylabel_wkt_label, polylabel_wkt_labels};
+    use crate::multi::Parts;
+
+    #[test]
+    fn test_parse() {
+        let poly = parse("POLYGON ((0 0, 4 0, 4 4, 0 0), (1 1, 2 1, 2 2, 1 1))").unwrap();
+        assert_eq!(poly.0.len(), 1);
+        assert_eq!(poly.0[0].exterior().0[2].x, 4.0);
+        assert_eq!(poly.0[0].interiors().len(), 1);
+        let multi = parse(
+            "multipolygon Z (((0 0 1, 1 0 1, 1 1 1, 0 0 1)), ((5 5 2, 6 5 2, 6 6 2, 5 5 2)))",
+        )
+        .unwrap();
+        assert_eq!(multi.0.len(), 2);
+        assert_eq!(multi.0[1].exterior().0[1].x, 6.0);
+        let ewkt = parse("SRID=4326;POLYGON((-1.5e1 0,1 0,1 1,-15 0))").unwrap();
+        assert_eq!(ewkt.0[0].exterior().0[0].x, -15.0);
+        assert!(parse("POLYGON EMPTY").unwrap().0.is_empty());
+        assert!(parse("POINT (0 0)").is_none());
+        assert!(parse("POLYGON ((0 0, 1 0, 1 1, 0 0)").is_none());
+        assert!(parse("POLYGON ((0 0, 1 0, 1 1, 0 0)) trailing").is_none());
+    }
+
+    #[test]
+    fn test_polylabel_wkt() {
+        let multi =
+            "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 1, 0 0)), ((10 10, 14 10, 14 14, 10 14, 10 10)))";
+        assert_eq!(polylabel_wkt(multi, 0.1).as_deref(), Some("POINT(12 12)"));
+        let label = polylabel_wkt_label(multi, 0.1).unwrap();
+        assert_eq!(label.distance, 2.0);
+        assert_eq!(
+            polylabel_wkt_labels(multi, 0.1, Parts::All).map(|labels| labels.len()),
+            Some(2)
+        );
+        assert_eq!(
+            polylabel_wkt(&format!("srid=27700; {}", multi), 0.1).as_deref(),
+            Some("srid=27700;POINT(12 12)")
+        );
+        assert!(polylabel_wkt("MULTIPOLYGON EMPTY", 0.1).is_none());
+        assert!(polylabel_wkt("POINT (0 0)", 0.1).is_none());
+    }
+}
@@ -0,0 +1,507 @@
+vec![
+    vec![
+        (100.0, 0.0),
+        (103.972913, 13.688294),
+        (104.056684, 27.881905),
+        (99.21638, 41.09677),
+        (90.066642, 52.0),
+        (78.506907, 60.240468),
+        (66.710678, 66.710678),
+        (56.047716, 73.042805),
+        (46.535898, 80.60254),
+        (37.09677, 89.559526),
+        (26.417803, 98.592583),
+        (13.881046, 105.437015),
+        (0.0, 108.0),
+        (-13.881046, 105.437015),
+        (-26.417803, 98.592583),
+        (-37.09677, 89.559526),
+        (-46.535898, 80.60254),
+        (-56.047716, 73.042805),
+        (-66.710678, 66.710678),
+        (-78.506907, 60.240468),
+        (-90.066642, 52.0),
+        (-99.21638, 41.09677),
+        (-104.056684, 27.881905),
+        (-103.972913, 13.688294),
+        (-100.0, 0.0),
+        (-94.316059, -12.416945),
+        (-89.128481, -23.881905),
+        (-85.559526, -35.439916),
+        (-83.138439, -48.0),
+        (-80.163761, -61.511817),
+        (-74.710678, -74.710678),
+        (-65.70457, -85.627863),
+        (-53.464102, -92.60254),
+        (-39.439916, -95.21638),
+        (-25.346006, -94.592583),
+        (-12.224192, -92.851957),
+        (-0.0, -92.0),
+        (12.224192, -92.851957),
+        (25.346006, -94.592583),
+        (39.439916, -95.21638),
+        (53.464102, -92.60254),
+        (65.70457, -85.627863),
+        (74.710678, -74.710678),
+        (80.163761, -61.511817),
+        (83.138439, -48.0),
+        (85.559526, -35.439916),
+        (89.128481, -23.881905),
+        (94.316059, -12.416945),
+        (100.0, 0.0),
+    ],
+    vec![
+        (-73.224779, -12.622055),
+        (-74.682622, -15.808974),
+        (-73.09994, -18.935772),
+        (-69.668524, -19.647908),
+        (-66.9723, -17.409129),
+        (-67.041581, -13.90528),
+        (-69.824196, -11.774832),
+        (-73.224779, -12.622055),
+    ],
+    vec![
+        (-66.939917, 2.349841),
+        (-70.695529, 4.118328),
+        (-74.10489, 1.750116),
+        (-73.758639, -2.386582),
+        (-70.003027, -4.15507),
+        (-66.593666, -1.786858),
+        (-66.939917, 2.349841),
+    ],
+    vec![
+        (-70.702147, 17.123874),
+        (-70.772746, 15.120009),
+        (-69.072647, 14.056937),
+        (-67.30195, 14.997729),
+        (-67.231352, 17.001594),
+        (-68.931451, 18.064666),
+        (-70.702147, 17.123874),
+    ],
+    vec![
+        (-51.918166, -29.390935),
+        (-55.353301, -29.470527),
+        (-57.726028, -31.955814),
+        (-57.646436, -35.390948),
+        (-55.161149, -37.763676),
+        (-51.726015, -37.684084),
+        (-49.353287, -35.198797),
+        (-49.432879, -31.763662),
+        (-51.918166, -29.390935),
+    ],
+    vec![
+        (-50.699299, -16.66793),
+        (-54.287172, -16.648297),
+        (-56.539523, -19.441168),
+        (-55.760287, -22.943454),
+        (-52.536244, -24.517862),
+        (-49.295164, -22.978831),
+        (-48.477647, -19.485283),
+        (-50.699299, -16.66793),
+    ],
+    vec![
+        (-56.449682, 39.698325),
+        (-59.055555, 39.026242),
+        (-60.422952, 36.708377),
+        (-59.750869, 34.102503),
+        (-57.433004, 32.735107),
+        (-54.82713, 33.40719),
+        (-53.459734, 35.725055),
+        (-54.131817, 38.330928),
+        (-56.449682, 39.698325),
+    ],
+    vec![
+        (-51.97357, 58.186128),
+        (-56.743961, 56.614353),
+        (-58.489388, 51.904725),
+        (-55.895509, 47.60369),
+        (-50.915568, 46.950015),
+        (-47.299561, 50.435931),
+        (-47.770416, 55.436471),
+        (-51.97357, 58.186128),
+    ],
+    vec![
+        (-41.585682, -54.720113),
+        (-40.660851, -56.847836),
+        (-38.420706, -57.451387),
+        (-36.552124, -56.07628),
+        (-36.462184, -53.757998),
+        (-38.218613, -52.242255),
+        (-40.498785, -52.670436),
+        (-41.585682, -54.720113),
+    ],
+    vec![
+        (-32.674174, -28.712924),
+        (-36.450473, -28.313761),
+        (-39.402971, -30.701756),
+        (-39.802134, -34.478055),
+        (-37.414139, -37.430553),
+        (-33.63784, -37.829717),
+        (-30.685341, -35.441721),
+        (-30.286178, -31.665422),
+        (-32.674174, -28.712924),
+    ],
+    vec![
+        (-30.612752, 2.594969),
+        (-33.212164, 4.316776),
+        (-36.267728, 3.696215),
+        (-37.989535, 1.096803),
+        (-37.368974, -1.958761),
+        (-34.769562, -3.680568),
+        (-31.713998, -3.060007),
+        (-29.992191, -0.460595),
+        (-30.612752, 2.594969),
+    ],
+    vec![
+        (-37.38901, 24.246121),
+        (-39.664943, 21.307693),
+        (-38.786609, 17.696216),
+        (-35.415411, 16.131207),
+        (-32.089929, 17.791148),
+        (-31.31432, 21.426071),
+        (-33.672633, 24.298804),
+        (-37.38901, 24.246121),
+    ],
+    vec![
+        (-39.773761, 41.242655),
+        (-41.693536, 38.798098),
+        (-41.592839, 35.69145),
+        (-39.518786, 33.376348),
+        (-36.44185, 32.936051),
+        (-33.801763, 34.576581),
+        (-32.833852, 37.530315),
+        (-33.991012, 40.415168),
+        (-36.731796, 41.881286),
+        (-39.773761, 41.242655),
+    ],
+    vec![
+        (-32.165217, 53.901035),
+        (-34.347092, 55.118069),
+        (-36.750483, 54.435823),
+        (-37.967517, 52.253948),
+        (-37.285272, 49.850557),
+        (-35.103397, 48.633523),
+        (-32.700006, 49.315768),
+        (-31.482972, 51.497643),
+        (-32.165217, 53.901035),
+    ],
+    vec![
+        (-21.257141, -71.304256),
+        (-18.320094, -78.035029),
+        (-11.01115, -77.321653),
+        (-9.431023, -70.14999),
+        (-15.763393, -66.431034),
+        (-21.257141, -71.304256),
+    ],
+    vec![
+        (-12.300281, -55.731997),
+        (-14.120618, -52.853395),
+        (-17.506161, -52.481813),
+        (-19.907527, -54.89706),
+        (-19.516439, -58.280405),
+        (-16.627394, -60.084121),
+        (-13.415901, -58.949973),
+        (-12.300281, -55.731997),
+    ],
+    vec![
+        (-16.299439, -32.529294),
+        (-18.604291, -30.712136),
+        (-21.537957, -30.801642),
+        (-23.727742, -32.755932),
+        (-24.149022, -35.660572),
+        (-22.604674, -38.156448),
+        (-19.817316, -39.075713),
+        (-17.091185, -37.988231),
+        (-15.701867, -35.402849),
+        (-16.299439, -32.529294),
+    ],
+    vec![
+        (-20.990592, -1.888976),
+        (-20.141215, -4.298238),
+        (-17.941911, -5.597872),
+        (-15.421759, -5.179763),
+        (-13.759965, -3.23955),
+        (-13.734101, -0.68508),
+        (-15.356271, 1.288382),
+        (-17.867441, 1.757431),
+        (-20.092608, 0.502594),
+        (-20.990592, -1.888976),
+    ],
+    vec![
+        (-26.766277, 20.82962),
+        (-27.088239, 16.497411),
+        (-24.550186, 12.971793),
+        (-20.339701, 11.902443),
+        (-16.426917, 13.78972),
+        (-14.642669, 17.750547),
+        (-15.821827, 21.931609),
+        (-19.412649, 24.376541),
+        (-23.73495, 23.941331),
+        (-26.766277, 20.82962),
+    ],
+    vec![
+        (-18.714295, 37.862151),
+        (-20.380807, 35.688908),
+        (-18.828911, 33.432393),
+        (-16.203275, 34.211032),
+        (-16.132439, 36.948773),
+        (-18.714295, 37.862151),
+    ],
+    vec![
+        (-2.883008, -72.454103),
+        (-0.644666, -77.787443),
+        (5.119328, -77.306746),
+        (6.44333, -71.676319),
+        (1.497614, -68.67722),
+        (-2.883008, -72.454103),
+    ],
+    vec![
+        (-3.990984, -52.585624),
+        (-3.870774, -57.399985),
+        (-0.381506, -60.719251),
+        (4.432855, -60.599041),
+        (7.752121, -57.109773),
+        (7.631911, -52.295412),
+        (4.142643, -48.976146),
+        (-0.671718, -49.096356),
+        (-3.990984, -52.585624),
+    ],
+    vec![
+        (3.196874, -34.77142),
+        (1.130574, -33.020821),
+        (-1.172867, -34.445025),
+        (-0.530171, -37.07583),
+        (2.170477, -37.277554),
+        (3.196874, -34.77142),
+    ],
+    vec![
+        (3.243681, 17.722387),
+        (-2.255792, 22.57764),
+        (-8.572842, 18.847686),
+        (-6.977521, 11.687195),
+        (0.325492, 10.991722),
+        (3.243681, 17.722387),
+    ],
+    vec![
+        (-0.305918, 39.736971),
+        (-2.25374, 39.262157),
+        (-3.096963, 37.443245),
+        (-2.200621, 35.649915),
+        (-0.239679, 35.232579),
+        (1.309234, 36.505501),
+        (1.279755, 38.510144),
+        (-0.305918, 39.736971),
+    ],
+    vec![
+        (4.621708, 57.163556),
+        (1.777217, 57.912662),
+        (-0.76384, 56.431),
+        (-1.512946, 53.586509),
+        (-0.031285, 51.045452),
+        (2.813207, 50.296346),
+        (5.354264, 51.778007),
+        (6.10337, 54.622499),
+        (4.621708, 57.163556),
+    ],
+    vec![
+        (12.015726, -71.340959),
+        (12.575905, -75.215212),
+        (15.954184, -77.192803),
+        (19.606649, -75.784567),
+        (20.782921, -72.050933),
+        (18.597242, -68.803405),
+        (14.695473, -68.487437),
+        (12.015726, -71.340959),
+    ],
+    vec![
+        (14.374758, -53.490244),
+        (15.521351, -56.814522),
+        (18.682734, -58.354377),
+        (22.007011, -57.207784),
+        (23.546866, -54.046401),
+        (22.400274, -50.722124),
+        (19.238891, -49.182269),
+        (15.914613, -50.328861),
+        (14.374758, -53.490244),
+    ],
+    vec![
+        (22.801116, -33.497476),
+        (18.878003, -33.114752),
+        (16.584998, -36.320906),
+        (18.215106, -39.909783),
+        (22.138219, -40.292507),
+        (24.431224, -37.086353),
+        (22.801116, -33.497476),
+    ],
+    vec![
+        (25.230548, -14.565299),
+        (21.656327, -12.302728),
+        (17.463963, -12.866962),
+        (14.615109, -15.993991),
+        (14.442775, -20.220642),
+        (17.027598, -23.569219),
+        (21.160111, -24.472885),
+        (24.906666, -22.508805),
+        (26.514206, -18.595994),
+        (25.230548, -14.565299),
+    ],
+    vec![
+        (20.411722, 19.076195),
+        (18.633583, 21.674299),
+        (15.539112, 22.254101),
+        (12.941008, 20.475961),
+        (12.361206, 17.38149),
+        (14.139345, 14.783387),
+        (17.233816, 14.203585),
+        (19.83192, 15.981724),
+        (20.411722, 19.076195),
+    ],
+    vec![
+        (18.073007, 38.617169),
+        (16.036433, 36.597407),
+        (17.328005, 34.036369),
+        (20.162815, 34.473323),
+        (20.623251, 37.304413),
+        (18.073007, 38.617169),
+    ],
+    vec![
+        (15.299469, 74.735857),
+        (13.640845, 73.635161),
+        (13.077779, 71.725837),
+        (13.873735, 69.901278),
+        (15.656277, 69.015215),
+        (17.591334, 69.482248),
+        (18.773471, 71.083846),
+        (18.649552, 73.070604),
+        (17.277562, 74.512896),
+        (15.299469, 74.735857),
+    ],
+    vec![
+        (31.131626, -55.297203),
+        (32.130711, -59.383914),
+        (35.726912, -61.567196),
+        (39.813624, -60.568111),
+        (41.996905, -56.971909),
+        (40.99782, -52.885198),
+        (37.401619, -50.701916),
+        (33.314907, -51.701002),
+        (31.131626, -55.297203),
+    ],
+    vec![
+        (28.588726, -33.618757),
+        (30.35779, -37.848421),
+        (34.767667, -39.102464),
+        (38.497631, -36.436566),
+        (38.738943, -31.858203),
+        (35.309889, -28.814975),
+        (30.792618, -29.598495),
+        (28.588726, -33.618757),
+    ],
+    vec![
+        (34.517731, -16.63995),
+        (31.912915, -17.517673),
+        (30.4817, -19.864391),
+        (30.893767, -22.582049),
+        (32.956306, -24.399024),
+        (35.704231, -24.465133),
+        (37.851758, -22.749444),
+        (38.394035, -20.054746),
+        (37.077325, -17.641918),
+        (34.517731, -16.63995),
+    ],
+    vec![
+        (32.137363, 0.272446),
+        (30.871088, -2.429671),
+        (31.637952, -5.313559),
+        (34.079131, -7.029814),
+        (37.052371, -6.775382),
+        (39.166459, -4.669314),
+        (39.43219, -1.697063),
+        (37.725226, 0.750622),
+        (34.844273, 1.528442),
+        (32.137363, 0.272446),
+    ],
+    vec![
+        (31.601305, 20.962546),
+        (28.997165, 15.553869),
+        (32.379148, 10.59428),
+        (38.365269, 11.043367),
+        (40.969408, 16.452045),
+        (37.587426, 21.411634),
+        (31.601305, 20.962546),
+    ],
+    vec![
+        (32.410336, 59.781925),
+        (31.870476, 55.27825),
+        (35.054994, 52.048174),
+        (39.565883, 52.524012),
+        (42.006352, 56.347447),
+        (40.538677, 60.639355),
+        (36.268048, 62.167842),
+        (32.410336, 59.781925),
+    ],
+    vec![
+        (53.946004, -36.504587),
+        (51.67305, -35.620667),
+        (49.440803, -36.602863),
+        (48.556884, -38.875818),
+        (49.53908, -41.108064),
+        (51.812034, -41.991983),
+        (54.044281, -41.009787),
+        (54.9282, -38.736833),
+        (53.946004, -36.504587),
+    ],
+    vec![
+        (55.100803, -16.379483),
+        (52.779197, -16.667772),
+        (51.341425, -18.513246),
+        (51.629714, -20.834852),
+        (53.475189, -22.272624),
+        (55.796794, -21.984335),
+        (57.234566, -20.138861),
+        (56.946277, -17.817255),
+        (55.100803, -16.379483),
+    ],
+    vec![
+        (53.485101, 0.879159),
+        (52.412948, -0.584204),
+        (52.888575, -2.33484),
+        (54.553825, -3.054483),
+        (56.154732, -2.201228),
+        (56.485779, -0.417593),
+        (55.297681, 0.953308),
+        (53.485101, 0.879159),
+    ],
+    vec![
+        (51.377673, 20.055517),
+        (49.10187, 16.292313),
+        (51.222998, 12.439809),
+        (55.61993, 12.350507),
+        (57.895733, 16.113711),
+        (55.774604, 19.966216),
+        (51.377673, 20.055517),
+    ],
+    vec![
+        (64.834889, -17.13617),
+        (65.324679, -20.907624),
+        (68.337833, -23.228111),
+        (72.109287, -22.738322),
+        (74.429775, -19.725167),
+        (73.939985, -15.953713),
+        (70.926831, -13.633226),
+        (67.155376, -14.123015),
+        (64.834889, -17.13617),
+    ],
+    vec![
+        (73.469567, 4.241778),
+        (70.261418, 5.990008),
+        (66.680093, 5.267071),
+        (64.401333, 2.411238),
+        (64.491397, -1.241215),
+        (66.908141, -3.981265),
+        (70.520744, -4.526812),
+        (73.63883, -2.622589),
+        (74.803411, 0.840398),
+        (73.469567, 4.241778),
+    ],
+]